@@ -14,15 +14,17 @@
 // You should have received a copy of the GNU General Public License along with
 // P2Poolv2. If not, see <https://www.gnu.org/licenses/>.
 
+use crate::api::error::ApiError;
 use crate::api::server::AppState;
 use axum::{
     extract::{Request, State},
     http::{HeaderMap, StatusCode},
     middleware::Next,
-    response::Response,
+    response::{IntoResponse, Response},
 };
 use base64::Engine;
 use hmac::{Hmac, Mac};
+use p2poolv2_lib::config::{ApiKeyConfig, ApiScope};
 use sha2::Sha256;
 use std::sync::Arc;
 use tracing::warn;
@@ -52,60 +54,193 @@ fn validate_password(password: &str, stored_token: &str) -> bool {
     computed_hmac == expected_hmac
 }
 
-/// Authentication middleware that checks for valid Basic authentication
+/// Decode the username and password from a Basic Authorization header.
+/// The scheme name is case-insensitive, as some clients send "basic".
+fn parse_basic_credentials(header: &str) -> Option<(String, String)> {
+    let (scheme, credentials) = header.split_once(' ')?;
+    if !scheme.eq_ignore_ascii_case("Basic") {
+        warn!("Invalid Authorization header format, expected Basic auth");
+        return None;
+    }
+
+    // Decode base64
+    let Ok(decoded) = base64::engine::general_purpose::STANDARD.decode(credentials.trim()) else {
+        warn!("Failed to decode Basic auth credentials");
+        return None;
+    };
+
+    let Ok(decoded_str) = String::from_utf8(decoded) else {
+        warn!("Invalid UTF-8 in Basic auth credentials");
+        return None;
+    };
+
+    // Parse username:password, the password may itself contain colons
+    let Some((username, password)) = decoded_str.split_once(':') else {
+        warn!("Invalid Basic auth format");
+        return None;
+    };
+    Some((username.to_string(), password.to_string()))
+}
+
+/// Scope of the first key matching the credentials, None when no key
+/// matches
+fn key_scope(api_keys: &[ApiKeyConfig], username: &str, password: &str) -> Option<ApiScope> {
+    api_keys
+        .iter()
+        .find(|key| key.user == username && validate_password(password, &key.token))
+        .map(|key| key.scope)
+}
+
+/// Authentication middleware that checks for valid Basic authentication.
+/// The scope of the matching API key is added to the request extensions
+/// for require_admin_scope.
 pub(crate) async fn auth_middleware(
     State(state): State<Arc<AppState>>,
     headers: HeaderMap,
-    request: Request,
+    mut request: Request,
     next: Next,
 ) -> Result<Response, StatusCode> {
     // If no auth is configured, allow all requests
-    let (Some(expected_user), Some(expected_token)) = (&state.auth_user, &state.auth_token) else {
+    if state.api_keys.is_empty() {
+        request.extensions_mut().insert(ApiScope::Admin);
         return Ok(next.run(request).await);
-    };
+    }
 
     // Get Authorization header
-    let auth_header = headers.get("Authorization").and_then(|h| h.to_str().ok());
-
-    match auth_header {
-        Some(header) if header.starts_with("Basic ") => {
-            let credentials = &header[6..]; // Skip "Basic "
-
-            // Decode base64
-            let Ok(decoded) = base64::engine::general_purpose::STANDARD.decode(credentials) else {
-                warn!("Failed to decode Basic auth credentials");
-                return Err(StatusCode::UNAUTHORIZED);
-            };
-
-            let Ok(decoded_str) = String::from_utf8(decoded) else {
-                warn!("Invalid UTF-8 in Basic auth credentials");
-                return Err(StatusCode::UNAUTHORIZED);
-            };
-
-            // Parse username:password
-            let parts: Vec<&str> = decoded_str.splitn(2, ':').collect();
-            if parts.len() != 2 {
-                warn!("Invalid Basic auth format");
-                return Err(StatusCode::UNAUTHORIZED);
-            }
-
-            let (username, password) = (parts[0], parts[1]);
-
-            // Validate username and password
-            if username == expected_user && validate_password(password, expected_token) {
-                Ok(next.run(request).await)
-            } else {
-                warn!("Invalid username or password");
-                Err(StatusCode::UNAUTHORIZED)
-            }
-        }
-        Some(_) => {
-            warn!("Invalid Authorization header format, expected Basic auth");
-            Err(StatusCode::UNAUTHORIZED)
-        }
-        None => {
-            warn!("Missing Authorization header");
-            Err(StatusCode::UNAUTHORIZED)
+    let Some(auth_header) = headers.get("Authorization").and_then(|h| h.to_str().ok()) else {
+        warn!("Missing Authorization header");
+        return Err(StatusCode::UNAUTHORIZED);
+    };
+
+    let Some((username, password)) = parse_basic_credentials(auth_header) else {
+        return Err(StatusCode::UNAUTHORIZED);
+    };
+
+    // Validate username and password
+    let Some(scope) = key_scope(&state.api_keys, &username, &password) else {
+        warn!("Invalid username or password");
+        return Err(StatusCode::UNAUTHORIZED);
+    };
+    request.extensions_mut().insert(scope);
+    Ok(next.run(request).await)
+}
+
+/// Refuse requests without an admin scope with 403 Forbidden. Layered on
+/// the admin routes, inside auth_middleware.
+pub(crate) async fn require_admin_scope(request: Request, next: Next) -> Response {
+    match request.extensions().get::<ApiScope>() {
+        Some(ApiScope::Admin) => next.run(request).await,
+        _ => ApiError::Forbidden("This endpoint needs an API key with admin scope".to_string())
+            .into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // HMAC-SHA256 of "testpassword" keyed with this salt, as made by gen_auth
+    const TEST_TOKEN: &str = "0123456789abcdef0123456789abcdef$ae9b643bfa9f224a9c11accafec1ab89c3851c54ac036af2ac7f5b7a7d064fcb";
+
+    fn basic_header(scheme: &str, credentials: &str) -> String {
+        format!(
+            "{scheme} {}",
+            base64::engine::general_purpose::STANDARD.encode(credentials)
+        )
+    }
+
+    #[test]
+    fn test_parse_valid_basic_credentials() {
+        let header = basic_header("Basic", "testuser:testpassword");
+        assert_eq!(
+            parse_basic_credentials(&header),
+            Some(("testuser".to_string(), "testpassword".to_string()))
+        );
+
+        // Scheme is case-insensitive, as sent by some clients
+        let header = basic_header("basic", "testuser:testpassword");
+        assert_eq!(
+            parse_basic_credentials(&header),
+            Some(("testuser".to_string(), "testpassword".to_string()))
+        );
+
+        // Only the first colon separates the username
+        let header = basic_header("Basic", "testuser:pass:word");
+        assert_eq!(
+            parse_basic_credentials(&header),
+            Some(("testuser".to_string(), "pass:word".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid_basic_credentials() {
+        assert_eq!(parse_basic_credentials("Basic"), None);
+        assert_eq!(parse_basic_credentials("Basic not-base64!"), None);
+        assert_eq!(
+            parse_basic_credentials(&basic_header("Basic", "no-colon")),
+            None
+        );
+        assert_eq!(
+            parse_basic_credentials(&basic_header("Bearer", "testuser:testpassword")),
+            None
+        );
+    }
+
+    fn api_key(user: &str, scope: ApiScope) -> ApiKeyConfig {
+        ApiKeyConfig {
+            user: user.to_string(),
+            token: TEST_TOKEN.to_string(),
+            scope,
         }
     }
+
+    #[test]
+    fn test_key_scope() {
+        let api_keys = vec![
+            api_key("dashboard", ApiScope::Read),
+            api_key("operator", ApiScope::Admin),
+        ];
+        assert_eq!(
+            key_scope(&api_keys, "dashboard", "testpassword"),
+            Some(ApiScope::Read)
+        );
+        assert_eq!(
+            key_scope(&api_keys, "operator", "testpassword"),
+            Some(ApiScope::Admin)
+        );
+        assert_eq!(key_scope(&api_keys, "dashboard", "wrongpassword"), None);
+        assert_eq!(key_scope(&api_keys, "unknown", "testpassword"), None);
+    }
+
+    #[tokio::test]
+    async fn test_require_admin_scope_rejects_read_key_on_delete() {
+        use axum::{Router, body::Body, routing::delete};
+        use tower::ServiceExt;
+
+        let app = |scope: ApiScope| {
+            Router::new()
+                .route("/entry", delete(|| async { StatusCode::NO_CONTENT }))
+                .route_layer(axum::middleware::from_fn(require_admin_scope))
+                .layer(axum::Extension(scope))
+        };
+        let request = || {
+            Request::builder()
+                .method("DELETE")
+                .uri("/entry")
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let response = app(ApiScope::Read).oneshot(request()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = app(ApiScope::Admin).oneshot(request()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    }
+
+    #[test]
+    fn test_validate_password() {
+        assert!(validate_password("testpassword", TEST_TOKEN));
+        assert!(!validate_password("wrongpassword", TEST_TOKEN));
+        assert!(!validate_password("testpassword", "missing-separator"));
+    }
 }
@@ -0,0 +1,194 @@
+// Copyright (C) 2024, 2025 P2Poolv2 Developers (see AUTHORS)
+//
+// This file is part of P2Poolv2
+//
+// P2Poolv2 is free software: you can redistribute it and/or modify it under
+// the terms of the GNU General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// P2Poolv2 is distributed in the hope that it will be useful, but WITHOUT ANY
+// WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along with
+// P2Poolv2. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::api::error::ApiError;
+use crate::api::pretty::is_json;
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::header,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+use serde_json::Value;
+use tracing::warn;
+
+/// Coinbase value fields in /chain/coinbase_totals
+pub(crate) const COINBASE_BIGINT_FIELDS: &[&str] = &["total_sats", "sats"];
+
+/// Difficulty fields in /pplns_shares
+pub(crate) const PPLNS_SHARES_BIGINT_FIELDS: &[&str] = &["difficulty"];
+
+/// Value and difficulty fields in /pplns/estimate
+pub(crate) const PPLNS_ESTIMATE_BIGINT_FIELDS: &[&str] =
+    &["estimated_sats", "miner_difficulty", "window_difficulty"];
+
+#[derive(Deserialize)]
+struct BigIntQuery {
+    bigint: Option<String>,
+}
+
+/// Encode the given numeric fields of a JSON response as strings when
+/// the request has ?bigint=string, as JS clients lose precision on
+/// numbers above 2^53. ?bigint=number, the default, leaves responses
+/// unchanged. Fields are matched by name at any depth, before any
+/// ?case=camel renaming.
+pub(crate) async fn bigint_string_middleware(
+    State(fields): State<&'static [&'static str]>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let bigint = request
+        .uri()
+        .query()
+        .and_then(|query| serde_urlencoded::from_str::<BigIntQuery>(query).ok())
+        .and_then(|query| query.bigint);
+    match bigint.as_deref() {
+        None | Some("number") => return next.run(request).await,
+        Some("string") => {}
+        Some(other) => {
+            return ApiError::BadRequest(format!(
+                "Invalid bigint {other}: expected string or number"
+            ))
+            .into_response();
+        }
+    }
+    let response = next.run(request).await;
+    if !is_json(&response) {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            warn!("Failed to read response body to encode big integers: {e}");
+            return Response::from_parts(parts, Body::empty());
+        }
+    };
+    let string_body = serde_json::from_slice::<Value>(&bytes)
+        .and_then(|value| serde_json::to_vec(&numbers_to_strings(value, fields)));
+    match string_body {
+        Ok(string_body) => {
+            parts.headers.remove(header::CONTENT_LENGTH);
+            Response::from_parts(parts, Body::from(string_body))
+        }
+        Err(_) => Response::from_parts(parts, Body::from(bytes)),
+    }
+}
+
+/// Replace the numbers under the given keys with their decimal string,
+/// recursively. serde_json keeps u64 and i64 exact, so no digits are lost.
+fn numbers_to_strings(value: Value, fields: &[&str]) -> Value {
+    match value {
+        Value::Object(object) => Value::Object(
+            object
+                .into_iter()
+                .map(|(key, value)| {
+                    let value = match value {
+                        Value::Number(number) if fields.contains(&key.as_str()) => {
+                            Value::String(number.to_string())
+                        }
+                        other => numbers_to_strings(other, fields),
+                    };
+                    (key, value)
+                })
+                .collect(),
+        ),
+        Value::Array(values) => Value::Array(
+            values
+                .into_iter()
+                .map(|value| numbers_to_strings(value, fields))
+                .collect(),
+        ),
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{Json, Router, http::StatusCode, middleware, routing::get};
+    use serde_json::json;
+    use tower::ServiceExt;
+
+    /// Above 2^53, so not exactly representable as an f64
+    const BIG_SATS: u64 = (1 << 53) + 1;
+
+    fn app() -> Router {
+        Router::new()
+            .route(
+                "/",
+                get(|| async {
+                    Json(json!({
+                        "total_sats": BIG_SATS,
+                        "per_address": [{"address": "addr", "sats": BIG_SATS}],
+                        "share_count": 1,
+                    }))
+                }),
+            )
+            .layer(middleware::from_fn_with_state(
+                COINBASE_BIGINT_FIELDS,
+                bigint_string_middleware,
+            ))
+    }
+
+    async fn get_json(uri: &str) -> (StatusCode, Value) {
+        let response = app()
+            .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (
+            status,
+            serde_json::from_slice(&bytes).unwrap_or(Value::Null),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_bigint_string_round_trips_above_2_pow_53() {
+        let (status, body) = get_json("/?bigint=string").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["total_sats"], json!(BIG_SATS.to_string()));
+        let sats: u64 = body["per_address"][0]["sats"]
+            .as_str()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert_eq!(sats, BIG_SATS);
+        // Fields not listed stay numbers
+        assert_eq!(body["share_count"], json!(1));
+    }
+
+    #[tokio::test]
+    async fn test_bigint_number_is_default() {
+        for uri in ["/", "/?bigint=number"] {
+            let (status, body) = get_json(uri).await;
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(body["total_sats"].as_u64(), Some(BIG_SATS));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_bigint_invalid_value_rejected() {
+        let (status, _) = get_json("/?bigint=hex").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+}
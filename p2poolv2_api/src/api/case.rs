@@ -0,0 +1,196 @@
+// Copyright (C) 2024, 2025 P2Poolv2 Developers (see AUTHORS)
+//
+// This file is part of P2Poolv2
+//
+// P2Poolv2 is free software: you can redistribute it and/or modify it under
+// the terms of the GNU General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// P2Poolv2 is distributed in the hope that it will be useful, but WITHOUT ANY
+// WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along with
+// P2Poolv2. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::api::error::ApiError;
+use crate::api::pretty::is_json;
+use axum::{
+    body::Body,
+    extract::Request,
+    http::header,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use tracing::warn;
+
+/// Response fields holding maps keyed by data, like the column family
+/// names in /db/properties. The field is renamed but its map keys are kept.
+const MAP_FIELDS: &[&str] = &["column_families"];
+
+#[derive(Deserialize)]
+struct CaseQuery {
+    case: Option<String>,
+}
+
+/// Rename snake_case object keys in JSON responses to camelCase when the
+/// request has ?case=camel, for JS clients. ?case=snake, the default,
+/// leaves responses unchanged.
+///
+/// Keys of the form lower_snake_case are renamed, except the keys of the
+/// fields in MAP_FIELDS, which are data rather than field names.
+/// Responses that are not application/json, like the ndjson and SSE
+/// streams, pass through unchanged.
+pub(crate) async fn camel_case_middleware(request: Request, next: Next) -> Response {
+    let case = request
+        .uri()
+        .query()
+        .and_then(|query| serde_urlencoded::from_str::<CaseQuery>(query).ok())
+        .and_then(|query| query.case);
+    match case.as_deref() {
+        None | Some("snake") => return next.run(request).await,
+        Some("camel") => {}
+        Some(other) => {
+            return ApiError::BadRequest(format!("Invalid case {other}: expected camel or snake"))
+                .into_response();
+        }
+    }
+    let response = next.run(request).await;
+    if !is_json(&response) {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            warn!("Failed to read response body to rename keys: {e}");
+            return Response::from_parts(parts, Body::empty());
+        }
+    };
+    let camel_body = serde_json::from_slice::<Value>(&bytes)
+        .and_then(|value| serde_json::to_vec(&camel_case_keys(value)));
+    match camel_body {
+        Ok(camel_body) => {
+            parts.headers.remove(header::CONTENT_LENGTH);
+            Response::from_parts(parts, Body::from(camel_body))
+        }
+        Err(_) => Response::from_parts(parts, Body::from(bytes)),
+    }
+}
+
+/// Rename the object keys in value to camelCase, recursively. The keys
+/// of MAP_FIELDS maps are kept, though their values are renamed.
+fn camel_case_keys(value: Value) -> Value {
+    match value {
+        Value::Object(object) => Value::Object(
+            object
+                .into_iter()
+                .map(|(key, value)| {
+                    let value = if MAP_FIELDS.contains(&key.as_str()) {
+                        camel_case_map_values(value)
+                    } else {
+                        camel_case_keys(value)
+                    };
+                    (to_camel_case(&key), value)
+                })
+                .collect::<Map<String, Value>>(),
+        ),
+        Value::Array(values) => Value::Array(values.into_iter().map(camel_case_keys).collect()),
+        other => other,
+    }
+}
+
+/// Rename the keys inside each value of a map, keeping the map's own keys
+fn camel_case_map_values(value: Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .map(|(key, value)| (key, camel_case_keys(value)))
+                .collect(),
+        ),
+        other => camel_case_keys(other),
+    }
+}
+
+/// Convert a lower_snake_case key to camelCase. Keys of any other form,
+/// e.g. hashes or already camelCase, are returned as is.
+fn to_camel_case(key: &str) -> String {
+    let is_snake_case = key.starts_with(|c: char| c.is_ascii_lowercase())
+        && key.contains('_')
+        && key
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+    if !is_snake_case {
+        return key.to_string();
+    }
+    let mut camel = String::with_capacity(key.len());
+    let mut upper_next = false;
+    for c in key.chars() {
+        if c == '_' {
+            upper_next = true;
+        } else if upper_next {
+            camel.push(c.to_ascii_uppercase());
+            upper_next = false;
+        } else {
+            camel.push(c);
+        }
+    }
+    camel
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_to_camel_case() {
+        assert_eq!(to_camel_case("prev_share_blockhash"), "prevShareBlockhash");
+        assert_eq!(to_camel_case("miner_pubkey"), "minerPubkey");
+        assert_eq!(to_camel_case("tip_height_2"), "tipHeight2");
+        assert_eq!(to_camel_case("height"), "height");
+        assert_eq!(to_camel_case("alreadyCamel"), "alreadyCamel");
+        assert_eq!(to_camel_case("_private"), "_private");
+        assert_eq!(to_camel_case("Not_Snake"), "Not_Snake");
+    }
+
+    #[test]
+    fn test_camel_case_keys_renames_nested_keys_only() {
+        let value = json!({
+            "tip_hash": "prev_share_blockhash",
+            "nodes": [{ "miner_pubkey": "02ab", "is_main_chain": true }],
+            "edges": { "edge_type": null },
+        });
+        assert_eq!(
+            camel_case_keys(value),
+            json!({
+                "tipHash": "prev_share_blockhash",
+                "nodes": [{ "minerPubkey": "02ab", "isMainChain": true }],
+                "edges": { "edgeType": null },
+            })
+        );
+    }
+
+    #[test]
+    fn test_camel_case_keys_keeps_map_field_keys() {
+        let value = json!({
+            "column_families": {
+                "miner_shares": { "rocksdb.estimate-num-keys": 1 },
+                "block_height": { "sampled_entries": 2 },
+            },
+        });
+        assert_eq!(
+            camel_case_keys(value),
+            json!({
+                "columnFamilies": {
+                    "miner_shares": { "rocksdb.estimate-num-keys": 1 },
+                    "block_height": { "sampledEntries": 2 },
+                },
+            })
+        );
+    }
+}
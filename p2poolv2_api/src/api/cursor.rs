@@ -0,0 +1,134 @@
+// Copyright (C) 2024, 2025 P2Poolv2 Developers (see AUTHORS)
+//
+// This file is part of P2Poolv2
+//
+// P2Poolv2 is free software: you can redistribute it and/or modify it under
+// the terms of the GNU General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// P2Poolv2 is distributed in the hope that it will be useful, but WITHOUT ANY
+// WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along with
+// P2Poolv2. If not, see <https://www.gnu.org/licenses/>.
+
+//! Opaque cursors for seek based paging through column family listings.
+//!
+//! A cursor is base64 of the column family name, the last key listed and
+//! a MAC over both, so clients can't read the key format or forge a seek
+//! into another column family. The MAC key is made at startup, so cursors
+//! stop working when the node restarts.
+
+use crate::api::error::ApiError;
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use hmac::{Hmac, Mac};
+use p2poolv2_lib::store::column_families::ColumnFamily;
+use sha2::Sha256;
+use std::sync::LazyLock;
+use uuid::Uuid;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Bytes of the MAC kept in a cursor
+const CURSOR_MAC_LEN: usize = 16;
+
+static CURSOR_KEY: LazyLock<[u8; 32]> = LazyLock::new(|| {
+    let mut key = [0u8; 32];
+    key[..16].copy_from_slice(Uuid::new_v4().as_bytes());
+    key[16..].copy_from_slice(Uuid::new_v4().as_bytes());
+    key
+});
+
+fn cursor_mac() -> HmacSha256 {
+    HmacSha256::new_from_slice(CURSOR_KEY.as_slice()).expect("HMAC takes keys of any length")
+}
+
+/// Encode a cursor resuming a listing of cf after key
+pub(crate) fn encode_cursor(cf: ColumnFamily, key: &[u8]) -> String {
+    let cf_name = cf.as_str().as_bytes();
+    let mut token = Vec::with_capacity(1 + cf_name.len() + key.len() + CURSOR_MAC_LEN);
+    token.push(cf_name.len() as u8);
+    token.extend_from_slice(cf_name);
+    token.extend_from_slice(key);
+
+    let mut mac = cursor_mac();
+    mac.update(&token);
+    token.extend_from_slice(&mac.finalize().into_bytes()[..CURSOR_MAC_LEN]);
+    URL_SAFE_NO_PAD.encode(token)
+}
+
+/// Decode a cursor into the key to resume after, checking it was made by
+/// this node for a listing of cf
+pub(crate) fn decode_cursor(cursor: &str, cf: ColumnFamily) -> Result<Vec<u8>, ApiError> {
+    let invalid = || ApiError::BadRequest("Invalid cursor".into());
+    let token = URL_SAFE_NO_PAD.decode(cursor).map_err(|_| invalid())?;
+    let body_len = token
+        .len()
+        .checked_sub(CURSOR_MAC_LEN)
+        .ok_or_else(invalid)?;
+    let (body, tag) = token.split_at(body_len);
+
+    let mut mac = cursor_mac();
+    mac.update(body);
+    mac.verify_truncated_left(tag).map_err(|_| invalid())?;
+
+    let (&name_len, rest) = body.split_first().ok_or_else(invalid)?;
+    if rest.len() < name_len as usize {
+        return Err(invalid());
+    }
+    let (cf_name, key) = rest.split_at(name_len as usize);
+    if cf_name != cf.as_str().as_bytes() {
+        return Err(ApiError::BadRequest(format!(
+            "Cursor is not for column family {}",
+            cf.as_str()
+        )));
+    }
+    Ok(key.to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cursor_round_trip() {
+        let key = [0u8, 1, 2, 255];
+        let cursor = encode_cursor(ColumnFamily::Block, &key);
+        assert_eq!(decode_cursor(&cursor, ColumnFamily::Block).unwrap(), key);
+
+        let cursor = encode_cursor(ColumnFamily::Block, &[]);
+        assert!(
+            decode_cursor(&cursor, ColumnFamily::Block)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_cursor_rejected_for_other_column_family() {
+        let cursor = encode_cursor(ColumnFamily::Block, b"key");
+        assert!(matches!(
+            decode_cursor(&cursor, ColumnFamily::Metadata),
+            Err(ApiError::BadRequest(_))
+        ));
+    }
+
+    #[test]
+    fn test_cursor_rejected_when_forged_or_malformed() {
+        let cursor = encode_cursor(ColumnFamily::Block, b"key");
+        let mut token = URL_SAFE_NO_PAD.decode(&cursor).unwrap();
+        // Point the cursor at another key without re-signing it
+        token[1 + "block".len()] ^= 1;
+        let forged = URL_SAFE_NO_PAD.encode(token);
+
+        for cursor in [forged.as_str(), "not base64!", "", "AAAA"] {
+            assert!(matches!(
+                decode_cursor(cursor, ColumnFamily::Block),
+                Err(ApiError::BadRequest(_))
+            ));
+        }
+    }
+}
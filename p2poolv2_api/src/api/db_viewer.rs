@@ -14,14 +14,21 @@
 // You should have received a copy of the GNU General Public License along with
 // P2Poolv2. If not, see <https://www.gnu.org/licenses/>.
 
+use crate::api::cursor::{decode_cursor, encode_cursor};
 use crate::api::error::ApiError;
-use crate::api::server::AppState;
+use crate::api::server::{AppState, etag_response, ndjson_line};
 use axum::{
-    extract::{Path, Query, State},
+    body::Body,
+    extract::{OriginalUri, Path, Query, State},
+    http::{HeaderMap, StatusCode, header},
+    response::{IntoResponse, Response},
     Json,
 };
 use p2poolv2_lib::store::column_families::ColumnFamily;
+use p2poolv2_lib::store::db_viewer_ops::{KeyEncoding, ListOptions, decode_key, format_height_key};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
 use std::sync::Arc;
 
 // ============================================================================
@@ -33,6 +40,30 @@ pub struct ListQuery {
     pub page: Option<u32>,
     pub page_size: Option<u32>,
     pub search: Option<String>,
+    /// key or size, defaults to key
+    pub sort: Option<String>,
+    /// asc or desc, defaults to asc
+    pub dir: Option<String>,
+    /// next_cursor from the previous page of a key sorted listing. The
+    /// listing seeks past the cursor's key and page is not used.
+    pub cursor: Option<String>,
+    /// Bytes of each value to show, capped at MAX_PREVIEW_BYTES. Longer
+    /// values are cut and flagged truncated. Without it, binary values
+    /// are cut to MAX_LIST_VALUE_BYTES.
+    pub preview_bytes: Option<usize>,
+}
+
+#[derive(Deserialize)]
+pub struct EntryQuery {
+    /// One of hex, utf8 or auto. Defaults per column family.
+    pub key_encoding: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct BatchGetRequest {
+    pub keys: Vec<String>,
+    /// One of hex, utf8 or auto, applied to all keys. Defaults per column family.
+    pub key_encoding: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -50,11 +81,35 @@ pub struct ColumnFamiliesResponse {
     pub total: usize,
 }
 
+/// A column family's info with its size and key range, everything the
+/// browser dashboard shows per column family
+#[derive(Serialize)]
+pub struct CfOverview {
+    #[serde(flatten)]
+    pub info: ColumnFamilyInfo,
+    /// 0 for column families not known to this version
+    pub estimated_size_bytes: u64,
+    pub first_key: Option<String>,
+    pub last_key: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct FullOverviewResponse {
+    pub column_families: Vec<CfOverview>,
+    pub total: usize,
+}
+
 #[derive(Serialize)]
 pub struct DbEntry {
     pub key: String,
+    /// Shown as hex and cut to the preview length when truncated is set,
+    /// the full value is available from the single entry endpoint
     pub value: String,
+    /// Size of the full value in bytes
     pub size: usize,
+    pub truncated: bool,
+    /// Hash of the full value, as in the single entry endpoint's ETag
+    pub value_hash: String,
 }
 
 #[derive(Serialize)]
@@ -65,6 +120,67 @@ pub struct DbListResponse {
     pub page_size: u32,
     pub total_entries: u64,
     pub has_more: bool,
+    pub links: PageLinks,
+    pub sort: ListSort,
+    /// True when the scan budget ran out before the page was filled
+    pub truncated: bool,
+    /// Opaque cursor for the next page of a key sorted listing, pass it
+    /// back as cursor
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+    /// True when an iterator error ended the scan early, entries read
+    /// before it are still returned
+    pub partial: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SortField {
+    Key,
+    Size,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SortDirection {
+    Asc,
+    Desc,
+}
+
+/// The sort applied to a listing
+#[derive(Serialize)]
+pub struct ListSort {
+    pub sort: SortField,
+    pub dir: SortDirection,
+    /// Size sorting only reorders the entries within the returned page,
+    /// pages themselves are still taken in key order
+    pub page_local: bool,
+}
+
+/// Relative URLs to other pages of a listing, None past the boundaries
+#[derive(Serialize)]
+pub struct PageLinks {
+    pub first: String,
+    pub prev: Option<String>,
+    pub next: Option<String>,
+    /// None when the number of pages is unknown, i.e. while searching
+    pub last: Option<String>,
+}
+
+#[derive(Serialize, Clone, Copy)]
+struct PageLinkQuery<'a> {
+    page: u32,
+    page_size: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    search: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sort: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dir: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    preview_bytes: Option<usize>,
 }
 
 #[derive(Serialize)]
@@ -74,6 +190,8 @@ pub struct DbGetResponse {
     pub value: Option<String>,
     pub found: bool,
     pub size: Option<usize>,
+    /// Hash of the value, also sent as the ETag
+    pub value_hash: Option<String>,
 }
 
 // ============================================================================
@@ -162,6 +280,16 @@ fn get_cf_info(cf: ColumnFamily) -> ColumnFamilyInfo {
             "OutPoint",
             "Txid",
         ),
+        ColumnFamily::BitcoinBlockToShare => (
+            "Bitcoin block hash to share mapping",
+            "BlockHash (bitcoin)",
+            "BlockHash (share)",
+        ),
+        ColumnFamily::MinerShares => (
+            "Shares by miner and time",
+            "CompressedPublicKey + u32 (time, big endian) + BlockHash",
+            "Empty",
+        ),
     };
 
     ColumnFamilyInfo {
@@ -173,127 +301,470 @@ fn get_cf_info(cf: ColumnFamily) -> ColumnFamilyInfo {
     }
 }
 
+/// Info for a column family found in the DB, with a generic description
+/// when this version has no ColumnFamily variant for it
+fn get_named_cf_info(name: &str) -> ColumnFamilyInfo {
+    match ColumnFamily::ALL.into_iter().find(|cf| cf.as_str() == name) {
+        Some(cf) => get_cf_info(cf),
+        None => ColumnFamilyInfo {
+            name: name.to_string(),
+            description: "Column family not known to this version".to_string(),
+            estimated_entries: 0,
+            key_format: "bytes".to_string(),
+            value_format: "bytes".to_string(),
+        },
+    }
+}
+
 // ============================================================================
 // API Handlers
 // ============================================================================
 
-/// List all column families with their info
+/// List the column families present in the DB with their info
 pub async fn list_column_families(
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<ColumnFamiliesResponse>, ApiError> {
-    let all_cfs = vec![
-        ColumnFamily::Block,
-        ColumnFamily::BlockTxids,
-        ColumnFamily::TxidsBlocks,
-        ColumnFamily::Uncles,
-        ColumnFamily::BitcoinTxids,
-        ColumnFamily::Inputs,
-        ColumnFamily::Outputs,
-        ColumnFamily::Tx,
-        ColumnFamily::BlockIndex,
-        ColumnFamily::BlockHeight,
-        ColumnFamily::Share,
-        ColumnFamily::Job,
-        ColumnFamily::User,
-        ColumnFamily::UserIndex,
-        ColumnFamily::Metadata,
-        ColumnFamily::SpendsIndex,
-    ];
+    let cf_infos = column_family_infos(&state)?;
+    Ok(Json(ColumnFamiliesResponse {
+        total: cf_infos.len(),
+        column_families: cf_infos,
+    }))
+}
+
+/// Every column family's info with its estimated size and first and last
+/// keys, so the browser dashboard loads with one request instead of one
+/// per column family. Counts and sizes come from RocksDB properties and
+/// the keys from a seek to each end, so nothing is scanned.
+pub async fn get_full_overview(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<FullOverviewResponse>, ApiError> {
+    let mut overviews = Vec::new();
+    for info in column_family_infos(&state)? {
+        let cf = ColumnFamily::ALL
+            .into_iter()
+            .find(|cf| cf.as_str() == info.name);
+        let overview = match cf {
+            Some(cf) => {
+                let boundary_key = |last: bool| {
+                    state
+                        .chain_store_handle
+                        .get_cf_boundary_entry(cf, last)
+                        .map(|entry| entry.map(|(key, _)| format_key(cf, &key)))
+                        .map_err(ApiError::ServerError)
+                };
+                CfOverview {
+                    estimated_size_bytes: state
+                        .chain_store_handle
+                        .get_cf_size_estimate(cf)
+                        .unwrap_or(0),
+                    first_key: boundary_key(false)?,
+                    last_key: boundary_key(true)?,
+                    info,
+                }
+            }
+            None => CfOverview {
+                info,
+                estimated_size_bytes: 0,
+                first_key: None,
+                last_key: None,
+            },
+        };
+        overviews.push(overview);
+    }
+
+    Ok(Json(FullOverviewResponse {
+        total: overviews.len(),
+        column_families: overviews,
+    }))
+}
+
+/// Info for the column families present in the DB with their estimated
+/// entry counts. Known column families come first in ColumnFamily::ALL
+/// order, followed by any unknown ones in DB order.
+fn column_family_infos(state: &AppState) -> Result<Vec<ColumnFamilyInfo>, ApiError> {
+    let mut names = state
+        .chain_store_handle
+        .list_column_family_names()
+        .map_err(ApiError::ServerError)?;
+    names.retain(|name| state.app_config.db_cf_access.allows(name));
+    names.sort_by_key(|name| {
+        ColumnFamily::ALL
+            .iter()
+            .position(|cf| cf.as_str() == name)
+            .unwrap_or(ColumnFamily::ALL.len())
+    });
 
     let mut cf_infos = Vec::new();
-    for cf in all_cfs {
-        let mut info = get_cf_info(cf);
-        
+    for name in names {
+        let mut info = get_named_cf_info(&name);
+
         // Get estimated entry count from RocksDB
-        if let Ok(count) = state.chain_store_handle.get_cf_entry_count(cf) {
+        if let Ok(count) = state.chain_store_handle.get_named_cf_entry_count(&name) {
             info.estimated_entries = count;
         }
-        
+
         cf_infos.push(info);
     }
-
-    Ok(Json(ColumnFamiliesResponse {
-        total: cf_infos.len(),
-        column_families: cf_infos,
-    }))
+    Ok(cf_infos)
 }
 
 /// List entries in a specific column family with pagination
 pub async fn list_cf_entries(
     State(state): State<Arc<AppState>>,
+    OriginalUri(uri): OriginalUri,
     Path(cf_name): Path<String>,
     Query(params): Query<ListQuery>,
 ) -> Result<Json<DbListResponse>, ApiError> {
     // Parse column family name
-    let cf = parse_column_family(&cf_name)?;
-    
+    let cf = parse_column_family(&state, &cf_name)?;
+    let sort = parse_list_sort(params.sort.as_deref(), params.dir.as_deref())?;
+    let start_after = match params.cursor.as_deref() {
+        Some(_) if sort.sort != SortField::Key => {
+            return Err(ApiError::BadRequest(
+                "cursor can only be used with sort=key".into(),
+            ));
+        }
+        Some(cursor) => Some(decode_cursor(cursor, cf)?),
+        None => None,
+    };
+
     let page = params.page.unwrap_or(1).max(1);
-    let page_size = params.page_size.unwrap_or(50).min(100).max(1);
-    let skip = ((page - 1) * page_size) as usize;
+    let page_size = state.app_config.page_size.resolve(params.page_size);
+    let preview_bytes = params
+        .preview_bytes
+        .map(|bytes| bytes.min(MAX_PREVIEW_BYTES));
+    let skip = if start_after.is_some() {
+        0
+    } else {
+        ((page - 1) * page_size) as usize
+    };
 
-    // Get entries from the store
-    let (entries, total) = state
+    // Key order comes from the iterator, descending keys iterate from the end
+    let reverse = sort.sort == SortField::Key && sort.dir == SortDirection::Desc;
+    let listing = state
         .chain_store_handle
-        .list_cf_entries(cf, skip, page_size as usize, params.search.as_deref())
+        .list_cf_entries(
+            cf,
+            &ListOptions {
+                start_after: start_after.as_deref(),
+                skip,
+                limit: page_size as usize,
+                search: params.search.as_deref(),
+                reverse,
+                max_scan: state.app_config.max_scan_items as usize,
+            },
+        )
         .map_err(|e| ApiError::ServerError(e.to_string()))?;
+    if let Some(e) = &listing.error {
+        tracing::warn!("Partial listing of column family {}: {}", cf.as_str(), e);
+    }
+    let mut entries = listing.entries;
+    let total = listing.total_count;
+
+    if sort.sort == SortField::Size {
+        // Stable sort, so equal sizes stay in key order
+        match sort.dir {
+            SortDirection::Asc => entries.sort_by_key(|(_, value)| value.len()),
+            SortDirection::Desc => entries.sort_by_key(|(_, value)| std::cmp::Reverse(value.len())),
+        }
+    }
+
+    let has_more = (skip + entries.len()) < total as usize;
+    // Size sorted pages are reordered, so only key order can resume by seek
+    let next_cursor = if has_more && sort.sort == SortField::Key {
+        entries.last().map(|(key, _)| encode_cursor(cf, key))
+    } else {
+        None
+    };
 
     let db_entries: Vec<DbEntry> = entries
         .into_iter()
-        .map(|(key, value)| {
-            let size = value.len();
-            DbEntry {
-                key: format_key(&key),
-                value: format_value(&value),
-                size,
-            }
-        })
+        .map(|(key, value)| list_entry(cf, &key, &value, preview_bytes))
         .collect();
 
-    let has_more = (skip + db_entries.len()) < total as usize;
+    // The listing stops counting once the page is filled, so use the
+    // estimated key count for the last page. Matches can't be estimated.
+    let last_page = if !has_more {
+        Some(page)
+    } else if params.search.is_some() {
+        None
+    } else {
+        let estimated = state.chain_store_handle.get_cf_entry_count(cf).unwrap_or(0);
+        let estimated_pages = estimated.div_ceil(page_size as u64);
+        let estimated_pages = u32::try_from(estimated_pages).unwrap_or(u32::MAX);
+        Some(estimated_pages.max(page + 1))
+    };
+    let links = page_links(
+        uri.path(),
+        PageLinkQuery {
+            page,
+            page_size,
+            search: params.search.as_deref(),
+            sort: params.sort.as_deref(),
+            dir: params.dir.as_deref(),
+            preview_bytes,
+        },
+        has_more,
+        last_page,
+    );
 
     Ok(Json(DbListResponse {
-        column_family: cf_name,
+        column_family: cf.into(),
         entries: db_entries,
         page,
         page_size,
         total_entries: total,
         has_more,
+        links,
+        sort,
+        truncated: listing.truncated,
+        next_cursor,
+        partial: listing.error.is_some(),
+        error: listing.error,
     }))
 }
 
-/// Get a specific entry by key from a column family
+/// Get a specific entry by key from a column family. Found entries are
+/// sent with the value hash as ETag, and If-None-Match with that hash
+/// gets a 304 Not Modified, so clients can cache large values.
 pub async fn get_cf_entry(
     State(state): State<Arc<AppState>>,
     Path((cf_name, key)): Path<(String, String)>,
-) -> Result<Json<DbGetResponse>, ApiError> {
+    Query(params): Query<EntryQuery>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
     // Parse column family name
-    let cf = parse_column_family(&cf_name)?;
+    let cf = parse_column_family(&state, &cf_name)?;
+    let key_encoding = parse_key_encoding(params.key_encoding.as_deref(), cf)?;
+    let key_bytes = decode_key(&key, key_encoding).map_err(ApiError::BadRequest)?;
     
     // Get the entry
     let result = state
         .chain_store_handle
-        .get_cf_entry(cf, &key)
+        .get_cf_entry(cf, &key_bytes)
         .map_err(|e| ApiError::ServerError(e.to_string()))?;
 
-    match result {
-        Some(value) => {
-            let size = value.len();
-            Ok(Json(DbGetResponse {
-                column_family: cf_name,
-                key: key.clone(),
-                value: Some(format_value(&value)),
-                found: true,
-                size: Some(size),
-            }))
+    let response = entry_response(cf.into(), key, result);
+    match response.value_hash.clone() {
+        Some(value_hash) => {
+            let etag = format!("\"{value_hash}\"");
+            Ok(etag_response(&headers, etag, response))
         }
-        None => Ok(Json(DbGetResponse {
-            column_family: cf_name,
-            key,
-            value: None,
-            found: false,
-            size: None,
-        })),
+        None => Ok(Json(response).into_response()),
+    }
+}
+
+/// Get the exact stored bytes of an entry as application/octet-stream,
+/// without the display formatting get_cf_entry applies. Missing keys are
+/// a 404 with the usual JSON error body.
+pub async fn get_cf_entry_raw(
+    State(state): State<Arc<AppState>>,
+    Path((cf_name, key)): Path<(String, String)>,
+    Query(params): Query<EntryQuery>,
+) -> Result<Response, ApiError> {
+    let cf = parse_column_family(&state, &cf_name)?;
+    let key_encoding = parse_key_encoding(params.key_encoding.as_deref(), cf)?;
+    let key_bytes = decode_key(&key, key_encoding).map_err(ApiError::BadRequest)?;
+
+    let value = state
+        .chain_store_handle
+        .get_cf_entry(cf, &key_bytes)
+        .map_err(|e| ApiError::ServerError(e.to_string()))?
+        .ok_or_else(|| ApiError::NotFound(format!("Key {key} not found in {cf_name}")))?;
+    Ok(([(header::CONTENT_TYPE, "application/octet-stream")], value).into_response())
+}
+
+/// Delete an entry from a column family. Refused with 403 before the
+/// store is touched when the API is configured read only, the default.
+pub async fn delete_cf_entry(
+    State(state): State<Arc<AppState>>,
+    Path((cf_name, key)): Path<(String, String)>,
+    Query(params): Query<EntryQuery>,
+) -> Result<StatusCode, ApiError> {
+    ensure_writable(&state)?;
+    let cf = parse_column_family(&state, &cf_name)?;
+    let key_encoding = parse_key_encoding(params.key_encoding.as_deref(), cf)?;
+    let key_bytes = decode_key(&key, key_encoding).map_err(ApiError::BadRequest)?;
+
+    state
+        .chain_store_handle
+        .delete_cf_entry(cf, &key_bytes)
+        .map_err(ApiError::ServerError)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Deserialize)]
+pub struct DeletePrefixQuery {
+    /// One of hex, utf8 or auto. Defaults per column family.
+    pub key_encoding: Option<String>,
+    /// Must be true to delete with an empty prefix, i.e. every entry
+    pub confirm: Option<bool>,
+}
+
+#[derive(Serialize)]
+pub struct DeletePrefixResponse {
+    pub cf: String,
+    pub prefix: String,
+    pub deleted: u64,
+}
+
+/// Delete every entry of a column family whose key starts with a prefix.
+/// Refused with 403 when the API is configured read only.
+pub async fn delete_cf_prefix(
+    State(state): State<Arc<AppState>>,
+    Path((cf_name, prefix)): Path<(String, String)>,
+    Query(params): Query<DeletePrefixQuery>,
+) -> Result<Json<DeletePrefixResponse>, ApiError> {
+    delete_prefix(&state, &cf_name, prefix, params)
+}
+
+/// Delete every entry of a column family, the empty prefix. Requires
+/// confirm=true on top of the API not being read only.
+pub async fn delete_cf_all(
+    State(state): State<Arc<AppState>>,
+    Path(cf_name): Path<String>,
+    Query(params): Query<DeletePrefixQuery>,
+) -> Result<Json<DeletePrefixResponse>, ApiError> {
+    delete_prefix(&state, &cf_name, String::new(), params)
+}
+
+fn delete_prefix(
+    state: &AppState,
+    cf_name: &str,
+    prefix: String,
+    params: DeletePrefixQuery,
+) -> Result<Json<DeletePrefixResponse>, ApiError> {
+    ensure_writable(state)?;
+    let cf = parse_column_family(state, cf_name)?;
+    let key_encoding = parse_key_encoding(params.key_encoding.as_deref(), cf)?;
+    let prefix_bytes = decode_key(&prefix, key_encoding).map_err(ApiError::BadRequest)?;
+    if prefix_bytes.is_empty() && !params.confirm.unwrap_or(false) {
+        return Err(ApiError::BadRequest(
+            "An empty prefix deletes every entry, pass confirm=true to proceed".to_string(),
+        ));
+    }
+
+    let deleted = state
+        .chain_store_handle
+        .delete_cf_prefix(cf, &prefix_bytes)
+        .map_err(ApiError::ServerError)?;
+    Ok(Json(DeletePrefixResponse {
+        cf: cf.into(),
+        prefix,
+        deleted,
+    }))
+}
+
+/// Reject requests that would modify a read only store
+fn ensure_writable(state: &AppState) -> Result<(), ApiError> {
+    if state.app_config.db_read_only {
+        return Err(ApiError::Forbidden(
+            "Database is read only, set db_read_only = false to allow changes".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Get the first entry of a column family in key order
+pub async fn get_cf_first_entry(
+    State(state): State<Arc<AppState>>,
+    Path(cf_name): Path<String>,
+) -> Result<Json<DbEntry>, ApiError> {
+    cf_boundary_entry(&state, &cf_name, false)
+}
+
+/// Get the last entry of a column family in key order
+pub async fn get_cf_last_entry(
+    State(state): State<Arc<AppState>>,
+    Path(cf_name): Path<String>,
+) -> Result<Json<DbEntry>, ApiError> {
+    cf_boundary_entry(&state, &cf_name, true)
+}
+
+fn cf_boundary_entry(
+    state: &AppState,
+    cf_name: &str,
+    last: bool,
+) -> Result<Json<DbEntry>, ApiError> {
+    let cf = parse_column_family(state, cf_name)?;
+    let (key, value) = state
+        .chain_store_handle
+        .get_cf_boundary_entry(cf, last)
+        .map_err(ApiError::ServerError)?
+        .ok_or_else(|| ApiError::NotFound(format!("Column family {} is empty", cf.as_str())))?;
+    Ok(Json(list_entry(cf, &key, &value, None)))
+}
+
+/// Values larger than this many bytes are cut when listed as hex
+pub const MAX_LIST_VALUE_BYTES: usize = 512;
+
+/// Largest preview_bytes accepted by listings, larger requests are capped
+pub const MAX_PREVIEW_BYTES: usize = 64 * 1024;
+
+/// Maximum number of keys accepted by a single batch_get request
+const MAX_BATCH_GET_KEYS: usize = 1000;
+
+/// Get multiple entries by key from a column family in one request.
+/// Results are in the same order as the requested keys.
+pub async fn batch_get_cf_entries(
+    State(state): State<Arc<AppState>>,
+    Path(cf_name): Path<String>,
+    Json(request): Json<BatchGetRequest>,
+) -> Result<Json<Vec<DbGetResponse>>, ApiError> {
+    let cf = parse_column_family(&state, &cf_name)?;
+    if request.keys.len() > MAX_BATCH_GET_KEYS {
+        return Err(ApiError::BadRequest(format!(
+            "Too many keys: {} requested, at most {} allowed",
+            request.keys.len(),
+            MAX_BATCH_GET_KEYS
+        )));
+    }
+    let key_encoding = parse_key_encoding(request.key_encoding.as_deref(), cf)?;
+    let key_bytes = request
+        .keys
+        .iter()
+        .map(|key| decode_key(key, key_encoding))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(ApiError::BadRequest)?;
+
+    let values = state
+        .chain_store_handle
+        .multi_get_cf_entries(cf, &key_bytes)
+        .map_err(ApiError::ServerError)?;
+
+    Ok(Json(
+        request
+            .keys
+            .into_iter()
+            .zip(values)
+            .map(|(key, value)| entry_response(cf.into(), key, value))
+            .collect(),
+    ))
+}
+
+/// RocksDB properties for each column family, keyed by column family name
+#[derive(Serialize)]
+pub struct DbPropertiesResponse {
+    pub column_families: BTreeMap<String, BTreeMap<String, Option<u64>>>,
+}
+
+/// Get live RocksDB properties, like running compactions and pending
+/// flushes, for the column families db_cf_access allows
+pub async fn get_db_properties(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<DbPropertiesResponse>, ApiError> {
+    let mut column_families = BTreeMap::new();
+    for cf in ColumnFamily::ALL {
+        if !state.app_config.db_cf_access.allows(cf.as_str()) {
+            continue;
+        }
+        let properties = state
+            .chain_store_handle
+            .get_cf_properties(cf)
+            .map_err(ApiError::ServerError)?;
+        column_families.insert(cf.as_str().to_string(), properties);
     }
+    Ok(Json(DbPropertiesResponse { column_families }))
 }
 
 /// Get statistics for a specific column family
@@ -302,14 +773,43 @@ pub struct CfStatsResponse {
     pub column_family: String,
     pub total_entries: u64,
     pub estimated_size_bytes: u64,
+    /// Estimated live data size divided by the estimated entry count
+    pub avg_bytes_per_entry: u64,
+    /// Largest value size seen in the first CF_STATS_SAMPLE_LIMIT entries
+    pub largest_value_bytes: u64,
+    pub sampled_entries: u64,
+    pub first_key: Option<String>,
+    pub last_key: Option<String>,
     pub description: String,
+    /// Estimated live data size divided by the total SST files size,
+    /// None when there are no SST files yet
+    pub live_to_sst_ratio: Option<f64>,
+    /// Set when live_to_sst_ratio is below COMPACTION_LIVE_RATIO, i.e.
+    /// deletes and overwrites have left space a compaction could reclaim
+    pub needs_compaction: bool,
+}
+
+/// Maximum number of entries scanned when sampling value sizes for stats
+const CF_STATS_SAMPLE_LIMIT: usize = 1000;
+
+/// Live to SST size ratio below which a column family needs compaction
+const COMPACTION_LIVE_RATIO: f64 = 0.5;
+
+/// Ratio of live data to on disk SST size, and whether it is low enough
+/// to suggest a compaction
+fn compaction_hint(live_bytes: u64, sst_bytes: u64) -> (Option<f64>, bool) {
+    if sst_bytes == 0 {
+        return (None, false);
+    }
+    let ratio = live_bytes as f64 / sst_bytes as f64;
+    (Some(ratio), ratio < COMPACTION_LIVE_RATIO)
 }
 
 pub async fn get_cf_stats(
     State(state): State<Arc<AppState>>,
     Path(cf_name): Path<String>,
 ) -> Result<Json<CfStatsResponse>, ApiError> {
-    let cf = parse_column_family(&cf_name)?;
+    let cf = parse_column_family(&state, &cf_name)?;
     let info = get_cf_info(cf);
     
     let total_entries = state
@@ -322,41 +822,280 @@ pub async fn get_cf_stats(
         .get_cf_size_estimate(cf)
         .unwrap_or(0);
 
+    let avg_bytes_per_entry = estimated_size.checked_div(total_entries).unwrap_or(0);
+
+    let sample = state
+        .chain_store_handle
+        .sample_cf_stats(cf, CF_STATS_SAMPLE_LIMIT)
+        .map_err(ApiError::ServerError)?;
+
+    let sst_files_size = state
+        .chain_store_handle
+        .get_cf_sst_files_size(cf)
+        .map_err(ApiError::ServerError)?;
+    let (live_to_sst_ratio, needs_compaction) = compaction_hint(estimated_size, sst_files_size);
+
     Ok(Json(CfStatsResponse {
-        column_family: cf_name,
+        column_family: cf.into(),
         total_entries,
         estimated_size_bytes: estimated_size,
+        avg_bytes_per_entry,
+        largest_value_bytes: sample.largest_value_bytes,
+        sampled_entries: sample.sampled_entries,
+        first_key: sample.first_key.as_deref().map(|key| format_key(cf, key)),
+        last_key: sample.last_key.as_deref().map(|key| format_key(cf, key)),
         description: info.description,
+        live_to_sst_ratio,
+        needs_compaction,
     }))
 }
 
+/// Lines buffered between the export reader and the response body
+const EXPORT_CHANNEL_CAPACITY: usize = 64;
+
+/// Bytes an export line adds around the key and value text: the field
+/// names, quotes, size, truncated flag and newline
+const EXPORT_LINE_OVERHEAD_BYTES: u64 = 60;
+
+/// Stream every entry of a column family as newline delimited JSON, one
+/// DbEntry per line in key order.
+///
+/// The column family is read on a blocking thread and handed to the body
+/// through a bounded channel, so memory stays bounded for large exports.
+pub async fn export_cf_entries(
+    State(state): State<Arc<AppState>>,
+    Path(cf_name): Path<String>,
+) -> Result<Response, ApiError> {
+    let cf = parse_column_family(&state, &cf_name)?;
+
+    let (tx, rx) = tokio::sync::mpsc::channel::<Vec<u8>>(EXPORT_CHANNEL_CAPACITY);
+    let chain_store_handle = state.chain_store_handle.clone();
+    tokio::task::spawn_blocking(move || {
+        let result = chain_store_handle.for_each_cf_entry(cf, |key, value| {
+            let entry = list_entry(cf, key, value, None);
+            match ndjson_line(&entry) {
+                // Stop reading once the client has gone away
+                Some(line) => tx.blocking_send(line).is_ok(),
+                None => true,
+            }
+        });
+        if let Err(e) = result {
+            tracing::error!("Failed to export column family {}: {}", cf.as_str(), e);
+        }
+    });
+
+    let lines = futures::stream::unfold(rx, |mut rx| async move {
+        rx.recv()
+            .await
+            .map(|line| (Ok::<_, std::convert::Infallible>(line), rx))
+    });
+    Ok((
+        [(header::CONTENT_TYPE, "application/x-ndjson")],
+        Body::from_stream(lines),
+    )
+        .into_response())
+}
+
+/// HEAD for the export, with an estimated Content-Length and no body.
+///
+/// The length is derived from RocksDB's estimated key count and live data
+/// size, assuming keys and values are rendered as hex. It can be off in
+/// either direction: UTF-8 values are not hex encoded and large values are
+/// truncated, so use it for progress display only.
+pub async fn export_cf_entries_head(
+    State(state): State<Arc<AppState>>,
+    Path(cf_name): Path<String>,
+) -> Result<Response, ApiError> {
+    let cf = parse_column_family(&state, &cf_name)?;
+
+    let total_entries = state
+        .chain_store_handle
+        .get_cf_entry_count(cf)
+        .map_err(ApiError::ServerError)?;
+    let live_bytes = state
+        .chain_store_handle
+        .get_cf_size_estimate(cf)
+        .unwrap_or(0);
+    let estimate = live_bytes
+        .saturating_mul(2)
+        .saturating_add(total_entries.saturating_mul(EXPORT_LINE_OVERHEAD_BYTES));
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/x-ndjson".to_string()),
+            (header::CONTENT_LENGTH, estimate.to_string()),
+        ],
+        Body::empty(),
+    )
+        .into_response())
+}
+
 // ============================================================================
 // Helper Functions
 // ============================================================================
 
-fn parse_column_family(name: &str) -> Result<ColumnFamily, ApiError> {
+/// Which column families the DB viewer may browse, from the
+/// db_allowed_cfs and db_denied_cfs config. All are allowed by default.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CfAccess {
+    /// Only these column families are browsable, all when None
+    allowed: Option<Vec<ColumnFamily>>,
+    /// Never browsable, even when also allowed
+    denied: Vec<ColumnFamily>,
+}
+
+impl CfAccess {
+    /// Build from column family names, failing on names this version
+    /// doesn't know, so a typo can't leave a column family exposed
+    pub fn from_config(
+        allowed: Option<&[String]>,
+        denied: Option<&[String]>,
+    ) -> Result<Self, String> {
+        let parse = |names: &[String]| {
+            names
+                .iter()
+                .map(|name| {
+                    ColumnFamily::from_name(name)
+                        .ok_or_else(|| format!("Unknown column family {name} in DB access config"))
+                })
+                .collect::<Result<Vec<_>, _>>()
+        };
+        Ok(Self {
+            allowed: allowed.map(parse).transpose()?,
+            denied: denied.map(parse).transpose()?.unwrap_or_default(),
+        })
+    }
+
+    /// True when the column family named name may be browsed. Column
+    /// families unknown to this version are only shown when no allowlist
+    /// is set.
+    pub fn allows(&self, name: &str) -> bool {
+        match ColumnFamily::from_name(name) {
+            Some(cf) => {
+                !self.denied.contains(&cf)
+                    && self
+                        .allowed
+                        .as_ref()
+                        .is_none_or(|allowed| allowed.contains(&cf))
+            }
+            None => self.allowed.is_none(),
+        }
+    }
+}
+
+/// Resolve a column family name from a request path, refusing column
+/// families the config doesn't allow with 403 before the DB is read
+fn parse_column_family(state: &AppState, name: &str) -> Result<ColumnFamily, ApiError> {
+    let cf = ColumnFamily::from_name(name)
+        .ok_or_else(|| ApiError::NotFound(format!("Unknown column family: {}", name)))?;
+    if !state.app_config.db_cf_access.allows(name) {
+        return Err(ApiError::Forbidden(format!(
+            "Column family {} is not browsable",
+            name
+        )));
+    }
+    Ok(cf)
+}
+
+fn parse_key_encoding(name: Option<&str>, cf: ColumnFamily) -> Result<KeyEncoding, ApiError> {
     match name {
-        "block" => Ok(ColumnFamily::Block),
-        "block_txids" => Ok(ColumnFamily::BlockTxids),
-        "txids_blocks" => Ok(ColumnFamily::TxidsBlocks),
-        "uncles" => Ok(ColumnFamily::Uncles),
-        "bitcoin_txids" => Ok(ColumnFamily::BitcoinTxids),
-        "inputs" => Ok(ColumnFamily::Inputs),
-        "outputs" => Ok(ColumnFamily::Outputs),
-        "tx" => Ok(ColumnFamily::Tx),
-        "block_index" => Ok(ColumnFamily::BlockIndex),
-        "block_height" => Ok(ColumnFamily::BlockHeight),
-        "share" => Ok(ColumnFamily::Share),
-        "job" => Ok(ColumnFamily::Job),
-        "user" => Ok(ColumnFamily::User),
-        "user_index" => Ok(ColumnFamily::UserIndex),
-        "metadata" => Ok(ColumnFamily::Metadata),
-        "spends_index" => Ok(ColumnFamily::SpendsIndex),
-        _ => Err(ApiError::NotFound(format!("Unknown column family: {}", name))),
-    }
-}
-
-fn format_key(key: &[u8]) -> String {
+        None => Ok(KeyEncoding::default_for(cf)),
+        Some("hex") => Ok(KeyEncoding::Hex),
+        Some("utf8") => Ok(KeyEncoding::Utf8),
+        Some("auto") => Ok(KeyEncoding::Auto),
+        Some("height") => Ok(KeyEncoding::Height),
+        Some(other) => Err(ApiError::BadRequest(format!(
+            "Unknown key_encoding: {}, expected hex, utf8, auto or height",
+            other
+        ))),
+    }
+}
+
+/// Build first/prev/next/last links for a listing at path, keeping the
+/// rest of the current query, i.e. page size, search term and sort.
+fn page_links(
+    path: &str,
+    current: PageLinkQuery,
+    has_more: bool,
+    last_page: Option<u32>,
+) -> PageLinks {
+    let link = |page: u32| {
+        let query =
+            serde_urlencoded::to_string(PageLinkQuery { page, ..current }).unwrap_or_default();
+        format!("{path}?{query}")
+    };
+    PageLinks {
+        first: link(1),
+        prev: (current.page > 1).then(|| link(current.page - 1)),
+        next: has_more.then(|| link(current.page + 1)),
+        last: last_page.map(link),
+    }
+}
+
+fn parse_list_sort(sort: Option<&str>, dir: Option<&str>) -> Result<ListSort, ApiError> {
+    let sort = match sort {
+        None | Some("key") => SortField::Key,
+        Some("size") => SortField::Size,
+        Some(other) => {
+            return Err(ApiError::BadRequest(format!(
+                "Unknown sort: {}, expected key or size",
+                other
+            )));
+        }
+    };
+    let dir = match dir {
+        None | Some("asc") => SortDirection::Asc,
+        Some("desc") => SortDirection::Desc,
+        Some(other) => {
+            return Err(ApiError::BadRequest(format!(
+                "Unknown dir: {}, expected asc or desc",
+                other
+            )));
+        }
+    };
+    Ok(ListSort {
+        sort,
+        dir,
+        page_local: sort == SortField::Size,
+    })
+}
+
+fn entry_response(cf_name: String, key: String, value: Option<Vec<u8>>) -> DbGetResponse {
+    match value {
+        Some(value) => DbGetResponse {
+            column_family: cf_name,
+            key,
+            value: Some(format_value(&value)),
+            found: true,
+            size: Some(value.len()),
+            value_hash: Some(value_hash(&value)),
+        },
+        None => DbGetResponse {
+            column_family: cf_name,
+            key,
+            value: None,
+            found: false,
+            size: None,
+            value_hash: None,
+        },
+    }
+}
+
+/// Short hash of a value for cache validation, the first 8 bytes of its
+/// SHA-256 as hex
+fn value_hash(value: &[u8]) -> String {
+    hex::encode(&Sha256::digest(value)[..8])
+}
+
+fn format_key(cf: ColumnFamily, key: &[u8]) -> String {
+    // Heights are big-endian integers, show them in decimal
+    let height = match cf {
+        ColumnFamily::BlockHeight => format_height_key(key),
+        _ => None,
+    };
+    if let Some(height) = height {
+        return height;
+    }
     // Try to decode as UTF-8 first
     if let Ok(s) = std::str::from_utf8(key) {
         s.to_string()
@@ -366,6 +1105,7 @@ fn format_key(key: &[u8]) -> String {
     }
 }
 
+/// Full display form of a value, used by the single entry lookups
 fn format_value(value: &[u8]) -> String {
     // For small values, try UTF-8, otherwise show hex
     if value.len() < 1024 {
@@ -373,11 +1113,1031 @@ fn format_value(value: &[u8]) -> String {
             return s.to_string();
         }
     }
-    
-    // For binary data or large values, show hex with truncation
-    if value.len() > 512 {
-        format!("{}... ({} bytes)", hex::encode(&value[..512]), value.len())
-    } else {
-        hex::encode(value)
+    hex::encode(value)
+}
+
+/// Display form of a value in listings, returns whether the value was cut.
+/// Values longer than preview_bytes are cut to it and shown as hex.
+/// Without preview_bytes, UTF-8 values under 1024 bytes are shown whole
+/// and others are cut to MAX_LIST_VALUE_BYTES.
+fn format_list_value(value: &[u8], preview_bytes: Option<usize>) -> (String, bool) {
+    let preview_bytes = match preview_bytes {
+        Some(preview_bytes) => preview_bytes,
+        None if value.len() < 1024 && std::str::from_utf8(value).is_ok() => value.len(),
+        None => MAX_LIST_VALUE_BYTES,
+    };
+    if value.len() > preview_bytes {
+        return (hex::encode(&value[..preview_bytes]), true);
+    }
+    match std::str::from_utf8(value) {
+        Ok(s) => (s.to_string(), false),
+        Err(_) => (hex::encode(value), false),
+    }
+}
+
+/// Build the listing form of an entry
+fn list_entry(cf: ColumnFamily, key: &[u8], value: &[u8], preview_bytes: Option<usize>) -> DbEntry {
+    let (formatted, truncated) = format_list_value(value, preview_bytes);
+    DbEntry {
+        key: format_key(cf, key),
+        value: formatted,
+        size: value.len(),
+        truncated,
+        value_hash: value_hash(value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::server::AppConfig;
+    use crate::api::server::tests::{test_app_state, test_metrics_handle};
+    use bitcoin::hashes::Hash;
+    use bitcoin::{Amount, TxOut};
+    use p2poolv2_lib::shares::share_block::ShareTransaction;
+    use p2poolv2_lib::test_utils::{
+        TestShareBlockBuilder, genesis_for_tests, setup_test_chain_store_handle,
+    };
+
+    #[test_log::test(tokio::test)]
+    async fn test_db_list_entries_page_links() {
+        let (metrics_handle, _metrics_dir) = test_metrics_handle().await;
+        let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
+
+        let genesis = genesis_for_tests();
+        chain_store_handle
+            .init_or_setup_genesis(genesis.clone())
+            .await
+            .unwrap();
+        let share1 = TestShareBlockBuilder::new()
+            .prev_share_blockhash(genesis.block_hash().to_string())
+            .work(1)
+            .build();
+        chain_store_handle.add_share(&share1, true).await.unwrap();
+        let share2 = TestShareBlockBuilder::new()
+            .prev_share_blockhash(share1.block_hash().to_string())
+            .work(1)
+            .build();
+        chain_store_handle.add_share(&share2, true).await.unwrap();
+
+        let state = Arc::new(test_app_state(chain_store_handle, metrics_handle));
+
+        let list = |page: u32, page_size: u32, search: Option<&str>| {
+            let state = state.clone();
+            let search = search.map(str::to_string);
+            async move {
+                list_cf_entries(
+                    State(state),
+                    axum::extract::OriginalUri("/db/cf/block/entries".parse().unwrap()),
+                    Path("block".to_string()),
+                    Query(ListQuery {
+                        page: Some(page),
+                        page_size: Some(page_size),
+                        search,
+                        sort: None,
+                        dir: None,
+                        cursor: None,
+                        preview_bytes: None,
+                    }),
+                )
+                .await
+                .unwrap()
+            }
+        };
+
+        let total = list(1, 100, None).await.entries.len() as u32;
+        assert!(total >= 3);
+
+        let first = list(1, 1, None).await;
+        assert_eq!(first.entries.len(), 1);
+        assert!(first.has_more);
+        let links = &first.links;
+        assert_eq!(links.first, "/db/cf/block/entries?page=1&page_size=1");
+        assert_eq!(links.prev, None);
+        assert_eq!(
+            links.next.as_deref(),
+            Some("/db/cf/block/entries?page=2&page_size=1")
+        );
+        // Last page comes from the estimated key count
+        assert!(links.last.is_some());
+
+        let last = list(total, 1, None).await;
+        assert!(!last.has_more);
+        assert_eq!(
+            last.links.prev,
+            Some(format!(
+                "/db/cf/block/entries?page={}&page_size=1",
+                total - 1
+            ))
+        );
+        assert_eq!(last.links.next, None);
+        assert_eq!(
+            last.links.last,
+            Some(format!("/db/cf/block/entries?page={total}&page_size=1"))
+        );
+
+        // Search terms are kept and encoded
+        let searching = list(1, 1, Some("a b&c")).await;
+        assert_eq!(
+            searching.links.first,
+            "/db/cf/block/entries?page=1&page_size=1&search=a+b%26c"
+        );
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_db_list_entries_cursor_paging() {
+        let (metrics_handle, _metrics_dir) = test_metrics_handle().await;
+        let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
+
+        let genesis = genesis_for_tests();
+        chain_store_handle
+            .init_or_setup_genesis(genesis.clone())
+            .await
+            .unwrap();
+        let share1 = TestShareBlockBuilder::new()
+            .prev_share_blockhash(genesis.block_hash().to_string())
+            .work(1)
+            .build();
+        chain_store_handle.add_share(&share1, true).await.unwrap();
+        let share2 = TestShareBlockBuilder::new()
+            .prev_share_blockhash(share1.block_hash().to_string())
+            .work(1)
+            .build();
+        chain_store_handle.add_share(&share2, true).await.unwrap();
+
+        let state = Arc::new(test_app_state(chain_store_handle, metrics_handle));
+        let list = |cf: &str, page_size: u32, cursor: Option<String>| {
+            list_cf_entries(
+                State(state.clone()),
+                axum::extract::OriginalUri("/db/cf/block/entries".parse().unwrap()),
+                Path(cf.to_string()),
+                Query(ListQuery {
+                    page: None,
+                    page_size: Some(page_size),
+                    search: None,
+                    sort: None,
+                    dir: None,
+                    cursor,
+                    preview_bytes: None,
+                }),
+            )
+        };
+        let keys = |listing: &DbListResponse| -> Vec<String> {
+            listing
+                .entries
+                .iter()
+                .map(|entry| entry.key.clone())
+                .collect()
+        };
+
+        let all = list("block", 100, None).await.unwrap();
+        assert!(all.entries.len() >= 3);
+        assert!(all.next_cursor.is_none());
+
+        // Following cursors one entry at a time visits every key once
+        let mut walked = Vec::new();
+        let mut cursor = None;
+        loop {
+            let page = list("block", 1, cursor).await.unwrap();
+            walked.extend(keys(&page));
+            cursor = page.next_cursor;
+            if cursor.is_none() {
+                assert!(!page.has_more);
+                break;
+            }
+        }
+        assert_eq!(walked, keys(&all));
+
+        // A cursor from one column family is rejected for another
+        let first = list("block", 1, None).await.unwrap();
+        let result = list("metadata", 1, first.next_cursor).await;
+        assert!(matches!(result, Err(ApiError::BadRequest(_))));
+
+        let result = list("block", 1, Some("forged".to_string())).await;
+        assert!(matches!(result, Err(ApiError::BadRequest(_))));
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_db_cf_stats_compaction_hint() {
+        let (metrics_handle, _metrics_dir) = test_metrics_handle().await;
+        let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
+
+        let genesis = genesis_for_tests();
+        chain_store_handle
+            .init_or_setup_genesis(genesis.clone())
+            .await
+            .unwrap();
+
+        let state = Arc::new(test_app_state(chain_store_handle.clone(), metrics_handle));
+
+        // Nothing is flushed yet, so there are no SST files to compare with
+        let stats = get_cf_stats(State(state.clone()), Path("block".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(stats.live_to_sst_ratio, None);
+        assert!(!stats.needs_compaction);
+
+        let db = chain_store_handle.store_handle().store().get_db();
+        db.flush_cf(&db.cf_handle("block").unwrap()).unwrap();
+
+        let stats = get_cf_stats(State(state), Path("block".to_string()))
+            .await
+            .unwrap();
+        let ratio = stats.live_to_sst_ratio.unwrap();
+        assert!(ratio > 0.0);
+        assert_eq!(stats.needs_compaction, ratio < 0.5);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_db_first_and_last_entries() {
+        let (metrics_handle, _metrics_dir) = test_metrics_handle().await;
+        let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
+
+        let genesis = genesis_for_tests();
+        chain_store_handle
+            .init_or_setup_genesis(genesis.clone())
+            .await
+            .unwrap();
+        let share = TestShareBlockBuilder::new()
+            .prev_share_blockhash(genesis.block_hash().to_string())
+            .build();
+        chain_store_handle.add_share(&share, true).await.unwrap();
+
+        let state = Arc::new(test_app_state(chain_store_handle, metrics_handle));
+
+        let listing = |dir: &str| {
+            list_cf_entries(
+                State(state.clone()),
+                axum::extract::OriginalUri("/db/cf/block/entries".parse().unwrap()),
+                Path("block".to_string()),
+                Query(ListQuery {
+                    page: Some(1),
+                    page_size: Some(1),
+                    search: None,
+                    sort: Some("key".to_string()),
+                    dir: Some(dir.to_string()),
+                    cursor: None,
+                    preview_bytes: None,
+                }),
+            )
+        };
+        let first_listed = listing("asc").await.unwrap();
+        let last_listed = listing("desc").await.unwrap();
+
+        let first = get_cf_first_entry(State(state.clone()), Path("block".to_string()))
+            .await
+            .unwrap();
+        let last = get_cf_last_entry(State(state.clone()), Path("block".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(first.key, first_listed.entries[0].key);
+        assert_eq!(first.value, first_listed.entries[0].value);
+        assert_eq!(last.key, last_listed.entries[0].key);
+        assert_ne!(first.key, last.key);
+
+        let empty =
+            get_cf_first_entry(State(state.clone()), Path("uncles".to_string())).await;
+        assert!(matches!(empty, Err(ApiError::NotFound(_))));
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_db_export_and_head() {
+        let (metrics_handle, _metrics_dir) = test_metrics_handle().await;
+        let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
+
+        let genesis = genesis_for_tests();
+        chain_store_handle
+            .init_or_setup_genesis(genesis.clone())
+            .await
+            .unwrap();
+        let share = TestShareBlockBuilder::new()
+            .prev_share_blockhash(genesis.block_hash().to_string())
+            .build();
+        chain_store_handle.add_share(&share, true).await.unwrap();
+
+        let state = Arc::new(test_app_state(chain_store_handle, metrics_handle));
+
+        let listing = list_cf_entries(
+            State(state.clone()),
+            axum::extract::OriginalUri("/db/cf/block/entries".parse().unwrap()),
+            Path("block".to_string()),
+            Query(ListQuery {
+                page: Some(1),
+                page_size: Some(100),
+                search: None,
+                sort: None,
+                dir: None,
+                cursor: None,
+                preview_bytes: None,
+            }),
+        )
+        .await
+        .unwrap();
+
+        let response =
+            export_cf_entries(State(state.clone()), Path("block".to_string()))
+                .await
+                .unwrap();
+        assert_eq!(
+            response.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/x-ndjson"
+        );
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let keys: Vec<String> = body
+            .split(|b| *b == b'\n')
+            .filter(|line| !line.is_empty())
+            .map(|line| {
+                let entry: serde_json::Value = serde_json::from_slice(line).unwrap();
+                entry["key"].as_str().unwrap().to_string()
+            })
+            .collect();
+        let listed_keys: Vec<String> = listing.entries.iter().map(|e| e.key.clone()).collect();
+        assert_eq!(keys, listed_keys);
+
+        let response = export_cf_entries_head(State(state), Path("block".to_string()))
+            .await
+            .unwrap();
+        let content_length: u64 = response
+            .headers()
+            .get(header::CONTENT_LENGTH)
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!(content_length > 0);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert!(body.is_empty());
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_db_list_entries_sorted() {
+        let (metrics_handle, _metrics_dir) = test_metrics_handle().await;
+        let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
+
+        let genesis = genesis_for_tests();
+        chain_store_handle
+            .init_or_setup_genesis(genesis.clone())
+            .await
+            .unwrap();
+        let share1 = TestShareBlockBuilder::new()
+            .prev_share_blockhash(genesis.block_hash().to_string())
+            .work(1)
+            .build();
+        chain_store_handle.add_share(&share1, true).await.unwrap();
+
+        let state = Arc::new(test_app_state(chain_store_handle, metrics_handle));
+
+        let list = |sort: Option<&str>, dir: Option<&str>| {
+            let state = state.clone();
+            let sort = sort.map(str::to_string);
+            let dir = dir.map(str::to_string);
+            async move {
+                list_cf_entries(
+                    State(state),
+                    axum::extract::OriginalUri("/db/cf/block/entries".parse().unwrap()),
+                    Path("block".to_string()),
+                    Query(ListQuery {
+                        page: Some(1),
+                        page_size: Some(100),
+                        search: None,
+                        sort,
+                        dir,
+                        cursor: None,
+                        preview_bytes: None,
+                    }),
+                )
+                .await
+            }
+        };
+
+        let ascending = list(None, None).await.unwrap();
+        assert_eq!(ascending.sort.sort, SortField::Key);
+        assert_eq!(ascending.sort.dir, SortDirection::Asc);
+        assert!(!ascending.sort.page_local);
+        let mut keys: Vec<String> = ascending.entries.iter().map(|e| e.key.clone()).collect();
+        assert!(keys.len() >= 2);
+
+        let descending = list(Some("key"), Some("desc")).await.unwrap();
+        keys.reverse();
+        let descending_keys: Vec<String> =
+            descending.entries.iter().map(|e| e.key.clone()).collect();
+        assert_eq!(descending_keys, keys);
+        assert_eq!(
+            descending.links.first,
+            "/db/cf/block/entries?page=1&page_size=100&sort=key&dir=desc"
+        );
+
+        let by_size = list(Some("size"), Some("desc")).await.unwrap();
+        assert!(by_size.sort.page_local);
+        assert_eq!(by_size.entries.len(), ascending.entries.len());
+        assert!(by_size.entries.windows(2).all(|w| w[0].size >= w[1].size));
+
+        assert!(matches!(
+            list(Some("value"), None).await,
+            Err(ApiError::BadRequest(_))
+        ));
+        assert!(matches!(
+            list(None, Some("up")).await,
+            Err(ApiError::BadRequest(_))
+        ));
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_db_list_entries_flags_truncated_values() {
+        let (metrics_handle, _metrics_dir) = test_metrics_handle().await;
+        let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
+
+        let genesis = genesis_for_tests();
+        chain_store_handle
+            .init_or_setup_genesis(genesis.clone())
+            .await
+            .unwrap();
+        // A transaction well over the listing cut off
+        let large_tx = bitcoin::Transaction {
+            version: bitcoin::transaction::Version::TWO,
+            lock_time: bitcoin::absolute::LockTime::ZERO,
+            input: vec![],
+            output: vec![TxOut {
+                value: Amount::ZERO,
+                script_pubkey: bitcoin::ScriptBuf::from_bytes(vec![0x6a; 700]),
+            }],
+        };
+        let mut share = TestShareBlockBuilder::new()
+            .prev_share_blockhash(genesis.block_hash().to_string())
+            .build();
+        share.transactions = vec![ShareTransaction(large_tx.clone())];
+        chain_store_handle.add_share(&share, true).await.unwrap();
+
+        let state = Arc::new(test_app_state(chain_store_handle, metrics_handle));
+
+        let listing = list_cf_entries(
+            State(state.clone()),
+            axum::extract::OriginalUri("/db/cf/outputs/entries".parse().unwrap()),
+            Path("outputs".to_string()),
+            Query(ListQuery {
+                page: Some(1),
+                page_size: Some(10),
+                search: None,
+                sort: None,
+                dir: None,
+                cursor: None,
+                preview_bytes: None,
+            }),
+        )
+        .await
+        .unwrap();
+        let entry = listing
+            .entries
+            .iter()
+            .find(|entry| entry.size > MAX_LIST_VALUE_BYTES)
+            .unwrap();
+        assert!(entry.truncated);
+        assert_eq!(entry.value.len(), 2 * MAX_LIST_VALUE_BYTES);
+        assert!(hex::decode(&entry.value).is_ok());
+
+        // The single entry lookup returns the whole value
+        let Json(entries) = batch_get_cf_entries(
+            State(state),
+            Path("outputs".to_string()),
+            Json(BatchGetRequest {
+                keys: vec![entry.key.clone()],
+                key_encoding: Some("utf8".to_string()),
+            }),
+        )
+        .await
+        .unwrap();
+        let full = entries[0].value.as_ref().unwrap();
+        assert_eq!(full.len(), 2 * entry.size);
+        assert!(full.starts_with(&entry.value));
+
+        let list_with_preview = |preview_bytes| {
+            list_cf_entries(
+                State(state.clone()),
+                axum::extract::OriginalUri("/db/cf/outputs/entries".parse().unwrap()),
+                Path("outputs".to_string()),
+                Query(ListQuery {
+                    page: Some(1),
+                    page_size: Some(10),
+                    search: None,
+                    sort: None,
+                    dir: None,
+                    cursor: None,
+                    preview_bytes: Some(preview_bytes),
+                }),
+            )
+        };
+        let find = |listing: &DbListResponse| {
+            listing
+                .entries
+                .iter()
+                .find(|listed| listed.key == entry.key)
+                .map(|listed| (listed.value.clone(), listed.truncated))
+                .unwrap()
+        };
+
+        // A shorter preview cuts the value further
+        let listing = list_with_preview(16).await.unwrap();
+        assert_eq!(find(&listing), (full[..32].to_string(), true));
+        // The preview length is kept in the page links
+        assert!(listing.links.first.contains("preview_bytes=16"));
+
+        // A preview covering the whole value shows it uncut
+        let listing = list_with_preview(entry.size).await.unwrap();
+        assert_eq!(find(&listing), (full.clone(), false));
+
+        // Larger requests are capped
+        let listing = list_with_preview(usize::MAX).await.unwrap();
+        assert_eq!(find(&listing), (full.clone(), false));
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_db_batch_get_entries() {
+        let (metrics_handle, _metrics_dir) = test_metrics_handle().await;
+        let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
+
+        let genesis = genesis_for_tests();
+        chain_store_handle
+            .init_or_setup_genesis(genesis.clone())
+            .await
+            .unwrap();
+        let share1 = TestShareBlockBuilder::new()
+            .prev_share_blockhash(genesis.block_hash().to_string())
+            .work(1)
+            .build();
+        chain_store_handle.add_share(&share1, true).await.unwrap();
+
+        let state = Arc::new(test_app_state(chain_store_handle, metrics_handle));
+
+        // Block CF keys are the raw block hash bytes
+        let share1_key = hex::encode(share1.block_hash().as_byte_array());
+        let missing_key = hex::encode([0u8; 32]);
+        let genesis_key = hex::encode(genesis.block_hash().as_byte_array());
+
+        let batch_get = |keys: Vec<String>, key_encoding: Option<&str>| {
+            batch_get_cf_entries(
+                State(state.clone()),
+                Path("block".to_string()),
+                Json(BatchGetRequest {
+                    keys,
+                    key_encoding: key_encoding.map(str::to_string),
+                }),
+            )
+        };
+
+        let Json(entries) = batch_get(
+            vec![share1_key.clone(), missing_key.clone(), genesis_key.clone()],
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].key, share1_key);
+        assert!(entries[0].found);
+        assert_eq!(entries[1].key, missing_key);
+        assert!(!entries[1].found);
+        assert_eq!(entries[1].value, None);
+        assert_eq!(entries[2].key, genesis_key);
+        assert!(entries[2].found);
+
+        let too_many = batch_get(vec![missing_key; 1001], None).await;
+        assert!(matches!(too_many, Err(ApiError::BadRequest(_))));
+
+        let invalid_hex = batch_get(vec!["not hex".to_string()], Some("hex")).await;
+        assert!(matches!(invalid_hex, Err(ApiError::BadRequest(_))));
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_db_delete_entry_refused_when_read_only() {
+        let (metrics_handle, _metrics_dir) = test_metrics_handle().await;
+        let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
+
+        let genesis = genesis_for_tests();
+        chain_store_handle
+            .init_or_setup_genesis(genesis.clone())
+            .await
+            .unwrap();
+        let share1 = TestShareBlockBuilder::new()
+            .prev_share_blockhash(genesis.block_hash().to_string())
+            .work(1)
+            .build();
+        chain_store_handle.add_share(&share1, true).await.unwrap();
+
+        let state_with_read_only = |db_read_only: bool| {
+            Arc::new(AppState {
+                app_config: AppConfig {
+                    db_read_only,
+                    ..AppConfig::default()
+                },
+                ..test_app_state(chain_store_handle.clone(), metrics_handle.clone())
+            })
+        };
+        let share1_key = hex::encode(share1.block_hash().as_byte_array());
+        let delete = |state: Arc<AppState>| {
+            delete_cf_entry(
+                State(state),
+                Path(("block".to_string(), share1_key.clone())),
+                Query(EntryQuery { key_encoding: None }),
+            )
+        };
+        let stored = || {
+            chain_store_handle
+                .get_cf_entry(ColumnFamily::Block, share1.block_hash().as_byte_array())
+                .unwrap()
+        };
+
+        // Refused before the store is touched
+        let refused = delete(state_with_read_only(true)).await;
+        assert!(matches!(refused, Err(ApiError::Forbidden(_))));
+        assert_eq!(
+            refused.unwrap_err().into_response().status(),
+            StatusCode::FORBIDDEN
+        );
+        assert!(stored().is_some());
+
+        let deleted = delete(state_with_read_only(false)).await.unwrap();
+        assert_eq!(deleted, StatusCode::NO_CONTENT);
+        assert!(stored().is_none());
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_db_get_entry_raw() {
+        let (metrics_handle, _metrics_dir) = test_metrics_handle().await;
+        let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
+
+        let genesis = genesis_for_tests();
+        chain_store_handle
+            .init_or_setup_genesis(genesis.clone())
+            .await
+            .unwrap();
+
+        let state = Arc::new(test_app_state(chain_store_handle.clone(), metrics_handle));
+        let genesis_key = hex::encode(genesis.block_hash().as_byte_array());
+        let raw = |key: String, key_encoding: Option<&str>| {
+            get_cf_entry_raw(
+                State(state.clone()),
+                Path(("block".to_string(), key)),
+                Query(EntryQuery {
+                    key_encoding: key_encoding.map(String::from),
+                }),
+            )
+        };
+
+        // The body is the stored value byte for byte
+        let response = raw(genesis_key.clone(), None).await.unwrap();
+        assert_eq!(
+            response.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/octet-stream"
+        );
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let stored = chain_store_handle
+            .get_cf_entry(ColumnFamily::Block, genesis.block_hash().as_byte_array())
+            .unwrap()
+            .unwrap();
+        assert_eq!(body.to_vec(), stored);
+
+        let missing = raw(hex::encode([0u8; 32]), Some("hex")).await;
+        let response = missing.unwrap_err().into_response();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert!(json["error"].is_string());
+
+        assert!(matches!(
+            raw("not-hex".to_string(), Some("hex")).await,
+            Err(ApiError::BadRequest(_))
+        ));
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_db_get_entry_not_modified_for_value_hash() {
+        let (metrics_handle, _metrics_dir) = test_metrics_handle().await;
+        let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
+
+        let genesis = genesis_for_tests();
+        chain_store_handle
+            .init_or_setup_genesis(genesis.clone())
+            .await
+            .unwrap();
+
+        let state = Arc::new(test_app_state(chain_store_handle, metrics_handle));
+        let genesis_key = hex::encode(genesis.block_hash().as_byte_array());
+        let entry = |if_none_match: Option<String>| {
+            let mut headers = HeaderMap::new();
+            if let Some(etag) = if_none_match {
+                headers.insert(header::IF_NONE_MATCH, etag.parse().unwrap());
+            }
+            get_cf_entry(
+                State(state.clone()),
+                Path(("block".to_string(), genesis_key.clone())),
+                Query(EntryQuery { key_encoding: None }),
+                headers,
+            )
+        };
+
+        let response = entry(None).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let etag = response
+            .headers()
+            .get(header::ETAG)
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_string();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let value_hash = json["value_hash"].as_str().unwrap();
+        assert_eq!(value_hash.len(), 16);
+        assert_eq!(etag, format!("\"{value_hash}\""));
+
+        // Re-requesting with the returned hash is a 304
+        let response = entry(Some(etag.clone())).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(response.headers().get(header::ETAG).unwrap(), &etag);
+
+        let response = entry(Some("\"0000000000000000\"".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_db_delete_prefix() {
+        let (metrics_handle, _metrics_dir) = test_metrics_handle().await;
+        let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
+
+        let genesis = genesis_for_tests();
+        chain_store_handle
+            .init_or_setup_genesis(genesis.clone())
+            .await
+            .unwrap();
+        let share1 = TestShareBlockBuilder::new()
+            .prev_share_blockhash(genesis.block_hash().to_string())
+            .work(1)
+            .build();
+        chain_store_handle.add_share(&share1, true).await.unwrap();
+
+        let state_with_read_only = |db_read_only: bool| {
+            Arc::new(AppState {
+                app_config: AppConfig {
+                    db_read_only,
+                    ..AppConfig::default()
+                },
+                ..test_app_state(chain_store_handle.clone(), metrics_handle.clone())
+            })
+        };
+        let query = |confirm| {
+            Query(DeletePrefixQuery {
+                key_encoding: None,
+                confirm,
+            })
+        };
+        let share1_key = hex::encode(share1.block_hash().as_byte_array());
+        let genesis_key = hex::encode(genesis.block_hash().as_byte_array());
+        let stored = |key: &str| {
+            chain_store_handle
+                .get_cf_entry(ColumnFamily::Block, &hex::decode(key).unwrap())
+                .unwrap()
+        };
+
+        let refused = delete_cf_prefix(
+            State(state_with_read_only(true)),
+            Path(("block".to_string(), share1_key.clone())),
+            query(None),
+        )
+        .await;
+        assert!(matches!(refused, Err(ApiError::Forbidden(_))));
+
+        // The whole column family needs an explicit confirm
+        let unconfirmed = delete_cf_all(
+            State(state_with_read_only(false)),
+            Path("block".to_string()),
+            query(None),
+        )
+        .await;
+        assert!(matches!(unconfirmed, Err(ApiError::BadRequest(_))));
+        assert!(stored(&genesis_key).is_some());
+
+        let Json(response) = delete_cf_prefix(
+            State(state_with_read_only(false)),
+            Path(("block".to_string(), share1_key.clone())),
+            query(None),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.cf, "block");
+        assert_eq!(response.deleted, 1);
+        assert!(stored(&share1_key).is_none());
+        assert!(stored(&genesis_key).is_some());
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_list_column_families_from_db() {
+        let (metrics_handle, _metrics_dir) = test_metrics_handle().await;
+        let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
+
+        let state = Arc::new(test_app_state(chain_store_handle, metrics_handle));
+
+        let Json(response) = list_column_families(State(state)).await.unwrap();
+        assert_eq!(response.total, ColumnFamily::ALL.len());
+        let names: Vec<&str> = response
+            .column_families
+            .iter()
+            .map(|info| info.name.as_str())
+            .collect();
+        let expected: Vec<&str> = ColumnFamily::ALL.iter().map(|cf| cf.as_str()).collect();
+        assert_eq!(names, expected);
+        assert_eq!(
+            response.column_families[0].description,
+            "Share blocks in the P2Pool chain"
+        );
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_db_full_overview() {
+        let (metrics_handle, _metrics_dir) = test_metrics_handle().await;
+        let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
+
+        let genesis = genesis_for_tests();
+        chain_store_handle
+            .init_or_setup_genesis(genesis.clone())
+            .await
+            .unwrap();
+        let share1 = TestShareBlockBuilder::new()
+            .prev_share_blockhash(genesis.block_hash().to_string())
+            .nonce(1)
+            .build();
+        chain_store_handle.add_share(&share1, true).await.unwrap();
+
+        let state = Arc::new(test_app_state(chain_store_handle, metrics_handle));
+
+        let Json(overview) = get_full_overview(State(state.clone()))
+            .await
+            .unwrap();
+        let Json(listing) = list_column_families(State(state.clone()))
+            .await
+            .unwrap();
+        assert_eq!(overview.total, listing.total);
+        let names: Vec<&str> = overview
+            .column_families
+            .iter()
+            .map(|cf| cf.info.name.as_str())
+            .collect();
+        let expected: Vec<&str> = listing
+            .column_families
+            .iter()
+            .map(|info| info.name.as_str())
+            .collect();
+        assert_eq!(names, expected);
+
+        // Key range matches the first and last entry endpoints
+        let block = &overview.column_families[0];
+        assert_eq!(block.info.name, "block");
+        let Json(first) =
+            get_cf_first_entry(State(state.clone()), Path("block".to_string()))
+                .await
+                .unwrap();
+        let Json(last) =
+            get_cf_last_entry(State(state.clone()), Path("block".to_string()))
+                .await
+                .unwrap();
+        assert_eq!(block.first_key, Some(first.key));
+        assert_eq!(block.last_key, Some(last.key));
+
+        // The info fields are flattened next to the size and keys
+        let json = serde_json::to_value(&overview).unwrap();
+        assert_eq!(json["column_families"][0]["name"], "block");
+        assert!(json["column_families"][0]["estimated_size_bytes"].is_u64());
+        assert!(json["column_families"][0].get("info").is_none());
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_db_denied_column_family_forbidden() {
+        let (metrics_handle, _metrics_dir) = test_metrics_handle().await;
+        let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
+
+        let genesis = genesis_for_tests();
+        chain_store_handle
+            .init_or_setup_genesis(genesis.clone())
+            .await
+            .unwrap();
+
+        let state = |db_cf_access| {
+            Arc::new(AppState {
+                app_config: AppConfig {
+                    db_cf_access,
+                    ..AppConfig::default()
+                },
+                ..test_app_state(chain_store_handle.clone(), metrics_handle.clone())
+            })
+        };
+        let names = |names: &[&str]| {
+            names
+                .iter()
+                .map(|name| name.to_string())
+                .collect::<Vec<_>>()
+        };
+        let listing = |state: Arc<AppState>, cf: &str| {
+            list_cf_entries(
+                State(state),
+                axum::extract::OriginalUri(format!("/db/cf/{cf}/entries").parse().unwrap()),
+                Path(cf.to_string()),
+                Query(ListQuery {
+                    page: None,
+                    page_size: None,
+                    search: None,
+                    sort: None,
+                    dir: None,
+                    cursor: None,
+                    preview_bytes: None,
+                }),
+            )
+        };
+
+        let denied =
+            state(CfAccess::from_config(None, Some(&names(&["user", "user_index"]))).unwrap());
+        // Aliases of a denied column family are refused too
+        for cf in ["user", "users", "user_index"] {
+            assert!(matches!(
+                listing(denied.clone(), cf).await,
+                Err(ApiError::Forbidden(_))
+            ));
+        }
+        assert!(listing(denied.clone(), "block").await.is_ok());
+        let Json(families) = list_column_families(State(denied.clone()))
+            .await
+            .unwrap();
+        assert!(
+            families
+                .column_families
+                .iter()
+                .all(|info| info.name != "user" && info.name != "user_index")
+        );
+        assert_eq!(families.total, ColumnFamily::ALL.len() - 2);
+        let Json(properties) = get_db_properties(State(denied.clone()))
+            .await
+            .unwrap();
+        assert!(!properties.column_families.contains_key("user"));
+        assert!(!properties.column_families.contains_key("user_index"));
+        assert_eq!(
+            properties.column_families.len(),
+            ColumnFamily::ALL.len() - 2
+        );
+
+        let allowed =
+            state(CfAccess::from_config(Some(&names(&["block", "share"])), None).unwrap());
+        assert!(matches!(
+            listing(allowed.clone(), "metadata").await,
+            Err(ApiError::Forbidden(_))
+        ));
+        assert!(matches!(
+            get_cf_first_entry(State(allowed.clone()), Path("user".to_string())).await,
+            Err(ApiError::Forbidden(_))
+        ));
+        let Json(families) = list_column_families(State(allowed.clone()))
+            .await
+            .unwrap();
+        let listed: Vec<&str> = families
+            .column_families
+            .iter()
+            .map(|info| info.name.as_str())
+            .collect();
+        assert_eq!(listed, vec!["block", "share"]);
+        let Json(properties) = get_db_properties(State(allowed)).await.unwrap();
+        let listed: Vec<&str> = properties
+            .column_families
+            .keys()
+            .map(String::as_str)
+            .collect();
+        assert_eq!(listed, vec!["block", "share"]);
+
+        // Unknown names are a config error, not silently ignored
+        assert!(CfAccess::from_config(None, Some(&names(&["usr"]))).is_err());
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_db_properties_for_all_column_families() {
+        let (metrics_handle, _metrics_dir) = test_metrics_handle().await;
+        let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
+
+        let state = Arc::new(test_app_state(chain_store_handle, metrics_handle));
+
+        let Json(response) = get_db_properties(State(state)).await.unwrap();
+        assert_eq!(response.column_families.len(), ColumnFamily::ALL.len());
+        let block = &response.column_families["block"];
+        assert!(block.contains_key("rocksdb.num-running-compactions"));
+        assert!(block.contains_key("rocksdb.mem-table-flush-pending"));
+        assert!(block.contains_key("rocksdb.estimate-num-keys"));
+        assert!(block.contains_key("rocksdb.total-sst-files-size"));
     }
 }
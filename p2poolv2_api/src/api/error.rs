@@ -27,6 +27,9 @@ use std::fmt;
 pub enum ApiError {
     ServerError(String),
     NotFound(String),
+    BadRequest(String),
+    Forbidden(String),
+    ServiceUnavailable(String),
 }
 
 impl fmt::Display for ApiError {
@@ -34,6 +37,9 @@ impl fmt::Display for ApiError {
         match self {
             ApiError::ServerError(msg) => write!(f, "axum server error: {msg}"),
             ApiError::NotFound(msg) => write!(f, "not found: {msg}"),
+            ApiError::BadRequest(msg) => write!(f, "bad request: {msg}"),
+            ApiError::Forbidden(msg) => write!(f, "forbidden: {msg}"),
+            ApiError::ServiceUnavailable(msg) => write!(f, "service unavailable: {msg}"),
         }
     }
 }
@@ -51,6 +57,18 @@ impl IntoResponse for ApiError {
                 let body = Json(json!({ "error": msg }));
                 (StatusCode::NOT_FOUND, body).into_response()
             }
+            ApiError::BadRequest(msg) => {
+                let body = Json(json!({ "error": msg }));
+                (StatusCode::BAD_REQUEST, body).into_response()
+            }
+            ApiError::Forbidden(msg) => {
+                let body = Json(json!({ "error": msg }));
+                (StatusCode::FORBIDDEN, body).into_response()
+            }
+            ApiError::ServiceUnavailable(msg) => {
+                let body = Json(json!({ "error": msg }));
+                (StatusCode::SERVICE_UNAVAILABLE, body).into_response()
+            }
         }
     }
 }
@@ -0,0 +1,120 @@
+// Copyright (C) 2024, 2025 P2Poolv2 Developers (see AUTHORS)
+//
+// This file is part of P2Poolv2
+//
+// P2Poolv2 is free software: you can redistribute it and/or modify it under
+// the terms of the GNU General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// P2Poolv2 is distributed in the hope that it will be useful, but WITHOUT ANY
+// WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along with
+// P2Poolv2. If not, see <https://www.gnu.org/licenses/>.
+
+//! Write periodic snapshots of the /metrics exposition to disk, for a
+//! local metrics history where no scraper is running.
+
+use crate::api::server::{AppState, metrics_exposition};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::time::Duration;
+use tracing::warn;
+
+const SNAPSHOT_PREFIX: &str = "metrics-";
+const SNAPSHOT_SUFFIX: &str = ".prom";
+
+/// Write a metrics snapshot to dir every interval, keeping the newest
+/// keep files. Runs until aborted by the server on shutdown.
+pub(crate) async fn export_metrics(
+    state: Arc<AppState>,
+    dir: PathBuf,
+    interval: Duration,
+    keep: usize,
+) {
+    let mut interval = tokio::time::interval(interval);
+    loop {
+        interval.tick().await;
+        let exposition = metrics_exposition(&state).await;
+        let now_millis = chrono::Utc::now().timestamp_millis().max(0) as u64;
+        if let Err(e) = write_snapshot(&dir, &exposition, now_millis, keep) {
+            warn!("Failed to write metrics snapshot to {}: {e}", dir.display());
+        }
+    }
+}
+
+/// Write the exposition to a new snapshot file and remove the oldest
+/// snapshots beyond keep. The file is renamed into place so readers
+/// never see a partial snapshot.
+fn write_snapshot(
+    dir: &Path,
+    exposition: &str,
+    now_millis: u64,
+    keep: usize,
+) -> std::io::Result<()> {
+    // Zero padded, so file names sort in time order
+    let name = format!("{SNAPSHOT_PREFIX}{now_millis:020}{SNAPSHOT_SUFFIX}");
+    let tmp_path = dir.join(format!("{name}.tmp"));
+    fs::write(&tmp_path, exposition)?;
+    fs::rename(&tmp_path, dir.join(name))?;
+
+    let mut snapshots: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| {
+                    name.starts_with(SNAPSHOT_PREFIX) && name.ends_with(SNAPSHOT_SUFFIX)
+                })
+        })
+        .collect();
+    snapshots.sort();
+    let excess = snapshots.len().saturating_sub(keep);
+    for path in &snapshots[..excess] {
+        fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_snapshot_keeps_newest() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        fs::write(temp_dir.path().join("other.txt"), "not a snapshot").unwrap();
+
+        for now_millis in [1000, 3000, 2000, 4000] {
+            write_snapshot(
+                temp_dir.path(),
+                &format!("shares_accepted_total {now_millis}\n"),
+                now_millis,
+                2,
+            )
+            .unwrap();
+        }
+
+        let mut names: Vec<String> = fs::read_dir(temp_dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        names.sort();
+        assert_eq!(
+            names,
+            vec![
+                "metrics-00000000000000003000.prom",
+                "metrics-00000000000000004000.prom",
+                "other.txt",
+            ]
+        );
+        assert_eq!(
+            fs::read_to_string(temp_dir.path().join("metrics-00000000000000004000.prom")).unwrap(),
+            "shares_accepted_total 4000\n"
+        );
+    }
+}
@@ -15,6 +15,16 @@
 // P2Poolv2. If not, see <https://www.gnu.org/licenses/>.
 
 mod auth;
+mod bigint;
+mod case;
+mod cursor;
 pub mod db_viewer;
 pub mod error;
+mod metrics_export;
+mod msgpack;
+mod pretty;
+mod request_id;
+mod retry;
+#[cfg(feature = "schema")]
+mod schema;
 pub mod server;
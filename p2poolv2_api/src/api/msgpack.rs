@@ -0,0 +1,195 @@
+// Copyright (C) 2024, 2025 P2Poolv2 Developers (see AUTHORS)
+//
+// This file is part of P2Poolv2
+//
+// P2Poolv2 is free software: you can redistribute it and/or modify it under
+// the terms of the GNU General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// P2Poolv2 is distributed in the hope that it will be useful, but WITHOUT ANY
+// WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along with
+// P2Poolv2. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::api::pretty::is_json;
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{HeaderMap, HeaderValue, header},
+    middleware::Next,
+    response::Response,
+};
+use tracing::warn;
+
+const MSGPACK_CONTENT_TYPE: &str = "application/msgpack";
+
+/// Encode JSON responses as MessagePack when the request has
+/// Accept: application/msgpack, for polling clients where parsing JSON
+/// is the bottleneck. Other requests get JSON as before. Structs are
+/// encoded as maps keyed by field name, so the body has the same shape
+/// as the JSON one and decodes into the same response structs. Responses
+/// that are not application/json, like the ndjson and SSE streams, pass
+/// through unchanged.
+pub(crate) async fn msgpack_middleware(request: Request, next: Next) -> Response {
+    let msgpack = accepts_msgpack(request.headers());
+    let mut response = next.run(request).await;
+    // Caches must not hand a MessagePack body to a JSON client
+    response
+        .headers_mut()
+        .append(header::VARY, HeaderValue::from_static("accept"));
+    if !msgpack || !is_json(&response) {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            warn!("Failed to read response body to encode as MessagePack: {e}");
+            return Response::from_parts(parts, Body::empty());
+        }
+    };
+    let msgpack_body = serde_json::from_slice::<serde_json::Value>(&bytes)
+        .map_err(|e| e.to_string())
+        .and_then(|value| rmp_serde::to_vec_named(&value).map_err(|e| e.to_string()));
+    match msgpack_body {
+        Ok(msgpack_body) => {
+            parts.headers.remove(header::CONTENT_LENGTH);
+            parts.headers.insert(
+                header::CONTENT_TYPE,
+                HeaderValue::from_static(MSGPACK_CONTENT_TYPE),
+            );
+            Response::from_parts(parts, Body::from(msgpack_body))
+        }
+        Err(e) => {
+            warn!("Failed to encode response as MessagePack: {e}");
+            Response::from_parts(parts, Body::from(bytes))
+        }
+    }
+}
+
+/// True when an Accept header lists MessagePack. Quality values are not
+/// weighed, as clients asking for MessagePack at all can decode it.
+fn accepts_msgpack(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(header::ACCEPT)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(|media_type| media_type.split(';').next())
+        .any(|media_type| {
+            let media_type = media_type.trim();
+            media_type.eq_ignore_ascii_case(MSGPACK_CONTENT_TYPE)
+                || media_type.eq_ignore_ascii_case("application/x-msgpack")
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{Json, Router, http::StatusCode, middleware, routing::get};
+    use serde::{Deserialize, Serialize};
+    use tower::ServiceExt;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Node {
+        hash: String,
+        height: u32,
+        difficulty: u64,
+        uncles: Vec<String>,
+        miner: Option<String>,
+    }
+
+    fn nodes() -> Vec<Node> {
+        vec![
+            Node {
+                hash: "aa".to_string(),
+                height: 1,
+                difficulty: (1 << 53) + 1,
+                uncles: vec!["bb".to_string()],
+                miner: None,
+            },
+            Node {
+                hash: "cc".to_string(),
+                height: 2,
+                difficulty: 7,
+                uncles: vec![],
+                miner: Some("miner".to_string()),
+            },
+        ]
+    }
+
+    fn app() -> Router {
+        Router::new()
+            .route("/", get(|| async { Json(nodes()) }))
+            .route("/text", get(|| async { "plain" }))
+            .layer(middleware::from_fn(msgpack_middleware))
+    }
+
+    async fn get_with_accept(uri: &str, accept: Option<&str>) -> Response {
+        let mut request = Request::builder().uri(uri);
+        if let Some(accept) = accept {
+            request = request.header(header::ACCEPT, accept);
+        }
+        app()
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_msgpack_decodes_into_response_struct() {
+        let response = get_with_accept("/", Some("application/msgpack")).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get(header::CONTENT_TYPE).unwrap(),
+            MSGPACK_CONTENT_TYPE
+        );
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let decoded: Vec<Node> = rmp_serde::from_slice(&bytes).unwrap();
+        assert_eq!(decoded, nodes());
+    }
+
+    #[tokio::test]
+    async fn test_json_is_default() {
+        for accept in [None, Some("application/json"), Some("*/*")] {
+            let response = get_with_accept("/", accept).await;
+            assert!(is_json(&response));
+            assert_eq!(response.headers().get(header::VARY).unwrap(), "accept");
+            let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            let decoded: Vec<Node> = serde_json::from_slice(&bytes).unwrap();
+            assert_eq!(decoded, nodes());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_msgpack_leaves_other_content_types() {
+        let response = get_with_accept("/text", Some("application/msgpack")).await;
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&bytes[..], b"plain");
+    }
+
+    #[test]
+    fn test_accepts_msgpack() {
+        let accepts = |accept: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(header::ACCEPT, accept.parse().unwrap());
+            accepts_msgpack(&headers)
+        };
+        assert!(accepts("application/msgpack"));
+        assert!(accepts("application/json;q=0.5, application/x-msgpack"));
+        assert!(accepts("Application/MsgPack; q=1"));
+        assert!(!accepts("application/json"));
+        assert!(!accepts("*/*"));
+        assert!(!accepts_msgpack(&HeaderMap::new()));
+    }
+}
@@ -0,0 +1,77 @@
+// Copyright (C) 2024, 2025 P2Poolv2 Developers (see AUTHORS)
+//
+// This file is part of P2Poolv2
+//
+// P2Poolv2 is free software: you can redistribute it and/or modify it under
+// the terms of the GNU General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// P2Poolv2 is distributed in the hope that it will be useful, but WITHOUT ANY
+// WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along with
+// P2Poolv2. If not, see <https://www.gnu.org/licenses/>.
+
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{HeaderValue, header},
+    middleware::Next,
+    response::Response,
+};
+use serde::Deserialize;
+use tracing::warn;
+
+#[derive(Deserialize)]
+struct PrettyQuery {
+    pretty: Option<bool>,
+}
+
+/// Pretty print JSON responses when the request has ?pretty=true, for
+/// reading with curl. Responses that are not application/json, like the
+/// ndjson and SSE streams, pass through unchanged. Object keys come out
+/// sorted.
+pub(crate) async fn pretty_json_middleware(request: Request, next: Next) -> Response {
+    let pretty = request
+        .uri()
+        .query()
+        .and_then(|query| serde_urlencoded::from_str::<PrettyQuery>(query).ok())
+        .and_then(|query| query.pretty)
+        .unwrap_or(false);
+    let response = next.run(request).await;
+    if !pretty || !is_json(&response) {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            warn!("Failed to read response body to pretty print: {e}");
+            return Response::from_parts(parts, Body::empty());
+        }
+    };
+    let pretty_body = serde_json::from_slice::<serde_json::Value>(&bytes)
+        .and_then(|value| serde_json::to_string_pretty(&value));
+    match pretty_body {
+        Ok(pretty_body) => {
+            parts.headers.remove(header::CONTENT_LENGTH);
+            parts.headers.insert(
+                header::CONTENT_TYPE,
+                HeaderValue::from_static("application/json"),
+            );
+            Response::from_parts(parts, Body::from(pretty_body))
+        }
+        Err(_) => Response::from_parts(parts, Body::from(bytes)),
+    }
+}
+
+pub(crate) fn is_json(response: &Response) -> bool {
+    response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .is_some_and(|content_type| content_type.starts_with("application/json"))
+}
@@ -0,0 +1,115 @@
+// Copyright (C) 2024, 2025 P2Poolv2 Developers (see AUTHORS)
+//
+// This file is part of P2Poolv2
+//
+// P2Poolv2 is free software: you can redistribute it and/or modify it under
+// the terms of the GNU General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// P2Poolv2 is distributed in the hope that it will be useful, but WITHOUT ANY
+// WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along with
+// P2Poolv2. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::api::error::ApiError;
+use std::error::Error;
+use std::time::Duration;
+use tracing::warn;
+
+/// Number of times a store read is attempted before giving up.
+const READ_ATTEMPTS: u32 = 3;
+/// Delay before the first retry, doubled on every further retry.
+const READ_BACKOFF: Duration = Duration::from_millis(20);
+
+/// RocksDB status messages for errors that clear up on their own, e.g.
+/// while a compaction or flush holds the resource. The store wraps
+/// RocksDB errors in formatted strings, so match on the message.
+const TRANSIENT_STATUSES: [&str; 4] = [
+    "Resource busy",
+    "Operation timed out",
+    "Try again",
+    "Result incomplete",
+];
+
+fn is_transient(error: &(dyn Error + Send + Sync)) -> bool {
+    let message = error.to_string();
+    TRANSIENT_STATUSES
+        .iter()
+        .any(|status| message.contains(status))
+}
+
+/// Run a store read, retrying with a short backoff on transient errors.
+///
+/// Transient errors that persist across all attempts are reported as 503
+/// so clients know to retry. Any other error is a 500 straight away.
+pub(crate) async fn retry_read<T, F>(mut read: F) -> Result<T, ApiError>
+where
+    F: FnMut() -> Result<T, Box<dyn Error + Send + Sync>>,
+{
+    let mut backoff = READ_BACKOFF;
+    let mut attempt = 1;
+    loop {
+        match read() {
+            Ok(value) => return Ok(value),
+            Err(e) if !is_transient(e.as_ref()) => {
+                return Err(ApiError::ServerError(e.to_string()));
+            }
+            Err(e) if attempt >= READ_ATTEMPTS => {
+                return Err(ApiError::ServiceUnavailable(e.to_string()));
+            }
+            Err(e) => {
+                warn!("Transient store error on attempt {attempt}, retrying: {e}");
+                tokio::time::sleep(backoff).await;
+                backoff *= 2;
+                attempt += 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_retry_read_recovers_from_transient_error() {
+        let mut calls = 0;
+        let result = retry_read(|| {
+            calls += 1;
+            if calls < READ_ATTEMPTS {
+                Err("Resource busy: compaction in progress".into())
+            } else {
+                Ok(calls)
+            }
+        })
+        .await;
+        assert_eq!(result.unwrap(), READ_ATTEMPTS);
+    }
+
+    #[tokio::test]
+    async fn test_retry_read_unavailable_after_attempts() {
+        let mut calls = 0;
+        let result: Result<(), ApiError> = retry_read(|| {
+            calls += 1;
+            Err("Error reading metadata: Operation failed. Try again.: busy".into())
+        })
+        .await;
+        assert!(matches!(result, Err(ApiError::ServiceUnavailable(_))));
+        assert_eq!(calls, READ_ATTEMPTS);
+    }
+
+    #[tokio::test]
+    async fn test_retry_read_does_not_retry_other_errors() {
+        let mut calls = 0;
+        let result: Result<(), ApiError> = retry_read(|| {
+            calls += 1;
+            Err("No metadata found for blockhash".into())
+        })
+        .await;
+        assert!(matches!(result, Err(ApiError::ServerError(_))));
+        assert_eq!(calls, 1);
+    }
+}
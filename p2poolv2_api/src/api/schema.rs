@@ -0,0 +1,63 @@
+// Copyright (C) 2024, 2025 P2Poolv2 Developers (see AUTHORS)
+//
+// This file is part of P2Poolv2
+//
+// P2Poolv2 is free software: you can redistribute it and/or modify it under
+// the terms of the GNU General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// P2Poolv2 is distributed in the hope that it will be useful, but WITHOUT ANY
+// WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along with
+// P2Poolv2. If not, see <https://www.gnu.org/licenses/>.
+
+//! JSON Schema for the main API response types, for generating client
+//! types. Only built with the schema feature.
+
+use crate::api::server::{ChainInfoResponse, DagResponse, ShareInfo};
+use axum::Json;
+use p2poolv2_lib::accounting::simple_pplns::SimplePplnsShare;
+use schemars::schema::RootSchema;
+use schemars::schema_for;
+use std::collections::BTreeMap;
+
+/// JSON Schema for each main response type, keyed by type name. The
+/// schemas are derived from the response structs themselves, so they
+/// follow any change to the structs and their serde attributes.
+pub(crate) async fn api_schema() -> Json<BTreeMap<&'static str, RootSchema>> {
+    Json(response_schemas())
+}
+
+fn response_schemas() -> BTreeMap<&'static str, RootSchema> {
+    BTreeMap::from([
+        ("ChainInfoResponse", schema_for!(ChainInfoResponse)),
+        ("DagResponse", schema_for!(DagResponse)),
+        ("ShareInfo", schema_for!(ShareInfo)),
+        ("SimplePplnsShare", schema_for!(SimplePplnsShare)),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_response_schemas_follow_serde_fields() {
+        let schemas = serde_json::to_value(response_schemas()).unwrap();
+
+        let chain_info = &schemas["ChainInfoResponse"]["properties"];
+        assert!(chain_info["tip"].is_object());
+        assert!(chain_info["ancestry_depth_limit"].is_object());
+
+        // Nested types are kept as definitions
+        assert!(schemas["DagResponse"]["definitions"]["DagNode"].is_object());
+
+        // Fields skipped by serde are left out
+        let pplns = &schemas["SimplePplnsShare"]["properties"];
+        assert!(pplns["job_id"].is_object());
+        assert!(pplns.get("btcaddress").is_none());
+    }
+}
@@ -14,30 +14,79 @@
 // You should have received a copy of the GNU General Public License along with
 // P2Poolv2. If not, see <https://www.gnu.org/licenses/>.
 
-use crate::api::auth::auth_middleware;
-use crate::api::db_viewer;
+use crate::api::auth::{auth_middleware, require_admin_scope};
+use crate::api::bigint::{
+    COINBASE_BIGINT_FIELDS, PPLNS_ESTIMATE_BIGINT_FIELDS, PPLNS_SHARES_BIGINT_FIELDS,
+    bigint_string_middleware,
+};
+use crate::api::case::camel_case_middleware;
+use crate::api::db_viewer::{self, CfAccess};
 use crate::api::error::ApiError;
+use crate::api::metrics_export;
+use crate::api::msgpack::msgpack_middleware;
+use crate::api::pretty::pretty_json_middleware;
+use crate::api::request_id::request_id_middleware;
+use crate::api::retry::retry_read;
+#[cfg(feature = "schema")]
+use crate::api::schema;
 use axum::{
     Extension, Json, Router,
-    extract::{FromRef, Path, Query, State},
+    body::Body,
+    extract::{DefaultBodyLimit, FromRef, FromRequestParts, Path, Query, State},
+    http::{HeaderMap, StatusCode, Uri, header, request::Parts},
     middleware::{self},
-    routing::get,
+    response::{
+        IntoResponse, Response,
+        sse::{Event, KeepAlive, Sse},
+    },
+    routing::{delete, get, post},
 };
 use bitcoin::hashes::Hash;
 
-use bitcoin::BlockHash;
-use chrono::DateTime;
-use p2poolv2_lib::stratum::work::tracker::{JobTracker, parse_coinbase};
+use bitcoin::{
+    Address, BlockHash, CompactTarget, CompressedPublicKey, OutPoint, Target, Transaction, Txid,
+    Work,
+};
+use chrono::{DateTime, NaiveDate, NaiveTime};
+use futures::{StreamExt, stream};
+use p2poolv2_lib::stratum::messages::Notify;
+use p2poolv2_lib::stratum::work::coinbase::{join_coinbase, parse_address};
+use p2poolv2_lib::stratum::work::tracker::{
+    JobDetails, JobId, JobTracker,
+    parse_coinbase::{self, CoinbaseRoles},
+};
 use p2poolv2_lib::{
-    accounting::{simple_pplns::SimplePplnsShare, stats::metrics::MetricsHandle},
-    config::ApiConfig,
-    shares::chain::chain_store_handle::ChainStoreHandle,
+    accounting::{
+        simple_pplns::SimplePplnsShare,
+        stats::{metrics::MetricsHandle, prom},
+    },
+    config::{ApiConfig, ApiKeyConfig, ApiScope},
+    shares::{
+        chain::chain_store_handle::{
+            ChainStoreHandle, REORG_HISTORY_CAPACITY, TIP_HISTORY_CAPACITY,
+        },
+        genesis,
+        share_block::{ShareBlock, StorageShareBlock},
+        validation,
+    },
+    store::column_families::ColumnFamily,
+    utils::{time_provider::SystemTimeProvider, work::sum_work},
 };
+use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
-use std::{net::SocketAddr, sync::Arc};
-use tokio::sync::oneshot;
-use tracing::info;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::convert::Infallible;
+use std::ops::Deref;
+use std::str::FromStr;
+use std::{
+    net::{IpAddr, SocketAddr},
+    sync::{Arc, Mutex, PoisonError, RwLock},
+};
+use tokio::sync::{Semaphore, broadcast, oneshot};
+use tokio::time::Duration;
+use tower_http::decompression::RequestDecompressionLayer;
+use tracing::{info, warn};
 
 #[derive(Clone)]
 pub struct AppState {
@@ -45,8 +94,40 @@ pub struct AppState {
     pub(crate) chain_store_handle: ChainStoreHandle,
     pub(crate) metrics_handle: MetricsHandle,
     pub(crate) tracker_handle: Arc<JobTracker>,
-    pub(crate) auth_user: Option<String>,
-    pub(crate) auth_token: Option<String>,
+    /// Keys accepted by auth_middleware. The API is open when empty.
+    pub(crate) api_keys: Vec<ApiKeyConfig>,
+    /// Publishes a TipEvent each time the chain tip changes
+    pub(crate) tip_events: TipEvents,
+    /// Latest precomputed DAG for /chain/dag/snapshot
+    pub(crate) dag_snapshot: Arc<RwLock<Option<DagSnapshot>>>,
+    /// Held while the DAG snapshot is computed, so a tip change starts
+    /// one computation rather than one per request
+    pub(crate) dag_snapshot_compute: Arc<tokio::sync::Mutex<()>>,
+    /// Limits how many expensive requests run at once
+    pub(crate) heavy_requests: HeavyRequestLimit,
+    /// Latest chain stats counted for /chain/info/extended
+    pub(crate) stats_snapshot: Arc<RwLock<Option<StatsSnapshot>>>,
+}
+
+/// Heavy requests allowed at once when the config doesn't set
+/// max_concurrent_heavy_requests
+const DEFAULT_MAX_CONCURRENT_HEAVY_REQUESTS: usize = 4;
+
+/// Permits shared by the expensive routes, such as /chain/dag and
+/// /chain/stats, so a burst of them can't starve the rest of the API
+#[derive(Clone)]
+pub struct HeavyRequestLimit(Arc<Semaphore>);
+
+impl HeavyRequestLimit {
+    pub fn new(max_concurrent: usize) -> Self {
+        Self(Arc::new(Semaphore::new(max_concurrent)))
+    }
+}
+
+impl Default for HeavyRequestLimit {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_CONCURRENT_HEAVY_REQUESTS)
+    }
 }
 
 /// Stores application config values that don't change across requests
@@ -55,6 +136,59 @@ pub struct AppState {
 pub struct AppConfig {
     pub pool_signature_length: usize,
     pub network: bitcoin::Network,
+    pub stale_after_secs: Option<u64>,
+    pub page_size: PageSizeLimits,
+    /// Fee and donation addresses for labelling coinbase outputs in metrics
+    pub coinbase_roles: CoinbaseRoles,
+    /// Most heights or entries a single request may scan
+    pub max_scan_items: u32,
+    /// Prepended to every metric name on /metrics
+    pub metrics_prefix: Option<String>,
+    /// Refuse DB viewer requests that modify the store
+    pub db_read_only: bool,
+    /// Column families the DB viewer may browse
+    pub db_cf_access: CfAccess,
+    /// Jobs older than this are pruned from the Job CF and left out of /jobs
+    pub job_ttl: Duration,
+}
+
+/// Scan budget per request when the config doesn't set max_scan_items
+const DEFAULT_MAX_SCAN_ITEMS: u32 = 10_000;
+
+/// Hard ceiling on max_page_size, whatever the config asks for
+const MAX_PAGE_SIZE_CEILING: u32 = 1000;
+
+/// Default and maximum page size shared by the paginated endpoints
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PageSizeLimits {
+    pub default: u32,
+    pub max: u32,
+}
+
+impl Default for PageSizeLimits {
+    fn default() -> Self {
+        Self {
+            default: 50,
+            max: 100,
+        }
+    }
+}
+
+impl PageSizeLimits {
+    /// Build limits from the configured values, falling back to the
+    /// defaults and keeping max within 1..=MAX_PAGE_SIZE_CEILING and
+    /// default within 1..=max.
+    pub fn from_config(default: Option<u32>, max: Option<u32>) -> Self {
+        let defaults = Self::default();
+        let max = max.unwrap_or(defaults.max).clamp(1, MAX_PAGE_SIZE_CEILING);
+        let default = default.unwrap_or(defaults.default).clamp(1, max);
+        Self { default, max }
+    }
+
+    /// The page size to use for a request, within 1..=max
+    pub fn resolve(&self, requested: Option<u32>) -> u32 {
+        requested.unwrap_or(self.default).clamp(1, self.max)
+    }
 }
 
 /// Get AppConfig from AppState ref
@@ -69,6 +203,31 @@ pub struct PplnsQuery {
     limit: Option<usize>,
     start_time: Option<String>,
     end_time: Option<String>,
+    /// Only return shares paid to this btc address
+    address: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct PplnsEstimateQuery {
+    /// Miner btc address to estimate the payout for
+    address: String,
+    /// Reward to split, e.g. the current block reward, in sats
+    reward_sats: u64,
+    /// Window of PPLNS shares, as for /pplns_shares
+    limit: Option<usize>,
+    start_time: Option<String>,
+    end_time: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct PplnsEstimateResponse {
+    pub address: String,
+    /// The miner's share of the window's total difficulty, 0 to 1
+    pub fraction: f64,
+    /// reward_sats times fraction, rounded down
+    pub estimated_sats: u64,
+    pub miner_difficulty: f64,
+    pub window_difficulty: f64,
 }
 
 // ============================================================================
@@ -80,11 +239,99 @@ pub struct ChainTipResponse {
     pub tip: String,
 }
 
+/// Sent to /chain/events subscribers when the chain tip changes
+#[derive(Clone, Debug, Serialize)]
+pub struct TipEvent {
+    /// Goes up by one per tip change, from the node's start time in unix
+    /// milliseconds, so IDs keep increasing across restarts
+    pub id: u64,
+    pub tip: String,
+    pub height: Option<u32>,
+}
+
+/// The bitcoin header the tip share commits to
+#[derive(Serialize)]
+pub struct TipBitcoinHeaderResponse {
+    pub share_hash: String,
+    pub bitcoin_block_hash: String,
+    pub version: i32,
+    pub prev_blockhash: String,
+    pub merkle_root: String,
+    pub time: u32,
+    /// Compact encoding of the bitcoin target, as hex
+    pub bits: String,
+    pub nonce: u32,
+}
+
+/// A coinbase output, with the address and role of outputs paying one
+#[derive(Serialize)]
+pub struct CoinbaseOutput {
+    pub index: usize,
+    pub value_sats: u64,
+    pub script_pubkey: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+    /// pool, donation or miner
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub role: Option<&'static str>,
+}
+
+/// The coinbase transaction of the latest job, with the extranonces that
+/// each miner fills in left as the placeholder separator
+#[derive(Serialize)]
+pub struct TipCoinbaseResponse {
+    pub job_id: u64,
+    /// Bitcoin height of the job's block template
+    pub height: u32,
+    pub txid: String,
+    pub version: i32,
+    pub lock_time: u32,
+    pub script_sig: String,
+    pub outputs: Vec<CoinbaseOutput>,
+    pub total_sats: u64,
+    /// The whole transaction, consensus encoded as hex
+    pub hex: String,
+}
+
+/// The latest job the pool is handing to miners
+#[derive(Serialize)]
+pub struct CurrentJobResponse {
+    pub job_id: u64,
+    /// Bitcoin height of the job's block template
+    pub height: u32,
+    pub previousblockhash: String,
+    pub bits: String,
+    pub coinbasevalue: u64,
+    pub outputs: Vec<CoinbaseOutput>,
+}
+
+#[derive(Deserialize)]
+pub struct JobsQuery {
+    pub limit: Option<u32>,
+}
+
+/// A job saved to the Job CF when it was sent to miners
+#[derive(Serialize)]
+pub struct JobHistoryInfo {
+    /// Job ID as sent in mining.notify, None if the saved job doesn't parse
+    pub job_id: Option<String>,
+    /// Bitcoin height from the coinbase, None if it can't be read
+    pub height: Option<u32>,
+    /// When the job was saved, in microseconds since the epoch
+    pub timestamp_micros: u64,
+}
+
 #[derive(Serialize)]
 pub struct ChainHeightResponse {
     pub height: Option<u32>,
 }
 
+#[derive(Serialize)]
+pub struct ChainHeightHashResponse {
+    pub height: u32,
+    pub hash: String,
+}
+
 #[derive(Serialize)]
 pub struct ChainTipWithUnclesResponse {
     pub tip: String,
@@ -97,10 +344,155 @@ pub struct SharesAtHeightResponse {
     pub shares: Vec<ShareInfo>,
 }
 
+#[derive(Deserialize)]
+pub struct SharesAtHeightQuery {
+    /// Comma separated fields to return for each record
+    pub fields: Option<String>,
+    /// One of difficulty, hash or time, see ShareOrder. Defaults to
+    /// difficulty.
+    pub sort: Option<String>,
+}
+
+/// Order of the shares returned by /chain/shares/:height. The store
+/// returns shares at a height as a map, so without a sort their order can
+/// change between requests. Every order ends with the hash, so it is
+/// total and repeated requests render identically.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ShareOrder {
+    /// Highest difficulty first, the default
+    #[default]
+    Difficulty,
+    /// Hash only, in the order the hashes display
+    Hash,
+    /// Oldest miner timestamp first
+    Time,
+}
+
+impl ShareOrder {
+    fn parse(sort: Option<&str>) -> Result<Self, ApiError> {
+        match sort {
+            None | Some("difficulty") => Ok(ShareOrder::Difficulty),
+            Some("hash") => Ok(ShareOrder::Hash),
+            Some("time") => Ok(ShareOrder::Time),
+            Some(other) => Err(ApiError::BadRequest(format!(
+                "Invalid sort {other}, expected difficulty, hash or time"
+            ))),
+        }
+    }
+
+    fn sort(self, shares: &mut [(BlockHash, ShareBlock)]) {
+        // Hashes display byte reversed, compare them the same way
+        let by_hash = |a: &BlockHash, b: &BlockHash| {
+            a.as_byte_array()
+                .iter()
+                .rev()
+                .cmp(b.as_byte_array().iter().rev())
+        };
+        shares.sort_by(|(a_hash, a), (b_hash, b)| {
+            let primary = match self {
+                ShareOrder::Difficulty => b.header.get_work().cmp(&a.header.get_work()),
+                ShareOrder::Hash => std::cmp::Ordering::Equal,
+                ShareOrder::Time => a.header.time.cmp(&b.header.time),
+            };
+            primary.then_with(|| by_hash(a_hash, b_hash))
+        });
+    }
+}
+
+/// An uncle referenced by a share, with the uncle share when it is stored
+#[derive(Serialize)]
+pub struct UncleInfo {
+    pub blockhash: String,
+    /// The share at the requested height that included this uncle
+    pub included_by: String,
+    pub share: Option<ShareInfo>,
+}
+
+/// A share with the shares around it, for one round trip explorer views
+#[derive(Serialize)]
+pub struct ShareContextResponse {
+    pub share: ShareInfo,
+    pub height: Option<u32>,
+    pub bitcoin_block_hash: String,
+    /// None for genesis or when the parent is not stored
+    pub parent: Option<ShareInfo>,
+    pub uncles: Vec<ContextUncle>,
+}
+
+/// Result of checking a stored share's proof of work against its bits
+#[derive(Serialize)]
+pub struct VerifyPowResponse {
+    pub meets_target: bool,
+    /// Hash of the bitcoin header the share was mined on
+    pub hash: String,
+    /// Share target decoded from bits, as big endian hex
+    pub target: String,
+}
+
+/// An uncle named by a share. share is None when the uncle is not stored
+/// or is past the resolution cap.
+#[derive(Serialize)]
+pub struct ContextUncle {
+    pub blockhash: String,
+    pub share: Option<ShareInfo>,
+}
+
+/// An uncle with its full share info, or just its hash and available
+/// false when the uncle is not stored or is past the cap
 #[derive(Serialize)]
+pub struct FullUncle {
+    pub hash: String,
+    pub available: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub miner_pubkey: Option<String>,
+    #[serde(flatten, skip_serializing_if = "Option::is_none")]
+    pub share: Option<ShareInfo>,
+}
+
+#[derive(Serialize)]
+pub struct UnclesFullResponse {
+    pub blockhash: String,
+    /// In the order the share names them
+    pub uncles: Vec<FullUncle>,
+}
+
+#[derive(Serialize)]
+pub struct UnclesAtHeightResponse {
+    pub height: u32,
+    pub uncles: Vec<UncleInfo>,
+}
+
+#[derive(Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ShareInfo {
     pub blockhash: String,
     pub prev_share_blockhash: String,
+    /// Shares on top of this one along the main chain, 0 for the tip and
+    /// for shares off the main chain. Also 0 for shares more than
+    /// max_scan_items below the tip that the confirmed height index
+    /// doesn't cover, as the main chain walk stops there.
+    pub confirmations: u32,
+    /// Compact encoding of the share target, as hex
+    pub bits: String,
+    /// Difficulty of the share target relative to the difficulty 1 target
+    pub difficulty_value: f64,
+}
+
+impl ShareInfo {
+    fn new(hash: &BlockHash, share: &ShareBlock, confirmations: u32) -> Self {
+        Self {
+            blockhash: hash.to_string(),
+            prev_share_blockhash: share.header.prev_share_blockhash.to_string(),
+            confirmations,
+            bits: format!("{:08x}", share.header.bits.to_consensus()),
+            difficulty_value: compact_difficulty(share.header.bits),
+        }
+    }
+}
+
+/// Difficulty of the target encoded by bits, where 0x1d00ffff is 1.0
+fn compact_difficulty(bits: CompactTarget) -> f64 {
+    Target::from_compact(bits).difficulty_float()
 }
 
 #[derive(Serialize)]
@@ -108,524 +500,8437 @@ pub struct TotalWorkResponse {
     pub total_work: String,
 }
 
+#[derive(Deserialize)]
+pub struct WorkCompareQuery {
+    /// Hex work, as returned by /chain/total_work, with or without 0x
+    pub a: String,
+    pub b: String,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct WorkCompareResponse {
+    pub a_ge_b: bool,
+    /// Absolute difference between a and b, in the same hex format
+    pub difference: String,
+}
+
 #[derive(Serialize)]
 pub struct ChainLocatorResponse {
     pub locator: Vec<String>,
 }
 
+/// A locator hash as resolved against the local store
+#[derive(Clone, Serialize)]
+pub struct LocatorEntry {
+    /// Position of the hash in the submitted locator
+    pub index: usize,
+    pub hash: String,
+    pub known: bool,
+    pub height: Option<u32>,
+}
+
+#[derive(Serialize)]
+pub struct LocatorResolveResponse {
+    pub entries: Vec<LocatorEntry>,
+    /// First entry the node knows, where the two chains meet
+    pub fork_point: Option<LocatorEntry>,
+}
+
 #[derive(Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ChainInfoResponse {
     pub tip: String,
     pub height: Option<u32>,
     pub total_work: String,
     pub uncles: Vec<String>,
     pub network: String,
+    /// Seconds since the tip share's timestamp. Left out of the ETag, so
+    /// a 304 leaves the client with the age from its cached copy.
+    pub tip_age_secs: Option<u64>,
+    /// Most shares an ancestry walk visits on this network
+    pub ancestry_depth_limit: usize,
+    /// Share target of the latest job handed to miners, as big endian
+    /// hex. None when no job with a share commitment is loaded.
+    pub current_share_target: Option<String>,
+    /// Difficulty of current_share_target
+    pub current_share_difficulty: Option<f64>,
 }
 
-// ============================================================================
-// DAG API Response Structs
-// ============================================================================
+/// chain_info plus aggregate counters, for status pages that want one
+/// cheap call
+#[derive(Serialize)]
+pub struct ChainInfoExtendedResponse {
+    #[serde(flatten)]
+    pub info: ChainInfoResponse,
+    /// From the stats cache, None until it is first filled. Counted up
+    /// to stats_height, which can trail the tip by the refresh interval.
+    pub total_shares: Option<u64>,
+    /// From the stats cache. Uncle references per share.
+    pub uncle_rate: Option<f64>,
+    /// From the stats cache. Work summed over the counted shares, in hex.
+    /// Also None when the sum overflowed.
+    pub total_difficulty: Option<String>,
+    /// From the stats cache. Tip height when the counters were taken.
+    pub stats_height: Option<u32>,
+    /// From the stats cache. True when the chain was longer than the scan
+    /// budget, so only the most recent heights were counted.
+    pub stats_truncated: Option<bool>,
+    /// Reorgs since the node started, from the chain store counter
+    pub reorg_count: u64,
+}
 
 #[derive(Serialize)]
-pub struct DagNode {
-    pub hash: String,
-    pub prev_hash: String,
-    pub uncles: Vec<String>,
+pub struct FoundBlock {
+    pub share_hash: String,
+    pub bitcoin_block_hash: String,
     pub height: u32,
-    pub miner_pubkey: String,
     pub timestamp: u32,
-    pub is_main_chain: bool,
-    pub is_uncle: bool,
+    pub miner_pubkey: String,
 }
 
 #[derive(Serialize)]
-pub struct DagEdge {
-    pub from: String,
-    pub to: String,
-    pub edge_type: String,
+pub struct BlocksFoundResponse {
+    pub blocks: Vec<FoundBlock>,
+    pub from_height: u32,
+    pub to_height: u32,
+}
+
+#[derive(Deserialize)]
+pub struct BlocksFoundQuery {
+    pub limit: Option<usize>,
 }
 
 #[derive(Serialize)]
-pub struct DagResponse {
-    pub nodes: Vec<DagNode>,
-    pub edges: Vec<DagEdge>,
-    pub tip_hash: String,
+pub struct OrphanShare {
+    pub hash: String,
+    pub prev_hash: String,
+    pub height: u32,
+    pub miner_pubkey: String,
+}
+
+#[derive(Serialize)]
+pub struct OrphansResponse {
+    pub orphans: Vec<OrphanShare>,
     pub from_height: u32,
     pub to_height: u32,
+    /// True when the window was cut to the scan budget
+    pub truncated: bool,
 }
 
 #[derive(Deserialize)]
-pub struct DagQuery {
-    pub from_height: Option<u32>,
-    pub to_height: Option<u32>,
-    pub limit: Option<u32>,
+pub struct MinerSharesQuery {
+    pub page: Option<u32>,
+    pub page_size: Option<u32>,
+    /// RFC3339 timestamp or YYYY-MM-DD date, inclusive
+    pub from: Option<String>,
+    /// RFC3339 timestamp or YYYY-MM-DD date, inclusive
+    pub to: Option<String>,
 }
 
-// ============================================================================
-// Chain API Handlers
-// ============================================================================
+#[derive(Serialize)]
+pub struct MinerShare {
+    pub hash: String,
+    /// None when the share's height is not in the store
+    pub height: Option<u32>,
+    pub timestamp: u32,
+}
 
-async fn chain_tip(State(state): State<Arc<AppState>>) -> Json<ChainTipResponse> {
-    let tip = state.chain_store_handle.get_chain_tip();
-    Json(ChainTipResponse {
-        tip: tip.to_string(),
-    })
+#[derive(Serialize)]
+pub struct MinerSharesResponse {
+    pub miner_pubkey: String,
+    /// Newest first
+    pub shares: Vec<MinerShare>,
+    pub page: u32,
+    pub page_size: u32,
+    pub has_more: bool,
 }
 
-async fn chain_height(
-    State(state): State<Arc<AppState>>,
-) -> Result<Json<ChainHeightResponse>, ApiError> {
-    let height = state
-        .chain_store_handle
-        .get_tip_height()
-        .map_err(|e| ApiError::ServerError(e.to_string()))?;
-    Ok(Json(ChainHeightResponse { height }))
+#[derive(Deserialize)]
+pub struct KnownMinersQuery {
+    /// Defaults to DEFAULT_KNOWN_MINERS_LIMIT, capped at MAX_KNOWN_MINERS_LIMIT
+    pub limit: Option<usize>,
 }
 
-async fn chain_tip_with_uncles(
-    State(state): State<Arc<AppState>>,
-) -> Json<ChainTipWithUnclesResponse> {
-    let (tip, uncles) = state.chain_store_handle.get_chain_tip_and_uncles();
-    Json(ChainTipWithUnclesResponse {
-        tip: tip.to_string(),
-        uncles: uncles.iter().map(|u| u.to_string()).collect(),
-    })
+#[derive(Serialize)]
+pub struct KnownMiner {
+    pub miner_pubkey: String,
+    /// Height of the miner's earliest indexed share, None when not in the store
+    pub first_seen_height: Option<u32>,
+    pub first_seen_time: u32,
+    /// Height of the miner's latest indexed share, None when not in the store
+    pub last_seen_height: Option<u32>,
+    pub last_seen_time: u32,
 }
 
-async fn shares_at_height(
-    State(state): State<Arc<AppState>>,
-    Path(height): Path<u32>,
-) -> Result<Json<SharesAtHeightResponse>, ApiError> {
-    let shares = state
-        .chain_store_handle
-        .get_shares_at_height(height)
-        .map_err(|e| ApiError::ServerError(e.to_string()))?;
+#[derive(Serialize)]
+pub struct KnownMinersResponse {
+    /// Most recently seen first
+    pub miners: Vec<KnownMiner>,
+    /// True when more miners than the scan budget are indexed, so only
+    /// the first max_scan_items miners by pubkey were considered
+    pub truncated: bool,
+}
 
-    let share_infos: Vec<ShareInfo> = shares
-        .iter()
-        .map(|(hash, share)| ShareInfo {
-            blockhash: hash.to_string(),
-            prev_share_blockhash: share.header.prev_share_blockhash.to_string(),
-        })
-        .collect();
+#[derive(Deserialize)]
+pub struct DensityQuery {
+    pub from: Option<u32>,
+    pub to: Option<u32>,
+}
 
-    Ok(Json(SharesAtHeightResponse {
-        height,
-        shares: share_infos,
-    }))
+/// Number of shares stored at a height
+#[derive(Serialize)]
+pub struct HeightDensity {
+    pub height: u32,
+    pub share_count: usize,
+    /// Uncles referenced by the shares at this height
+    pub uncle_count: usize,
 }
 
-async fn total_work(
-    State(state): State<Arc<AppState>>,
-) -> Result<Json<TotalWorkResponse>, ApiError> {
-    let work = state
-        .chain_store_handle
-        .get_total_work()
-        .map_err(|e| ApiError::ServerError(e.to_string()))?;
-    Ok(Json(TotalWorkResponse {
-        total_work: format!("{:x}", work),
-    }))
+#[derive(Deserialize)]
+pub struct ShareRateQuery {
+    /// Seconds before now to cover, defaults to an hour
+    pub window: Option<u64>,
+    /// Seconds per bucket, defaults to a minute
+    pub bucket: Option<u64>,
 }
 
-async fn chain_locator(
-    State(state): State<Arc<AppState>>,
-) -> Result<Json<ChainLocatorResponse>, ApiError> {
-    let locator = state
-        .chain_store_handle
-        .build_locator()
-        .map_err(|e| ApiError::ServerError(e.to_string()))?;
-    Ok(Json(ChainLocatorResponse {
-        locator: locator.iter().map(|h| h.to_string()).collect(),
-    }))
+/// Shares with a header time in [bucket_start, bucket_start + bucket)
+#[derive(Serialize)]
+pub struct ShareRateBucket {
+    pub bucket_start: u64,
+    pub count: usize,
+    /// Sum of the difficulty of the shares' targets
+    pub total_difficulty: f64,
 }
 
-async fn chain_info(
-    State(state): State<Arc<AppState>>,
-) -> Result<Json<ChainInfoResponse>, ApiError> {
-    let tip = state.chain_store_handle.get_chain_tip();
-    let height = state
-        .chain_store_handle
-        .get_tip_height()
-        .map_err(|e| ApiError::ServerError(e.to_string()))?;
-    let total_work = state
-        .chain_store_handle
-        .get_total_work()
-        .map_err(|e| ApiError::ServerError(e.to_string()))?;
-    let (_, uncles) = state.chain_store_handle.get_chain_tip_and_uncles();
+#[derive(Serialize)]
+pub struct ShareRateResponse {
+    pub window: u64,
+    pub bucket: u64,
+    /// Oldest first, including empty buckets
+    pub buckets: Vec<ShareRateBucket>,
+    /// True when the scan budget ran out before the start of the window
+    pub truncated: bool,
+}
 
-    Ok(Json(ChainInfoResponse {
-        tip: tip.to_string(),
-        height,
-        total_work: format!("{:x}", total_work),
-        uncles: uncles.iter().map(|u| u.to_string()).collect(),
-        network: state.app_config.network.to_string(),
-    }))
+#[derive(Deserialize)]
+pub struct SubmissionIntervalsQuery {
+    /// Number of most recent shares to cover
+    pub window: Option<usize>,
 }
 
-// ============================================================================
-// DAG API Handler
-// ============================================================================
+/// Distribution of the seconds between consecutive share header times.
+/// The statistics are null when fewer than 2 shares were found.
+#[derive(Serialize)]
+pub struct SubmissionIntervalsResponse {
+    pub window: usize,
+    /// Shares found, fewer than window near genesis or when truncated
+    pub shares: usize,
+    pub min: Option<u64>,
+    pub median: Option<u64>,
+    pub p95: Option<u64>,
+    pub max: Option<u64>,
+    /// True when the scan budget ran out before window shares were found
+    pub truncated: bool,
+}
 
-async fn chain_dag(
+#[derive(Deserialize)]
+pub struct MainChainQuery {
+    pub from: Option<u32>,
+    pub to: Option<u32>,
+}
+
+/// The main chain share at a height
+#[derive(Serialize)]
+pub struct MainChainShare {
+    pub height: u32,
+    pub share: ShareInfo,
+}
+
+#[derive(Serialize)]
+pub struct MainChainResponse {
+    /// In ascending height order
+    pub shares: Vec<MainChainShare>,
+    pub from_height: u32,
+    pub to_height: u32,
+    /// True when from_height was raised to keep the walk back from the tip
+    /// within the scan budget
+    pub truncated: bool,
+}
+
+#[derive(Deserialize)]
+pub struct RawShareQuery {
+    /// "hex" returns a hex string instead of raw bytes
+    pub format: Option<String>,
+    /// "wire" (default) for the full share as sent to peers, "storage" for
+    /// the encoding kept in the block column family, with txids in place
+    /// of transactions
+    pub encoding: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct CoinbaseTotalsQuery {
+    pub window: Option<u32>,
+}
+
+/// Coinbase value paid to one address
+#[derive(Serialize)]
+pub struct AddressTotal {
+    pub address: String,
+    pub sats: u64,
+}
+
+#[derive(Serialize)]
+pub struct CoinbaseTotalsResponse {
+    /// Sum of all coinbase outputs of the shares in the window
+    pub total_sats: u64,
+    /// Totals per address, largest first
+    pub per_address: Vec<AddressTotal>,
+    /// Main chain shares summed, fewer than window near genesis
+    pub share_count: u32,
+}
+
+#[derive(Deserialize)]
+pub struct VerifyQuery {
+    /// Most main chain shares to check, capped at the scan budget
+    pub limit: Option<u32>,
+}
+
+/// An inconsistency found by /chain/verify
+#[derive(Serialize)]
+pub struct ChainProblem {
+    pub hash: String,
+    pub problem: String,
+}
+
+#[derive(Serialize)]
+pub struct VerifyResponse {
+    /// Main chain shares checked, counting down from the tip
+    pub checked: u32,
+    pub errors: Vec<ChainProblem>,
+    /// True when the walk stopped at the limit before reaching genesis
+    pub truncated: bool,
+}
+
+#[derive(Deserialize)]
+pub struct ReorgsQuery {
+    pub limit: Option<u32>,
+}
+
+/// A past switch of the chain tip to another branch
+#[derive(Serialize)]
+pub struct ReorgInfo {
+    pub old_tip: String,
+    pub new_tip: String,
+    /// Old tip shares above the common ancestor
+    pub depth: u32,
+    /// Unix time in seconds
+    pub timestamp: u64,
+}
+
+#[derive(Deserialize)]
+pub struct TipHistoryQuery {
+    pub limit: Option<u32>,
+}
+
+/// A recent chain tip
+#[derive(Serialize)]
+pub struct TipHistoryInfo {
+    pub hash: String,
+    pub height: u32,
+    /// Chain work up to the tip, in hex
+    pub total_work: String,
+    /// Unix time in seconds the share became the tip
+    pub timestamp: u64,
+}
+
+#[derive(Deserialize)]
+pub struct OrphansQuery {
+    pub window: Option<u32>,
+}
+
+#[derive(Serialize)]
+pub struct ChainStatsResponse {
+    pub height: Option<u32>,
+    pub total_shares: u64,
+    pub total_uncles: u64,
+    /// None when the sum overflowed, see total_difficulty_overflowed
+    pub total_difficulty: Option<String>,
+    pub total_difficulty_decimal: Option<String>,
+    /// True when the work summed over the counted shares doesn't fit in
+    /// 256 bits, so no total is reported
+    pub total_difficulty_overflowed: bool,
+    /// Lowest height counted. Above 0 only when truncated.
+    pub from_height: u32,
+    /// True when the chain is longer than the scan budget, so only the
+    /// heights from from_height up to the tip were counted
+    pub truncated: bool,
+}
+
+#[derive(Deserialize)]
+pub struct ChainStatsQuery {
+    pub exact: Option<bool>,
+    pub parallel: Option<bool>,
+}
+
+#[derive(Deserialize)]
+pub struct ChainStatsExactQuery {
+    pub parallel: Option<bool>,
+}
+
+#[derive(Serialize)]
+pub struct ChainHealthResponse {
+    pub status: String,
+    pub chain_tip_reachable: bool,
+    pub metrics_reachable: bool,
+    pub tip_age_secs: Option<u64>,
+    /// True when the tip is older than the configured stale_after_secs
+    pub tip_stale: bool,
+}
+
+#[derive(Serialize)]
+pub struct ReadinessResponse {
+    pub status: String,
+    pub chain_tip_reachable: bool,
+    pub metrics_reachable: bool,
+}
+
+/// A field from two shares, with whether the values differ
+#[derive(Serialize)]
+pub struct FieldComparison<T> {
+    pub a: T,
+    pub b: T,
+    pub differs: bool,
+}
+
+impl<T: PartialEq> FieldComparison<T> {
+    fn new(a: T, b: T) -> Self {
+        let differs = a != b;
+        Self { a, b, differs }
+    }
+}
+
+#[derive(Serialize)]
+pub struct ShareCompareResponse {
+    pub a: String,
+    pub b: String,
+    pub common_ancestor: Option<String>,
+    pub prev_hash: FieldComparison<String>,
+    pub uncles: FieldComparison<Vec<String>>,
+    pub miner_pubkey: FieldComparison<String>,
+    pub timestamp: FieldComparison<u32>,
+    pub bits: FieldComparison<String>,
+    pub bitcoin_block_hash: FieldComparison<String>,
+}
+
+#[derive(Deserialize)]
+pub struct CompareQuery {
+    pub a: String,
+    pub b: String,
+}
+
+#[derive(Serialize)]
+pub struct GenesisResponse {
+    pub network: String,
+    pub hash: String,
+    pub genesis: ShareBlock,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SubmitShareResponse {
+    pub block_hash: String,
+}
+
+#[derive(Deserialize)]
+pub struct ValidateShareQuery {
+    /// Run every rule instead of stopping at the first failure
+    pub verbose: Option<bool>,
+}
+
+/// Outcome of one validation rule
+#[derive(Serialize, Deserialize)]
+pub struct ValidationRuleInfo {
+    pub rule: String,
+    pub passed: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ValidateShareResponse {
+    pub block_hash: String,
+    pub valid: bool,
+    /// Why validation failed, in the default short form
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// Every rule checked, with ?verbose=true
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rules: Option<Vec<ValidationRuleInfo>>,
+}
+
+#[derive(Serialize)]
+pub struct SpendResponse {
+    pub txid: String,
+    pub vout: u32,
+    pub spending_txid: String,
+    pub spending_input_index: u32,
+}
+
+// ============================================================================
+// DAG API Response Structs
+// ============================================================================
+
+#[derive(Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct DagNode {
+    pub hash: String,
+    pub prev_hash: String,
+    pub uncles: Vec<String>,
+    pub height: u32,
+    pub miner_pubkey: String,
+    pub timestamp: u32,
+    pub is_main_chain: bool,
+    pub is_uncle: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct DagEdge {
+    pub from: String,
+    pub to: String,
+    pub edge_type: String,
+}
+
+#[derive(Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct DagResponse {
+    pub nodes: Vec<DagNode>,
+    pub edges: Vec<DagEdge>,
+    pub tip_hash: String,
+    pub from_height: u32,
+    pub to_height: u32,
+    /// True when the requested range was cut to the scan budget, either
+    /// for the heights read or for the main chain walk from the tip
+    pub truncated: bool,
+}
+
+/// The default /chain/dag response serialized ahead of time, along with
+/// the tip it was computed for
+#[derive(Clone)]
+pub struct DagSnapshot {
+    pub tip: BlockHash,
+    pub body: axum::body::Bytes,
+}
+
+/// Chain stats counted ahead of time by refresh_stats_snapshot, along
+/// with the tip height they were counted at
+#[derive(Clone)]
+pub struct StatsSnapshot {
+    pub height: u32,
+    pub total_shares: u64,
+    pub total_uncles: u64,
+    /// None when the sum overflowed
+    pub total_difficulty: Option<Work>,
+    pub from_height: u32,
+    pub truncated: bool,
+}
+
+#[derive(Serialize)]
+pub struct DagSnapshotResponse {
+    #[serde(flatten)]
+    pub dag: DagResponse,
+    /// Unix time the DAG was computed
+    pub generated_at: u64,
+}
+
+#[derive(Deserialize)]
+pub struct DagQuery {
+    pub from_height: Option<u32>,
+    pub to_height: Option<u32>,
+    pub limit: Option<u32>,
+    /// Only return shares mined by this compressed public key (hex)
+    pub miner_pubkey: Option<String>,
+    /// With miner_pubkey, also return the direct parent of each matching
+    /// share so the subgraph can be drawn against the chain.
+    pub include_parents: Option<bool>,
+    /// Comma separated DagNode fields to return, e.g. "hash,height"
+    pub fields: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct FieldsQuery {
+    /// Comma separated fields to return for each record
+    pub fields: Option<String>,
+}
+
+/// A response made of records that ?fields= can trim down
+pub(crate) trait FieldMasked: Serialize {
+    /// Fields a record can have
+    const FIELDS: &'static [&'static str];
+    /// Key of the array of records in the response
+    const RECORDS: &'static str;
+}
+
+impl FieldMasked for SharesAtHeightResponse {
+    const FIELDS: &'static [&'static str] = &[
+        "blockhash",
+        "prev_share_blockhash",
+        "confirmations",
+        "bits",
+        "difficulty_value",
+    ];
+    const RECORDS: &'static str = "shares";
+}
+
+impl FieldMasked for DagResponse {
+    const FIELDS: &'static [&'static str] = DAG_NODE_FIELDS;
+    const RECORDS: &'static str = "nodes";
+}
+
+const DAG_NODE_FIELDS: &[&str] = &[
+    "hash",
+    "prev_hash",
+    "uncles",
+    "height",
+    "miner_pubkey",
+    "timestamp",
+    "is_main_chain",
+    "is_uncle",
+];
+
+/// Parse a ?fields= list against the known fields. Unknown names are
+/// ignored with a warning. Returns None, so records are left whole, when
+/// no fields were asked for or none of them are known.
+fn parse_fields(fields: Option<&str>, known: &[&str]) -> Option<HashSet<String>> {
+    let selected: HashSet<String> = fields?
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .filter(|name| {
+            let is_known = known.contains(name);
+            if !is_known {
+                warn!("Ignoring unknown field {name} in fields query");
+            }
+            is_known
+        })
+        .map(String::from)
+        .collect();
+    (!selected.is_empty()).then_some(selected)
+}
+
+/// Keep only the selected keys of a JSON object
+fn project_fields(record: &mut serde_json::Value, fields: &HashSet<String>) {
+    if let serde_json::Value::Object(map) = record {
+        map.retain(|key, _| fields.contains(key));
+    }
+}
+
+/// JSON response with each record projected to the fields asked for.
+/// Derefs to the full response.
+pub(crate) struct Masked<T> {
+    value: T,
+    fields: Option<HashSet<String>>,
+}
+
+impl<T: FieldMasked> Masked<T> {
+    fn new(value: T, fields: Option<&str>) -> Self {
+        Self {
+            value,
+            fields: parse_fields(fields, T::FIELDS),
+        }
+    }
+}
+
+impl<T> Deref for Masked<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T: FieldMasked> IntoResponse for Masked<T> {
+    fn into_response(self) -> Response {
+        let Some(fields) = self.fields else {
+            return Json(self.value).into_response();
+        };
+        let mut value = match serde_json::to_value(&self.value) {
+            Ok(value) => value,
+            Err(e) => return ApiError::ServerError(e.to_string()).into_response(),
+        };
+        if let Some(serde_json::Value::Array(records)) = value.get_mut(T::RECORDS) {
+            for record in records {
+                project_fields(record, &fields);
+            }
+        }
+        Json(value).into_response()
+    }
+}
+
+// ============================================================================
+// Chain API Handlers
+// ============================================================================
+
+/// Weak ETag for responses derived from the chain tip.
+///
+/// Fields that change with the clock, like tip_age_secs, are not part of
+/// the tag, so a 304 leaves the client with the age from its cached copy.
+///
+/// The uncle count is included for responses that list uncles, as new
+/// uncles can arrive without the tip changing. With the tip fixed the uncle
+/// set only grows, so the count is enough to detect a change.
+///
+/// The share target is included for responses that report it, as new jobs
+/// can change it without the tip changing.
+fn tip_etag(
+    tip: &BlockHash,
+    height: Option<u32>,
+    uncle_count: usize,
+    share_target: Option<&str>,
+) -> String {
+    let height = height.map_or_else(|| "none".to_string(), |height| height.to_string());
+    match share_target {
+        Some(target) => format!("W/\"{tip}-{height}-{uncle_count}-{target}\""),
+        None => format!("W/\"{tip}-{height}-{uncle_count}\""),
+    }
+}
+
+/// Returns true if the If-None-Match header lists etag, using weak
+/// comparison, or is "*".
+fn etag_matches(headers: &HeaderMap, etag: &str) -> bool {
+    let Some(if_none_match) = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+    else {
+        return false;
+    };
+    let opaque_tag = |tag: &str| tag.trim().trim_start_matches("W/").to_string();
+    if_none_match
+        .split(',')
+        .any(|tag| tag.trim() == "*" || opaque_tag(tag) == opaque_tag(etag))
+}
+
+/// Respond with 304 Not Modified if the client already has etag, otherwise
+/// with the JSON body. The ETag header is set on both.
+pub(crate) fn etag_response<T: Serialize>(headers: &HeaderMap, etag: String, body: T) -> Response {
+    if etag_matches(headers, &etag) {
+        (StatusCode::NOT_MODIFIED, [(header::ETAG, etag)]).into_response()
+    } else {
+        ([(header::ETAG, etag)], Json(body)).into_response()
+    }
+}
+
+async fn chain_tip(
     State(state): State<Arc<AppState>>,
-    Query(params): Query<DagQuery>,
-) -> Result<Json<DagResponse>, ApiError> {
-    let tip_height = state
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let tip = state.chain_store_handle.get_chain_tip();
+    let height = state
         .chain_store_handle
         .get_tip_height()
-        .map_err(|e| ApiError::ServerError(e.to_string()))?
-        .unwrap_or(0);
-
-    let limit = params.limit.unwrap_or(50).min(100);
-    let to_height = params.to_height.unwrap_or(tip_height);
-    let from_height = params
-        .from_height
-        .unwrap_or(to_height.saturating_sub(limit));
+        .map_err(|e| ApiError::ServerError(e.to_string()))?;
+    Ok(etag_response(
+        &headers,
+        tip_etag(&tip, height, 0, None),
+        ChainTipResponse {
+            tip: tip.to_string(),
+        },
+    ))
+}
 
+/// The bitcoin header embedded in the tip share, anchoring the share
+/// chain to the bitcoin chain
+async fn chain_tip_bitcoin(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<TipBitcoinHeaderResponse>, ApiError> {
     let tip = state.chain_store_handle.get_chain_tip();
-    let (_, current_uncles) = state.chain_store_handle.get_chain_tip_and_uncles();
+    let share = state
+        .chain_store_handle
+        .get_share(&tip)
+        .ok_or_else(|| ApiError::NotFound(format!("Tip share {tip} not found")))?;
+    let header = share.header.bitcoin_header;
+    Ok(Json(TipBitcoinHeaderResponse {
+        share_hash: tip.to_string(),
+        bitcoin_block_hash: header.block_hash().to_string(),
+        version: header.version.to_consensus(),
+        prev_blockhash: header.prev_blockhash.to_string(),
+        merkle_root: header.merkle_root.to_string(),
+        time: header.time,
+        bits: format!("{:08x}", header.bits.to_consensus()),
+        nonce: header.nonce,
+    }))
+}
 
-    let mut nodes: Vec<DagNode> = Vec::new();
-    let mut edges: Vec<DagEdge> = Vec::new();
-    let mut seen_hashes: HashSet<String> = HashSet::new();
+/// The coinbase the pool is currently asking miners to work on, rebuilt
+/// from the latest job's coinbase1 and coinbase2. Outputs are labelled
+/// with their address and role the same way as the coinbase metrics.
+async fn chain_tip_coinbase(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<TipCoinbaseResponse>, ApiError> {
+    let (job_id, job) = latest_job(&state.tracker_handle)?;
+    let coinbase = join_coinbase(&job.coinbase1, &job.coinbase2)
+        .map_err(|e| ApiError::ServerError(format!("Invalid coinbase in latest job: {e}")))?;
+    let outputs = coinbase_outputs(&state, &coinbase);
 
-    for height in from_height..=to_height {
-        if let Ok(shares) = state.chain_store_handle.get_shares_at_height(height) {
-            for (hash, share) in shares {
-                let hash_str = hash.to_string();
-                if seen_hashes.contains(&hash_str) {
-                    continue;
-                }
-                seen_hashes.insert(hash_str.clone());
-
-                let is_main_chain = is_on_main_chain(&state.chain_store_handle, &hash, &tip);
-                let is_uncle = current_uncles.contains(&hash);
-
-                let prev_hash_str = share.header.prev_share_blockhash.to_string();
-                if share.header.prev_share_blockhash != BlockHash::all_zeros() {
-                    edges.push(DagEdge {
-                        from: hash_str.clone(),
-                        to: prev_hash_str.clone(),
-                        edge_type: "parent".to_string(),
-                    });
-                }
+    Ok(Json(TipCoinbaseResponse {
+        job_id: job_id.0,
+        height: job.blocktemplate.height,
+        txid: coinbase.compute_txid().to_string(),
+        version: coinbase.version.0,
+        lock_time: coinbase.lock_time.to_consensus_u32(),
+        script_sig: coinbase
+            .input
+            .first()
+            .map(|input| hex::encode(input.script_sig.as_bytes()))
+            .unwrap_or_default(),
+        outputs,
+        total_sats: coinbase
+            .output
+            .iter()
+            .map(|tx_out| tx_out.value.to_sat())
+            .sum(),
+        hex: bitcoin::consensus::encode::serialize_hex(&coinbase),
+    }))
+}
 
-                for uncle in &share.header.uncles {
-                    edges.push(DagEdge {
-                        from: hash_str.clone(),
-                        to: uncle.to_string(),
-                        edge_type: "uncle".to_string(),
-                    });
-                }
+/// The latest job in the tracker, for inspecting what miners are told to
+/// work on when debugging stratum issues
+async fn job_current(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<CurrentJobResponse>, ApiError> {
+    let (job_id, job) = latest_job(&state.tracker_handle)?;
+    let coinbase = join_coinbase(&job.coinbase1, &job.coinbase2)
+        .map_err(|e| ApiError::ServerError(format!("Invalid coinbase in latest job: {e}")))?;
 
-                nodes.push(DagNode {
-                    hash: hash_str,
-                    prev_hash: prev_hash_str,
-                    uncles: share.header.uncles.iter().map(|u| u.to_string()).collect(),
-                    height,
-                    miner_pubkey: share.header.miner_pubkey.to_string(),
-                    timestamp: share.header.time,
-                    is_main_chain,
-                    is_uncle,
-                });
-            }
+    Ok(Json(CurrentJobResponse {
+        job_id: job_id.0,
+        height: job.blocktemplate.height,
+        previousblockhash: job.blocktemplate.previousblockhash.clone(),
+        bits: job.blocktemplate.bits.clone(),
+        coinbasevalue: job.blocktemplate.coinbasevalue,
+        outputs: coinbase_outputs(&state, &coinbase),
+    }))
+}
+
+/// Recent jobs from the Job CF, newest first, for auditing job churn.
+/// limit is capped at the max page size. Jobs older than the prune window
+/// are left out even when the background prune hasn't removed them yet.
+async fn jobs(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<JobsQuery>,
+) -> Result<Json<Vec<JobHistoryInfo>>, ApiError> {
+    let limit = state.app_config.page_size.resolve(params.limit) as usize;
+    let now_micros = chrono::Utc::now().timestamp_micros().max(0) as u64;
+    let start_time = now_micros.saturating_sub(state.app_config.job_ttl.as_micros() as u64);
+    let jobs = retry_read(|| {
+        state
+            .chain_store_handle
+            .store_handle()
+            .get_jobs(Some(start_time), Some(now_micros), limit)
+    })
+    .await?;
+    Ok(Json(
+        jobs.iter()
+            .map(|(timestamp, serialized_notify)| job_history_info(*timestamp, serialized_notify))
+            .collect(),
+    ))
+}
+
+/// Read the job ID and height back out of a saved mining.notify
+fn job_history_info(timestamp_micros: u64, serialized_notify: &str) -> JobHistoryInfo {
+    let notify = serde_json::from_str::<Notify>(serialized_notify).ok();
+    let height = notify
+        .as_ref()
+        .and_then(|notify| join_coinbase(&notify.params.coinbase1, &notify.params.coinbase2).ok())
+        .and_then(|coinbase| coinbase_height(&coinbase));
+    JobHistoryInfo {
+        job_id: notify.map(|notify| notify.params.job_id),
+        height,
+        timestamp_micros,
+    }
+}
+
+/// The BIP34 height pushed first in the coinbase script_sig. Heights up
+/// to 16 are pushed as small number opcodes rather than as bytes.
+fn coinbase_height(coinbase: &Transaction) -> Option<u32> {
+    let first = coinbase
+        .input
+        .first()?
+        .script_sig
+        .instructions()
+        .next()?
+        .ok()?;
+    match first {
+        bitcoin::script::Instruction::PushBytes(bytes) => {
+            let height = bitcoin::script::read_scriptint(bytes.as_bytes()).ok()?;
+            u32::try_from(height).ok()
         }
+        bitcoin::script::Instruction::Op(op) => match op.to_u8() {
+            opcode @ 0x51..=0x60 => Some(u32::from(opcode - 0x50)),
+            _ => None,
+        },
     }
+}
 
-    Ok(Json(DagResponse {
-        nodes,
-        edges,
-        tip_hash: tip.to_string(),
-        from_height,
-        to_height,
-    }))
+/// The latest job in the tracker, or 404 when none has been loaded yet
+fn latest_job(tracker: &JobTracker) -> Result<(JobId, JobDetails), ApiError> {
+    let job_id = tracker.get_latest_job_id();
+    let job = tracker
+        .get_job(job_id)
+        .ok_or_else(|| ApiError::NotFound("No job loaded".to_string()))?;
+    Ok((job_id, job))
 }
 
-fn is_on_main_chain(chain_store: &ChainStoreHandle, hash: &BlockHash, tip: &BlockHash) -> bool {
-    if hash == tip {
-        return true;
-    }
-    let mut current = *tip;
-    for _ in 0..1000 {
-        if &current == hash {
-            return true;
-        }
-        if let Some(share) = chain_store.get_share(&current) {
-            if share.header.prev_share_blockhash == BlockHash::all_zeros() {
-                break;
-            }
-            current = share.header.prev_share_blockhash;
-        } else {
-            break;
-        }
+/// Label coinbase outputs with their address and role the same way as the
+/// coinbase metrics
+fn coinbase_outputs(state: &AppState, coinbase: &Transaction) -> Vec<CoinbaseOutput> {
+    let addressed: HashMap<usize, Address> =
+        parse_coinbase::address_outputs(&coinbase.output, state.app_config.network)
+            .into_iter()
+            .map(|(index, address, _)| (index, address))
+            .collect();
+    coinbase
+        .output
+        .iter()
+        .enumerate()
+        .map(|(index, tx_out)| {
+            let address = addressed.get(&index);
+            CoinbaseOutput {
+                index,
+                value_sats: tx_out.value.to_sat(),
+                script_pubkey: hex::encode(tx_out.script_pubkey.as_bytes()),
+                address: address.map(|address| address.to_string()),
+                role: address.map(|address| state.app_config.coinbase_roles.role(address)),
+            }
+        })
+        .collect()
+}
+
+async fn chain_height(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<ChainHeightResponse>, ApiError> {
+    let height = state
+        .chain_store_handle
+        .get_tip_height()
+        .map_err(|e| ApiError::ServerError(e.to_string()))?;
+    Ok(Json(ChainHeightResponse { height }))
+}
+
+/// The main chain share hash at a height. The confirmed index in the
+/// BlockHeight CF is read first, otherwise the chain is walked back from
+/// the tip, within the max_scan_items budget.
+async fn chain_height_hash(
+    State(state): State<Arc<AppState>>,
+    HeightPath(height): HeightPath,
+) -> Result<Json<ChainHeightHashResponse>, ApiError> {
+    let not_found = || ApiError::NotFound(format!("No main chain share at height {height}"));
+    if let Some(hash) = state.chain_store_handle.get_confirmed_at_height(height) {
+        return Ok(Json(ChainHeightHashResponse {
+            height,
+            hash: hash.to_string(),
+        }));
+    }
+
+    let tip_height = state
+        .chain_store_handle
+        .get_tip_height()
+        .map_err(|e| ApiError::ServerError(e.to_string()))?
+        .ok_or_else(not_found)?;
+    if height > tip_height {
+        return Err(not_found());
+    }
+    let depth = tip_height - height;
+    if depth > state.app_config.max_scan_items {
+        return Err(ApiError::BadRequest(format!(
+            "Height {height} is more than {} shares below the tip",
+            state.app_config.max_scan_items
+        )));
+    }
+
+    let mut current = state.chain_store_handle.get_chain_tip();
+    for _ in 0..depth {
+        let share = state
+            .chain_store_handle
+            .get_share(&current)
+            .ok_or_else(not_found)?;
+        current = share.header.prev_share_blockhash;
+    }
+    if current == BlockHash::all_zeros() {
+        return Err(not_found());
+    }
+    Ok(Json(ChainHeightHashResponse {
+        height,
+        hash: current.to_string(),
+    }))
+}
+
+async fn chain_tip_with_uncles(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let (tip, uncles) = state.chain_store_handle.get_chain_tip_and_uncles();
+    let height = state
+        .chain_store_handle
+        .get_tip_height()
+        .map_err(|e| ApiError::ServerError(e.to_string()))?;
+    Ok(etag_response(
+        &headers,
+        tip_etag(&tip, height, uncles.len(), None),
+        ChainTipWithUnclesResponse {
+            tip: tip.to_string(),
+            uncles: uncles.iter().map(|u| u.to_string()).collect(),
+        },
+    ))
+}
+
+/// How far past the chain tip a requested height may be. Shares a little
+/// ahead of our tip may be about to arrive, anything further is an error.
+const MAX_HEIGHT_BEYOND_TIP: u32 = 100;
+
+/// Share height path parameter.
+///
+/// Heights that don't parse as a u32, e.g. negative or too large, are
+/// rejected with a JSON 400 instead of axum's plain text rejection.
+struct HeightPath(u32);
+
+#[axum::async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for HeightPath {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Path(segment) = Path::<String>::from_request_parts(parts, state)
+            .await
+            .map_err(|e| ApiError::BadRequest(e.body_text()))?;
+        segment.parse::<u32>().map(HeightPath).map_err(|_| {
+            ApiError::BadRequest(format!(
+                "Invalid height {segment}: expected an integer from 0 to {}",
+                u32::MAX
+            ))
+        })
+    }
+}
+
+/// Reject heights too far beyond the tip to ever hold shares
+fn check_height_near_tip(height: u32, tip_height: u32) -> Result<(), ApiError> {
+    if height > tip_height.saturating_add(MAX_HEIGHT_BEYOND_TIP) {
+        return Err(ApiError::BadRequest("height beyond tip".to_string()));
+    }
+    Ok(())
+}
+
+/// Shares stored at a height, in the ShareOrder picked by ?sort=
+async fn shares_at_height(
+    State(state): State<Arc<AppState>>,
+    HeightPath(height): HeightPath,
+    Query(params): Query<SharesAtHeightQuery>,
+) -> Result<Masked<SharesAtHeightResponse>, ApiError> {
+    let order = ShareOrder::parse(params.sort.as_deref())?;
+    let tip_height = state
+        .chain_store_handle
+        .get_tip_height()
+        .map_err(|e| ApiError::ServerError(e.to_string()))?
+        .unwrap_or(0);
+    check_height_near_tip(height, tip_height)?;
+
+    let shares = retry_read(|| state.chain_store_handle.get_shares_at_height(height)).await?;
+    let mut shares: Vec<(BlockHash, ShareBlock)> = shares.into_iter().collect();
+    order.sort(&mut shares);
+    let main_chain = MainChain::new(&state.chain_store_handle, state.app_config.max_scan_items);
+    let share_infos: Vec<ShareInfo> = shares
+        .iter()
+        .map(|(hash, share)| {
+            ShareInfo::new(hash, share, share_confirmations(&main_chain, hash, height))
+        })
+        .collect();
+
+    Ok(Masked::new(
+        SharesAtHeightResponse {
+            height,
+            shares: share_infos,
+        },
+        params.fields.as_deref(),
+    ))
+}
+
+async fn total_work(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<TotalWorkResponse>, ApiError> {
+    let work = retry_read(|| state.chain_store_handle.get_total_work()).await?;
+    Ok(Json(TotalWorkResponse {
+        total_work: format!("{:x}", work),
+    }))
+}
+
+/// Compare two hex work values, so frontends don't need big integers
+async fn work_compare(
+    Query(params): Query<WorkCompareQuery>,
+) -> Result<Json<WorkCompareResponse>, ApiError> {
+    let a = parse_work("a", &params.a)?;
+    let b = parse_work("b", &params.b)?;
+    // Subtract the smaller from the larger, Work can't go negative
+    let difference = if a >= b { a - b } else { b - a };
+    Ok(Json(WorkCompareResponse {
+        a_ge_b: a >= b,
+        difference: format!("{:x}", difference),
+    }))
+}
+
+/// Parse hex work of at most 64 digits, with an optional 0x prefix
+fn parse_work(field: &str, value: &str) -> Result<Work, ApiError> {
+    let digits = value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+        .unwrap_or(value);
+    // Checked up front, the hex parser slices by byte and accepts a sign
+    if digits.is_empty() || digits.len() > 64 || !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(ApiError::BadRequest(format!(
+            "Invalid {field}: expected up to 64 hex digits"
+        )));
+    }
+    Work::from_unprefixed_hex(digits)
+        .map_err(|e| ApiError::BadRequest(format!("Invalid {field}: {e}")))
+}
+
+async fn chain_locator(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<ChainLocatorResponse>, ApiError> {
+    let locator = state
+        .chain_store_handle
+        .build_locator()
+        .map_err(|e| ApiError::ServerError(e.to_string()))?;
+    Ok(Json(ChainLocatorResponse {
+        locator: locator.iter().map(|h| h.to_string()).collect(),
+    }))
+}
+
+/// Resolve each hash of a peer's locator against the local store, for
+/// debugging sync. Read only. The locator is limited to the max page size.
+async fn chain_locator_resolve(
+    State(state): State<Arc<AppState>>,
+    Json(locator): Json<Vec<String>>,
+) -> Result<Json<LocatorResolveResponse>, ApiError> {
+    let max_entries = state.app_config.page_size.max as usize;
+    if locator.len() > max_entries {
+        return Err(ApiError::BadRequest(format!(
+            "Locator has {} hashes, at most {max_entries} are allowed",
+            locator.len()
+        )));
+    }
+
+    let mut entries = Vec::with_capacity(locator.len());
+    for (index, hash) in locator.iter().enumerate() {
+        let blockhash: BlockHash = hash.parse().map_err(|e| {
+            ApiError::BadRequest(format!("Invalid hash {hash} at index {index}: {e}"))
+        })?;
+        let known = state.chain_store_handle.get_share(&blockhash).is_some();
+        let height = if known {
+            state.chain_store_handle.get_share_height(&blockhash)
+        } else {
+            None
+        };
+        entries.push(LocatorEntry {
+            index,
+            hash: blockhash.to_string(),
+            known,
+            height,
+        });
+    }
+
+    let fork_point = entries.iter().find(|entry| entry.known).cloned();
+    Ok(Json(LocatorResolveResponse {
+        entries,
+        fork_point,
+    }))
+}
+
+async fn chain_info(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let (tip, info) = chain_info_response(&state).await?;
+    let etag = tip_etag(
+        &tip,
+        info.height,
+        info.uncles.len(),
+        info.current_share_target.as_deref(),
+    );
+    Ok(etag_response(&headers, etag, info))
+}
+
+/// Share target of the latest job in the tracker, None when no job is
+/// loaded or it has no share commitment
+fn current_share_target(tracker: &JobTracker) -> Option<CompactTarget> {
+    tracker
+        .get_job(tracker.get_latest_job_id())?
+        .share_commitment
+        .map(|commitment| commitment.bits)
+}
+
+/// The chain_info body, along with the tip it describes
+async fn chain_info_response(state: &AppState) -> Result<(BlockHash, ChainInfoResponse), ApiError> {
+    let tip = state.chain_store_handle.get_chain_tip();
+    let height = state
+        .chain_store_handle
+        .get_tip_height()
+        .map_err(|e| ApiError::ServerError(e.to_string()))?;
+    let total_work = retry_read(|| state.chain_store_handle.get_total_work()).await?;
+    let (_, uncles) = state.chain_store_handle.get_chain_tip_and_uncles();
+    let share_target = current_share_target(&state.tracker_handle);
+
+    let info = ChainInfoResponse {
+        tip: tip.to_string(),
+        height,
+        total_work: format!("{:x}", total_work),
+        uncles: uncles.iter().map(|u| u.to_string()).collect(),
+        network: state.app_config.network.to_string(),
+        tip_age_secs: tip_age_secs(&state.chain_store_handle),
+        ancestry_depth_limit: ancestry_depth_limit(state.app_config.network),
+        current_share_target: share_target
+            .map(|bits| hex::encode(Target::from_compact(bits).to_be_bytes())),
+        current_share_difficulty: share_target.map(compact_difficulty),
+    };
+    Ok((tip, info))
+}
+
+/// chain_info with share and reorg counters. The share counters come from
+/// the stats cache refreshed in the background, so this never scans the
+/// chain. The reorg count is kept by the chain store.
+async fn chain_info_extended(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<ChainInfoExtendedResponse>, ApiError> {
+    let (_, info) = chain_info_response(&state).await?;
+    let stats = state
+        .stats_snapshot
+        .read()
+        .ok()
+        .and_then(|snapshot| snapshot.clone());
+    Ok(Json(ChainInfoExtendedResponse {
+        info,
+        total_shares: stats.as_ref().map(|stats| stats.total_shares),
+        uncle_rate: stats.as_ref().map(|stats| {
+            if stats.total_shares == 0 {
+                0.0
+            } else {
+                stats.total_uncles as f64 / stats.total_shares as f64
+            }
+        }),
+        total_difficulty: stats
+            .as_ref()
+            .and_then(|stats| stats.total_difficulty)
+            .map(|work| format!("{:x}", work)),
+        stats_height: stats.as_ref().map(|stats| stats.height),
+        stats_truncated: stats.as_ref().map(|stats| stats.truncated),
+        reorg_count: state.chain_store_handle.get_reorg_count(),
+    }))
+}
+
+/// Counters accumulated over a range of share heights
+struct ChainStats {
+    total_shares: u64,
+    total_uncles: u64,
+    /// None once the sum has overflowed
+    total_difficulty: Option<Work>,
+}
+
+impl ChainStats {
+    fn empty() -> Self {
+        Self {
+            total_shares: 0,
+            total_uncles: 0,
+            total_difficulty: Some(Work::from_be_bytes([0; 32])),
+        }
+    }
+
+    /// Add the shares stored at height to the counters
+    fn add_height(mut self, chain_store: &ChainStoreHandle, height: u32) -> Self {
+        if let Ok(shares) = chain_store.get_shares_at_height(height) {
+            for share in shares.values() {
+                self.total_shares += 1;
+                self.total_uncles += share.header.uncles.len() as u64;
+            }
+            self.total_difficulty = self.total_difficulty.and_then(|total| {
+                sum_work(
+                    std::iter::once(total)
+                        .chain(shares.values().map(|share| share.header.get_work())),
+                )
+            });
+        }
+        self
+    }
+
+    /// Combine counters from two disjoint height ranges
+    fn merge(self, other: Self) -> Self {
+        Self {
+            total_shares: self.total_shares + other.total_shares,
+            total_uncles: self.total_uncles + other.total_uncles,
+            total_difficulty: self
+                .total_difficulty
+                .zip(other.total_difficulty)
+                .and_then(|(a, b)| sum_work([a, b])),
+        }
+    }
+}
+
+/// Count shares, uncle references and the work their bits commit to over
+/// the inclusive height range.
+fn count_chain_stats(
+    chain_store: &ChainStoreHandle,
+    from_height: u32,
+    to_height: u32,
+) -> ChainStats {
+    (from_height..=to_height).fold(ChainStats::empty(), |stats, height| {
+        stats.add_height(chain_store, height)
+    })
+}
+
+/// Same as count_chain_stats, but splits the height range across the rayon
+/// thread pool. Store reads go straight to RocksDB, which is safe to read
+/// from concurrently.
+fn count_chain_stats_parallel(
+    chain_store: &ChainStoreHandle,
+    from_height: u32,
+    to_height: u32,
+) -> ChainStats {
+    (from_height..=to_height)
+        .into_par_iter()
+        .fold(ChainStats::empty, |stats, height| {
+            stats.add_height(chain_store, height)
+        })
+        .reduce(ChainStats::empty, ChainStats::merge)
+}
+
+/// Returns share counts and accumulated share difficulty for the whole chain,
+/// or for the heights below the tip that fit in the scan budget.
+///
+/// With exact=true&parallel=true the heights are counted in parallel on the
+/// rayon pool, otherwise they are counted serially. Same as
+/// /chain/stats/exact, kept for existing clients.
+async fn chain_stats(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ChainStatsQuery>,
+) -> Result<Json<ChainStatsResponse>, ApiError> {
+    let parallel = query.exact.unwrap_or(false) && query.parallel.unwrap_or(false);
+    count_chain_stats_response(&state, parallel).await.map(Json)
+}
+
+/// Counts the chain stats by scanning every height up to the tip, bounded
+/// by the scan budget. This is expensive: it reads every share in the
+/// range, and is behind the heavy request limit, so callers beyond
+/// max_concurrent_heavy_requests get a 503. Status pages should use
+/// /chain/stats/cached instead.
+///
+/// With parallel=true the heights are counted on the rayon pool.
+async fn chain_stats_exact(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ChainStatsExactQuery>,
+) -> Result<Json<ChainStatsResponse>, ApiError> {
+    count_chain_stats_response(&state, query.parallel.unwrap_or(false))
+        .await
+        .map(Json)
+}
+
+/// Returns the chain stats last counted by the background stats cache,
+/// without touching the chain. The counts are as of the returned height,
+/// which may be behind the tip by up to the stats cache interval.
+/// Answers 503 until the cache is first filled.
+async fn chain_stats_cached(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<ChainStatsResponse>, ApiError> {
+    let stats = state
+        .stats_snapshot
+        .read()
+        .ok()
+        .and_then(|snapshot| snapshot.clone())
+        .ok_or_else(|| {
+            ApiError::ServiceUnavailable("Chain stats have not been counted yet".to_string())
+        })?;
+    Ok(Json(ChainStatsResponse {
+        height: Some(stats.height),
+        total_shares: stats.total_shares,
+        total_uncles: stats.total_uncles,
+        total_difficulty: stats.total_difficulty.map(|work| format!("{:x}", work)),
+        total_difficulty_decimal: stats.total_difficulty.map(|work| work.to_string()),
+        total_difficulty_overflowed: stats.total_difficulty.is_none(),
+        from_height: stats.from_height,
+        truncated: stats.truncated,
+    }))
+}
+
+/// Scan the heights up to the tip that fit in the scan budget and count
+/// the chain stats over them
+async fn count_chain_stats_response(
+    state: &AppState,
+    parallel: bool,
+) -> Result<ChainStatsResponse, ApiError> {
+    let height = state
+        .chain_store_handle
+        .get_tip_height()
+        .map_err(|e| ApiError::ServerError(e.to_string()))?;
+
+    let chain_store = state.chain_store_handle.clone();
+    let to_height = height.unwrap_or(0);
+    let (from_height, truncated) = scan_budget_from(&state.app_config, 0, to_height);
+    let stats = tokio::task::spawn_blocking(move || {
+        if parallel {
+            count_chain_stats_parallel(&chain_store, from_height, to_height)
+        } else {
+            count_chain_stats(&chain_store, from_height, to_height)
+        }
+    })
+    .await
+    .map_err(|e| ApiError::ServerError(e.to_string()))?;
+
+    Ok(ChainStatsResponse {
+        height,
+        total_shares: stats.total_shares,
+        total_uncles: stats.total_uncles,
+        total_difficulty: stats.total_difficulty.map(|work| format!("{:x}", work)),
+        total_difficulty_decimal: stats.total_difficulty.map(|work| work.to_string()),
+        total_difficulty_overflowed: stats.total_difficulty.is_none(),
+        from_height,
+        truncated,
+    })
+}
+
+/// Number of share heights below the tip scanned by blocks_found
+const BLOCKS_FOUND_SCAN_WINDOW: u32 = 1000;
+
+/// Returns the most recent shares whose bitcoin header meets its own
+/// bitcoin target, i.e. shares that were also valid bitcoin blocks.
+///
+/// Only the last BLOCKS_FOUND_SCAN_WINDOW heights are scanned, so older
+/// blocks are not reported. Results are ordered newest first.
+async fn blocks_found(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<BlocksFoundQuery>,
+) -> Result<Json<BlocksFoundResponse>, ApiError> {
+    let tip_height = state
+        .chain_store_handle
+        .get_tip_height()
+        .map_err(|e| ApiError::ServerError(e.to_string()))?
+        .unwrap_or(0);
+
+    let limit = params.limit.unwrap_or(10).min(100);
+    let from_height = tip_height.saturating_sub(BLOCKS_FOUND_SCAN_WINDOW - 1);
+
+    let mut blocks: Vec<FoundBlock> = Vec::new();
+    for height in (from_height..=tip_height).rev() {
+        if blocks.len() >= limit {
+            break;
+        }
+        let Ok(shares) = state.chain_store_handle.get_shares_at_height(height) else {
+            continue;
+        };
+        let mut found: Vec<FoundBlock> = shares
+            .iter()
+            .filter_map(|(hash, share)| {
+                let bitcoin_header = &share.header.bitcoin_header;
+                let bitcoin_block_hash =
+                    bitcoin_header.validate_pow(bitcoin_header.target()).ok()?;
+                Some(FoundBlock {
+                    share_hash: hash.to_string(),
+                    bitcoin_block_hash: bitcoin_block_hash.to_string(),
+                    height,
+                    timestamp: share.header.time,
+                    miner_pubkey: share.header.miner_pubkey.to_string(),
+                })
+            })
+            .collect();
+        found.sort_by(|a, b| a.share_hash.cmp(&b.share_hash));
+        blocks.extend(found);
+    }
+    blocks.truncate(limit);
+
+    Ok(Json(BlocksFoundResponse {
+        blocks,
+        from_height,
+        to_height: tip_height,
+    }))
+}
+
+/// Resolve an inclusive from..=to height range from query parameters. to
+/// defaults to the tip height and from to a default page below to. The
+/// range is capped at the configured max page size, counting down from to.
+fn capped_height_range(
+    state: &AppState,
+    from: Option<u32>,
+    to: Option<u32>,
+) -> Result<(u32, u32), ApiError> {
+    let to_height = match to {
+        Some(to) => to,
+        None => state
+            .chain_store_handle
+            .get_tip_height()
+            .map_err(|e| ApiError::ServerError(e.to_string()))?
+            .unwrap_or(0),
+    };
+    let page_size = state.app_config.page_size;
+    let from_height = from.unwrap_or(to_height.saturating_sub(page_size.default.saturating_sub(1)));
+    if from_height > to_height {
+        return Err(ApiError::BadRequest(format!(
+            "from ({from_height}) must not be greater than to ({to_height})"
+        )));
+    }
+    let from_height = from_height.max(to_height.saturating_sub(page_size.max.saturating_sub(1)));
+    Ok((from_height, to_height))
+}
+
+/// Share and uncle counts for each height in from..=to, so a chart of
+/// chain density needs one request. The range is capped at the configured
+/// max page size, counting down from to.
+async fn chain_density(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<DensityQuery>,
+) -> Result<Json<Vec<HeightDensity>>, ApiError> {
+    let (from_height, to_height) = capped_height_range(&state, params.from, params.to)?;
+
+    let density = (from_height..=to_height)
+        .map(|height| {
+            let shares = state
+                .chain_store_handle
+                .get_shares_at_height(height)
+                .map_err(|e| ApiError::ServerError(e.to_string()))?;
+            Ok(HeightDensity {
+                height,
+                share_count: shares.len(),
+                uncle_count: shares.values().map(|s| s.header.uncles.len()).sum(),
+            })
+        })
+        .collect::<Result<Vec<_>, ApiError>>()?;
+    Ok(Json(density))
+}
+
+/// Most buckets a single /chain/share_rate request may return
+const MAX_SHARE_RATE_BUCKETS: u64 = 1440;
+
+/// Share counts and difficulty in time buckets over a recent window, for
+/// a throughput chart. Buckets are aligned to multiples of the bucket
+/// size and bucketed by share header time.
+async fn chain_share_rate(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<ShareRateQuery>,
+) -> Result<Json<ShareRateResponse>, ApiError> {
+    let window = params.window.unwrap_or(3600);
+    let bucket = params.bucket.unwrap_or(60);
+    if bucket == 0 {
+        return Err(ApiError::BadRequest(
+            "bucket must be at least 1".to_string(),
+        ));
+    }
+    if window / bucket > MAX_SHARE_RATE_BUCKETS {
+        return Err(ApiError::BadRequest(format!(
+            "window / bucket must be at most {MAX_SHARE_RATE_BUCKETS} buckets"
+        )));
+    }
+
+    let tip_height = state
+        .chain_store_handle
+        .get_tip_height()
+        .map_err(|e| ApiError::ServerError(e.to_string()))?
+        .unwrap_or(0);
+    let (from_height, budget_cut) = scan_budget_from(&state.app_config, 0, tip_height);
+    let now = chrono::Utc::now().timestamp().max(0) as u64;
+    let chain_store = state.chain_store_handle.clone();
+    let (buckets, reached_window_start) = tokio::task::spawn_blocking(move || {
+        share_rate_buckets(&chain_store, from_height, tip_height, now, window, bucket)
+    })
+    .await
+    .map_err(|e| ApiError::ServerError(e.to_string()))??;
+
+    Ok(Json(ShareRateResponse {
+        window,
+        bucket,
+        buckets,
+        truncated: budget_cut && !reached_window_start,
+    }))
+}
+
+/// Bucket the shares timed in the window ending at now, walking down from
+/// to_height. The walk stops at the first height whose shares all predate
+/// the window, or at from_height. Returns the buckets and whether the
+/// start of the window was reached.
+fn share_rate_buckets(
+    chain_store: &ChainStoreHandle,
+    from_height: u32,
+    to_height: u32,
+    now: u64,
+    window: u64,
+    bucket: u64,
+) -> Result<(Vec<ShareRateBucket>, bool), ApiError> {
+    let first_bucket_start = now.saturating_sub(window) / bucket * bucket;
+    let mut buckets: Vec<ShareRateBucket> = (first_bucket_start..=now)
+        .step_by(bucket as usize)
+        .map(|bucket_start| ShareRateBucket {
+            bucket_start,
+            count: 0,
+            total_difficulty: 0.0,
+        })
+        .collect();
+
+    for height in (from_height..=to_height).rev() {
+        let shares = chain_store
+            .get_shares_at_height(height)
+            .map_err(|e| ApiError::ServerError(e.to_string()))?;
+        if shares
+            .values()
+            .all(|share| u64::from(share.header.time) < first_bucket_start)
+        {
+            return Ok((buckets, true));
+        }
+        for share in shares.values() {
+            let time = u64::from(share.header.time);
+            if time < first_bucket_start || time > now {
+                continue;
+            }
+            let entry = &mut buckets[((time - first_bucket_start) / bucket) as usize];
+            entry.count += 1;
+            entry.total_difficulty += compact_difficulty(share.header.bits);
+        }
+    }
+    Ok((buckets, from_height == 0))
+}
+
+/// Shares covered by /chain/submission_intervals and the
+/// share_submission_interval_p95_seconds gauge when no window is given
+const DEFAULT_SUBMISSION_INTERVALS_WINDOW: usize = 100;
+/// Most shares a single /chain/submission_intervals request may cover
+const MAX_SUBMISSION_INTERVALS_WINDOW: usize = 10_000;
+
+/// Distribution of the time between share submissions over the last
+/// window shares, from share header times. A low median or p95 next to
+/// the usual share interval points at miners submitting in bursts.
+async fn chain_submission_intervals(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<SubmissionIntervalsQuery>,
+) -> Result<Json<SubmissionIntervalsResponse>, ApiError> {
+    let window = params.window.unwrap_or(DEFAULT_SUBMISSION_INTERVALS_WINDOW);
+    if !(2..=MAX_SUBMISSION_INTERVALS_WINDOW).contains(&window) {
+        return Err(ApiError::BadRequest(format!(
+            "window must be between 2 and {MAX_SUBMISSION_INTERVALS_WINDOW}"
+        )));
+    }
+    let compute_state = state.clone();
+    let response =
+        tokio::task::spawn_blocking(move || submission_intervals(&compute_state, window))
+            .await
+            .map_err(|e| ApiError::ServerError(e.to_string()))??;
+    Ok(Json(response))
+}
+
+/// Collect the header times of the last window shares, walking down from
+/// the tip over the scan budget, and summarise the gaps between them
+fn submission_intervals(
+    state: &AppState,
+    window: usize,
+) -> Result<SubmissionIntervalsResponse, ApiError> {
+    let tip_height = state
+        .chain_store_handle
+        .get_tip_height()
+        .map_err(|e| ApiError::ServerError(e.to_string()))?
+        .unwrap_or(0);
+    let (from_height, budget_cut) = scan_budget_from(&state.app_config, 0, tip_height);
+
+    let mut times: Vec<u32> = Vec::with_capacity(window);
+    for height in (from_height..=tip_height).rev() {
+        if times.len() >= window {
+            break;
+        }
+        let shares = state
+            .chain_store_handle
+            .get_shares_at_height(height)
+            .map_err(|e| ApiError::ServerError(e.to_string()))?;
+        times.extend(shares.values().map(|share| share.header.time));
+    }
+    let truncated = budget_cut && times.len() < window;
+
+    // Several shares at the last height read can overshoot the window, so
+    // keep the newest
+    times.sort_unstable();
+    let times = &times[times.len().saturating_sub(window)..];
+    let mut intervals: Vec<u64> = times
+        .windows(2)
+        .map(|pair| u64::from(pair[1] - pair[0]))
+        .collect();
+    intervals.sort_unstable();
+
+    Ok(SubmissionIntervalsResponse {
+        window,
+        shares: times.len(),
+        min: intervals.first().copied(),
+        median: nearest_rank(&intervals, 50),
+        p95: nearest_rank(&intervals, 95),
+        max: intervals.last().copied(),
+        truncated,
+    })
+}
+
+/// Nearest rank percentile of sorted values, None when empty
+fn nearest_rank(sorted: &[u64], percentile: usize) -> Option<u64> {
+    let rank = (sorted.len() * percentile).div_ceil(100).max(1);
+    sorted.get(rank - 1).copied()
+}
+
+/// The main chain share at each height in from..=to, in ascending height
+/// order. Heights without a main chain share, e.g. above the tip, are
+/// skipped. The range is capped like /chain/density.
+///
+/// The shares come from a single walk back from the tip to from, so from
+/// is raised to keep the walk within max_scan_items, like /chain/stats.
+async fn chain_main(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<MainChainQuery>,
+) -> Result<Json<MainChainResponse>, ApiError> {
+    let (from_height, to_height) = capped_height_range(&state, params.from, params.to)?;
+    let tip = state.chain_store_handle.get_chain_tip();
+    let tip_height = state
+        .chain_store_handle
+        .get_tip_height()
+        .map_err(|e| ApiError::ServerError(e.to_string()))?
+        .unwrap_or(0);
+
+    let (from_height, truncated) = scan_budget_from(&state.app_config, from_height, tip_height);
+
+    let mut shares: Vec<MainChainShare> =
+        walk_main_chain(&state.chain_store_handle, tip, tip_height, from_height)
+            .filter(|(height, _, _)| (from_height..=to_height).contains(height))
+            .map(|(height, hash, share)| MainChainShare {
+                height,
+                share: ShareInfo::new(&hash, &share, tip_height - height),
+            })
+            .collect();
+    shares.reverse();
+    Ok(Json(MainChainResponse {
+        shares,
+        from_height,
+        to_height,
+        truncated,
+    }))
+}
+
+/// Default number of main chain shares summed by coinbase_totals
+const COINBASE_TOTALS_DEFAULT_WINDOW: u32 = 100;
+/// Largest window accepted by coinbase_totals
+const COINBASE_TOTALS_MAX_WINDOW: u32 = 1000;
+
+/// Sums the bitcoin coinbase outputs of the last `window` main chain
+/// shares, in total and per address. Addresses are parsed the same way as
+/// the coinbase distribution metric. The window defaults to
+/// COINBASE_TOTALS_DEFAULT_WINDOW and is capped at
+/// COINBASE_TOTALS_MAX_WINDOW.
+async fn coinbase_totals(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<CoinbaseTotalsQuery>,
+) -> Result<Json<CoinbaseTotalsResponse>, ApiError> {
+    let window = params
+        .window
+        .unwrap_or(COINBASE_TOTALS_DEFAULT_WINDOW)
+        .min(COINBASE_TOTALS_MAX_WINDOW);
+
+    let mut total_sats = 0u64;
+    let mut per_address: HashMap<String, u64> = HashMap::new();
+    let mut share_count = 0;
+    let mut current = state.chain_store_handle.get_chain_tip();
+    while share_count < window {
+        let Some(share) = state.chain_store_handle.get_share(&current) else {
+            break;
+        };
+        share_count += 1;
+        if let Some(coinbase) = share
+            .bitcoin_transactions
+            .first()
+            .filter(|tx| tx.is_coinbase())
+        {
+            total_sats += coinbase
+                .output
+                .iter()
+                .map(|tx_out| tx_out.value.to_sat())
+                .sum::<u64>();
+            for (_, address, value) in
+                parse_coinbase::address_outputs(&coinbase.output, state.app_config.network)
+            {
+                *per_address.entry(address.to_string()).or_default() += value.to_sat();
+            }
+        }
+        if share.header.prev_share_blockhash == BlockHash::all_zeros() {
+            break;
+        }
+        current = share.header.prev_share_blockhash;
+    }
+
+    let mut per_address: Vec<AddressTotal> = per_address
+        .into_iter()
+        .map(|(address, sats)| AddressTotal { address, sats })
+        .collect();
+    per_address.sort_by(|a, b| b.sats.cmp(&a.sats).then_with(|| a.address.cmp(&b.address)));
+
+    Ok(Json(CoinbaseTotalsResponse {
+        total_sats,
+        per_address,
+        share_count,
+    }))
+}
+
+/// Walks the main chain from the tip towards genesis and reports store
+/// inconsistencies: a parent share that is missing, a stored height that
+/// doesn't follow the walk, a parent whose block index doesn't list the
+/// share as a child, or an uncle that is missing.
+///
+/// The walk stops at the limit, which defaults to and is capped at the
+/// scan budget.
+async fn chain_verify(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<VerifyQuery>,
+) -> Result<Json<VerifyResponse>, ApiError> {
+    let limit = params
+        .limit
+        .unwrap_or(state.app_config.max_scan_items)
+        .min(state.app_config.max_scan_items);
+    let chain_store = &state.chain_store_handle;
+    let mut expected_height = chain_store
+        .get_tip_height()
+        .map_err(|e| ApiError::ServerError(e.to_string()))?;
+
+    let mut checked = 0;
+    let mut errors: Vec<ChainProblem> = Vec::new();
+    let mut current = chain_store.get_chain_tip();
+    let mut reached_genesis = false;
+    while checked < limit {
+        let hash = current.to_string();
+        let mut problem = |problem: String| {
+            errors.push(ChainProblem {
+                hash: hash.clone(),
+                problem,
+            })
+        };
+        let Some(share) = chain_store.get_share(&current) else {
+            problem("share not found".to_string());
+            break;
+        };
+        checked += 1;
+
+        let height = chain_store.get_share_height(&current);
+        if height != expected_height {
+            problem(format!(
+                "height {height:?} does not match walk height {expected_height:?}"
+            ));
+        }
+        for uncle in chain_store.get_missing_blockhashes(&share.header.uncles) {
+            problem(format!("uncle {uncle} not found"));
+        }
+
+        let prev = share.header.prev_share_blockhash;
+        if prev == BlockHash::all_zeros() {
+            reached_genesis = true;
+            break;
+        }
+        if chain_store.get_share(&prev).is_none() {
+            problem(format!("parent {prev} not found"));
+            break;
+        }
+        let children = chain_store
+            .store_handle()
+            .get_children_blockhashes(&prev)
+            .ok()
+            .flatten()
+            .unwrap_or_default();
+        if !children.contains(&current) {
+            problem(format!(
+                "parent {prev} block index does not list this share"
+            ));
+        }
+        expected_height = height.and_then(|height| height.checked_sub(1));
+        current = prev;
+    }
+
+    Ok(Json(VerifyResponse {
+        checked,
+        errors,
+        truncated: !reached_genesis && checked == limit,
+    }))
+}
+
+/// Returns the most recent reorgs, newest first. limit defaults to and is
+/// capped at the REORG_HISTORY_CAPACITY reorgs the chain store keeps.
+async fn chain_reorgs(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<ReorgsQuery>,
+) -> Json<Vec<ReorgInfo>> {
+    let limit = params
+        .limit
+        .map_or(REORG_HISTORY_CAPACITY, |limit| limit as usize)
+        .min(REORG_HISTORY_CAPACITY);
+    let reorgs = state
+        .chain_store_handle
+        .get_reorg_history(limit)
+        .into_iter()
+        .map(|reorg| ReorgInfo {
+            old_tip: reorg.old_tip.to_string(),
+            new_tip: reorg.new_tip.to_string(),
+            depth: reorg.depth,
+            timestamp: reorg.timestamp,
+        })
+        .collect();
+    Json(reorgs)
+}
+
+/// Returns the most recent chain tips, newest first. limit defaults to and
+/// is capped at the TIP_HISTORY_CAPACITY tips the chain store keeps.
+async fn chain_tip_history(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<TipHistoryQuery>,
+) -> Json<Vec<TipHistoryInfo>> {
+    let limit = params
+        .limit
+        .map_or(TIP_HISTORY_CAPACITY, |limit| limit as usize)
+        .min(TIP_HISTORY_CAPACITY);
+    let tips = state
+        .chain_store_handle
+        .get_tip_history(limit)
+        .into_iter()
+        .map(|tip| TipHistoryInfo {
+            hash: tip.hash.to_string(),
+            height: tip.height,
+            total_work: format!("{:x}", tip.total_work),
+            timestamp: tip.timestamp,
+        })
+        .collect();
+    Json(tips)
+}
+
+/// Returns shares in the last `window` heights that are neither on the
+/// main chain nor included as uncles, sorted by height descending.
+///
+/// Shares are counted as uncles if the current tip lists them or if any
+/// main chain share in the window references them.
+async fn chain_orphans(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<OrphansQuery>,
+) -> Result<Json<OrphansResponse>, ApiError> {
+    let tip_height = state
+        .chain_store_handle
+        .get_tip_height()
+        .map_err(|e| ApiError::ServerError(e.to_string()))?
+        .unwrap_or(0);
+
+    let window = params.window.unwrap_or(50).clamp(1, 100);
+    // The window is read and classified walking down from the tip, so it
+    // has to fit in the scan budget
+    let (from_height, truncated) = scan_budget_from(
+        &state.app_config,
+        tip_height.saturating_sub(window - 1),
+        tip_height,
+    );
+
+    let main_chain = MainChain::new(&state.chain_store_handle, state.app_config.max_scan_items);
+    let (_, mut uncles) = state.chain_store_handle.get_chain_tip_and_uncles();
+
+    let mut off_chain: Vec<(u32, BlockHash, BlockHash, String)> = Vec::new();
+    for height in (from_height..=tip_height).rev() {
+        let Ok(shares) = state.chain_store_handle.get_shares_at_height(height) else {
+            continue;
+        };
+        for (hash, share) in shares {
+            if main_chain.contains(&hash, height) {
+                uncles.extend(share.header.uncles.iter().copied());
+            } else {
+                off_chain.push((
+                    height,
+                    hash,
+                    share.header.prev_share_blockhash,
+                    share.header.miner_pubkey.to_string(),
+                ));
+            }
+        }
+    }
+
+    let mut orphans: Vec<OrphanShare> = off_chain
+        .into_iter()
+        .filter(|(_, hash, _, _)| !uncles.contains(hash))
+        .map(|(height, hash, prev_hash, miner_pubkey)| OrphanShare {
+            hash: hash.to_string(),
+            prev_hash: prev_hash.to_string(),
+            height,
+            miner_pubkey,
+        })
+        .collect();
+    orphans.sort_by(|a, b| b.height.cmp(&a.height).then_with(|| a.hash.cmp(&b.hash)));
+
+    Ok(Json(OrphansResponse {
+        orphans,
+        from_height,
+        to_height: tip_height,
+        truncated,
+    }))
+}
+
+/// Seconds since the timestamp of the chain tip share, or None if the tip
+/// share can't be read. A tip timestamp in the future, e.g. from clock skew,
+/// gives an age of 0.
+fn tip_age_secs(chain_store: &ChainStoreHandle) -> Option<u64> {
+    let tip = chain_store.get_chain_tip();
+    let share = chain_store.get_share(&tip)?;
+    let now = chrono::Utc::now().timestamp().max(0) as u64;
+    Some(now.saturating_sub(share.header.time as u64))
+}
+
+/// How long /chain/health waits for the metrics actor to respond
+const METRICS_HEALTH_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Readiness check that verifies the chain store and metrics actor respond.
+///
+/// Returns 503 if the tip share can't be read from the store, the metrics
+/// actor doesn't answer within METRICS_HEALTH_TIMEOUT, or the tip is older
+/// than the configured stale_after_secs.
+async fn chain_health(
+    State(state): State<Arc<AppState>>,
+) -> (StatusCode, Json<ChainHealthResponse>) {
+    let tip_age_secs = tip_age_secs(&state.chain_store_handle);
+    let chain_tip_reachable = tip_age_secs.is_some();
+    let tip_stale = match (tip_age_secs, state.app_config.stale_after_secs) {
+        (Some(age), Some(stale_after)) => age > stale_after,
+        _ => false,
+    };
+
+    let metrics_reachable = metrics_reachable(&state.metrics_handle).await;
+
+    let healthy = chain_tip_reachable && metrics_reachable && !tip_stale;
+    let status_code = if healthy {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (
+        status_code,
+        Json(ChainHealthResponse {
+            status: if healthy { "ok" } else { "unhealthy" }.to_string(),
+            chain_tip_reachable,
+            metrics_reachable,
+            tip_age_secs,
+            tip_stale,
+        }),
+    )
+}
+
+/// Whether the metrics actor answers within METRICS_HEALTH_TIMEOUT
+async fn metrics_reachable(metrics_handle: &MetricsHandle) -> bool {
+    // Run in a task so a dead metrics actor shows up as an error, not a panic
+    let metrics_handle = metrics_handle.clone();
+    matches!(
+        tokio::time::timeout(
+            METRICS_HEALTH_TIMEOUT,
+            tokio::spawn(async move { metrics_handle.get_metrics().await }),
+        )
+        .await,
+        Ok(Ok(_))
+    )
+}
+
+/// Readiness probe for orchestrators: 200 once the chain store has a tip
+/// share and the metrics actor responds, 503 before that. Unlike
+/// /chain/health a stale tip does not make the node unready.
+async fn health_ready(State(state): State<Arc<AppState>>) -> (StatusCode, Json<ReadinessResponse>) {
+    let chain_tip_reachable = tip_age_secs(&state.chain_store_handle).is_some();
+    let metrics_reachable = metrics_reachable(&state.metrics_handle).await;
+
+    let ready = chain_tip_reachable && metrics_reachable;
+    let status_code = if ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (
+        status_code,
+        Json(ReadinessResponse {
+            status: if ready { "ready" } else { "not ready" }.to_string(),
+            chain_tip_reachable,
+            metrics_reachable,
+        }),
+    )
+}
+
+/// Most shares walked back from a share, e.g. from each side looking for
+/// a common ancestor. Mainnet pools run much longer share chains than
+/// the short test chains on signet and regtest.
+fn ancestry_depth_limit(network: bitcoin::Network) -> usize {
+    match network {
+        bitcoin::Network::Bitcoin => 20_000,
+        bitcoin::Network::Testnet | bitcoin::Network::Testnet4 => 5_000,
+        _ => 1_000,
+    }
+}
+
+/// Compare two shares field by field, e.g. competing shares at the same height.
+async fn chain_compare(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<CompareQuery>,
+) -> Result<Json<ShareCompareResponse>, ApiError> {
+    let a_hash = parse_share_hash("a", &query.a)?;
+    let b_hash = parse_share_hash("b", &query.b)?;
+    let a = state
+        .chain_store_handle
+        .get_share(&a_hash)
+        .ok_or_else(|| ApiError::NotFound(format!("Share {a_hash} not found")))?;
+    let b = state
+        .chain_store_handle
+        .get_share(&b_hash)
+        .ok_or_else(|| ApiError::NotFound(format!("Share {b_hash} not found")))?;
+
+    let common_ancestor = find_common_ancestor(
+        &state.chain_store_handle,
+        a_hash,
+        b_hash,
+        ancestry_depth_limit(state.app_config.network),
+    );
+    let uncles = |share: &ShareBlock| -> Vec<String> {
+        share.header.uncles.iter().map(|u| u.to_string()).collect()
+    };
+
+    Ok(Json(ShareCompareResponse {
+        a: a_hash.to_string(),
+        b: b_hash.to_string(),
+        common_ancestor: common_ancestor.map(|h| h.to_string()),
+        prev_hash: FieldComparison::new(
+            a.header.prev_share_blockhash.to_string(),
+            b.header.prev_share_blockhash.to_string(),
+        ),
+        uncles: FieldComparison::new(uncles(&a), uncles(&b)),
+        miner_pubkey: FieldComparison::new(
+            a.header.miner_pubkey.to_string(),
+            b.header.miner_pubkey.to_string(),
+        ),
+        timestamp: FieldComparison::new(a.header.time, b.header.time),
+        bits: FieldComparison::new(
+            format!("{:08x}", a.header.bits.to_consensus()),
+            format!("{:08x}", b.header.bits.to_consensus()),
+        ),
+        bitcoin_block_hash: FieldComparison::new(
+            a.header.bitcoin_header.block_hash().to_string(),
+            b.header.bitcoin_header.block_hash().to_string(),
+        ),
+    }))
+}
+
+fn parse_share_hash(field: &str, value: &str) -> Result<BlockHash, ApiError> {
+    value
+        .parse()
+        .map_err(|e| ApiError::BadRequest(format!("Invalid {field} share hash: {e}")))
+}
+
+/// Collect up to max_walk shares walking back from hash along
+/// prev_share_blockhash, starting with hash itself.
+fn walk_ancestors(
+    chain_store: &ChainStoreHandle,
+    hash: BlockHash,
+    max_walk: usize,
+) -> Vec<BlockHash> {
+    let mut ancestors = Vec::new();
+    let mut current = hash;
+    while ancestors.len() < max_walk {
+        let Some(share) = chain_store.get_share(&current) else {
+            break;
+        };
+        ancestors.push(current);
+        if share.header.prev_share_blockhash == BlockHash::all_zeros() {
+            break;
+        }
+        current = share.header.prev_share_blockhash;
+    }
+    ancestors
+}
+
+/// Find the most recent share that is an ancestor of both a and b, where a
+/// share counts as its own ancestor. Each side walks at most max_walk
+/// shares.
+fn find_common_ancestor(
+    chain_store: &ChainStoreHandle,
+    a: BlockHash,
+    b: BlockHash,
+    max_walk: usize,
+) -> Option<BlockHash> {
+    let a_ancestors: HashSet<BlockHash> = walk_ancestors(chain_store, a, max_walk)
+        .into_iter()
+        .collect();
+    walk_ancestors(chain_store, b, max_walk)
+        .into_iter()
+        .find(|hash| a_ancestors.contains(hash))
+}
+
+/// Returns the genesis share for the configured network, so clients can
+/// detect a network mismatch before syncing.
+async fn chain_genesis(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<GenesisResponse>, ApiError> {
+    let network = state.app_config.network;
+    if genesis::genesis_data(network).is_err() {
+        return Err(ApiError::NotFound(format!(
+            "No genesis share defined for network {network}"
+        )));
+    }
+    let genesis = ShareBlock::build_genesis_for_network(network);
+    Ok(Json(GenesisResponse {
+        network: network.to_string(),
+        hash: genesis.block_hash().to_string(),
+        genesis,
+    }))
+}
+
+/// Validate a share posted as JSON without adding it to the chain.
+///
+/// By default validation stops at the first failed rule, like
+/// submit_share, and returns its reason. With ?verbose=true every rule is
+/// run and reported, so miner implementers can see all the rules a share
+/// breaks. Invalid shares are not an error, they get valid: false.
+async fn validate_share(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<ValidateShareQuery>,
+    Json(share): Json<ShareBlock>,
+) -> Json<ValidateShareResponse> {
+    let block_hash = share.block_hash().to_string();
+    if params.verbose.unwrap_or(false) {
+        let rules: Vec<ValidationRuleInfo> =
+            validation::validate_all(&share, &state.chain_store_handle, &SystemTimeProvider)
+                .await
+                .into_iter()
+                .map(|result| ValidationRuleInfo {
+                    rule: result.rule.to_string(),
+                    passed: result.passed,
+                    detail: result.detail,
+                })
+                .collect();
+        return Json(ValidateShareResponse {
+            block_hash,
+            valid: rules.iter().all(|rule| rule.passed),
+            reason: None,
+            rules: Some(rules),
+        });
+    }
+
+    let reason = validation::validate(&share, &state.chain_store_handle, &SystemTimeProvider)
+        .await
+        .err()
+        .map(|e| e.to_string());
+    Json(ValidateShareResponse {
+        block_hash,
+        valid: reason.is_none(),
+        reason,
+        rules: None,
+    })
+}
+
+/// Validate a share posted as JSON and add it to the chain.
+///
+/// Shares go through the same validation as shares received from peers.
+/// Only available when API auth is configured, so an open API can't be
+/// used to add shares.
+async fn submit_share(
+    State(state): State<Arc<AppState>>,
+    Json(share): Json<ShareBlock>,
+) -> Result<Json<SubmitShareResponse>, ApiError> {
+    if state.api_keys.is_empty() {
+        return Err(ApiError::Forbidden(
+            "Share submission requires API auth to be configured".to_string(),
+        ));
+    }
+
+    validation::validate(&share, &state.chain_store_handle, &SystemTimeProvider)
+        .await
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+
+    state
+        .chain_store_handle
+        .add_share(&share, true)
+        .await
+        .map_err(|e| ApiError::ServerError(e.to_string()))?;
+
+    Ok(Json(SubmitShareResponse {
+        block_hash: share.block_hash().to_string(),
+    }))
+}
+
+/// Uncles included by the shares at a height, resolved to their shares.
+///
+/// The uncles column family is keyed by uncle hash, not height, so the
+/// uncles are read from the headers of the shares at the height instead.
+async fn uncles_at_height(
+    State(state): State<Arc<AppState>>,
+    HeightPath(height): HeightPath,
+) -> Result<Json<UnclesAtHeightResponse>, ApiError> {
+    let tip_height = state
+        .chain_store_handle
+        .get_tip_height()
+        .map_err(|e| ApiError::ServerError(e.to_string()))?
+        .unwrap_or(0);
+    check_height_near_tip(height, tip_height)?;
+
+    let shares = retry_read(|| state.chain_store_handle.get_shares_at_height(height)).await?;
+
+    let main_chain = MainChain::new(&state.chain_store_handle, state.app_config.max_scan_items);
+    let mut uncles: Vec<UncleInfo> = Vec::new();
+    for (nephew_hash, nephew) in &shares {
+        for uncle_hash in &nephew.header.uncles {
+            let share = state
+                .chain_store_handle
+                .get_share(uncle_hash)
+                .map(|uncle| stored_share_info(&main_chain, uncle_hash, &uncle));
+            uncles.push(UncleInfo {
+                blockhash: uncle_hash.to_string(),
+                included_by: nephew_hash.to_string(),
+                share,
+            });
+        }
+    }
+
+    Ok(Json(UnclesAtHeightResponse { height, uncles }))
+}
+
+/// Most uncles chain_share_context resolves to shares
+const MAX_CONTEXT_UNCLES: usize = 16;
+
+/// ShareInfo for a stored share, with confirmations when its height is
+/// known and it is on the main chain
+fn stored_share_info(main_chain: &MainChain, hash: &BlockHash, share: &ShareBlock) -> ShareInfo {
+    let confirmations = main_chain
+        .chain_store
+        .get_share_height(hash)
+        .map_or(0, |height| share_confirmations(main_chain, hash, height));
+    ShareInfo::new(hash, share, confirmations)
+}
+
+/// A share with its parent, its uncles and its bitcoin block hash. Parent
+/// and uncles that are not stored are returned as null rather than
+/// failing the request. At most MAX_CONTEXT_UNCLES uncles are resolved.
+async fn chain_share_context(
+    State(state): State<Arc<AppState>>,
+    Path(hash): Path<String>,
+) -> Result<Json<ShareContextResponse>, ApiError> {
+    let share_hash: BlockHash = hash
+        .parse()
+        .map_err(|e| ApiError::BadRequest(format!("Invalid share hash: {e}")))?;
+    let chain_store = &state.chain_store_handle;
+    let share = chain_store
+        .get_share(&share_hash)
+        .ok_or_else(|| ApiError::NotFound(format!("Share {share_hash} not found")))?;
+
+    let main_chain = MainChain::new(chain_store, state.app_config.max_scan_items);
+    let resolve = |hash: &BlockHash| {
+        chain_store
+            .get_share(hash)
+            .map(|share| stored_share_info(&main_chain, hash, &share))
+    };
+
+    let prev_hash = share.header.prev_share_blockhash;
+    let parent = if prev_hash == BlockHash::all_zeros() {
+        None
+    } else {
+        resolve(&prev_hash)
+    };
+    let uncles = share
+        .header
+        .uncles
+        .iter()
+        .enumerate()
+        .map(|(index, uncle_hash)| ContextUncle {
+            blockhash: uncle_hash.to_string(),
+            share: if index < MAX_CONTEXT_UNCLES {
+                resolve(uncle_hash)
+            } else {
+                None
+            },
+        })
+        .collect();
+
+    Ok(Json(ShareContextResponse {
+        share: stored_share_info(&main_chain, &share_hash, &share),
+        height: chain_store.get_share_height(&share_hash),
+        bitcoin_block_hash: share.header.bitcoin_header.block_hash().to_string(),
+        parent,
+        uncles,
+    }))
+}
+
+/// Most uncles chain_share_uncles_full resolves to shares
+const MAX_FULL_UNCLES: usize = 16;
+
+/// The full share info and miner of each uncle a share names, saving a
+/// request per uncle. Uncles that are not stored, or past the first
+/// MAX_FULL_UNCLES, are returned with available false.
+async fn chain_share_uncles_full(
+    State(state): State<Arc<AppState>>,
+    Path(hash): Path<String>,
+) -> Result<Json<UnclesFullResponse>, ApiError> {
+    let share_hash: BlockHash = hash
+        .parse()
+        .map_err(|e| ApiError::BadRequest(format!("Invalid share hash: {e}")))?;
+    let chain_store = &state.chain_store_handle;
+    let share = chain_store
+        .get_share(&share_hash)
+        .ok_or_else(|| ApiError::NotFound(format!("Share {share_hash} not found")))?;
+
+    let main_chain = MainChain::new(chain_store, state.app_config.max_scan_items);
+    let uncles = share
+        .header
+        .uncles
+        .iter()
+        .enumerate()
+        .map(|(index, uncle_hash)| {
+            let uncle = if index < MAX_FULL_UNCLES {
+                chain_store.get_share(uncle_hash)
+            } else {
+                None
+            };
+            FullUncle {
+                hash: uncle_hash.to_string(),
+                available: uncle.is_some(),
+                miner_pubkey: uncle
+                    .as_ref()
+                    .map(|uncle| uncle.header.miner_pubkey.to_string()),
+                share: uncle.map(|uncle| stored_share_info(&main_chain, uncle_hash, &uncle)),
+            }
+        })
+        .collect();
+
+    Ok(Json(UnclesFullResponse {
+        blockhash: share_hash.to_string(),
+        uncles,
+    }))
+}
+
+/// Find the share that produced a bitcoin block, using the bitcoin block
+/// to share index written as shares are stored.
+async fn chain_share_by_bitcoin_block(
+    State(state): State<Arc<AppState>>,
+    Path(hash): Path<String>,
+) -> Result<Json<ShareInfo>, ApiError> {
+    let bitcoin_blockhash: BlockHash = hash
+        .parse()
+        .map_err(|e| ApiError::BadRequest(format!("Invalid bitcoin block hash: {e}")))?;
+
+    let share = state
+        .chain_store_handle
+        .get_share_for_bitcoin_block(&bitcoin_blockhash)
+        .and_then(|share_hash| {
+            state
+                .chain_store_handle
+                .get_share(&share_hash)
+                .map(|share| (share_hash, share))
+        });
+    match share {
+        Some((share_hash, share)) => {
+            let main_chain =
+                MainChain::new(&state.chain_store_handle, state.app_config.max_scan_items);
+            Ok(Json(stored_share_info(&main_chain, &share_hash, &share)))
+        }
+        None => Err(ApiError::NotFound(format!(
+            "No share found for bitcoin block {bitcoin_blockhash}"
+        ))),
+    }
+}
+
+/// Consensus serialized bytes of a share, for verifying its hash and proof
+/// of work outside the node. See RawShareQuery for the available formats.
+async fn chain_share_raw(
+    State(state): State<Arc<AppState>>,
+    Path(hash): Path<String>,
+    Query(params): Query<RawShareQuery>,
+) -> Result<Response, ApiError> {
+    let share_hash: BlockHash = hash
+        .parse()
+        .map_err(|e| ApiError::BadRequest(format!("Invalid share hash: {e}")))?;
+    let share = state
+        .chain_store_handle
+        .get_share(&share_hash)
+        .ok_or_else(|| ApiError::NotFound(format!("Share {share_hash} not found")))?;
+
+    let bytes = match params.encoding.as_deref() {
+        None | Some("wire") => bitcoin::consensus::serialize(&share),
+        Some("storage") => bitcoin::consensus::serialize(&StorageShareBlock::from(&share)),
+        Some(other) => {
+            return Err(ApiError::BadRequest(format!(
+                "Invalid encoding {other}, expected wire or storage"
+            )));
+        }
+    };
+    match params.format.as_deref() {
+        None | Some("raw") => {
+            Ok(([(header::CONTENT_TYPE, "application/octet-stream")], bytes).into_response())
+        }
+        Some("hex") => Ok(hex::encode(bytes).into_response()),
+        Some(other) => Err(ApiError::BadRequest(format!(
+            "Invalid format {other}, expected raw or hex"
+        ))),
+    }
+}
+
+/// Recompute a share's proof of work and compare it to the share target.
+/// The work is the bitcoin header hash, the same hash stratum checks
+/// submissions against.
+async fn chain_share_verify_pow(
+    State(state): State<Arc<AppState>>,
+    Path(hash): Path<String>,
+) -> Result<Json<VerifyPowResponse>, ApiError> {
+    let share_hash: BlockHash = hash
+        .parse()
+        .map_err(|e| ApiError::BadRequest(format!("Invalid share hash: {e}")))?;
+    let share = state
+        .chain_store_handle
+        .get_share(&share_hash)
+        .ok_or_else(|| ApiError::NotFound(format!("Share {share_hash} not found")))?;
+
+    let pow_hash = share.header.bitcoin_header.block_hash();
+    let target = Target::from_compact(share.header.bits);
+    Ok(Json(VerifyPowResponse {
+        meets_target: target.is_met_by(pow_hash),
+        hash: pow_hash.to_string(),
+        target: hex::encode(target.to_be_bytes()),
+    }))
+}
+
+/// A page of one miner's shares, newest first, from the miner shares
+/// index.
+async fn chain_shares_by_miner(
+    State(state): State<Arc<AppState>>,
+    Path(pubkey): Path<String>,
+    Query(params): Query<MinerSharesQuery>,
+) -> Result<Json<MinerSharesResponse>, ApiError> {
+    let miner_pubkey = CompressedPublicKey::from_str(&pubkey)
+        .map_err(|e| ApiError::BadRequest(format!("Invalid miner pubkey: {e}")))?;
+    // Share times are u32, so later filters cover everything
+    let time_filter = |field: &str, value: Option<&str>| {
+        value
+            .map(|value| {
+                parse_time_filter(field, value).map(|time| u32::try_from(time).unwrap_or(u32::MAX))
+            })
+            .transpose()
+    };
+    let from_time = time_filter("from", params.from.as_deref())?;
+    let to_time = time_filter("to", params.to.as_deref())?;
+    if matches!((from_time, to_time), (Some(from), Some(to)) if to < from) {
+        return Err(ApiError::BadRequest(
+            "Invalid date range: to is before from".into(),
+        ));
+    }
+
+    let page = params.page.unwrap_or(1).max(1);
+    let page_size = state.app_config.page_size.resolve(params.page_size);
+    let skip = (page as usize - 1) * page_size as usize;
+
+    // One extra share tells whether there is a next page
+    let mut shares = state
+        .chain_store_handle
+        .get_shares_by_miner(
+            &miner_pubkey,
+            from_time,
+            to_time,
+            skip,
+            page_size as usize + 1,
+        )
+        .map_err(|e| ApiError::ServerError(format!("Failed to read miner shares: {e}")))?;
+    let has_more = shares.len() > page_size as usize;
+    shares.truncate(page_size as usize);
+
+    let shares = shares
+        .into_iter()
+        .map(|(timestamp, hash)| MinerShare {
+            hash: hash.to_string(),
+            height: state.chain_store_handle.get_share_height(&hash),
+            timestamp,
+        })
+        .collect();
+
+    Ok(Json(MinerSharesResponse {
+        miner_pubkey: miner_pubkey.to_string(),
+        shares,
+        page,
+        page_size,
+        has_more,
+    }))
+}
+
+/// Miners returned by /chain/known_miners when no limit is given
+const DEFAULT_KNOWN_MINERS_LIMIT: usize = 100;
+
+/// Most miners /chain/known_miners returns, whatever the limit
+const MAX_KNOWN_MINERS_LIMIT: usize = 1000;
+
+/// Distinct miner pubkeys from the miner shares index with the heights
+/// of their earliest and latest shares, most recently seen first. Up to
+/// max_scan_items miners are read to sort by last seen.
+async fn chain_known_miners(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<KnownMinersQuery>,
+) -> Result<Json<KnownMinersResponse>, ApiError> {
+    let limit = params
+        .limit
+        .unwrap_or(DEFAULT_KNOWN_MINERS_LIMIT)
+        .min(MAX_KNOWN_MINERS_LIMIT);
+    let max_miners = state.app_config.max_scan_items as usize;
+
+    // One extra miner tells whether the scan budget cut the list short
+    let mut indexed = state
+        .chain_store_handle
+        .get_known_miners(max_miners + 1)
+        .map_err(|e| ApiError::ServerError(format!("Failed to read known miners: {e}")))?;
+    let truncated = indexed.len() > max_miners;
+    indexed.truncate(max_miners);
+
+    let chain_store = &state.chain_store_handle;
+    let mut miners: Vec<KnownMiner> = indexed
+        .into_iter()
+        .map(|miner| KnownMiner {
+            miner_pubkey: miner.miner_pubkey.to_string(),
+            first_seen_height: chain_store.get_share_height(&miner.first_share.1),
+            first_seen_time: miner.first_share.0,
+            last_seen_height: chain_store.get_share_height(&miner.last_share.1),
+            last_seen_time: miner.last_share.0,
+        })
+        .collect();
+    // Unknown heights sort last, ties go to the later share time
+    miners.sort_by(|a, b| {
+        b.last_seen_height
+            .cmp(&a.last_seen_height)
+            .then(b.last_seen_time.cmp(&a.last_seen_time))
+    });
+    miners.truncate(limit);
+
+    Ok(Json(KnownMinersResponse { miners, truncated }))
+}
+
+/// Look up the transaction input spending the txid:vout outpoint in the
+/// spends index. Spends are indexed for main chain shares, confirmed or not.
+async fn chain_spends(
+    State(state): State<Arc<AppState>>,
+    Path((txid, vout)): Path<(String, String)>,
+) -> Result<Json<SpendResponse>, ApiError> {
+    if txid.len() != 64 {
+        return Err(ApiError::BadRequest(format!(
+            "Invalid txid {txid}: expected 32 bytes as 64 hex characters"
+        )));
+    }
+    let txid: Txid = txid
+        .parse()
+        .map_err(|e| ApiError::BadRequest(format!("Invalid txid {txid}: {e}")))?;
+    let vout: u32 = vout.parse().map_err(|_| {
+        ApiError::BadRequest(format!(
+            "Invalid vout {vout}: expected a non-negative integer"
+        ))
+    })?;
+
+    // The spends index is keyed by the txid:vout string
+    let key = format!("{txid}:{vout}");
+    let value = state
+        .chain_store_handle
+        .get_cf_entry(ColumnFamily::SpendsIndex, key.as_bytes())
+        .map_err(ApiError::ServerError)?
+        .ok_or_else(|| ApiError::NotFound(format!("No spend found for {key}")))?;
+
+    let spending: OutPoint = bitcoin::consensus::deserialize(&value)
+        .map_err(|e| ApiError::ServerError(format!("Failed to decode spend for {key}: {e}")))?;
+
+    Ok(Json(SpendResponse {
+        txid: txid.to_string(),
+        vout,
+        spending_txid: spending.txid.to_string(),
+        spending_input_index: spending.vout,
+    }))
+}
+
+// ============================================================================
+// DAG API Handler
+// ============================================================================
+
+/// Resolve the inclusive height range for a DAG request, capped by limit
+fn dag_height_range(state: &AppState, params: &DagQuery) -> Result<(u32, u32, bool), ApiError> {
+    let tip_height = state
+        .chain_store_handle
+        .get_tip_height()
+        .map_err(|e| ApiError::ServerError(e.to_string()))?
+        .unwrap_or(0);
+
+    let limit = state.app_config.page_size.resolve(params.limit);
+    let to_height = params.to_height.unwrap_or(tip_height);
+    let from_height = params
+        .from_height
+        .unwrap_or(to_height.saturating_sub(limit));
+    let (from_height, truncated) = scan_budget_from(&state.app_config, from_height, to_height);
+    // Main chain shares are labelled by walking back from the tip, so the
+    // walk down to from has to fit in the budget too
+    let (from_height, walk_cut) = scan_budget_from(&state.app_config, from_height, tip_height);
+    Ok((from_height, to_height, truncated || walk_cut))
+}
+
+/// Raise from so that from..=to covers at most max_scan_items heights,
+/// keeping the heights nearest to. Returns the new from and whether the
+/// range had to be cut.
+fn scan_budget_from(app_config: &AppConfig, from_height: u32, to_height: u32) -> (u32, bool) {
+    let budget_from = to_height.saturating_sub(app_config.max_scan_items.saturating_sub(1));
+    if from_height < budget_from {
+        (budget_from, true)
+    } else {
+        (from_height, false)
+    }
+}
+
+/// Build the DAG node for a share along with its parent and uncle edges
+fn dag_entries(
+    hash: &BlockHash,
+    share: &ShareBlock,
+    height: u32,
+    is_main_chain: bool,
+    is_uncle: bool,
+) -> (DagNode, Vec<DagEdge>) {
+    let hash_str = hash.to_string();
+    let prev_hash_str = share.header.prev_share_blockhash.to_string();
+
+    let mut edges: Vec<DagEdge> = Vec::new();
+    if share.header.prev_share_blockhash != BlockHash::all_zeros() {
+        edges.push(DagEdge {
+            from: hash_str.clone(),
+            to: prev_hash_str.clone(),
+            edge_type: "parent".to_string(),
+        });
+    }
+
+    for uncle in &share.header.uncles {
+        edges.push(DagEdge {
+            from: hash_str.clone(),
+            to: uncle.to_string(),
+            edge_type: "uncle".to_string(),
+        });
+    }
+
+    let node = DagNode {
+        hash: hash_str,
+        prev_hash: prev_hash_str,
+        uncles: share.header.uncles.iter().map(|u| u.to_string()).collect(),
+        height,
+        miner_pubkey: share.header.miner_pubkey.to_string(),
+        timestamp: share.header.time,
+        is_main_chain,
+        is_uncle,
+    };
+    (node, edges)
+}
+
+async fn chain_dag(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<DagQuery>,
+) -> Result<Masked<DagResponse>, ApiError> {
+    // Checked before reading any shares
+    let miner_filter = dag_miner_filter(&params)?;
+    let range = dag_height_range(&state, &params)?;
+    let shares = prefetch_shares(&state.chain_store_handle, range.0, range.1).await?;
+    let dag = dag_from_shares(&state, &params, miner_filter, range, shares);
+    Ok(Masked::new(dag, params.fields.as_deref()))
+}
+
+/// A height along with the shares stored at it
+type HeightShares = (u32, Vec<(BlockHash, ShareBlock)>);
+
+/// The shares at a height sorted by hash, so DAGs come out in the same
+/// order every time. A height that fails to read has no shares.
+fn sorted_shares_at_height(chain_store: &ChainStoreHandle, height: u32) -> HeightShares {
+    let mut shares: Vec<(BlockHash, ShareBlock)> = chain_store
+        .get_shares_at_height(height)
+        .map(|shares| shares.into_iter().collect())
+        .unwrap_or_default();
+    shares.sort_unstable_by_key(|(hash, _)| *hash);
+    (height, shares)
+}
+
+/// Most heights prefetch_shares reads at once
+const DAG_PREFETCH_CONCURRENCY: usize = 8;
+
+/// Read the shares at each height in from..=to on the blocking pool, up
+/// to DAG_PREFETCH_CONCURRENCY heights at a time, so the RocksDB reads
+/// overlap on a cold cache. Results are in height order however the reads
+/// finish.
+async fn prefetch_shares(
+    chain_store: &ChainStoreHandle,
+    from_height: u32,
+    to_height: u32,
+) -> Result<Vec<HeightShares>, ApiError> {
+    let permits = Arc::new(Semaphore::new(DAG_PREFETCH_CONCURRENCY));
+    let mut reads = Vec::new();
+    for height in from_height..=to_height {
+        let permit = permits
+            .clone()
+            .acquire_owned()
+            .await
+            .map_err(|e| ApiError::ServerError(e.to_string()))?;
+        let chain_store = chain_store.clone();
+        reads.push(tokio::task::spawn_blocking(move || {
+            let _permit = permit;
+            sorted_shares_at_height(&chain_store, height)
+        }));
+    }
+
+    let mut shares = Vec::with_capacity(reads.len());
+    for read in reads {
+        shares.push(
+            read.await
+                .map_err(|e| ApiError::ServerError(e.to_string()))?,
+        );
+    }
+    Ok(shares)
+}
+
+/// Nodes and edges for the shares in the requested height range, reading
+/// the heights one at a time
+fn build_dag(state: &AppState, params: &DagQuery) -> Result<DagResponse, ApiError> {
+    let miner_filter = dag_miner_filter(params)?;
+    let (from_height, to_height, truncated) = dag_height_range(state, params)?;
+    let shares = (from_height..=to_height)
+        .map(|height| sorted_shares_at_height(&state.chain_store_handle, height))
+        .collect();
+    Ok(dag_from_shares(
+        state,
+        params,
+        miner_filter,
+        (from_height, to_height, truncated),
+        shares,
+    ))
+}
+
+fn dag_miner_filter(params: &DagQuery) -> Result<Option<CompressedPublicKey>, ApiError> {
+    params
+        .miner_pubkey
+        .as_deref()
+        .map(|pubkey| {
+            CompressedPublicKey::from_str(pubkey)
+                .map_err(|e| ApiError::BadRequest(format!("Invalid miner_pubkey: {e}")))
+        })
+        .transpose()
+}
+
+/// Nodes and edges for shares already read for the height range
+fn dag_from_shares(
+    state: &AppState,
+    params: &DagQuery,
+    miner_filter: Option<CompressedPublicKey>,
+    (from_height, to_height, truncated): (u32, u32, bool),
+    shares_by_height: Vec<HeightShares>,
+) -> DagResponse {
+    // Plus one for the parents of the range's shares, one height below it
+    let main_chain = MainChain::new(
+        &state.chain_store_handle,
+        state.app_config.max_scan_items.saturating_add(1),
+    );
+    let (_, current_uncles) = state.chain_store_handle.get_chain_tip_and_uncles();
+
+    let mut nodes: Vec<DagNode> = Vec::new();
+    let mut edges: Vec<DagEdge> = Vec::new();
+    let mut seen_hashes: HashSet<String> = HashSet::new();
+    let mut parents: Vec<(BlockHash, u32)> = Vec::new();
+
+    for (height, shares) in shares_by_height {
+        for (hash, share) in shares {
+            if miner_filter.is_some_and(|miner| share.header.miner_pubkey != miner) {
+                continue;
+            }
+            let hash_str = hash.to_string();
+            if seen_hashes.contains(&hash_str) {
+                continue;
+            }
+            seen_hashes.insert(hash_str);
+
+            let is_main_chain = main_chain.contains(&hash, height);
+            let is_uncle = current_uncles.contains(&hash);
+
+            let (node, share_edges) = dag_entries(&hash, &share, height, is_main_chain, is_uncle);
+            nodes.push(node);
+            edges.extend(share_edges);
+            if share.header.prev_share_blockhash != BlockHash::all_zeros() {
+                parents.push((share.header.prev_share_blockhash, height.saturating_sub(1)));
+            }
+        }
+    }
+
+    if miner_filter.is_some() {
+        if params.include_parents.unwrap_or(false) {
+            for (parent_hash, parent_height) in parents {
+                if !seen_hashes.insert(parent_hash.to_string()) {
+                    continue;
+                }
+                let Some(parent) = state.chain_store_handle.get_share(&parent_hash) else {
+                    continue;
+                };
+                let is_main_chain = main_chain.contains(&parent_hash, parent_height);
+                let is_uncle = current_uncles.contains(&parent_hash);
+                let (node, _) = dag_entries(
+                    &parent_hash,
+                    &parent,
+                    parent_height,
+                    is_main_chain,
+                    is_uncle,
+                );
+                nodes.push(node);
+            }
+        }
+        edges.retain(|edge| seen_hashes.contains(&edge.to));
+    }
+
+    DagResponse {
+        nodes,
+        edges,
+        tip_hash: main_chain.tip.to_string(),
+        from_height,
+        to_height,
+        truncated,
+    }
+}
+
+/// Nodes and edges for shares above a height the client already has, for
+/// appending to a rendered DAG.
+///
+/// The delta runs from just above the given height to the tip. Labelling
+/// main chain shares walks back from the tip, so a client more than
+/// max_scan_items heights behind gets only the heights nearest the tip,
+/// flagged truncated, and should reload the DAG. Uncles of the new shares
+/// at or below the given height are included too, as they may have
+/// arrived after the client last synced.
+async fn chain_dag_since(
+    State(state): State<Arc<AppState>>,
+    HeightPath(height): HeightPath,
+    Query(params): Query<FieldsQuery>,
+) -> Result<Masked<DagResponse>, ApiError> {
+    let tip_height = state
+        .chain_store_handle
+        .get_tip_height()
+        .map_err(|e| ApiError::ServerError(e.to_string()))?
+        .unwrap_or(0);
+    let (from_height, truncated) =
+        scan_budget_from(&state.app_config, height.saturating_add(1), tip_height);
+    // An empty range, to_height below from_height, when nothing is new
+    let to_height = tip_height.max(height);
+
+    let range = DagQuery {
+        from_height: Some(from_height),
+        to_height: Some(to_height),
+        limit: None,
+        miner_pubkey: None,
+        include_parents: None,
+        fields: None,
+    };
+    let mut dag = build_dag(&state, &range)?;
+    dag.truncated = truncated;
+
+    let (_, current_uncles) = state.chain_store_handle.get_chain_tip_and_uncles();
+    let main_chain = MainChain::new(&state.chain_store_handle, state.app_config.max_scan_items);
+    let mut seen_hashes: HashSet<String> = dag.nodes.iter().map(|node| node.hash.clone()).collect();
+    let uncle_hashes: Vec<String> = dag
+        .nodes
+        .iter()
+        .flat_map(|node| node.uncles.iter().cloned())
+        .collect();
+    for uncle_hash in uncle_hashes {
+        if !seen_hashes.insert(uncle_hash.clone()) {
+            continue;
+        }
+        let Ok(uncle_hash) = BlockHash::from_str(&uncle_hash) else {
+            continue;
+        };
+        let Some(uncle) = state.chain_store_handle.get_share(&uncle_hash) else {
+            continue;
+        };
+        let Some(uncle_height) = state.chain_store_handle.get_share_height(&uncle_hash) else {
+            continue;
+        };
+        let is_main_chain = main_chain.contains(&uncle_hash, uncle_height);
+        let is_uncle = current_uncles.contains(&uncle_hash);
+        let (node, edges) = dag_entries(&uncle_hash, &uncle, uncle_height, is_main_chain, is_uncle);
+        dag.nodes.push(node);
+        dag.edges.extend(edges);
+    }
+
+    Ok(Masked::new(dag, params.fields.as_deref()))
+}
+
+/// The query a DAG snapshot is computed for: the default height range,
+/// as returned by /chain/dag without parameters
+const SNAPSHOT_DAG_QUERY: DagQuery = DagQuery {
+    from_height: None,
+    to_height: None,
+    limit: None,
+    miner_pubkey: None,
+    include_parents: None,
+    fields: None,
+};
+
+/// Compute and serialize the default DAG for the current tip
+fn compute_dag_snapshot(state: &AppState) -> Result<DagSnapshot, ApiError> {
+    let tip = state.chain_store_handle.get_chain_tip();
+    let response = DagSnapshotResponse {
+        dag: build_dag(state, &SNAPSHOT_DAG_QUERY)?,
+        generated_at: chrono::Utc::now().timestamp().max(0) as u64,
+    };
+    let body = serde_json::to_vec(&response)
+        .map_err(|e| ApiError::ServerError(format!("Failed to serialize DAG: {e}")))?;
+    Ok(DagSnapshot {
+        tip,
+        body: body.into(),
+    })
+}
+
+/// The cached DAG snapshot, if it was computed for the current tip
+fn current_dag_snapshot(state: &AppState) -> Option<DagSnapshot> {
+    let tip = state.chain_store_handle.get_chain_tip();
+    state
+        .dag_snapshot
+        .read()
+        .ok()
+        .and_then(|snapshot| snapshot.clone())
+        .filter(|snapshot| snapshot.tip == tip)
+}
+
+/// Compute the DAG snapshot on a blocking thread and cache it. Callers
+/// hold dag_snapshot_compute.
+async fn recompute_dag_snapshot(state: &Arc<AppState>) -> Result<DagSnapshot, ApiError> {
+    let compute_state = state.clone();
+    let snapshot = tokio::task::spawn_blocking(move || compute_dag_snapshot(&compute_state))
+        .await
+        .map_err(|e| ApiError::ServerError(e.to_string()))??;
+    if let Ok(mut cached) = state.dag_snapshot.write() {
+        *cached = Some(snapshot.clone());
+    }
+    Ok(snapshot)
+}
+
+/// The default DAG from the snapshot cache. A snapshot computed for an
+/// older tip is ignored. An empty or stale cache is recomputed once on a
+/// blocking thread, and requests arriving meanwhile wait for that result
+/// rather than computing their own.
+async fn chain_dag_snapshot(State(state): State<Arc<AppState>>) -> Result<Response, ApiError> {
+    let snapshot = match current_dag_snapshot(&state) {
+        Some(snapshot) => snapshot,
+        None => {
+            let _computing = state.dag_snapshot_compute.lock().await;
+            // Filled by the request or refresher that held the lock
+            match current_dag_snapshot(&state) {
+                Some(snapshot) => snapshot,
+                None => recompute_dag_snapshot(&state).await?,
+            }
+        }
+    };
+    Ok(([(header::CONTENT_TYPE, "application/json")], snapshot.body).into_response())
+}
+
+/// Recompute the DAG snapshot every interval and whenever the tip
+/// changes. The old snapshot is cleared as soon as a tip change is seen,
+/// so requests never get a DAG for a replaced tip. Runs until aborted by
+/// the server on shutdown.
+async fn refresh_dag_snapshot(state: Arc<AppState>, refresh_interval: Duration) {
+    let mut tip_events = state.tip_events.subscribe();
+    let mut interval = tokio::time::interval(refresh_interval);
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {}
+            event = tip_events.recv() => {
+                if let Err(broadcast::error::RecvError::Closed) = event {
+                    return;
+                }
+                if let Ok(mut snapshot) = state.dag_snapshot.write() {
+                    *snapshot = None;
+                }
+            }
+        }
+        let _computing = state.dag_snapshot_compute.lock().await;
+        if let Err(e) = recompute_dag_snapshot(&state).await {
+            warn!("Failed to compute DAG snapshot: {e:?}");
+        }
+    }
+}
+
+/// Count the chain stats for /chain/info/extended over the scan budget
+fn compute_stats_snapshot(state: &AppState) -> Result<StatsSnapshot, ApiError> {
+    let height = state
+        .chain_store_handle
+        .get_tip_height()
+        .map_err(|e| ApiError::ServerError(e.to_string()))?
+        .unwrap_or(0);
+    let (from_height, truncated) = scan_budget_from(&state.app_config, 0, height);
+    let stats = count_chain_stats(&state.chain_store_handle, from_height, height);
+    Ok(StatsSnapshot {
+        height,
+        total_shares: stats.total_shares,
+        total_uncles: stats.total_uncles,
+        total_difficulty: stats.total_difficulty,
+        from_height,
+        truncated,
+    })
+}
+
+/// Recount the chain stats every interval. Unlike the DAG snapshot the
+/// counters are not cleared on tip changes, since slightly old counts are
+/// still useful to a status page. Runs until aborted by the server on
+/// shutdown.
+async fn refresh_stats_snapshot(state: Arc<AppState>, refresh_interval: Duration) {
+    let mut interval = tokio::time::interval(refresh_interval);
+    loop {
+        interval.tick().await;
+        let compute_state = state.clone();
+        match tokio::task::spawn_blocking(move || compute_stats_snapshot(&compute_state)).await {
+            Ok(Ok(snapshot)) => {
+                if let Ok(mut cached) = state.stats_snapshot.write() {
+                    *cached = Some(snapshot);
+                }
+            }
+            Ok(Err(e)) => warn!("Failed to count chain stats: {e:?}"),
+            Err(e) => warn!("Chain stats task failed: {e}"),
+        }
+    }
+}
+
+/// Header set by chain_dag_stream, which has no body field to flag a
+/// range cut to the scan budget
+const TRUNCATED_HEADER: header::HeaderName = header::HeaderName::from_static("x-truncated");
+
+/// Lines buffered between the DAG stream reader and the response body
+const DAG_STREAM_CHANNEL_CAPACITY: usize = 64;
+
+/// Streams the same nodes and edges as chain_dag as newline delimited JSON.
+///
+/// Heights are read on a blocking thread and handed to the body through a
+/// bounded channel, so memory stays bounded and RocksDB reads stay off the
+/// async runtime. Each line is a DagNode or a DagEdge, as in chain_dag's
+/// nodes and edges, and only edge lines have an edge_type field. Each node
+/// line is followed by the lines for its edges. The miner_pubkey,
+/// include_parents and fields filters apply as in chain_dag, with the
+/// parents of a height's shares sent ahead of that height's nodes.
+async fn chain_dag_stream(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<DagQuery>,
+) -> Result<Response, ApiError> {
+    // Checked before reading any shares
+    let miner_filter = dag_miner_filter(&params)?;
+    let include_parents = miner_filter.is_some() && params.include_parents.unwrap_or(false);
+    let (from_height, to_height, truncated) = dag_height_range(&state, &params)?;
+
+    let (_, current_uncles) = state.chain_store_handle.get_chain_tip_and_uncles();
+    let node_fields = parse_fields(params.fields.as_deref(), DAG_NODE_FIELDS);
+    let node_line = move |node: &DagNode| match &node_fields {
+        Some(fields) => serde_json::to_value(node).ok().and_then(|mut value| {
+            project_fields(&mut value, fields);
+            ndjson_line(&value)
+        }),
+        None => ndjson_line(node),
+    };
+
+    let (tx, rx) = tokio::sync::mpsc::channel::<Vec<u8>>(DAG_STREAM_CHANNEL_CAPACITY);
+    tokio::task::spawn_blocking(move || {
+        // Plus one for the parents of the range's shares, as in chain_dag
+        let main_chain = MainChain::new(
+            &state.chain_store_handle,
+            state.app_config.max_scan_items.saturating_add(1),
+        );
+        // With a miner filter, edges are only sent to nodes already sent
+        let mut seen_hashes: HashSet<String> = HashSet::new();
+        for height in from_height..=to_height {
+            let (_, shares) = sorted_shares_at_height(&state.chain_store_handle, height);
+            let shares: Vec<(BlockHash, ShareBlock)> = shares
+                .into_iter()
+                .filter(|(_, share)| {
+                    miner_filter.is_none_or(|miner| share.header.miner_pubkey == miner)
+                })
+                .collect();
+            let mut lines = Vec::new();
+            if miner_filter.is_some() {
+                seen_hashes.extend(shares.iter().map(|(hash, _)| hash.to_string()));
+            }
+            if include_parents {
+                for (_, share) in &shares {
+                    let parent_hash = share.header.prev_share_blockhash;
+                    if parent_hash == BlockHash::all_zeros()
+                        || !seen_hashes.insert(parent_hash.to_string())
+                    {
+                        continue;
+                    }
+                    let Some(parent) = state.chain_store_handle.get_share(&parent_hash) else {
+                        continue;
+                    };
+                    let parent_height = height.saturating_sub(1);
+                    let (node, _) = dag_entries(
+                        &parent_hash,
+                        &parent,
+                        parent_height,
+                        main_chain.contains(&parent_hash, parent_height),
+                        current_uncles.contains(&parent_hash),
+                    );
+                    lines.extend(node_line(&node));
+                }
+            }
+            for (hash, share) in &shares {
+                let is_main_chain = main_chain.contains(hash, height);
+                let is_uncle = current_uncles.contains(hash);
+
+                let (node, edges) = dag_entries(hash, share, height, is_main_chain, is_uncle);
+                lines.extend(node_line(&node));
+                lines.extend(
+                    edges
+                        .iter()
+                        .filter(|edge| miner_filter.is_none() || seen_hashes.contains(&edge.to))
+                        .filter_map(ndjson_line),
+                );
+            }
+            for line in lines {
+                // Stop reading once the client has gone away
+                if tx.blocking_send(line).is_err() {
+                    return;
+                }
+            }
+        }
+    });
+
+    let lines = stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|line| (Ok::<_, Infallible>(line), rx))
+    });
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/x-ndjson"),
+            (TRUNCATED_HEADER, if truncated { "true" } else { "false" }),
+        ],
+        Body::from_stream(lines),
+    )
+        .into_response())
+}
+
+/// How often the tip watcher checks the chain tip for changes
+const TIP_POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// Capacity of the tip event channel. Slow subscribers skip missed events.
+const TIP_EVENTS_CAPACITY: usize = 16;
+/// Most recent tip events kept to replay to reconnecting subscribers
+const TIP_EVENTS_REPLAY: usize = 64;
+
+/// The tip change feed: a broadcast channel for live subscribers, and
+/// the most recent events for subscribers resuming from an event ID
+#[derive(Clone)]
+pub struct TipEvents {
+    sender: broadcast::Sender<TipEvent>,
+    recent: Arc<Mutex<RecentTipEvents>>,
+}
+
+struct RecentTipEvents {
+    next_id: u64,
+    events: VecDeque<TipEvent>,
+}
+
+impl TipEvents {
+    pub fn new() -> Self {
+        let started_millis = chrono::Utc::now().timestamp_millis().max(0) as u64;
+        Self {
+            sender: broadcast::channel(TIP_EVENTS_CAPACITY).0,
+            recent: Arc::new(Mutex::new(RecentTipEvents {
+                next_id: started_millis,
+                events: VecDeque::with_capacity(TIP_EVENTS_REPLAY),
+            })),
+        }
+    }
+
+    /// Give a tip change the next ID, keep it for replay and send it to
+    /// live subscribers
+    fn publish(&self, tip: String, height: Option<u32>) {
+        let mut recent = self.recent.lock().unwrap_or_else(PoisonError::into_inner);
+        let event = TipEvent {
+            id: recent.next_id,
+            tip,
+            height,
+        };
+        recent.next_id += 1;
+        if recent.events.len() == TIP_EVENTS_REPLAY {
+            recent.events.pop_front();
+        }
+        recent.events.push_back(event.clone());
+        // Sending only fails when there are no subscribers
+        let _ = self.sender.send(event);
+    }
+
+    fn subscribe(&self) -> broadcast::Receiver<TipEvent> {
+        self.sender.subscribe()
+    }
+
+    /// Subscribe to live events, along with the kept events after
+    /// last_id. Both are taken under the publish lock, so no event is
+    /// missed or sent twice between the replay and the live feed.
+    fn subscribe_since(&self, last_id: u64) -> (Vec<TipEvent>, broadcast::Receiver<TipEvent>) {
+        let recent = self.recent.lock().unwrap_or_else(PoisonError::into_inner);
+        let replay = recent
+            .events
+            .iter()
+            .filter(|event| event.id > last_id)
+            .cloned()
+            .collect();
+        (replay, self.sender.subscribe())
+    }
+}
+
+impl Default for TipEvents {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+/// Interval between SSE comment heartbeats, so proxies keep idle
+/// /chain/events connections open
+const SSE_HEARTBEAT_INTERVAL: Duration = Duration::from_secs(15);
+/// Sent by EventSource clients on reconnect with the last event ID seen
+const LAST_EVENT_ID_HEADER: &str = "last-event-id";
+
+/// Poll the chain tip and publish a TipEvent whenever it changes. Runs
+/// until aborted by the server on shutdown.
+async fn watch_chain_tip(
+    chain_store: ChainStoreHandle,
+    tip_events: TipEvents,
+    poll_interval: Duration,
+) {
+    let mut interval = tokio::time::interval(poll_interval);
+    let mut last_tip = chain_store.get_chain_tip();
+    loop {
+        interval.tick().await;
+        let tip = chain_store.get_chain_tip();
+        if tip == last_tip {
+            continue;
+        }
+        last_tip = tip;
+        tip_events.publish(tip.to_string(), chain_store.get_tip_height().ok().flatten());
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ChainEventsQuery {
+    /// Resume after this event ID, for clients that can't send a
+    /// Last-Event-ID header
+    pub last_event_id: Option<u64>,
+}
+
+/// SSE event for a tip change, with the event ID clients resume from
+fn tip_sse_event(tip_event: &TipEvent) -> Result<Event, axum::Error> {
+    Event::default()
+        .event("tip")
+        .id(tip_event.id.to_string())
+        .json_data(tip_event)
+}
+
+/// Server-sent events stream with a "tip" event for each chain tip change.
+/// The stream ends when the client disconnects and drops the response.
+///
+/// A client reconnecting with the Last-Event-ID header, or the
+/// last_event_id query, first gets the tip changes it missed, then the
+/// live feed. Only the last TIP_EVENTS_REPLAY changes are kept, so a
+/// longer gap is replayed from the oldest kept change.
+async fn chain_events(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(params): Query<ChainEventsQuery>,
+) -> Result<Sse<impl futures::Stream<Item = Result<Event, axum::Error>>>, ApiError> {
+    let last_event_id = match headers.get(LAST_EVENT_ID_HEADER) {
+        Some(value) => Some(
+            value
+                .to_str()
+                .ok()
+                .and_then(|value| value.trim().parse::<u64>().ok())
+                .ok_or_else(|| ApiError::BadRequest("Invalid Last-Event-ID".into()))?,
+        ),
+        None => params.last_event_id,
+    };
+    let (replay, receiver) = match last_event_id {
+        Some(last_event_id) => state.tip_events.subscribe_since(last_event_id),
+        None => (Vec::new(), state.tip_events.subscribe()),
+    };
+
+    let replayed = stream::iter(
+        replay
+            .into_iter()
+            .map(|tip_event| tip_sse_event(&tip_event)),
+    );
+    let live = stream::unfold(receiver, |mut receiver| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(tip_event) => return Some((tip_sse_event(&tip_event), receiver)),
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    });
+    Ok(Sse::new(replayed.chain(live)).keep_alive(
+        KeepAlive::new()
+            .interval(SSE_HEARTBEAT_INTERVAL)
+            .text("heartbeat"),
+    ))
+}
+
+pub(crate) fn ndjson_line<T: Serialize>(item: &T) -> Option<Vec<u8>> {
+    let mut line = serde_json::to_vec(item).ok()?;
+    line.push(b'\n');
+    Some(line)
+}
+
+/// Walk the main chain back from tip, yielding each share with its
+/// height, down to from_height or genesis. Stops early at a share missing
+/// from the store.
+fn walk_main_chain(
+    chain_store: &ChainStoreHandle,
+    tip: BlockHash,
+    tip_height: u32,
+    from_height: u32,
+) -> impl Iterator<Item = (u32, BlockHash, ShareBlock)> + '_ {
+    let mut next = Some((tip_height, tip));
+    std::iter::from_fn(move || {
+        let (height, hash) = next.take()?;
+        let share = chain_store.get_share(&hash)?;
+        let prev_hash = share.header.prev_share_blockhash;
+        if height > from_height && prev_hash != BlockHash::all_zeros() {
+            next = Some((height - 1, prev_hash));
+        }
+        Some((height, hash, share))
+    })
+}
+
+/// The main chain, walked back from the tip once per request and only as
+/// far down as the shares asked about, over at most max_scan shares.
+/// Heights in the confirmed height index are answered from the index
+/// without walking. Shares below the walk that the index doesn't cover
+/// are reported off the main chain, and the walk is flagged truncated.
+struct MainChain<'a> {
+    chain_store: &'a ChainStoreHandle,
+    tip: BlockHash,
+    tip_height: u32,
+    walk: RefCell<MainChainWalk>,
+}
+
+/// How far a MainChain has walked back from the tip
+struct MainChainWalk {
+    /// Height and hash of the next share to walk to, None once the walk
+    /// reached genesis or a share missing from the store
+    next: Option<(u32, BlockHash)>,
+    /// Shares left to walk in the scan budget
+    budget: u32,
+    /// Set when the budget ran out above a height that was asked about
+    truncated: bool,
+    hashes: HashSet<BlockHash>,
+}
+
+impl<'a> MainChain<'a> {
+    fn new(chain_store: &'a ChainStoreHandle, max_scan: u32) -> Self {
+        let tip = chain_store.get_chain_tip();
+        let tip_height = chain_store.get_tip_height().ok().flatten().unwrap_or(0);
+        Self {
+            chain_store,
+            tip,
+            tip_height,
+            walk: RefCell::new(MainChainWalk {
+                next: Some((tip_height, tip)),
+                budget: max_scan,
+                truncated: false,
+                hashes: HashSet::new(),
+            }),
+        }
+    }
+
+    /// Whether the share hash stored at height is on the main chain
+    fn contains(&self, hash: &BlockHash, height: u32) -> bool {
+        if hash == &self.tip {
+            return true;
+        }
+        if height > self.tip_height {
+            return false;
+        }
+        if let Some(confirmed) = self.chain_store.get_confirmed_at_height(height) {
+            return &confirmed == hash;
+        }
+        let mut walk = self.walk.borrow_mut();
+        walk.extend_to(self.chain_store, height);
+        walk.hashes.contains(hash)
+    }
+
+    /// Whether the scan budget stopped the walk above a height it was
+    /// asked about
+    fn truncated(&self) -> bool {
+        self.walk.borrow().truncated
+    }
+}
+
+impl MainChainWalk {
+    /// Walk down until height is covered, the chain ends or the budget
+    /// runs out
+    fn extend_to(&mut self, chain_store: &ChainStoreHandle, height: u32) {
+        while let Some((next_height, next_hash)) = self.next {
+            if next_height < height {
+                return;
+            }
+            if self.budget == 0 {
+                self.truncated = true;
+                return;
+            }
+            self.budget -= 1;
+            let Some(share) = chain_store.get_share(&next_hash) else {
+                self.next = None;
+                return;
+            };
+            self.hashes.insert(next_hash);
+            let prev_hash = share.header.prev_share_blockhash;
+            self.next = (next_height > 0 && prev_hash != BlockHash::all_zeros())
+                .then(|| (next_height - 1, prev_hash));
+        }
+    }
+}
+
+/// Confirmations for the share hash stored at height: tip_height - height
+/// for main chain shares, 0 for shares off the main chain. A share above
+/// tip_height, e.g. while the tip is being updated, also gets 0.
+fn share_confirmations(main_chain: &MainChain, hash: &BlockHash, height: u32) -> u32 {
+    if main_chain.contains(hash, height) {
+        main_chain.tip_height.saturating_sub(height)
+    } else {
+        0
+    }
+}
+
+// ============================================================================
+// Server Setup
+// ============================================================================
+
+/// How often the DAG snapshot is recomputed when the config doesn't set
+/// dag_snapshot_interval_secs
+const DEFAULT_DAG_SNAPSHOT_INTERVAL_SECS: u64 = 30;
+
+/// How often the stats behind /chain/info/extended are recounted when
+/// the config doesn't set stats_cache_interval_secs
+const DEFAULT_STATS_CACHE_INTERVAL_SECS: u64 = 60;
+
+/// How often metrics are written to disk when metrics_export_dir is set
+/// and the config doesn't set metrics_export_interval_secs
+const DEFAULT_METRICS_EXPORT_INTERVAL_SECS: u64 = 60;
+
+/// How many metrics files are kept when the config doesn't set
+/// metrics_export_keep
+const DEFAULT_METRICS_EXPORT_KEEP: usize = 10;
+
+/// Largest request body accepted, measured after gzip decompression
+const MAX_REQUEST_BODY_BYTES: usize = 2 * 1024 * 1024;
+
+/// Start the API server and return a shutdown channel
+pub async fn start_api_server(
+    config: ApiConfig,
+    chain_store_handle: ChainStoreHandle,
+    metrics_handle: MetricsHandle,
+    tracker_handle: Arc<JobTracker>,
+    network: bitcoin::Network,
+    pool_signature: Option<String>,
+    coinbase_roles: CoinbaseRoles,
+    job_ttl: Duration,
+) -> Result<oneshot::Sender<()>, std::io::Error> {
+    let db_cf_access = CfAccess::from_config(
+        config.db_allowed_cfs.as_deref(),
+        config.db_denied_cfs.as_deref(),
+    )
+    .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+    let app_config = AppConfig {
+        pool_signature_length: pool_signature.unwrap_or_default().len(),
+        network,
+        stale_after_secs: config.stale_after_secs,
+        page_size: PageSizeLimits::from_config(config.default_page_size, config.max_page_size),
+        coinbase_roles,
+        max_scan_items: config
+            .max_scan_items
+            .unwrap_or(DEFAULT_MAX_SCAN_ITEMS)
+            .max(1),
+        metrics_prefix: config.metrics_prefix.clone(),
+        db_read_only: config.db_read_only.unwrap_or(true),
+        db_cf_access,
+        job_ttl,
+    };
+    if let Some(prefix) = app_config
+        .metrics_prefix
+        .as_deref()
+        .filter(|prefix| !prom::is_valid_metric_prefix(prefix))
+    {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("Invalid metrics_prefix {prefix}, expected [a-zA-Z_:][a-zA-Z0-9_:]*"),
+        ));
+    }
+
+    let tip_events = TipEvents::new();
+    let app_state = Arc::new(AppState {
+        app_config: app_config.clone(),
+        chain_store_handle: chain_store_handle.clone(),
+        metrics_handle,
+        tracker_handle,
+        api_keys: api_keys(&config),
+        tip_events: tip_events.clone(),
+        dag_snapshot: Arc::default(),
+        dag_snapshot_compute: Arc::default(),
+        heavy_requests: HeavyRequestLimit::new(
+            config
+                .max_concurrent_heavy_requests
+                .unwrap_or(DEFAULT_MAX_CONCURRENT_HEAVY_REQUESTS)
+                .max(1),
+        ),
+        stats_snapshot: Arc::default(),
+    });
+
+    let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+    let addr = parse_bind_addr(&config.hostname, config.port)?;
+    let dag_snapshot_interval = Duration::from_secs(
+        config
+            .dag_snapshot_interval_secs
+            .unwrap_or(DEFAULT_DAG_SNAPSHOT_INTERVAL_SECS)
+            .max(1),
+    );
+    let snapshot_state = app_state.clone();
+    let stats_cache_interval = Duration::from_secs(
+        config
+            .stats_cache_interval_secs
+            .unwrap_or(DEFAULT_STATS_CACHE_INTERVAL_SECS)
+            .max(1),
+    );
+    let stats_state = app_state.clone();
+    let metrics_export = match &config.metrics_export_dir {
+        Some(dir) => {
+            std::fs::create_dir_all(dir)?;
+            Some((
+                app_state.clone(),
+                std::path::PathBuf::from(dir),
+                Duration::from_secs(
+                    config
+                        .metrics_export_interval_secs
+                        .unwrap_or(DEFAULT_METRICS_EXPORT_INTERVAL_SECS)
+                        .max(1),
+                ),
+                config
+                    .metrics_export_keep
+                    .unwrap_or(DEFAULT_METRICS_EXPORT_KEEP)
+                    .max(1),
+            ))
+        }
+        None => None,
+    };
+
+    let routes = Router::new()
+        // Health and metrics
+        .route(
+            "/",
+            get(|| async { axum::response::Html(include_str!("../../static/index.html")) }),
+        )
+        .route("/health", get(health_check))
+        .route("/health/live", get(health_check))
+        .route("/health/ready", get(health_ready))
+        .route("/metrics", get(metrics))
+        .route(
+            "/pplns_shares",
+            get(pplns_shares).layer(middleware::from_fn_with_state(
+                PPLNS_SHARES_BIGINT_FIELDS,
+                bigint_string_middleware,
+            )),
+        )
+        .route("/pplns_shares.csv", get(pplns_shares_csv))
+        .route(
+            "/pplns/estimate",
+            get(pplns_estimate).layer(middleware::from_fn_with_state(
+                PPLNS_ESTIMATE_BIGINT_FIELDS,
+                bigint_string_middleware,
+            )),
+        )
+        .route("/job/current", get(job_current))
+        .route("/jobs", get(jobs))
+        // Chain endpoints
+        .route("/chain/tip", get(chain_tip))
+        .route("/chain/tip/bitcoin", get(chain_tip_bitcoin))
+        .route("/chain/tip/coinbase", get(chain_tip_coinbase))
+        .route("/chain/tip/history", get(chain_tip_history))
+        .route("/chain/genesis", get(chain_genesis))
+        .route("/chain/height", get(chain_height))
+        .route("/chain/height/:height/hash", get(chain_height_hash))
+        .route("/chain/tip_with_uncles", get(chain_tip_with_uncles))
+        .route("/chain/shares/:height", get(shares_at_height))
+        .route("/chain/validate_share", post(validate_share))
+        .route("/chain/uncles/:height", get(uncles_at_height))
+        .route("/chain/total_work", get(total_work))
+        .route("/chain/work_compare", get(work_compare))
+        .route("/chain/locator", get(chain_locator))
+        .route("/chain/locator/resolve", post(chain_locator_resolve))
+        .route("/chain/info", get(chain_info))
+        .route("/chain/info/extended", get(chain_info_extended))
+        .route("/chain/stats/cached", get(chain_stats_cached))
+        .route("/chain/health", get(chain_health))
+        .route("/chain/compare", get(chain_compare))
+        .route("/chain/blocks_found", get(blocks_found))
+        .route("/chain/events", get(chain_events))
+        .route("/chain/reorgs", get(chain_reorgs))
+        .route(
+            "/chain/coinbase_totals",
+            get(coinbase_totals).layer(middleware::from_fn_with_state(
+                COINBASE_BIGINT_FIELDS,
+                bigint_string_middleware,
+            )),
+        )
+        .route("/chain/spends/:txid/:vout", get(chain_spends))
+        .route("/chain/share/:hash/raw", get(chain_share_raw))
+        .route("/chain/share/:hash/context", get(chain_share_context))
+        .route(
+            "/chain/share/:hash/uncles_full",
+            get(chain_share_uncles_full),
+        )
+        .route("/chain/share/:hash/verify_pow", get(chain_share_verify_pow))
+        .route(
+            "/chain/by_bitcoin_block/:hash",
+            get(chain_share_by_bitcoin_block),
+        )
+        .route("/chain/shares_by_miner/:pubkey", get(chain_shares_by_miner))
+        // Database viewer endpoints
+        .route("/db/properties", get(db_viewer::get_db_properties))
+        .route("/db/overview/full", get(db_viewer::get_full_overview))
+        .route("/db/cf", get(db_viewer::list_column_families))
+        .route("/db/cf/:cf/entries", get(db_viewer::list_cf_entries))
+        .route("/db/cf/:cf/entry/:key", get(db_viewer::get_cf_entry))
+        .route(
+            "/db/cf/:cf/entry/:key/raw",
+            get(db_viewer::get_cf_entry_raw),
+        )
+        .route("/db/cf/:cf/stats", get(db_viewer::get_cf_stats))
+        .route("/db/cf/:cf/first", get(db_viewer::get_cf_first_entry))
+        .route("/db/cf/:cf/last", get(db_viewer::get_cf_last_entry))
+        .route(
+            "/db/cf/:cf/batch_get",
+            post(db_viewer::batch_get_cf_entries),
+        )
+        .merge(heavy_routes(app_state.clone()))
+        .merge(admin_routes(app_state.clone()));
+    #[cfg(feature = "schema")]
+    let routes = routes.route("/schema", get(schema::api_schema));
+    let app = routes
+        .fallback(route_not_found)
+        // Middleware and state
+        .layer(middleware::from_fn(camel_case_middleware))
+        .layer(middleware::from_fn(pretty_json_middleware))
+        // After the layers rewriting JSON, so MessagePack carries the
+        // same renamed keys and string encoded numbers
+        .layer(middleware::from_fn(msgpack_middleware))
+        // Bodies are limited after decompression, so a small gzip body
+        // can't expand past MAX_REQUEST_BODY_BYTES
+        .layer(DefaultBodyLimit::max(MAX_REQUEST_BODY_BYTES))
+        .layer(RequestDecompressionLayer::new())
+        .layer(middleware::from_fn_with_state(
+            app_state.clone(),
+            auth_middleware,
+        ))
+        .layer(Extension(app_config))
+        // Outermost, so requests refused by the layers above are logged
+        // with a request ID too
+        .layer(middleware::from_fn(request_id_middleware))
+        .with_state(app_state);
+
+    let listener = match tokio::net::TcpListener::bind(addr).await {
+        Ok(listener) => listener,
+        Err(e) => return Err(e),
+    };
+    let tip_watcher = tokio::spawn(watch_chain_tip(
+        chain_store_handle,
+        tip_events,
+        TIP_POLL_INTERVAL,
+    ));
+    let dag_snapshot_refresher =
+        tokio::spawn(refresh_dag_snapshot(snapshot_state, dag_snapshot_interval));
+    let stats_snapshot_refresher =
+        tokio::spawn(refresh_stats_snapshot(stats_state, stats_cache_interval));
+    let metrics_exporter = metrics_export.map(|(state, dir, interval, keep)| {
+        tokio::spawn(metrics_export::export_metrics(state, dir, interval, keep))
+    });
+
+    info!("API server listening on {}", listener.local_addr()?);
+
+    tokio::spawn(async move {
+        let served = axum::serve(listener, app)
+            .with_graceful_shutdown(async move {
+                let _ = shutdown_rx.await;
+                info!("API server shutdown signal received");
+            })
+            .await;
+        tip_watcher.abort();
+        dag_snapshot_refresher.abort();
+        stats_snapshot_refresher.abort();
+        if let Some(metrics_exporter) = metrics_exporter {
+            metrics_exporter.abort();
+        }
+        served.map_err(|e| ApiError::ServerError(e.to_string()))?;
+
+        info!("API server stopped");
+        Ok::<(), ApiError>(())
+    });
+    Ok(shutdown_tx)
+}
+
+/// Routes that scan many heights or entries, behind the heavy request
+/// limit. Light routes like /health are not limited.
+fn heavy_routes(state: Arc<AppState>) -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/chain/stats", get(chain_stats))
+        .route("/chain/stats/exact", get(chain_stats_exact))
+        .route("/chain/dag", get(chain_dag))
+        .route("/chain/dag/stream", get(chain_dag_stream))
+        .route("/chain/dag/since/:height", get(chain_dag_since))
+        .route("/chain/dag/snapshot", get(chain_dag_snapshot))
+        .route("/chain/share_rate", get(chain_share_rate))
+        .route(
+            "/chain/submission_intervals",
+            get(chain_submission_intervals),
+        )
+        .route("/chain/density", get(chain_density))
+        .route("/chain/main", get(chain_main))
+        .route("/chain/orphans", get(chain_orphans))
+        .route("/chain/known_miners", get(chain_known_miners))
+        .route(
+            "/db/cf/:cf/export",
+            get(db_viewer::export_cf_entries).head(db_viewer::export_cf_entries_head),
+        )
+        .route_layer(middleware::from_fn_with_state(state, heavy_request_limit))
+}
+
+/// Routes that add to or delete from the store, or walk the whole chain
+/// to verify it. They need an admin scoped API key when auth is
+/// configured. /chain/verify is also behind the heavy request limit.
+fn admin_routes(state: Arc<AppState>) -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/chain/shares", post(submit_share))
+        .route(
+            "/chain/verify",
+            post(chain_verify).layer(middleware::from_fn_with_state(state, heavy_request_limit)),
+        )
+        .route("/db/cf/:cf/entry/:key", delete(db_viewer::delete_cf_entry))
+        .route("/db/cf/:cf/prefix", delete(db_viewer::delete_cf_all))
+        .route(
+            "/db/cf/:cf/prefix/:prefix",
+            delete(db_viewer::delete_cf_prefix),
+        )
+        .route_layer(middleware::from_fn(require_admin_scope))
+}
+
+/// The API keys from config. The auth_user and auth_token pair predates
+/// scoped keys and keeps full access.
+fn api_keys(config: &ApiConfig) -> Vec<ApiKeyConfig> {
+    let mut api_keys = config.api_keys.clone();
+    if let (Some(user), Some(token)) = (&config.auth_user, &config.auth_token) {
+        api_keys.push(ApiKeyConfig {
+            user: user.clone(),
+            token: token.clone(),
+            scope: ApiScope::Admin,
+        });
+    }
+    api_keys
+}
+
+/// Answer 503 straight away when all heavy request permits are taken,
+/// rather than queueing. For streamed responses the permit is held until
+/// the response starts, not for the whole body.
+async fn heavy_request_limit(
+    State(state): State<Arc<AppState>>,
+    request: axum::extract::Request,
+    next: middleware::Next,
+) -> Response {
+    let Ok(_permit) = state.heavy_requests.0.clone().try_acquire_owned() else {
+        return ApiError::ServiceUnavailable(
+            "Too many concurrent expensive requests, retry later".to_string(),
+        )
+        .into_response();
+    };
+    next.run(request).await
+}
+
+/// Socket address for the API to bind, from an IPv4 or IPv6 literal.
+/// IPv6 may be given with or without brackets, e.g. "::1" or "[::1]".
+/// "::" listens on all interfaces, and on IPv4 as well where the OS
+/// defaults to dual-stack sockets.
+fn parse_bind_addr(hostname: &str, port: u16) -> Result<SocketAddr, std::io::Error> {
+    let host = hostname
+        .strip_prefix('[')
+        .and_then(|host| host.strip_suffix(']'))
+        .unwrap_or(hostname);
+    let ip: IpAddr = host.parse().map_err(|e| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("Invalid API hostname {hostname}, expected an IP address: {e}"),
+        )
+    })?;
+    Ok(SocketAddr::new(ip, port))
+}
+
+/// Liveness check, OK whenever the server is running. Served on /health
+/// and /health/live.
+async fn health_check() -> String {
+    "OK".into()
+}
+
+/// Fallback for unknown routes, so clients get the same JSON error body
+/// as ApiError::NotFound, plus the path that did not match.
+async fn route_not_found(uri: Uri) -> Response {
+    let body = Json(serde_json::json!({
+        "error": "route not found",
+        "path": uri.path(),
+    }));
+    (StatusCode::NOT_FOUND, body).into_response()
+}
+
+/// Returns pool metrics in grafana exposition format
+async fn metrics(State(state): State<Arc<AppState>>) -> String {
+    metrics_exposition(&state).await
+}
+
+/// Build the /metrics exposition, shared with the metrics file exporter
+pub(crate) async fn metrics_exposition(state: &AppState) -> String {
+    let started = std::time::Instant::now();
+    let pool_metrics = state.metrics_handle.get_metrics().await;
+    let mut exposition = pool_metrics.get_exposition();
+
+    if let Some(coinbase_distribution) = parse_coinbase::get_distribution(
+        &state.tracker_handle,
+        state.app_config.pool_signature_length,
+        state.app_config.network,
+        &state.app_config.coinbase_roles,
+    ) {
+        exposition.push_str("# HELP coinbase_rewards_distribution Current coinbase rewards distribution between users\n");
+        exposition.push_str(&coinbase_distribution);
+    }
+
+    if let Some(tip_age) = tip_age_secs(&state.chain_store_handle) {
+        exposition
+            .push_str("# HELP chain_tip_age_seconds Seconds since the chain tip share timestamp\n");
+        exposition.push_str("# TYPE chain_tip_age_seconds gauge\n");
+        exposition.push_str(&format!("chain_tip_age_seconds {tip_age}\n"));
+    }
+
+    if let Ok(SubmissionIntervalsResponse { p95: Some(p95), .. }) =
+        submission_intervals(state, DEFAULT_SUBMISSION_INTERVALS_WINDOW)
+    {
+        exposition.push_str(&format!("# HELP share_submission_interval_p95_seconds 95th percentile of seconds between the last {DEFAULT_SUBMISSION_INTERVALS_WINDOW} share header times\n"));
+        exposition.push_str("# TYPE share_submission_interval_p95_seconds gauge\n");
+        exposition.push_str(&format!("share_submission_interval_p95_seconds {p95}\n"));
+    }
+
+    // Last, so it covers building everything above
+    exposition.push_str(
+        "# HELP metrics_scrape_duration_seconds Seconds taken to build this metrics exposition\n",
+    );
+    exposition.push_str("# TYPE metrics_scrape_duration_seconds gauge\n");
+    exposition.push_str(&format!(
+        "metrics_scrape_duration_seconds {}\n",
+        started.elapsed().as_secs_f64()
+    ));
+    match &state.app_config.metrics_prefix {
+        Some(prefix) => prom::prefix_metric_names(&exposition, prefix),
+        None => exposition,
+    }
+}
+
+/// Parse a pplns time filter as a unix timestamp.
+///
+/// Accepts RFC3339 timestamps and bare YYYY-MM-DD dates, which are
+/// taken as midnight UTC. Pre-epoch times are rejected.
+fn parse_time_filter(field: &str, value: &str) -> Result<u64, ApiError> {
+    let timestamp = match DateTime::parse_from_rfc3339(value) {
+        Ok(dt) => dt.timestamp(),
+        Err(_) => match NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+            Ok(date) => date.and_time(NaiveTime::MIN).and_utc().timestamp(),
+            Err(_) => {
+                return Err(ApiError::BadRequest(format!(
+                    "Invalid {field}: expected RFC3339 timestamp or YYYY-MM-DD date"
+                )));
+            }
+        },
+    };
+    u64::try_from(timestamp)
+        .map_err(|_| ApiError::BadRequest(format!("Invalid {field}: must not be before 1970")))
+}
+
+/// The address filter from a PplnsQuery, checked to be a valid address
+/// for the configured network
+fn pplns_address<'a>(state: &AppState, query: &'a PplnsQuery) -> Result<Option<&'a str>, ApiError> {
+    let Some(address) = query.address.as_deref() else {
+        return Ok(None);
+    };
+    parse_address(address, state.app_config.network)
+        .map_err(|e| ApiError::BadRequest(format!("Invalid address: {e}")))?;
+    Ok(Some(address))
+}
+
+/// Parse the start and end time filters from a PplnsQuery into unix timestamps
+fn pplns_time_range(query: &PplnsQuery) -> Result<(u64, u64), ApiError> {
+    let start_time = match query.start_time.as_ref() {
+        Some(s) => parse_time_filter("start_time", s)?,
+        None => 0,
+    };
+
+    let end_time = match query.end_time.as_ref() {
+        Some(s) => parse_time_filter("end_time", s)?,
+        None => {
+            let now = chrono::Utc::now();
+            now.timestamp() as u64
+        }
+    };
+
+    if end_time < start_time {
+        return Err(ApiError::BadRequest(
+            "Invalid date range: end_time is before start_time".into(),
+        ));
+    }
+    Ok((start_time, end_time))
+}
+
+async fn pplns_shares(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<PplnsQuery>,
+) -> Result<Json<Vec<SimplePplnsShare>>, ApiError> {
+    let (start_time, end_time) = pplns_time_range(&query)?;
+    let address = pplns_address(&state, &query)?;
+
+    let shares = state.chain_store_handle.get_pplns_shares_filtered(
+        query.limit,
+        Some(start_time),
+        Some(end_time),
+        address,
+    );
+
+    Ok(Json(shares))
+}
+
+/// Estimate a miner's cut of a reward from their fraction of the total
+/// difficulty in the PPLNS window. A miner with no shares in the window
+/// gets a fraction of 0. Donation and fee cuts are not taken out.
+async fn pplns_estimate(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<PplnsEstimateQuery>,
+) -> Result<Json<PplnsEstimateResponse>, ApiError> {
+    let window = PplnsQuery {
+        limit: query.limit,
+        start_time: query.start_time,
+        end_time: query.end_time,
+        address: Some(query.address),
+    };
+    let (start_time, end_time) = pplns_time_range(&window)?;
+    let address = pplns_address(&state, &window)?.unwrap_or_default();
+
+    let shares = state.chain_store_handle.get_pplns_shares_filtered(
+        window.limit,
+        Some(start_time),
+        Some(end_time),
+        None,
+    );
+    let window_difficulty: f64 = shares.iter().map(|share| share.difficulty).sum();
+    let miner_difficulty: f64 = shares
+        .iter()
+        .filter(|share| share.btcaddress.as_deref() == Some(address))
+        .map(|share| share.difficulty)
+        .sum();
+    let fraction = if window_difficulty > 0.0 {
+        (miner_difficulty / window_difficulty).min(1.0)
+    } else {
+        0.0
+    };
+
+    Ok(Json(PplnsEstimateResponse {
+        address: address.to_string(),
+        fraction,
+        estimated_sats: (query.reward_sats as f64 * fraction).floor() as u64,
+        miner_difficulty,
+        window_difficulty,
+    }))
+}
+
+const PPLNS_CSV_HEADER: &str =
+    "user_id,btcaddress,workername,difficulty,n_time,job_id,extranonce2,nonce\n";
+
+/// Lines buffered between the PPLNS CSV reader and the response body
+const PPLNS_CSV_CHANNEL_CAPACITY: usize = 64;
+
+/// Returns the same shares as pplns_shares as a CSV attachment.
+/// Workername is not stored, so that column is left empty.
+///
+/// Shares are read on a blocking thread and each row is handed to the body
+/// through a bounded channel, like chain_dag_stream, so the CSV is never
+/// built up in memory.
+async fn pplns_shares_csv(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<PplnsQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let (start_time, end_time) = pplns_time_range(&query)?;
+    let address = pplns_address(&state, &query)?.map(str::to_string);
+
+    let (tx, rx) = tokio::sync::mpsc::channel::<String>(PPLNS_CSV_CHANNEL_CAPACITY);
+    tokio::task::spawn_blocking(move || {
+        if tx.blocking_send(PPLNS_CSV_HEADER.to_string()).is_err() {
+            return;
+        }
+        let shares = state.chain_store_handle.get_pplns_shares_filtered(
+            query.limit,
+            Some(start_time),
+            Some(end_time),
+            address.as_deref(),
+        );
+        for share in &shares {
+            // Stop once the client has gone away
+            if tx.blocking_send(pplns_csv_row(share)).is_err() {
+                return;
+            }
+        }
+    });
+
+    let rows = stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|row| (Ok::<_, Infallible>(row), rx))
+    });
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/csv; charset=utf-8"),
+            (
+                header::CONTENT_DISPOSITION,
+                "attachment; filename=\"pplns_shares.csv\"",
+            ),
+        ],
+        Body::from_stream(rows),
+    ))
+}
+
+/// Format a PPLNS share as a line of the CSV export, in PPLNS_CSV_HEADER
+/// column order
+fn pplns_csv_row(share: &SimplePplnsShare) -> String {
+    let fields = [
+        share.user_id.to_string(),
+        csv_escape(share.btcaddress.as_deref().unwrap_or_default()),
+        csv_escape(share.workername.as_deref().unwrap_or_default()),
+        share.difficulty.to_string(),
+        share.n_time.to_string(),
+        csv_escape(&share.job_id),
+        csv_escape(&share.extranonce2),
+        csv_escape(&share.nonce),
+    ];
+    let mut row = fields.join(",");
+    row.push('\n');
+    row
+}
+
+/// Quote a CSV field if it contains a delimiter, quote or line break,
+/// doubling any embedded quotes as per RFC 4180.
+fn csv_escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use axum::extract::State;
+    use bitcoin::{Amount, Network, TxOut};
+    use p2poolv2_lib::accounting::stats::metrics;
+    use p2poolv2_lib::shares::share_block::ShareTransaction;
+    use p2poolv2_lib::shares::share_commitment::ShareCommitment;
+    use p2poolv2_lib::stratum::messages::NotifyParams;
+    use p2poolv2_lib::stratum::session::{EXTRANONCE1_SIZE, EXTRANONCE2_SIZE};
+    use p2poolv2_lib::stratum::work::block_template::BlockTemplate;
+    use p2poolv2_lib::stratum::work::coinbase::split_coinbase;
+    use p2poolv2_lib::stratum::work::tracker::start_tracker_actor;
+    use p2poolv2_lib::test_utils::{
+        TestShareBlockBuilder, genesis_for_tests, setup_test_chain_store_handle,
+    };
+    use std::collections::HashMap;
+    use std::str::FromStr;
+    use std::sync::Arc;
+
+    /// AppConfig for tests. Tests override the fields they exercise with
+    /// ..AppConfig::default()
+    impl Default for AppConfig {
+        fn default() -> Self {
+            Self {
+                pool_signature_length: 8,
+                network: Network::Signet,
+                stale_after_secs: None,
+                page_size: PageSizeLimits::default(),
+                coinbase_roles: CoinbaseRoles::default(),
+                max_scan_items: DEFAULT_MAX_SCAN_ITEMS,
+                metrics_prefix: None,
+                db_read_only: true,
+                db_cf_access: CfAccess::default(),
+                job_ttl: Duration::from_secs(24 * 60 * 60),
+            }
+        }
+    }
+
+    /// AppState over the given store and metrics, with the default test
+    /// config, a fresh job tracker and empty caches. Tests override the
+    /// fields they exercise with ..test_app_state(..)
+    pub(crate) fn test_app_state(
+        chain_store_handle: ChainStoreHandle,
+        metrics_handle: MetricsHandle,
+    ) -> AppState {
+        AppState {
+            app_config: AppConfig::default(),
+            chain_store_handle,
+            metrics_handle,
+            tracker_handle: start_tracker_actor(),
+            api_keys: Vec::new(),
+            tip_events: TipEvents::default(),
+            dag_snapshot: Arc::default(),
+            dag_snapshot_compute: Arc::default(),
+            heavy_requests: HeavyRequestLimit::default(),
+            stats_snapshot: Arc::default(),
+        }
+    }
+
+    /// Metrics handle saving to a temporary directory, kept alive by the
+    /// returned TempDir
+    pub(crate) async fn test_metrics_handle() -> (MetricsHandle, tempfile::TempDir) {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let metrics_handle = metrics::start_metrics(temp_dir.path().to_str().unwrap().to_string())
+            .await
+            .unwrap();
+        (metrics_handle, temp_dir)
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_metrics_endpoint_exposes_coinbase_split() {
+        let tracker_handle = start_tracker_actor();
+
+        let (metrics_handle, _metrics_dir) = test_metrics_handle().await;
+
+        let address = parse_address(
+            "tb1q3udk7r26qs32ltf9nmqrjaaa7tr55qmkk30q5d",
+            Network::Signet,
+        )
+        .unwrap();
+
+        let donation_address = parse_address(
+            "tb1q0afww6y0kgl4tyjjyv6xlttvfwdfqxvrfzz35f",
+            Network::Signet,
+        )
+        .unwrap();
+
+        let template = BlockTemplate {
+            default_witness_commitment: Some(
+                "6a24aa21a9ed010000000000000000000000000000000000000000000000000000000000"
+                    .to_string(),
+            ),
+            height: 100,
+            version: 0x20000000,
+            previousblockhash: "0000000000000000000000000000000000000000000000000000000000000000"
+                .to_string(),
+            bits: "1d00ffff".to_string(),
+            curtime: 1234567890,
+            transactions: vec![],
+            coinbasevalue: 50_0000_0000,
+            coinbaseaux: HashMap::new(),
+            rules: vec![],
+            vbavailable: HashMap::new(),
+            vbrequired: 0,
+            longpollid: "".to_string(),
+            target: "".to_string(),
+            mintime: 0,
+            mutable: vec![],
+            noncerange: "".to_string(),
+            sigoplimit: 0,
+            sizelimit: 0,
+            weightlimit: 0,
+        };
+
+        let pool_signature = b"P2Poolv2";
+
+        let outputs = vec![
+            TxOut {
+                value: Amount::from_str("49 BTC").unwrap(),
+                script_pubkey: address.script_pubkey(),
+            },
+            TxOut {
+                value: Amount::from_str("1 BTC").unwrap(),
+                script_pubkey: donation_address.script_pubkey(),
+            },
+        ];
+
+        let mut coinbase2_bytes = Vec::new();
+        coinbase2_bytes.push(pool_signature.len() as u8);
+        coinbase2_bytes.extend_from_slice(pool_signature);
+        coinbase2_bytes.extend_from_slice(&[0xff, 0xff, 0xff, 0xff]);
+        coinbase2_bytes.extend_from_slice(&bitcoin::consensus::serialize(&outputs));
+        coinbase2_bytes.extend_from_slice(&[0x00, 0x00, 0x00, 0x00]);
+
+        let coinbase2_hex = hex::encode(coinbase2_bytes);
+
+        let job_id = tracker_handle.get_next_job_id();
+        tracker_handle.insert_job(
+            Arc::new(template),
+            "".to_string(),
+            coinbase2_hex,
+            None,
+            job_id,
+        );
+
+        let _genesis = ShareBlock::build_genesis_for_network(Network::Signet);
+        let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
+
+        let state = Arc::new(AppState {
+            app_config: AppConfig {
+                coinbase_roles: CoinbaseRoles::new(None, Some(donation_address)),
+                ..AppConfig::default()
+            },
+            tracker_handle,
+            ..test_app_state(chain_store_handle, metrics_handle)
+        });
+
+        let response_body = metrics(State(state)).await;
+
+        let scrape_duration = response_body
+            .lines()
+            .last()
+            .and_then(|line| line.strip_prefix("metrics_scrape_duration_seconds "))
+            .and_then(|value| value.parse::<f64>().ok())
+            .unwrap();
+        assert!(scrape_duration >= 0.0);
+
+        assert!(response_body.contains(
+            "coinbase_output{index=\"0\",address=\"tb1q3udk7r26qs32ltf9nmqrjaaa7tr55qmkk30q5d\",role=\"miner\"} 4900000000"
+        ));
+        assert!(response_body.contains(
+            "coinbase_output{index=\"1\",address=\"tb1q0afww6y0kgl4tyjjyv6xlttvfwdfqxvrfzz35f\",role=\"donation\"} 100000000"
+        ));
+    }
+
+    #[test]
+    fn test_csv_escape() {
+        assert_eq!(csv_escape("tb1qaddress"), "tb1qaddress");
+        assert_eq!(csv_escape("a,b"), "\"a,b\"");
+        assert_eq!(csv_escape("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_escape("line\nbreak"), "\"line\nbreak\"");
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_pplns_shares_csv_export() {
+        let (metrics_handle, _metrics_dir) = test_metrics_handle().await;
+        let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
+
+        let user_id = chain_store_handle
+            .add_user("tb1q3udk7r26qs32ltf9nmqrjaaa7tr55qmkk30q5d".to_string())
+            .await
+            .unwrap();
+        chain_store_handle
+            .add_pplns_share(SimplePplnsShare::new(
+                user_id,
+                1.5,
+                "tb1q3udk7r26qs32ltf9nmqrjaaa7tr55qmkk30q5d".to_string(),
+                "worker1".to_string(),
+                1_700_000_000,
+                "job,1".to_string(),
+                "0000abcd".to_string(),
+                "deadbeef".to_string(),
+            ))
+            .await
+            .unwrap();
+
+        let state = Arc::new(test_app_state(chain_store_handle, metrics_handle));
+
+        let response = pplns_shares_csv(
+            State(state),
+            Query(PplnsQuery {
+                limit: None,
+                start_time: None,
+                end_time: None,
+                address: None,
+            }),
+        )
+        .await
+        .unwrap()
+        .into_response();
+
+        assert_eq!(
+            response.headers().get(header::CONTENT_TYPE).unwrap(),
+            "text/csv; charset=utf-8"
+        );
+        assert!(
+            response
+                .headers()
+                .get(header::CONTENT_DISPOSITION)
+                .unwrap()
+                .to_str()
+                .unwrap()
+                .starts_with("attachment")
+        );
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        let mut lines = body.lines();
+        assert_eq!(lines.next().unwrap(), PPLNS_CSV_HEADER.trim_end());
+        assert_eq!(
+            lines.next().unwrap(),
+            format!(
+                "{user_id},tb1q3udk7r26qs32ltf9nmqrjaaa7tr55qmkk30q5d,,1.5,1700000000,\"job,1\",0000abcd,deadbeef"
+            )
+        );
+        assert!(lines.next().is_none());
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_pplns_shares_filters_by_address() {
+        let (metrics_handle, _metrics_dir) = test_metrics_handle().await;
+        let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
+
+        let addresses = [
+            "tb1q3udk7r26qs32ltf9nmqrjaaa7tr55qmkk30q5d",
+            "tb1q0afww6y0kgl4tyjjyv6xlttvfwdfqxvrfzz35f",
+        ];
+        for (index, address) in addresses.iter().enumerate() {
+            let user_id = chain_store_handle
+                .add_user(address.to_string())
+                .await
+                .unwrap();
+            chain_store_handle
+                .add_pplns_share(SimplePplnsShare::new(
+                    user_id,
+                    1.0,
+                    address.to_string(),
+                    "worker1".to_string(),
+                    1_700_000_000 + index as u64,
+                    "job".to_string(),
+                    "extra".to_string(),
+                    "nonce".to_string(),
+                ))
+                .await
+                .unwrap();
+        }
+
+        let state = Arc::new(test_app_state(chain_store_handle, metrics_handle));
+        let query = |address: Option<&str>| PplnsQuery {
+            limit: None,
+            start_time: None,
+            end_time: None,
+            address: address.map(str::to_string),
+        };
+
+        let all = pplns_shares(State(state.clone()), Query(query(None)))
+            .await
+            .unwrap();
+        assert_eq!(all.len(), 2);
+
+        let filtered = pplns_shares(State(state.clone()), Query(query(Some(addresses[1]))))
+            .await
+            .unwrap();
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].btcaddress.as_deref(), Some(addresses[1]));
+
+        // A mainnet address does not match the signet config
+        let result = pplns_shares(
+            State(state),
+            Query(query(Some("bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq"))),
+        )
+        .await;
+        assert!(matches!(result, Err(ApiError::BadRequest(_))));
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_pplns_estimate() {
+        let (metrics_handle, _metrics_dir) = test_metrics_handle().await;
+        let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
+
+        let addresses = [
+            "tb1q3udk7r26qs32ltf9nmqrjaaa7tr55qmkk30q5d",
+            "tb1q0afww6y0kgl4tyjjyv6xlttvfwdfqxvrfzz35f",
+        ];
+        for (index, (address, difficulty)) in addresses.iter().zip([3.0, 1.0]).enumerate() {
+            let user_id = chain_store_handle
+                .add_user(address.to_string())
+                .await
+                .unwrap();
+            chain_store_handle
+                .add_pplns_share(SimplePplnsShare::new(
+                    user_id,
+                    difficulty,
+                    address.to_string(),
+                    "worker1".to_string(),
+                    1_700_000_000 + index as u64,
+                    "job".to_string(),
+                    "extra".to_string(),
+                    "nonce".to_string(),
+                ))
+                .await
+                .unwrap();
+        }
+
+        let state = Arc::new(test_app_state(chain_store_handle, metrics_handle));
+        let estimate = |address: &str| {
+            pplns_estimate(
+                State(state.clone()),
+                Query(PplnsEstimateQuery {
+                    address: address.to_string(),
+                    reward_sats: 1_000_001,
+                    limit: None,
+                    start_time: None,
+                    end_time: None,
+                }),
+            )
+        };
+
+        let Json(response) = estimate(addresses[0]).await.unwrap();
+        assert_eq!(response.fraction, 0.75);
+        assert_eq!(response.estimated_sats, 750_000);
+        assert_eq!(response.miner_difficulty, 3.0);
+        assert_eq!(response.window_difficulty, 4.0);
+
+        // A valid address without shares in the window gets nothing
+        let Json(response) = estimate("tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx")
+            .await
+            .unwrap();
+        assert_eq!(response.fraction, 0.0);
+        assert_eq!(response.estimated_sats, 0);
+
+        let result = estimate("bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq").await;
+        assert!(matches!(result, Err(ApiError::BadRequest(_))));
+    }
+
+    #[test]
+    fn test_parse_time_filter_accepts_rfc3339() {
+        assert_eq!(
+            parse_time_filter("start_time", "2025-01-01T00:00:00Z").unwrap(),
+            1_735_689_600
+        );
+        assert_eq!(
+            parse_time_filter("start_time", "2025-01-01T02:00:00+02:00").unwrap(),
+            1_735_689_600
+        );
+    }
+
+    #[test]
+    fn test_parse_time_filter_accepts_bare_date_as_utc_midnight() {
+        assert_eq!(
+            parse_time_filter("end_time", "2025-01-01").unwrap(),
+            1_735_689_600
+        );
+    }
+
+    #[test]
+    fn test_parse_time_filter_rejects_invalid_format() {
+        for value in ["yesterday", "2025-13-01", "2025/01/01", "1735689600"] {
+            match parse_time_filter("end_time", value) {
+                Err(ApiError::BadRequest(msg)) => assert!(msg.contains("end_time")),
+                other => panic!("expected BadRequest for {value}, got {other:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_time_filter_rejects_pre_epoch() {
+        for value in ["1969-12-31", "1969-12-31T23:59:59Z"] {
+            match parse_time_filter("start_time", value) {
+                Err(ApiError::BadRequest(msg)) => assert!(msg.contains("start_time")),
+                other => panic!("expected BadRequest for {value}, got {other:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_pplns_time_range_rejects_reversed_range() {
+        let query = PplnsQuery {
+            limit: None,
+            start_time: Some("2025-01-02".to_string()),
+            end_time: Some("2025-01-01".to_string()),
+            address: None,
+        };
+        assert!(matches!(
+            pplns_time_range(&query),
+            Err(ApiError::BadRequest(_))
+        ));
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_blocks_found_reports_only_bitcoin_block_solves() {
+        let (metrics_handle, _metrics_dir) = test_metrics_handle().await;
+        let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
+
+        let genesis = genesis_for_tests();
+        chain_store_handle
+            .init_or_setup_genesis(genesis.clone())
+            .await
+            .unwrap();
+
+        // The signet genesis block header carries valid proof of work
+        let solved = TestShareBlockBuilder::new()
+            .prev_share_blockhash(genesis.block_hash().to_string())
+            .bitcoin_header(bitcoin::blockdata::constants::genesis_block(
+                Network::Signet,
+            ))
+            .build();
+        chain_store_handle.add_share(&solved, true).await.unwrap();
+
+        let unsolved = TestShareBlockBuilder::new()
+            .prev_share_blockhash(solved.block_hash().to_string())
+            .build();
+        chain_store_handle.add_share(&unsolved, true).await.unwrap();
+
+        let state = Arc::new(test_app_state(chain_store_handle, metrics_handle));
+
+        let response = blocks_found(State(state), Query(BlocksFoundQuery { limit: None }))
+            .await
+            .unwrap();
+
+        assert_eq!(response.blocks.len(), 1);
+        let block = &response.blocks[0];
+        assert_eq!(block.share_hash, solved.block_hash().to_string());
+        assert_eq!(
+            block.bitcoin_block_hash,
+            bitcoin::blockdata::constants::genesis_block(Network::Signet)
+                .block_hash()
+                .to_string()
+        );
+        assert_eq!(block.height, 1);
+        assert_eq!(response.to_height, 2);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_chain_reorgs_newest_first() {
+        let (metrics_handle, _metrics_dir) = test_metrics_handle().await;
+        let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
+
+        let genesis = genesis_for_tests();
+        chain_store_handle
+            .init_or_setup_genesis(genesis.clone())
+            .await
+            .unwrap();
+        // Each branch from genesis outgrows the previous one by a share,
+        // so the tip switches branch twice
+        let mut tips = Vec::new();
+        for (branch, length) in [(1u32, 1u32), (2, 2), (3, 3)] {
+            let mut prev = genesis.block_hash();
+            for index in 0..length {
+                let share = TestShareBlockBuilder::new()
+                    .prev_share_blockhash(prev.to_string())
+                    .nonce(branch * 100 + index)
+                    .build();
+                chain_store_handle.add_share(&share, true).await.unwrap();
+                prev = share.block_hash();
+            }
+            tips.push(prev);
+        }
+
+        let state = Arc::new(test_app_state(chain_store_handle, metrics_handle));
+
+        let reorgs = chain_reorgs(State(state.clone()), Query(ReorgsQuery { limit: None })).await;
+        assert_eq!(reorgs.len(), 2);
+        assert_eq!(reorgs[0].old_tip, tips[1].to_string());
+        assert_eq!(reorgs[0].new_tip, tips[2].to_string());
+        assert_eq!(reorgs[0].depth, 2);
+        assert_eq!(reorgs[1].old_tip, tips[0].to_string());
+        assert_eq!(reorgs[1].new_tip, tips[1].to_string());
+        assert_eq!(reorgs[1].depth, 1);
+
+        let reorgs =
+            chain_reorgs(State(state.clone()), Query(ReorgsQuery { limit: Some(1) })).await;
+        assert_eq!(reorgs.len(), 1);
+        assert_eq!(reorgs[0].new_tip, tips[2].to_string());
+
+        // Limits above the buffer size are capped rather than rejected
+        let reorgs = chain_reorgs(
+            State(state),
+            Query(ReorgsQuery {
+                limit: Some(u32::MAX),
+            }),
+        )
+        .await;
+        assert_eq!(reorgs.len(), 2);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_chain_tip_history_newest_first() {
+        let (metrics_handle, _metrics_dir) = test_metrics_handle().await;
+        let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
+
+        let genesis = genesis_for_tests();
+        chain_store_handle
+            .init_or_setup_genesis(genesis.clone())
+            .await
+            .unwrap();
+        let mut tips = vec![genesis.block_hash()];
+        for nonce in 1..=3 {
+            let share = TestShareBlockBuilder::new()
+                .prev_share_blockhash(tips.last().unwrap().to_string())
+                .nonce(nonce)
+                .build();
+            chain_store_handle.add_share(&share, true).await.unwrap();
+            tips.push(share.block_hash());
+        }
+
+        let state = Arc::new(test_app_state(chain_store_handle, metrics_handle));
+
+        let history =
+            chain_tip_history(State(state.clone()), Query(TipHistoryQuery { limit: None })).await;
+        let hashes: Vec<String> = history.iter().map(|tip| tip.hash.clone()).collect();
+        let expected: Vec<String> = tips.iter().rev().map(|tip| tip.to_string()).collect();
+        assert_eq!(hashes, expected);
+        let heights: Vec<u32> = history.iter().map(|tip| tip.height).collect();
+        assert_eq!(heights, vec![3, 2, 1, 0]);
+
+        let history = chain_tip_history(
+            State(state.clone()),
+            Query(TipHistoryQuery { limit: Some(2) }),
+        )
+        .await;
+        let heights: Vec<u32> = history.iter().map(|tip| tip.height).collect();
+        assert_eq!(heights, vec![3, 2]);
+
+        // Limits above the buffer size are capped, a short history is
+        // returned whole
+        let history = chain_tip_history(
+            State(state),
+            Query(TipHistoryQuery {
+                limit: Some(u32::MAX),
+            }),
+        )
+        .await;
+        assert_eq!(history.len(), 4);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_chain_verify_reports_missing_uncles() {
+        let (metrics_handle, _metrics_dir) = test_metrics_handle().await;
+        let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
+
+        let genesis = genesis_for_tests();
+        chain_store_handle
+            .init_or_setup_genesis(genesis.clone())
+            .await
+            .unwrap();
+        let share1 = TestShareBlockBuilder::new()
+            .prev_share_blockhash(genesis.block_hash().to_string())
+            .nonce(1)
+            .build();
+        chain_store_handle.add_share(&share1, true).await.unwrap();
+        let share2 = TestShareBlockBuilder::new()
+            .prev_share_blockhash(share1.block_hash().to_string())
+            .nonce(2)
+            .build();
+        chain_store_handle.add_share(&share2, true).await.unwrap();
+
+        let state = Arc::new(test_app_state(chain_store_handle.clone(), metrics_handle));
+
+        let report = chain_verify(State(state.clone()), Query(VerifyQuery { limit: None }))
+            .await
+            .unwrap();
+        assert_eq!(report.checked, 3);
+        assert!(report.errors.is_empty());
+        assert!(!report.truncated);
+
+        let report = chain_verify(State(state.clone()), Query(VerifyQuery { limit: Some(2) }))
+            .await
+            .unwrap();
+        assert_eq!(report.checked, 2);
+        assert!(report.truncated);
+
+        // A tip naming an uncle the store never received
+        let missing_uncle = BlockHash::from_byte_array([7; 32]);
+        let share3 = TestShareBlockBuilder::new()
+            .prev_share_blockhash(share2.block_hash().to_string())
+            .uncles(vec![missing_uncle])
+            .nonce(3)
+            .build();
+        chain_store_handle.add_share(&share3, true).await.unwrap();
+
+        let report = chain_verify(State(state), Query(VerifyQuery { limit: None }))
+            .await
+            .unwrap();
+        assert_eq!(report.checked, 4);
+        assert_eq!(report.errors.len(), 1);
+        assert_eq!(report.errors[0].hash, share3.block_hash().to_string());
+        assert!(
+            report.errors[0]
+                .problem
+                .contains(&missing_uncle.to_string())
+        );
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_chain_share_context_resolves_parent_and_uncles() {
+        let (metrics_handle, _metrics_dir) = test_metrics_handle().await;
+        let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
+
+        let genesis = genesis_for_tests();
+        chain_store_handle
+            .init_or_setup_genesis(genesis.clone())
+            .await
+            .unwrap();
+        let share1 = TestShareBlockBuilder::new()
+            .prev_share_blockhash(genesis.block_hash().to_string())
+            .nonce(1)
+            .build();
+        chain_store_handle.add_share(&share1, true).await.unwrap();
+        let uncle = TestShareBlockBuilder::new()
+            .prev_share_blockhash(genesis.block_hash().to_string())
+            .nonce(10)
+            .build();
+        chain_store_handle.add_share(&uncle, true).await.unwrap();
+        let missing_uncle = BlockHash::from_byte_array([7; 32]);
+        let share2 = TestShareBlockBuilder::new()
+            .prev_share_blockhash(share1.block_hash().to_string())
+            .uncles(vec![uncle.block_hash(), missing_uncle])
+            .nonce(2)
+            .build();
+        chain_store_handle.add_share(&share2, true).await.unwrap();
+
+        let state = Arc::new(test_app_state(chain_store_handle, metrics_handle));
+
+        let context =
+            chain_share_context(State(state.clone()), Path(share2.block_hash().to_string()))
+                .await
+                .unwrap();
+        assert_eq!(context.share.blockhash, share2.block_hash().to_string());
+        assert_eq!(context.height, Some(2));
+        assert_eq!(
+            context.bitcoin_block_hash,
+            share2.header.bitcoin_header.block_hash().to_string()
+        );
+        assert_eq!(
+            context.parent.as_ref().unwrap().blockhash,
+            share1.block_hash().to_string()
+        );
+        assert_eq!(context.uncles.len(), 2);
+        assert_eq!(
+            context.uncles[0].share.as_ref().unwrap().blockhash,
+            uncle.block_hash().to_string()
+        );
+        assert_eq!(context.uncles[1].blockhash, missing_uncle.to_string());
+        assert!(context.uncles[1].share.is_none());
+
+        // Genesis has no parent
+        let context =
+            chain_share_context(State(state.clone()), Path(genesis.block_hash().to_string()))
+                .await
+                .unwrap();
+        assert!(context.parent.is_none());
+        assert!(context.uncles.is_empty());
+
+        let missing = chain_share_context(State(state), Path(missing_uncle.to_string())).await;
+        assert!(matches!(missing, Err(ApiError::NotFound(_))));
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_chain_share_uncles_full() {
+        let (metrics_handle, _metrics_dir) = test_metrics_handle().await;
+        let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
+
+        let genesis = genesis_for_tests();
+        chain_store_handle
+            .init_or_setup_genesis(genesis.clone())
+            .await
+            .unwrap();
+        let share1 = TestShareBlockBuilder::new()
+            .prev_share_blockhash(genesis.block_hash().to_string())
+            .nonce(1)
+            .build();
+        chain_store_handle.add_share(&share1, true).await.unwrap();
+        let uncle_miner = "02c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5";
+        let uncle = TestShareBlockBuilder::new()
+            .prev_share_blockhash(genesis.block_hash().to_string())
+            .miner_pubkey(uncle_miner)
+            .nonce(10)
+            .build();
+        chain_store_handle.add_share(&uncle, true).await.unwrap();
+        let missing_uncle = BlockHash::from_byte_array([7; 32]);
+        let share2 = TestShareBlockBuilder::new()
+            .prev_share_blockhash(share1.block_hash().to_string())
+            .uncles(vec![uncle.block_hash(), missing_uncle])
+            .nonce(2)
+            .build();
+        chain_store_handle.add_share(&share2, true).await.unwrap();
+
+        let state = Arc::new(test_app_state(chain_store_handle, metrics_handle));
+
+        let Json(response) =
+            chain_share_uncles_full(State(state.clone()), Path(share2.block_hash().to_string()))
+                .await
+                .unwrap();
+        assert_eq!(response.blockhash, share2.block_hash().to_string());
+        assert_eq!(response.uncles.len(), 2);
+        assert!(response.uncles[0].available);
+        assert_eq!(
+            response.uncles[0].miner_pubkey.as_deref(),
+            Some(uncle_miner)
+        );
+        assert_eq!(
+            response.uncles[0].share.as_ref().unwrap().blockhash,
+            uncle.block_hash().to_string()
+        );
+
+        // Missing uncles serialize as just the hash and available false
+        assert_eq!(
+            serde_json::to_value(&response.uncles[1]).unwrap(),
+            serde_json::json!({"hash": missing_uncle.to_string(), "available": false})
+        );
+
+        let missing = chain_share_uncles_full(State(state), Path(missing_uncle.to_string())).await;
+        assert!(matches!(missing, Err(ApiError::NotFound(_))));
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_chain_orphans_excludes_main_chain_and_uncles() {
+        let (metrics_handle, _metrics_dir) = test_metrics_handle().await;
+        let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
+
+        let genesis = genesis_for_tests();
+        chain_store_handle
+            .init_or_setup_genesis(genesis.clone())
+            .await
+            .unwrap();
+
+        // Two competing shares at height 1, share1 wins
+        let share1 = TestShareBlockBuilder::new()
+            .prev_share_blockhash(genesis.block_hash().to_string())
+            .nonce(1)
+            .build();
+        chain_store_handle.add_share(&share1, true).await.unwrap();
+        let uncle = TestShareBlockBuilder::new()
+            .prev_share_blockhash(genesis.block_hash().to_string())
+            .nonce(2)
+            .build();
+        chain_store_handle.add_share(&uncle, true).await.unwrap();
+        let orphan = TestShareBlockBuilder::new()
+            .prev_share_blockhash(genesis.block_hash().to_string())
+            .nonce(3)
+            .build();
+        chain_store_handle.add_share(&orphan, true).await.unwrap();
+
+        // share2 includes only one of the losing shares as uncle
+        let share2 = TestShareBlockBuilder::new()
+            .prev_share_blockhash(share1.block_hash().to_string())
+            .uncles(vec![uncle.block_hash()])
+            .work(2)
+            .build();
+        chain_store_handle.add_share(&share2, true).await.unwrap();
+
+        // Extend the chain so the losing shares are deeper than uncle depth
+        let mut prev = share2.block_hash();
+        for _ in 0..3 {
+            let share = TestShareBlockBuilder::new()
+                .prev_share_blockhash(prev.to_string())
+                .work(2)
+                .build();
+            chain_store_handle.add_share(&share, true).await.unwrap();
+            prev = share.block_hash();
+        }
+
+        let state = test_app_state(chain_store_handle, metrics_handle);
+
+        let response = chain_orphans(
+            State(Arc::new(state.clone())),
+            Query(OrphansQuery { window: None }),
+        )
+        .await
+        .unwrap();
+
+        assert!(!response.truncated);
+        assert_eq!(response.orphans.len(), 1);
+        assert_eq!(response.orphans[0].hash, orphan.block_hash().to_string());
+        assert_eq!(response.orphans[0].height, 1);
+        assert_eq!(
+            response.orphans[0].prev_hash,
+            genesis.block_hash().to_string()
+        );
+
+        // The window is cut to the scan budget, keeping the heights nearest
+        // the tip
+        let small_budget = AppState {
+            app_config: AppConfig {
+                max_scan_items: 3,
+                ..AppConfig::default()
+            },
+            ..state
+        };
+        let response = chain_orphans(
+            State(Arc::new(small_budget)),
+            Query(OrphansQuery { window: None }),
+        )
+        .await
+        .unwrap();
+        assert!(response.truncated);
+        assert_eq!(response.from_height, 3);
+        assert!(response.orphans.is_empty());
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_chain_density_counts_shares_and_uncles() {
+        let (metrics_handle, _metrics_dir) = test_metrics_handle().await;
+        let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
+
+        let genesis = genesis_for_tests();
+        chain_store_handle
+            .init_or_setup_genesis(genesis.clone())
+            .await
+            .unwrap();
+        let share1 = TestShareBlockBuilder::new()
+            .prev_share_blockhash(genesis.block_hash().to_string())
+            .nonce(1)
+            .build();
+        chain_store_handle.add_share(&share1, true).await.unwrap();
+        let uncle = TestShareBlockBuilder::new()
+            .prev_share_blockhash(genesis.block_hash().to_string())
+            .nonce(2)
+            .build();
+        chain_store_handle.add_share(&uncle, true).await.unwrap();
+        let share2 = TestShareBlockBuilder::new()
+            .prev_share_blockhash(share1.block_hash().to_string())
+            .uncles(vec![uncle.block_hash()])
+            .nonce(3)
+            .build();
+        chain_store_handle.add_share(&share2, true).await.unwrap();
+
+        let state = |page_size| {
+            Arc::new(AppState {
+                app_config: AppConfig {
+                    page_size,
+                    ..AppConfig::default()
+                },
+                ..test_app_state(chain_store_handle.clone(), metrics_handle.clone())
+            })
+        };
+        let query = |from, to| Query(DensityQuery { from, to });
+
+        let density = chain_density(
+            State(state(PageSizeLimits::default())),
+            query(Some(0), None),
+        )
+        .await
+        .unwrap();
+        let counts: Vec<(u32, usize, usize)> = density
+            .iter()
+            .map(|d| (d.height, d.share_count, d.uncle_count))
+            .collect();
+        assert_eq!(counts, vec![(0, 1, 0), (1, 2, 0), (2, 1, 1)]);
+
+        // The window is capped at the max page size, keeping the newest heights
+        let capped = PageSizeLimits::from_config(None, Some(2));
+        let density = chain_density(State(state(capped)), query(Some(0), Some(2)))
+            .await
+            .unwrap();
+        let heights: Vec<u32> = density.iter().map(|d| d.height).collect();
+        assert_eq!(heights, vec![1, 2]);
+
+        let result = chain_density(
+            State(state(PageSizeLimits::default())),
+            query(Some(2), Some(1)),
+        )
+        .await;
+        assert!(matches!(result, Err(ApiError::BadRequest(_))));
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_chain_share_rate_buckets() {
+        let (metrics_handle, _metrics_dir) = test_metrics_handle().await;
+        let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
+
+        let genesis = genesis_for_tests();
+        chain_store_handle
+            .init_or_setup_genesis(genesis.clone())
+            .await
+            .unwrap();
+        let now: u64 = 1_700_000_040;
+        let mut prev = genesis.block_hash();
+        let mut difficulties = Vec::new();
+        for (nonce, age) in [(1, 170), (2, 100), (3, 10)] {
+            let mut share = TestShareBlockBuilder::new()
+                .prev_share_blockhash(prev.to_string())
+                .nonce(nonce)
+                .build();
+            share.header.time = (now - age) as u32;
+            chain_store_handle.add_share(&share, true).await.unwrap();
+            prev = share.block_hash();
+            difficulties.push(compact_difficulty(share.header.bits));
+        }
+
+        let (buckets, reached_window_start) =
+            share_rate_buckets(&chain_store_handle, 0, 3, now, 180, 60).unwrap();
+        assert!(reached_window_start);
+        let counts: Vec<(u64, usize)> = buckets
+            .iter()
+            .map(|bucket| (bucket.bucket_start, bucket.count))
+            .collect();
+        assert_eq!(
+            counts,
+            vec![(now - 180, 1), (now - 120, 1), (now - 60, 1), (now, 0)]
+        );
+        assert_eq!(buckets[0].total_difficulty, difficulties[0]);
+        assert_eq!(buckets[3].total_difficulty, 0.0);
+
+        // Stopping short of the window start is reported
+        let (buckets, reached_window_start) =
+            share_rate_buckets(&chain_store_handle, 2, 3, now, 180, 60).unwrap();
+        assert!(!reached_window_start);
+        assert_eq!(buckets[0].count, 0);
+        assert_eq!(buckets[1].count, 1);
+
+        let state = Arc::new(test_app_state(chain_store_handle, metrics_handle));
+        let query = |window, bucket| Query(ShareRateQuery { window, bucket });
+
+        let Json(response) = chain_share_rate(State(state.clone()), query(None, None))
+            .await
+            .unwrap();
+        assert_eq!((response.window, response.bucket), (3600, 60));
+        assert!(response.buckets.len() <= 61);
+        assert!(!response.truncated);
+
+        let zero_bucket = chain_share_rate(State(state.clone()), query(None, Some(0))).await;
+        assert!(matches!(zero_bucket, Err(ApiError::BadRequest(_))));
+        let too_many = chain_share_rate(State(state), query(Some(86_400 * 2), Some(60))).await;
+        assert!(matches!(too_many, Err(ApiError::BadRequest(_))));
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_chain_submission_intervals() {
+        let (metrics_handle, _metrics_dir) = test_metrics_handle().await;
+        let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
+
+        let genesis = genesis_for_tests();
+        chain_store_handle
+            .init_or_setup_genesis(genesis.clone())
+            .await
+            .unwrap();
+
+        let state = Arc::new(test_app_state(chain_store_handle.clone(), metrics_handle));
+        let query = |window| Query(SubmissionIntervalsQuery { window });
+
+        // Only genesis, so there are no intervals to report
+        let Json(response) = chain_submission_intervals(State(state.clone()), query(None))
+            .await
+            .unwrap();
+        assert_eq!(response.shares, 1);
+        assert_eq!(
+            (response.min, response.median, response.p95, response.max),
+            (None, None, None, None)
+        );
+        let json = serde_json::to_value(&response).unwrap();
+        assert!(json["p95"].is_null());
+        assert!(
+            !metrics_exposition(&state)
+                .await
+                .contains("share_submission_interval_p95_seconds")
+        );
+
+        let mut prev = genesis.block_hash();
+        for (nonce, offset) in [(1, 10), (2, 12), (3, 40)] {
+            let mut share = TestShareBlockBuilder::new()
+                .prev_share_blockhash(prev.to_string())
+                .nonce(nonce)
+                .build();
+            share.header.time = genesis.header.time + offset;
+            chain_store_handle.add_share(&share, true).await.unwrap();
+            prev = share.block_hash();
+        }
+
+        // Gaps of 10, 2 and 28 seconds
+        let Json(response) = chain_submission_intervals(State(state.clone()), query(None))
+            .await
+            .unwrap();
+        assert_eq!(response.shares, 4);
+        assert_eq!(
+            (response.min, response.median, response.p95, response.max),
+            (Some(2), Some(10), Some(28), Some(28))
+        );
+        assert!(!response.truncated);
+
+        // The newest 3 shares only, gaps of 2 and 28 seconds
+        let Json(response) = chain_submission_intervals(State(state.clone()), query(Some(3)))
+            .await
+            .unwrap();
+        assert_eq!(response.shares, 3);
+        assert_eq!(
+            (response.min, response.median, response.p95, response.max),
+            (Some(2), Some(2), Some(28), Some(28))
+        );
+
+        let exposition = metrics_exposition(&state).await;
+        assert!(exposition.contains("# TYPE share_submission_interval_p95_seconds gauge\n"));
+        assert!(exposition.contains("share_submission_interval_p95_seconds 28\n"));
+
+        for window in [0, 1, MAX_SUBMISSION_INTERVALS_WINDOW + 1] {
+            let result =
+                chain_submission_intervals(State(state.clone()), query(Some(window))).await;
+            assert!(matches!(result, Err(ApiError::BadRequest(_))));
+        }
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_coinbase_totals() {
+        let (metrics_handle, _metrics_dir) = test_metrics_handle().await;
+        let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
+
+        let miner = parse_address(
+            "tb1q3udk7r26qs32ltf9nmqrjaaa7tr55qmkk30q5d",
+            Network::Signet,
+        )
+        .unwrap();
+        let donation = parse_address(
+            "tb1q0afww6y0kgl4tyjjyv6xlttvfwdfqxvrfzz35f",
+            Network::Signet,
+        )
+        .unwrap();
+        let coinbase = |miner_sats: u64| bitcoin::Transaction {
+            version: bitcoin::transaction::Version::TWO,
+            lock_time: bitcoin::absolute::LockTime::ZERO,
+            input: vec![bitcoin::TxIn {
+                previous_output: OutPoint::null(),
+                script_sig: bitcoin::ScriptBuf::new(),
+                sequence: bitcoin::Sequence::MAX,
+                witness: bitcoin::Witness::new(),
+            }],
+            output: vec![
+                TxOut {
+                    value: Amount::from_sat(miner_sats),
+                    script_pubkey: miner.script_pubkey(),
+                },
+                TxOut {
+                    value: Amount::from_sat(1_000),
+                    script_pubkey: donation.script_pubkey(),
+                },
+            ],
+        };
+
+        let genesis = genesis_for_tests();
+        chain_store_handle
+            .init_or_setup_genesis(genesis.clone())
+            .await
+            .unwrap();
+        let mut prev = genesis.block_hash();
+        for nonce in 1..=3 {
+            let mut share = TestShareBlockBuilder::new()
+                .prev_share_blockhash(prev.to_string())
+                .nonce(nonce)
+                .build();
+            // The store keeps bitcoin transactions as txids, so the
+            // coinbase is also stored as a sharechain transaction.
+            let cb = coinbase(10_000 * nonce as u64);
+            share.transactions = vec![ShareTransaction(cb.clone())];
+            share.bitcoin_transactions = vec![cb];
+            chain_store_handle.add_share(&share, true).await.unwrap();
+            prev = share.block_hash();
+        }
+        // A fork share is not on the main chain and is not counted
+        let mut fork = TestShareBlockBuilder::new()
+            .prev_share_blockhash(genesis.block_hash().to_string())
+            .nonce(9)
+            .build();
+        fork.transactions = vec![ShareTransaction(coinbase(1_000_000))];
+        fork.bitcoin_transactions = vec![coinbase(1_000_000)];
+        chain_store_handle.add_share(&fork, true).await.unwrap();
+
+        let state = Arc::new(test_app_state(chain_store_handle, metrics_handle));
+
+        let totals = coinbase_totals(
+            State(state.clone()),
+            Query(CoinbaseTotalsQuery { window: Some(2) }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(totals.share_count, 2);
+        assert_eq!(totals.total_sats, 30_000 + 20_000 + 2 * 1_000);
+        let per_address: Vec<(String, u64)> = totals
+            .per_address
+            .iter()
+            .map(|total| (total.address.clone(), total.sats))
+            .collect();
+        assert_eq!(
+            per_address,
+            vec![(miner.to_string(), 50_000), (donation.to_string(), 2_000)]
+        );
+
+        // A window past genesis stops at genesis
+        let totals = coinbase_totals(
+            State(state),
+            Query(CoinbaseTotalsQuery {
+                window: Some(u32::MAX),
+            }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(totals.share_count, 4);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_chain_main_skips_forks() {
+        let (metrics_handle, _metrics_dir) = test_metrics_handle().await;
+        let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
+
+        let genesis = genesis_for_tests();
+        chain_store_handle
+            .init_or_setup_genesis(genesis.clone())
+            .await
+            .unwrap();
+        let share1 = TestShareBlockBuilder::new()
+            .prev_share_blockhash(genesis.block_hash().to_string())
+            .nonce(1)
+            .build();
+        chain_store_handle.add_share(&share1, true).await.unwrap();
+        let fork = TestShareBlockBuilder::new()
+            .prev_share_blockhash(genesis.block_hash().to_string())
+            .nonce(2)
+            .build();
+        chain_store_handle.add_share(&fork, true).await.unwrap();
+        let share2 = TestShareBlockBuilder::new()
+            .prev_share_blockhash(share1.block_hash().to_string())
+            .nonce(3)
+            .build();
+        chain_store_handle.add_share(&share2, true).await.unwrap();
+
+        let state = |page_size, max_scan_items| {
+            Arc::new(AppState {
+                app_config: AppConfig {
+                    page_size,
+                    max_scan_items,
+                    ..AppConfig::default()
+                },
+                ..test_app_state(chain_store_handle.clone(), metrics_handle.clone())
+            })
+        };
+        let query = |from, to| Query(MainChainQuery { from, to });
+
+        // Heights above the tip are skipped
+        let main = chain_main(
+            State(state(PageSizeLimits::default(), DEFAULT_MAX_SCAN_ITEMS)),
+            query(Some(0), Some(5)),
+        )
+        .await
+        .unwrap();
+        assert!(!main.truncated);
+        let spine: Vec<(u32, String, u32)> = main
+            .shares
+            .iter()
+            .map(|m| (m.height, m.share.blockhash.clone(), m.share.confirmations))
+            .collect();
+        assert_eq!(
+            spine,
+            vec![
+                (0, genesis.block_hash().to_string(), 2),
+                (1, share1.block_hash().to_string(), 1),
+                (2, share2.block_hash().to_string(), 0),
+            ]
+        );
+
+        let capped = PageSizeLimits::from_config(None, Some(2));
+        let main = chain_main(
+            State(state(capped, DEFAULT_MAX_SCAN_ITEMS)),
+            query(Some(0), None),
+        )
+        .await
+        .unwrap();
+        let heights: Vec<u32> = main.shares.iter().map(|m| m.height).collect();
+        assert_eq!(heights, vec![1, 2]);
+
+        // The walk back from the tip stops at the scan budget, even for a
+        // range that ends far below the tip
+        let main = chain_main(
+            State(state(PageSizeLimits::default(), 2)),
+            query(Some(0), Some(1)),
+        )
+        .await
+        .unwrap();
+        assert!(main.truncated);
+        assert_eq!(main.from_height, 1);
+        let heights: Vec<u32> = main.shares.iter().map(|m| m.height).collect();
+        assert_eq!(heights, vec![1]);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_chain_height_hash() {
+        let (metrics_handle, _metrics_dir) = test_metrics_handle().await;
+        let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
+
+        let genesis = genesis_for_tests();
+        chain_store_handle
+            .init_or_setup_genesis(genesis.clone())
+            .await
+            .unwrap();
+        let share1 = TestShareBlockBuilder::new()
+            .prev_share_blockhash(genesis.block_hash().to_string())
+            .nonce(1)
+            .build();
+        chain_store_handle.add_share(&share1, true).await.unwrap();
+        let fork = TestShareBlockBuilder::new()
+            .prev_share_blockhash(genesis.block_hash().to_string())
+            .nonce(2)
+            .build();
+        chain_store_handle.add_share(&fork, true).await.unwrap();
+        let share2 = TestShareBlockBuilder::new()
+            .prev_share_blockhash(share1.block_hash().to_string())
+            .nonce(3)
+            .build();
+        chain_store_handle.add_share(&share2, true).await.unwrap();
+
+        let state = |max_scan_items| {
+            Arc::new(AppState {
+                app_config: AppConfig {
+                    max_scan_items,
+                    ..AppConfig::default()
+                },
+                ..test_app_state(chain_store_handle.clone(), metrics_handle.clone())
+            })
+        };
+
+        // The fork at height 1 is not on the main chain
+        for (height, share) in [(0, &genesis), (1, &share1), (2, &share2)] {
+            let Json(response) =
+                chain_height_hash(State(state(DEFAULT_MAX_SCAN_ITEMS)), HeightPath(height))
+                    .await
+                    .unwrap();
+            assert_eq!(response.height, height);
+            assert_eq!(response.hash, share.block_hash().to_string());
+        }
+
+        assert!(matches!(
+            chain_height_hash(State(state(DEFAULT_MAX_SCAN_ITEMS)), HeightPath(3)).await,
+            Err(ApiError::NotFound(_))
+        ));
+        // Walking back two shares from the tip is over a budget of one
+        assert!(matches!(
+            chain_height_hash(State(state(1)), HeightPath(0)).await,
+            Err(ApiError::BadRequest(_))
+        ));
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_chain_events_streams_tip_changes() {
+        let (metrics_handle, _metrics_dir) = test_metrics_handle().await;
+        let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
+
+        let genesis = genesis_for_tests();
+        chain_store_handle
+            .init_or_setup_genesis(genesis.clone())
+            .await
+            .unwrap();
+
+        let tip_events = TipEvents::new();
+        let state = Arc::new(AppState {
+            tip_events: tip_events.clone(),
+            ..test_app_state(chain_store_handle.clone(), metrics_handle)
+        });
+
+        let response = chain_events(
+            State(state),
+            HeaderMap::new(),
+            Query(ChainEventsQuery {
+                last_event_id: None,
+            }),
+        )
+        .await
+        .unwrap()
+        .into_response();
+        assert_eq!(
+            response.headers().get(header::CONTENT_TYPE).unwrap(),
+            "text/event-stream"
+        );
+        let mut body = response.into_body().into_data_stream();
+
+        let watcher = tokio::spawn(watch_chain_tip(
+            chain_store_handle.clone(),
+            tip_events,
+            Duration::from_millis(10),
+        ));
+        // Let the watcher record the genesis tip before it changes
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let share1 = TestShareBlockBuilder::new()
+            .prev_share_blockhash(genesis.block_hash().to_string())
+            .nonce(1)
+            .build();
+        chain_store_handle.add_share(&share1, true).await.unwrap();
+
+        let frame = tokio::time::timeout(Duration::from_secs(5), body.next())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        let frame = String::from_utf8(frame.to_vec()).unwrap();
+        assert!(frame.starts_with("event: tip\n"));
+        let data = frame
+            .lines()
+            .find_map(|line| line.strip_prefix("data: "))
+            .unwrap();
+        let event: serde_json::Value = serde_json::from_str(data).unwrap();
+        assert_eq!(event["tip"], share1.block_hash().to_string());
+        assert_eq!(event["height"], 1);
+        let id = frame
+            .lines()
+            .find_map(|line| line.strip_prefix("id: "))
+            .unwrap();
+        assert_eq!(id, event["id"].to_string());
+
+        watcher.abort();
+    }
+
+    #[test]
+    fn test_tip_events_replay_since_last_event_id() {
+        let tip_events = TipEvents::new();
+        for height in 0..3 {
+            tip_events.publish(format!("tip{height}"), Some(height));
+        }
+        let (all, _) = tip_events.subscribe_since(0);
+        let ids: Vec<u64> = all.iter().map(|event| event.id).collect();
+        assert_eq!(ids, vec![ids[0], ids[0] + 1, ids[0] + 2]);
+
+        let (replay, mut receiver) = tip_events.subscribe_since(ids[0]);
+        let tips: Vec<&str> = replay.iter().map(|event| event.tip.as_str()).collect();
+        assert_eq!(tips, vec!["tip1", "tip2"]);
+        assert!(tip_events.subscribe_since(ids[2]).0.is_empty());
+
+        // Events after the subscription come live, not in the replay
+        tip_events.publish("tip3".into(), Some(3));
+        assert_eq!(receiver.try_recv().unwrap().id, ids[2] + 1);
+
+        // Only the most recent events are kept for replay
+        for height in 4..(TIP_EVENTS_REPLAY as u32 + 10) {
+            tip_events.publish(format!("tip{height}"), Some(height));
+        }
+        let (replay, _) = tip_events.subscribe_since(ids[0]);
+        assert_eq!(replay.len(), TIP_EVENTS_REPLAY);
+        assert_eq!(
+            replay.last().unwrap().tip,
+            format!("tip{}", TIP_EVENTS_REPLAY + 9)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_chain_events_replays_after_last_event_id() {
+        let (metrics_handle, _metrics_dir) = test_metrics_handle().await;
+        let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
+
+        let tip_events = TipEvents::new();
+        tip_events.publish("tip0".into(), Some(0));
+        tip_events.publish("tip1".into(), Some(1));
+        let first_id = tip_events.subscribe_since(0).0[0].id;
+        let state = Arc::new(AppState {
+            tip_events,
+            ..test_app_state(chain_store_handle, metrics_handle)
+        });
+
+        let no_query = || {
+            Query(ChainEventsQuery {
+                last_event_id: None,
+            })
+        };
+        let mut resume_headers = HeaderMap::new();
+        resume_headers.insert(LAST_EVENT_ID_HEADER, first_id.to_string().parse().unwrap());
+        for (headers, query) in [
+            (resume_headers, no_query()),
+            (
+                HeaderMap::new(),
+                Query(ChainEventsQuery {
+                    last_event_id: Some(first_id),
+                }),
+            ),
+        ] {
+            let response = chain_events(State(state.clone()), headers, query)
+                .await
+                .unwrap()
+                .into_response();
+            let mut body = response.into_body().into_data_stream();
+            let frame = tokio::time::timeout(Duration::from_secs(5), body.next())
+                .await
+                .unwrap()
+                .unwrap()
+                .unwrap();
+            let frame = String::from_utf8(frame.to_vec()).unwrap();
+            assert!(frame.contains(&format!("id: {}\n", first_id + 1)));
+            assert!(frame.contains("\"tip\":\"tip1\""));
+        }
+
+        let mut headers = HeaderMap::new();
+        headers.insert(LAST_EVENT_ID_HEADER, "not-a-number".parse().unwrap());
+        assert!(matches!(
+            chain_events(State(state), headers, no_query()).await,
+            Err(ApiError::BadRequest(_))
+        ));
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_chain_dag_prefetch_matches_serial_reads() {
+        let (metrics_handle, _metrics_dir) = test_metrics_handle().await;
+        let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
+
+        let genesis = genesis_for_tests();
+        chain_store_handle
+            .init_or_setup_genesis(genesis.clone())
+            .await
+            .unwrap();
+        // A 100 height chain, with a second share at every tenth height so
+        // some heights need their shares ordered
+        let mut prev = genesis.block_hash();
+        for nonce in 1..=100u32 {
+            if nonce % 10 == 0 {
+                let sibling = TestShareBlockBuilder::new()
+                    .prev_share_blockhash(prev.to_string())
+                    .nonce(1000 + nonce)
+                    .build();
+                chain_store_handle.add_share(&sibling, true).await.unwrap();
+            }
+            let share = TestShareBlockBuilder::new()
+                .prev_share_blockhash(prev.to_string())
+                .nonce(nonce)
+                .build();
+            chain_store_handle.add_share(&share, true).await.unwrap();
+            prev = share.block_hash();
+        }
+
+        let state = Arc::new(test_app_state(chain_store_handle, metrics_handle));
+        let dag_query = || DagQuery {
+            from_height: Some(1),
+            to_height: Some(100),
+            limit: None,
+            miner_pubkey: None,
+            include_parents: None,
+            fields: None,
+        };
+
+        let started = std::time::Instant::now();
+        let serial = build_dag(&state, &dag_query()).unwrap();
+        let serial_elapsed = started.elapsed();
+        let started = std::time::Instant::now();
+        let prefetched = chain_dag(State(state.clone()), Query(dag_query()))
+            .await
+            .unwrap();
+        let prefetched_elapsed = started.elapsed();
+        info!(
+            "DAG over 100 heights: serial reads {serial_elapsed:?}, prefetched {prefetched_elapsed:?}"
+        );
+
+        assert_eq!(prefetched.nodes.len(), 110);
+        assert_eq!(
+            serde_json::to_value(&prefetched.nodes).unwrap(),
+            serde_json::to_value(&serial.nodes).unwrap()
+        );
+        assert_eq!(
+            serde_json::to_value(&prefetched.edges).unwrap(),
+            serde_json::to_value(&serial.edges).unwrap()
+        );
+        let heights: Vec<u32> = prefetched.nodes.iter().map(|node| node.height).collect();
+        assert!(heights.is_sorted());
+
+        // Repeat requests come out in the same order
+        let again = chain_dag(State(state), Query(dag_query())).await.unwrap();
+        let hashes = |dag: &DagResponse| -> Vec<String> {
+            dag.nodes.iter().map(|node| node.hash.clone()).collect()
+        };
+        assert_eq!(hashes(&again), hashes(&prefetched));
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_chain_dag_stream_matches_chain_dag() {
+        let (metrics_handle, _metrics_dir) = test_metrics_handle().await;
+        let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
+
+        let genesis = genesis_for_tests();
+        chain_store_handle
+            .init_or_setup_genesis(genesis.clone())
+            .await
+            .unwrap();
+        let share1 = TestShareBlockBuilder::new()
+            .prev_share_blockhash(genesis.block_hash().to_string())
+            .nonce(1)
+            .build();
+        chain_store_handle.add_share(&share1, true).await.unwrap();
+        let share2 = TestShareBlockBuilder::new()
+            .prev_share_blockhash(share1.block_hash().to_string())
+            .build();
+        chain_store_handle.add_share(&share2, true).await.unwrap();
+
+        let state = Arc::new(test_app_state(chain_store_handle, metrics_handle));
+
+        let dag_query = || DagQuery {
+            from_height: None,
+            to_height: None,
+            limit: None,
+            miner_pubkey: None,
+            include_parents: None,
+            fields: None,
+        };
+        let dag = chain_dag(State(state.clone()), Query(dag_query()))
+            .await
+            .unwrap();
+
+        let response = chain_dag_stream(State(state.clone()), Query(dag_query()))
+            .await
+            .unwrap();
+        assert_eq!(
+            response.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/x-ndjson"
+        );
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        // Edge lines are told apart from node lines by their edge_type
+        let (edges, nodes): (Vec<&[u8]>, Vec<&[u8]>) = body
+            .split(|b| *b == b'\n')
+            .filter(|line| !line.is_empty())
+            .partition(|line| {
+                serde_json::from_slice::<serde_json::Value>(line).unwrap()["edge_type"].is_string()
+            });
+
+        // Lines are the same objects as chain_dag's nodes and edges
+        let nodes: Vec<DagNode> = nodes
+            .iter()
+            .map(|line| serde_json::from_slice(line).unwrap())
+            .collect();
+        let edges: Vec<DagEdge> = edges
+            .iter()
+            .map(|line| serde_json::from_slice(line).unwrap())
+            .collect();
+        assert_eq!(nodes.len(), dag.nodes.len());
+        assert_eq!(nodes.len(), 3);
+        assert_eq!(edges.len(), dag.edges.len());
+        assert_eq!(edges.len(), 2);
+
+        let share2_node = nodes
+            .iter()
+            .find(|node| node.hash == share2.block_hash().to_string())
+            .unwrap();
+        assert_eq!(share2_node.height, 2);
+        assert_eq!(share2_node.prev_hash, share1.block_hash().to_string());
+        assert!(share2_node.is_main_chain);
+
+        // Selected fields trim the nodes in both forms, edges are untouched
+        let masked_query = || {
+            Query(DagQuery {
+                fields: Some("hash,height".to_string()),
+                ..dag_query()
+            })
+        };
+        let response = chain_dag(State(state.clone()), masked_query())
+            .await
+            .unwrap()
+            .into_response();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["edges"].as_array().unwrap().len(), 2);
+        for node in json["nodes"].as_array().unwrap() {
+            assert_eq!(node.as_object().unwrap().len(), 2);
+            assert!(node.get("hash").is_some() && node.get("height").is_some());
+        }
+
+        let response = chain_dag_stream(State(state), masked_query())
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let lines: Vec<serde_json::Value> = body
+            .split(|b| *b == b'\n')
+            .filter(|line| !line.is_empty())
+            .map(|line| serde_json::from_slice(line).unwrap())
+            .collect();
+        let (edges, nodes): (Vec<_>, Vec<_>) =
+            lines.iter().partition(|line| line["edge_type"].is_string());
+        assert_eq!(nodes.len(), 3);
+        for node in nodes {
+            assert_eq!(node.as_object().unwrap().len(), 2);
+            assert!(node.get("hash").is_some() && node.get("height").is_some());
+        }
+        assert_eq!(edges.len(), 2);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_chain_dag_snapshot_cache() {
+        let (metrics_handle, _metrics_dir) = test_metrics_handle().await;
+        let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
+
+        let genesis = genesis_for_tests();
+        chain_store_handle
+            .init_or_setup_genesis(genesis.clone())
+            .await
+            .unwrap();
+        let share1 = TestShareBlockBuilder::new()
+            .prev_share_blockhash(genesis.block_hash().to_string())
+            .nonce(1)
+            .build();
+        chain_store_handle.add_share(&share1, true).await.unwrap();
+
+        let state = Arc::new(test_app_state(chain_store_handle.clone(), metrics_handle));
+        let snapshot_json = |state: Arc<AppState>| async move {
+            let response = chain_dag_snapshot(State(state)).await.unwrap();
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            serde_json::from_slice::<serde_json::Value>(&body).unwrap()
+        };
+
+        // An empty cache is computed on the request and kept for the next
+        let json = snapshot_json(state.clone()).await;
+        assert_eq!(json["nodes"].as_array().unwrap().len(), 2);
+        assert_eq!(json["tip_hash"], share1.block_hash().to_string());
+        assert!(json["generated_at"].as_u64().unwrap() > 0);
+        let cached = state.dag_snapshot.read().unwrap().clone().unwrap();
+        assert_eq!(cached.tip, share1.block_hash());
+
+        // A snapshot for the current tip is served as is
+        *state.dag_snapshot.write().unwrap() = Some(DagSnapshot {
+            tip: share1.block_hash(),
+            body: axum::body::Bytes::from_static(b"{\"cached\":true}"),
+        });
+        let json = snapshot_json(state.clone()).await;
+        assert_eq!(json["cached"], true);
+
+        // Once the tip moves on the old snapshot is ignored
+        let share2 = TestShareBlockBuilder::new()
+            .prev_share_blockhash(share1.block_hash().to_string())
+            .build();
+        chain_store_handle.add_share(&share2, true).await.unwrap();
+        let json = snapshot_json(state).await;
+        assert!(json.get("cached").is_none());
+        assert_eq!(json["nodes"].as_array().unwrap().len(), 3);
+        assert_eq!(json["tip_hash"], share2.block_hash().to_string());
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_chain_dag_since() {
+        let (metrics_handle, _metrics_dir) = test_metrics_handle().await;
+        let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
+
+        let genesis = genesis_for_tests();
+        chain_store_handle
+            .init_or_setup_genesis(genesis.clone())
+            .await
+            .unwrap();
+        let share1 = TestShareBlockBuilder::new()
+            .prev_share_blockhash(genesis.block_hash().to_string())
+            .nonce(1)
+            .build();
+        chain_store_handle.add_share(&share1, true).await.unwrap();
+        let uncle = TestShareBlockBuilder::new()
+            .prev_share_blockhash(genesis.block_hash().to_string())
+            .nonce(2)
+            .build();
+        chain_store_handle.add_share(&uncle, true).await.unwrap();
+        let share2 = TestShareBlockBuilder::new()
+            .prev_share_blockhash(share1.block_hash().to_string())
+            .uncles(vec![
+                uncle.block_hash(),
+                BlockHash::from_byte_array([7; 32]),
+            ])
+            .nonce(3)
+            .build();
+        chain_store_handle.add_share(&share2, true).await.unwrap();
+
+        let state_with_scan_items = |max_scan_items: u32| {
+            Arc::new(AppState {
+                app_config: AppConfig {
+                    max_scan_items,
+                    ..AppConfig::default()
+                },
+                ..test_app_state(chain_store_handle.clone(), metrics_handle.clone())
+            })
+        };
+        let state = state_with_scan_items(DEFAULT_MAX_SCAN_ITEMS);
+        let node_hashes = |dag: &DagResponse| {
+            let mut hashes: Vec<String> = dag.nodes.iter().map(|node| node.hash.clone()).collect();
+            hashes.sort();
+            hashes
+        };
+        let sorted = |mut hashes: Vec<String>| {
+            hashes.sort();
+            hashes
+        };
+        let no_fields = || Query(FieldsQuery { fields: None });
+
+        // New shares plus the late uncle below the synced height
+        let dag = chain_dag_since(State(state.clone()), HeightPath(1), no_fields())
+            .await
+            .unwrap();
+        assert_eq!(
+            node_hashes(&dag),
+            sorted(vec![
+                share2.block_hash().to_string(),
+                uncle.block_hash().to_string()
+            ])
+        );
+        assert_eq!(dag.from_height, 2);
+        assert_eq!(dag.to_height, 2);
+        assert!(!dag.truncated);
+        assert!(
+            dag.edges
+                .iter()
+                .any(|edge| edge.from == uncle.block_hash().to_string())
+        );
+
+        // Uncles inside the delta are not repeated
+        let dag = chain_dag_since(State(state.clone()), HeightPath(0), no_fields())
+            .await
+            .unwrap();
+        assert_eq!(dag.nodes.len(), 3);
+
+        // Nothing new at or beyond the tip
+        for height in [2, 50, u32::MAX] {
+            let dag = chain_dag_since(State(state.clone()), HeightPath(height), no_fields())
+                .await
+                .unwrap();
+            assert!(dag.nodes.is_empty());
+            assert!(!dag.truncated);
+        }
+
+        // A client further behind than the scan budget gets the heights
+        // nearest the tip
+        let dag = chain_dag_since(State(state_with_scan_items(1)), HeightPath(0), no_fields())
+            .await
+            .unwrap();
+        assert!(dag.truncated);
+        assert_eq!(dag.from_height, 2);
+        assert_eq!(dag.to_height, 2);
+        assert_eq!(
+            node_hashes(&dag),
+            sorted(vec![
+                share2.block_hash().to_string(),
+                uncle.block_hash().to_string()
+            ])
+        );
+        let uncle_node = dag
+            .nodes
+            .iter()
+            .find(|node| node.hash == uncle.block_hash().to_string())
+            .unwrap();
+        assert!(!uncle_node.is_main_chain);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_chain_dag_main_chain_walk_fits_scan_budget() {
+        let (metrics_handle, _metrics_dir) = test_metrics_handle().await;
+        let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
+
+        let genesis = genesis_for_tests();
+        chain_store_handle
+            .init_or_setup_genesis(genesis.clone())
+            .await
+            .unwrap();
+        let mut prev = genesis.block_hash();
+        for nonce in 1..=10u32 {
+            let share = TestShareBlockBuilder::new()
+                .prev_share_blockhash(prev.to_string())
+                .nonce(nonce)
+                .build();
+            chain_store_handle.add_share(&share, true).await.unwrap();
+            prev = share.block_hash();
+        }
+
+        let state = AppState {
+            app_config: AppConfig {
+                max_scan_items: 4,
+                ..AppConfig::default()
+            },
+            ..test_app_state(chain_store_handle, metrics_handle)
+        };
+        let range = |from_height, to_height| DagQuery {
+            from_height: Some(from_height),
+            to_height: Some(to_height),
+            limit: None,
+            miner_pubkey: None,
+            include_parents: None,
+            fields: None,
+        };
+
+        // Labelling heights 0 to 2 would walk the whole chain back from
+        // the tip at 10, so the range is raised past them
+        let dag = build_dag(&state, &range(0, 2)).unwrap();
+        assert!(dag.truncated);
+        assert_eq!(dag.from_height, 7);
+        assert!(dag.nodes.is_empty());
+
+        let dag = build_dag(&state, &range(7, 10)).unwrap();
+        assert!(!dag.truncated);
+        assert_eq!(dag.nodes.len(), 4);
+        assert!(dag.nodes.iter().all(|node| node.is_main_chain));
+    }
+
+    #[test]
+    fn test_parse_bind_addr() {
+        assert_eq!(
+            parse_bind_addr("127.0.0.1", 3000).unwrap(),
+            "127.0.0.1:3000".parse::<SocketAddr>().unwrap()
+        );
+        assert_eq!(
+            parse_bind_addr("::1", 3000).unwrap(),
+            "[::1]:3000".parse::<SocketAddr>().unwrap()
+        );
+        assert_eq!(
+            parse_bind_addr("[::]", 3000).unwrap(),
+            "[::]:3000".parse::<SocketAddr>().unwrap()
+        );
+        let err = parse_bind_addr("localhost", 3000).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    }
+
+    #[tokio::test]
+    async fn test_work_compare() {
+        let compare = |a: &str, b: &str| {
+            work_compare(Query(WorkCompareQuery {
+                a: a.to_string(),
+                b: b.to_string(),
+            }))
+        };
+
+        let Json(response) = compare("0x100", "ff").await.unwrap();
+        assert_eq!(
+            response,
+            WorkCompareResponse {
+                a_ge_b: true,
+                difference: format!("{:x}", work_from_u8(1)),
+            }
+        );
+
+        // Larger than u128, the difference stays non-negative
+        let large = format!("{:x}", Work::from_be_bytes([0xff; 32]));
+        let Json(response) = compare("1", &large).await.unwrap();
+        assert!(!response.a_ge_b);
+        assert_eq!(
+            response.difference,
+            format!("{:x}", Work::from_be_bytes([0xff; 32]) - work_from_u8(1))
+        );
+
+        let Json(response) = compare(&large, &large).await.unwrap();
+        assert!(response.a_ge_b);
+        assert_eq!(response.difference, "0".repeat(64));
+
+        let too_long = "1".repeat(65);
+        for (a, b) in [
+            ("xyz", "1"),
+            ("1", ""),
+            ("+1", "1"),
+            (too_long.as_str(), "1"),
+        ] {
+            assert!(matches!(compare(a, b).await, Err(ApiError::BadRequest(_))));
+        }
+    }
+
+    fn work_from_u8(value: u8) -> Work {
+        let mut bytes = [0; 32];
+        bytes[31] = value;
+        Work::from_be_bytes(bytes)
+    }
+
+    #[test]
+    fn test_compact_difficulty() {
+        let diff_one = CompactTarget::from_consensus(0x1d00ffff);
+        assert_eq!(compact_difficulty(diff_one), 1.0);
+
+        // Mainnet block 100000
+        let bits = CompactTarget::from_consensus(0x1b04864c);
+        assert!((compact_difficulty(bits) - 14484.162361225399).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_page_size_limits_from_config() {
+        assert_eq!(
+            PageSizeLimits::from_config(None, None),
+            PageSizeLimits::default()
+        );
+        let limits = PageSizeLimits::from_config(Some(500), Some(250));
+        assert_eq!(
+            limits,
+            PageSizeLimits {
+                default: 250,
+                max: 250
+            }
+        );
+        let limits = PageSizeLimits::from_config(Some(0), Some(u32::MAX));
+        assert_eq!(
+            limits,
+            PageSizeLimits {
+                default: 1,
+                max: MAX_PAGE_SIZE_CEILING
+            }
+        );
+
+        let limits = PageSizeLimits::from_config(Some(20), Some(200));
+        assert_eq!(limits.resolve(None), 20);
+        assert_eq!(limits.resolve(Some(150)), 150);
+        assert_eq!(limits.resolve(Some(5000)), 200);
+        assert_eq!(limits.resolve(Some(0)), 1);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_chain_dag_uses_configured_page_size() {
+        let (metrics_handle, _metrics_dir) = test_metrics_handle().await;
+        let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
+
+        let genesis = genesis_for_tests();
+        chain_store_handle
+            .init_or_setup_genesis(genesis.clone())
+            .await
+            .unwrap();
+        let mut prev = genesis.block_hash();
+        for nonce in 1..=4 {
+            let share = TestShareBlockBuilder::new()
+                .prev_share_blockhash(prev.to_string())
+                .nonce(nonce)
+                .build();
+            chain_store_handle.add_share(&share, true).await.unwrap();
+            prev = share.block_hash();
+        }
+
+        let state = Arc::new(AppState {
+            app_config: AppConfig {
+                page_size: PageSizeLimits::from_config(Some(1), Some(2)),
+                ..AppConfig::default()
+            },
+            ..test_app_state(chain_store_handle, metrics_handle)
+        });
+
+        let dag_query = |limit| DagQuery {
+            from_height: None,
+            to_height: None,
+            limit,
+            miner_pubkey: None,
+            include_parents: None,
+            fields: None,
+        };
+        let dag = chain_dag(State(state.clone()), Query(dag_query(None)))
+            .await
+            .unwrap();
+        assert_eq!((dag.from_height, dag.to_height), (3, 4));
+
+        let dag = chain_dag(State(state), Query(dag_query(Some(100))))
+            .await
+            .unwrap();
+        assert_eq!((dag.from_height, dag.to_height), (2, 4));
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_chain_dag_filters_by_miner_pubkey() {
+        let (metrics_handle, _metrics_dir) = test_metrics_handle().await;
+        let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
+
+        let genesis = genesis_for_tests();
+        chain_store_handle
+            .init_or_setup_genesis(genesis.clone())
+            .await
+            .unwrap();
+
+        let miner = "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+        let share1 = TestShareBlockBuilder::new()
+            .prev_share_blockhash(genesis.block_hash().to_string())
+            .nonce(1)
+            .build();
+        chain_store_handle.add_share(&share1, true).await.unwrap();
+        let share2 = TestShareBlockBuilder::new()
+            .prev_share_blockhash(share1.block_hash().to_string())
+            .miner_pubkey(miner)
+            .build();
+        chain_store_handle.add_share(&share2, true).await.unwrap();
+        let share3 = TestShareBlockBuilder::new()
+            .prev_share_blockhash(share2.block_hash().to_string())
+            .miner_pubkey(miner)
+            .build();
+        chain_store_handle.add_share(&share3, true).await.unwrap();
+
+        let state = Arc::new(test_app_state(chain_store_handle, metrics_handle));
+
+        let dag_query = |miner_pubkey: &str, include_parents| DagQuery {
+            from_height: None,
+            to_height: None,
+            limit: None,
+            miner_pubkey: Some(miner_pubkey.to_string()),
+            include_parents,
+            fields: None,
+        };
+
+        let dag = chain_dag(State(state.clone()), Query(dag_query(miner, None)))
+            .await
+            .unwrap();
+        let mut hashes: Vec<&str> = dag.nodes.iter().map(|n| n.hash.as_str()).collect();
+        hashes.sort();
+        let mut expected = vec![
+            share2.block_hash().to_string(),
+            share3.block_hash().to_string(),
+        ];
+        expected.sort();
+        assert_eq!(hashes, expected);
+        assert!(dag.nodes.iter().all(|n| n.is_main_chain && !n.is_uncle));
+        assert_eq!(dag.edges.len(), 1);
+        assert_eq!(dag.edges[0].from, share3.block_hash().to_string());
+        assert_eq!(dag.edges[0].to, share2.block_hash().to_string());
+
+        let dag = chain_dag(State(state.clone()), Query(dag_query(miner, Some(true))))
+            .await
+            .unwrap();
+        assert_eq!(dag.nodes.len(), 3);
+        let parent = dag
+            .nodes
+            .iter()
+            .find(|n| n.hash == share1.block_hash().to_string())
+            .unwrap();
+        assert_eq!(parent.height, 1);
+        assert!(parent.is_main_chain);
+        assert_eq!(dag.edges.len(), 2);
+        assert!(
+            dag.edges
+                .iter()
+                .all(|e| e.to != genesis.block_hash().to_string())
+        );
+
+        // The stream applies the same filters to its nodes and edges
+        for include_parents in [None, Some(true)] {
+            let dag = chain_dag(
+                State(state.clone()),
+                Query(dag_query(miner, include_parents)),
+            )
+            .await
+            .unwrap();
+            let response = chain_dag_stream(
+                State(state.clone()),
+                Query(dag_query(miner, include_parents)),
+            )
+            .await
+            .unwrap();
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            let lines: Vec<serde_json::Value> = body
+                .split(|b| *b == b'\n')
+                .filter(|line| !line.is_empty())
+                .map(|line| serde_json::from_slice(line).unwrap())
+                .collect();
+            let (edges, nodes): (Vec<_>, Vec<_>) =
+                lines.iter().partition(|line| line["edge_type"].is_string());
+
+            let mut streamed: Vec<&str> = nodes
+                .iter()
+                .map(|node| node["hash"].as_str().unwrap())
+                .collect();
+            streamed.sort();
+            let mut expected: Vec<&str> = dag.nodes.iter().map(|n| n.hash.as_str()).collect();
+            expected.sort();
+            assert_eq!(streamed, expected);
+
+            let mut streamed: Vec<(&str, &str)> = edges
+                .iter()
+                .map(|edge| (edge["from"].as_str().unwrap(), edge["to"].as_str().unwrap()))
+                .collect();
+            streamed.sort();
+            let mut expected: Vec<(&str, &str)> = dag
+                .edges
+                .iter()
+                .map(|edge| (edge.from.as_str(), edge.to.as_str()))
+                .collect();
+            expected.sort();
+            assert_eq!(streamed, expected);
+        }
+
+        let result =
+            chain_dag_stream(State(state.clone()), Query(dag_query("not-a-pubkey", None))).await;
+        assert!(matches!(result, Err(ApiError::BadRequest(_))));
+
+        let result = chain_dag(State(state), Query(dag_query("not-a-pubkey", None))).await;
+        assert!(matches!(result, Err(ApiError::BadRequest(_))));
+    }
+
+    #[test]
+    fn test_chain_stats_merge_flags_overflow_instead_of_wrapping() {
+        let max_work = Work::from_be_bytes([0xff; 32]);
+        let stats = |total_difficulty| ChainStats {
+            total_shares: 1,
+            total_uncles: 0,
+            total_difficulty,
+        };
+        let merged = stats(Some(max_work)).merge(stats(Some(max_work)));
+        assert_eq!(merged.total_shares, 2);
+        assert_eq!(merged.total_difficulty, None);
+
+        // Once overflowed the total stays unknown
+        let merged = merged.merge(ChainStats::empty());
+        assert_eq!(merged.total_difficulty, None);
+
+        let response = ChainStatsResponse {
+            height: Some(1),
+            total_shares: merged.total_shares,
+            total_uncles: merged.total_uncles,
+            total_difficulty: None,
+            total_difficulty_decimal: None,
+            total_difficulty_overflowed: merged.total_difficulty.is_none(),
+            from_height: 0,
+            truncated: false,
+        };
+        let json = serde_json::to_value(&response).unwrap();
+        assert_eq!(json["total_difficulty_overflowed"], true);
+        assert!(json["total_difficulty"].is_null());
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_chain_stats_total_difficulty_sums_share_work() {
+        let (metrics_handle, _metrics_dir) = test_metrics_handle().await;
+        let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
+
+        let genesis = genesis_for_tests();
+        chain_store_handle
+            .init_or_setup_genesis(genesis.clone())
+            .await
+            .unwrap();
+        let share1 = TestShareBlockBuilder::new()
+            .prev_share_blockhash(genesis.block_hash().to_string())
+            .work(2)
+            .build();
+        chain_store_handle.add_share(&share1, true).await.unwrap();
+        let share2 = TestShareBlockBuilder::new()
+            .prev_share_blockhash(share1.block_hash().to_string())
+            .uncles(vec![genesis.block_hash()])
+            .work(3)
+            .build();
+        chain_store_handle.add_share(&share2, true).await.unwrap();
+
+        let expected = [&genesis, &share1, &share2]
+            .iter()
+            .map(|share| bitcoin::Target::from_compact(share.header.bits).to_work())
+            .fold(Work::from_be_bytes([0; 32]), |total, work| total + work);
+
+        let state = Arc::new(test_app_state(chain_store_handle, metrics_handle));
+
+        let response = chain_stats(
+            State(state.clone()),
+            Query(ChainStatsQuery {
+                exact: None,
+                parallel: None,
+            }),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(response.height, Some(2));
+        assert_eq!(response.total_shares, 3);
+        assert_eq!(response.total_uncles, 1);
+        assert_eq!(response.total_difficulty, Some(format!("{:x}", expected)));
+        assert_eq!(
+            response.total_difficulty_decimal,
+            Some(expected.to_string())
+        );
+        assert!(!response.total_difficulty_overflowed);
+
+        // The parallel path merges partial counts to the same totals
+        let parallel = chain_stats(
+            State(state),
+            Query(ChainStatsQuery {
+                exact: Some(true),
+                parallel: Some(true),
+            }),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(parallel.height, response.height);
+        assert_eq!(parallel.total_shares, response.total_shares);
+        assert_eq!(parallel.total_uncles, response.total_uncles);
+        assert_eq!(parallel.total_difficulty, response.total_difficulty);
+    }
+
+    /// Times count_chain_stats_parallel against count_chain_stats over a
+    /// 5000 height chain. Ignored by default as it only reports timings, run
+    /// with: cargo test -p p2poolv2_api bench_count_chain_stats -- --ignored
+    #[ignore]
+    #[test_log::test(tokio::test)]
+    async fn bench_count_chain_stats_parallel_vs_serial() {
+        const HEIGHTS: u32 = 5000;
+        const RUNS: u32 = 5;
+        let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
+
+        let genesis = genesis_for_tests();
+        chain_store_handle
+            .init_or_setup_genesis(genesis.clone())
+            .await
+            .unwrap();
+        let mut prev = genesis.block_hash();
+        for nonce in 1..=HEIGHTS {
+            let share = TestShareBlockBuilder::new()
+                .prev_share_blockhash(prev.to_string())
+                .nonce(nonce)
+                .build();
+            chain_store_handle.add_share(&share, true).await.unwrap();
+            prev = share.block_hash();
+        }
+
+        let best_of = |count: &dyn Fn() -> ChainStats| {
+            (0..RUNS)
+                .map(|_| {
+                    let started = std::time::Instant::now();
+                    assert_eq!(count().total_shares, u64::from(HEIGHTS) + 1);
+                    started.elapsed()
+                })
+                .min()
+                .unwrap()
+        };
+        let serial = best_of(&|| count_chain_stats(&chain_store_handle, 0, HEIGHTS));
+        let parallel = best_of(&|| count_chain_stats_parallel(&chain_store_handle, 0, HEIGHTS));
+        info!(
+            "count_chain_stats over {} heights on {} rayon threads: serial {:?}, parallel {:?}",
+            HEIGHTS + 1,
+            rayon::current_num_threads(),
+            serial,
+            parallel
+        );
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_chain_stats_cached_matches_exact() {
+        let (metrics_handle, _metrics_dir) = test_metrics_handle().await;
+        let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
+
+        let genesis = genesis_for_tests();
+        chain_store_handle
+            .init_or_setup_genesis(genesis.clone())
+            .await
+            .unwrap();
+        let share1 = TestShareBlockBuilder::new()
+            .prev_share_blockhash(genesis.block_hash().to_string())
+            .work(2)
+            .build();
+        chain_store_handle.add_share(&share1, true).await.unwrap();
+        let share2 = TestShareBlockBuilder::new()
+            .prev_share_blockhash(share1.block_hash().to_string())
+            .uncles(vec![genesis.block_hash()])
+            .work(3)
+            .build();
+        chain_store_handle.add_share(&share2, true).await.unwrap();
+
+        let state = Arc::new(test_app_state(chain_store_handle, metrics_handle));
+
+        // Nothing to serve until the cache is first filled
+        assert!(matches!(
+            chain_stats_cached(State(state.clone())).await,
+            Err(ApiError::ServiceUnavailable(_))
+        ));
+
+        let snapshot = compute_stats_snapshot(&state).unwrap();
+        *state.stats_snapshot.write().unwrap() = Some(snapshot);
+        let cached = chain_stats_cached(State(state.clone())).await.unwrap();
+        for parallel in [None, Some(true)] {
+            let exact = chain_stats_exact(
+                State(state.clone()),
+                Query(ChainStatsExactQuery { parallel }),
+            )
+            .await
+            .unwrap();
+            assert_eq!(
+                serde_json::to_value(&cached.0).unwrap(),
+                serde_json::to_value(&exact.0).unwrap()
+            );
+        }
+        assert_eq!(cached.height, Some(2));
+        assert_eq!(cached.total_shares, 3);
+        assert_eq!(cached.total_uncles, 1);
+
+        // The cached counters carry the work sum of every counted share
+        let expected = sum_work(
+            [&genesis, &share1, &share2]
+                .iter()
+                .map(|share| bitcoin::Target::from_compact(share.header.bits).to_work()),
+        )
+        .unwrap();
+        assert_eq!(cached.total_difficulty, Some(format!("{:x}", expected)));
+        let extended = chain_info_extended(State(state)).await.unwrap();
+        assert_eq!(extended.total_difficulty, cached.total_difficulty);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_scan_budget_truncates_huge_ranges() {
+        let (metrics_handle, _metrics_dir) = test_metrics_handle().await;
+        let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
+
+        let genesis = genesis_for_tests();
+        chain_store_handle
+            .init_or_setup_genesis(genesis.clone())
+            .await
+            .unwrap();
+        let mut prev = genesis.block_hash();
+        for nonce in 1..=3 {
+            let share = TestShareBlockBuilder::new()
+                .prev_share_blockhash(prev.to_string())
+                .nonce(nonce)
+                .build();
+            chain_store_handle.add_share(&share, true).await.unwrap();
+            prev = share.block_hash();
+        }
+
+        let state = Arc::new(AppState {
+            app_config: AppConfig {
+                max_scan_items: 2,
+                ..AppConfig::default()
+            },
+            ..test_app_state(chain_store_handle, metrics_handle)
+        });
+
+        // The whole u32 range is cut to the two heights below to_height
+        let dag = chain_dag(
+            State(state.clone()),
+            Query(DagQuery {
+                from_height: Some(0),
+                to_height: Some(u32::MAX),
+                limit: None,
+                miner_pubkey: None,
+                include_parents: None,
+                fields: None,
+            }),
+        )
+        .await
+        .unwrap();
+        assert!(dag.truncated);
+        assert_eq!(dag.from_height, u32::MAX - 1);
+        assert!(dag.nodes.is_empty());
+
+        let dag = chain_dag(
+            State(state.clone()),
+            Query(DagQuery {
+                from_height: Some(0),
+                to_height: Some(3),
+                limit: None,
+                miner_pubkey: None,
+                include_parents: None,
+                fields: None,
+            }),
+        )
+        .await
+        .unwrap();
+        assert!(dag.truncated);
+        assert_eq!(dag.from_height, 2);
+        assert_eq!(dag.nodes.len(), 2);
+
+        let stream = chain_dag_stream(
+            State(state.clone()),
+            Query(DagQuery {
+                from_height: Some(0),
+                to_height: Some(u32::MAX),
+                limit: None,
+                miner_pubkey: None,
+                include_parents: None,
+                fields: None,
+            }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(stream.headers()[TRUNCATED_HEADER], "true");
+
+        // Stats only count the heights that fit in the budget
+        let stats = chain_stats(
+            State(state.clone()),
+            Query(ChainStatsQuery {
+                exact: None,
+                parallel: None,
+            }),
+        )
+        .await
+        .unwrap();
+        assert!(stats.truncated);
+        assert_eq!(stats.from_height, 2);
+        assert_eq!(stats.total_shares, 2);
+
+        // Listing stops after two entries, short of the requested page
+        let listing = db_viewer::list_cf_entries(
+            State(state),
+            axum::extract::OriginalUri("/db/cf/block/entries".parse().unwrap()),
+            Path("block".to_string()),
+            Query(db_viewer::ListQuery {
+                page: Some(1),
+                page_size: Some(10),
+                search: None,
+                sort: None,
+                dir: None,
+                cursor: None,
+                preview_bytes: None,
+            }),
+        )
+        .await
+        .unwrap();
+        assert!(listing.truncated);
+        assert_eq!(listing.entries.len(), 2);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_heavy_requests_beyond_limit_get_503() {
+        use tower::ServiceExt;
+
+        let (metrics_handle, _metrics_dir) = test_metrics_handle().await;
+        let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
+        chain_store_handle
+            .init_or_setup_genesis(genesis_for_tests())
+            .await
+            .unwrap();
+
+        let max_concurrent = 2;
+        let state = Arc::new(AppState {
+            heavy_requests: HeavyRequestLimit::new(max_concurrent),
+            ..test_app_state(chain_store_handle, metrics_handle)
+        });
+        let app = heavy_routes(state.clone())
+            .route("/health", get(health_check))
+            .with_state(state.clone());
+        let status = |uri: &'static str| {
+            let app = app.clone();
+            async move {
+                app.oneshot(
+                    axum::http::Request::get(uri)
+                        .body(axum::body::Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap()
+                .status()
+            }
+        };
+
+        // Hold every permit, as max_concurrent DAG requests in flight would
+        let in_flight = state
+            .heavy_requests
+            .0
+            .clone()
+            .try_acquire_many_owned(max_concurrent as u32)
+            .unwrap();
+        for uri in [
+            "/chain/dag",
+            "/chain/stats",
+            "/chain/main",
+            "/chain/orphans",
+            "/chain/density",
+            "/chain/dag/snapshot",
+            "/chain/known_miners",
+            "/db/cf/block/export",
+        ] {
+            assert_eq!(status(uri).await, StatusCode::SERVICE_UNAVAILABLE, "{uri}");
+        }
+        assert_eq!(status("/health").await, StatusCode::OK);
+
+        drop(in_flight);
+        let statuses = futures::future::join_all(
+            std::iter::repeat_with(|| status("/chain/dag")).take(max_concurrent),
+        )
+        .await;
+        assert!(statuses.iter().all(|status| *status == StatusCode::OK));
+        assert_eq!(state.heavy_requests.0.available_permits(), max_concurrent);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_chain_verify_needs_admin_scope() {
+        use tower::ServiceExt;
+
+        let (metrics_handle, _metrics_dir) = test_metrics_handle().await;
+        let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
+        chain_store_handle
+            .init_or_setup_genesis(genesis_for_tests())
+            .await
+            .unwrap();
+
+        let state = Arc::new(test_app_state(chain_store_handle, metrics_handle));
+        let status = |scope: ApiScope| {
+            let app = admin_routes(state.clone())
+                .layer(Extension(scope))
+                .with_state(state.clone());
+            async move {
+                app.oneshot(
+                    axum::http::Request::post("/chain/verify")
+                        .body(axum::body::Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap()
+                .status()
+            }
+        };
+
+        assert_eq!(status(ApiScope::Read).await, StatusCode::FORBIDDEN);
+        assert_eq!(status(ApiScope::Admin).await, StatusCode::OK);
+
+        // Admin requests still count against the heavy request limit
+        let _in_flight = state
+            .heavy_requests
+            .0
+            .clone()
+            .try_acquire_many_owned(state.heavy_requests.0.available_permits() as u32)
+            .unwrap();
+        assert_eq!(
+            status(ApiScope::Admin).await,
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_health_ready_waits_for_chain_tip() {
+        let (metrics_handle, _metrics_dir) = test_metrics_handle().await;
+        let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
+
+        let state = Arc::new(AppState {
+            app_config: AppConfig {
+                // The genesis tip is long stale, which readiness ignores
+                stale_after_secs: Some(1),
+                ..AppConfig::default()
+            },
+            ..test_app_state(chain_store_handle.clone(), metrics_handle)
+        });
+
+        assert_eq!(health_check().await, "OK");
+
+        let (status, response) = health_ready(State(state.clone())).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.status, "not ready");
+        assert!(!response.chain_tip_reachable);
+        assert!(response.metrics_reachable);
+
+        chain_store_handle
+            .init_or_setup_genesis(genesis_for_tests())
+            .await
+            .unwrap();
+
+        let (status, response) = health_ready(State(state)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(response.status, "ready");
+        assert!(response.chain_tip_reachable);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_chain_health_reports_dependencies() {
+        let (metrics_handle, _metrics_dir) = test_metrics_handle().await;
+        let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
+
+        let state = Arc::new(test_app_state(chain_store_handle.clone(), metrics_handle));
+
+        // No tip share in the store yet
+        let (status, response) = chain_health(State(state.clone())).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.status, "unhealthy");
+        assert!(!response.chain_tip_reachable);
+        assert!(response.metrics_reachable);
+        assert_eq!(response.tip_age_secs, None);
+
+        let genesis = genesis_for_tests();
+        chain_store_handle
+            .init_or_setup_genesis(genesis.clone())
+            .await
+            .unwrap();
+
+        let (status, response) = chain_health(State(state.clone())).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(response.status, "ok");
+        assert!(response.chain_tip_reachable);
+        assert!(response.metrics_reachable);
+        assert!(response.tip_age_secs.is_some());
+        assert!(!response.tip_stale);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_chain_tip_age_staleness() {
+        let (metrics_handle, _metrics_dir) = test_metrics_handle().await;
+        let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
+
+        // The test genesis share timestamp is long in the past
+        let genesis = genesis_for_tests();
+        chain_store_handle
+            .init_or_setup_genesis(genesis.clone())
+            .await
+            .unwrap();
+
+        let state = Arc::new(AppState {
+            app_config: AppConfig {
+                stale_after_secs: Some(600),
+                ..AppConfig::default()
+            },
+            ..test_app_state(chain_store_handle.clone(), metrics_handle)
+        });
+
+        let (status, response) = chain_health(State(state.clone())).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert!(response.chain_tip_reachable);
+        assert!(response.tip_stale);
+        assert!(response.tip_age_secs.unwrap() > 600);
+
+        let exposition = metrics(State(state.clone())).await;
+        assert!(exposition.contains("# TYPE chain_tip_age_seconds gauge\n"));
+        assert!(exposition.contains(&format!(
+            "chain_tip_age_seconds {}\n",
+            response.tip_age_secs.unwrap()
+        )));
+
+        // /chain/info reports the age but leaves it out of the ETag, so the
+        // tag holds while the tip ages
+        let info = || {
+            let state = state.clone();
+            async move {
+                let response = chain_info(State(state), HeaderMap::new()).await.unwrap();
+                let etag = response.headers()[header::ETAG].clone();
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .unwrap();
+                let info: serde_json::Value = serde_json::from_slice(&body).unwrap();
+                (etag, info["tip_age_secs"].as_u64().unwrap())
+            }
+        };
+        let (etag, age) = info().await;
+        assert!(age > 600);
+        tokio::time::sleep(Duration::from_millis(1100)).await;
+        let (later_etag, later_age) = info().await;
+        assert!(later_age > age);
+        assert_eq!(later_etag, etag);
+        let mut headers = HeaderMap::new();
+        headers.insert(header::IF_NONE_MATCH, etag);
+        let response = chain_info(State(state.clone()), headers).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+
+        // A tip timestamp ahead of the local clock is clamped to age 0
+        let mut share1 = TestShareBlockBuilder::new()
+            .prev_share_blockhash(genesis.block_hash().to_string())
+            .work(1)
+            .build();
+        share1.header.time = (chrono::Utc::now().timestamp() + 30) as u32;
+        chain_store_handle.add_share(&share1, true).await.unwrap();
+
+        let (status, response) = chain_health(State(state.clone())).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(response.tip_age_secs, Some(0));
+        assert!(!response.tip_stale);
+
+        let (_, age) = info().await;
+        assert_eq!(age, 0);
+        let Json(extended) = chain_info_extended(State(state)).await.unwrap();
+        assert_eq!(extended.info.tip_age_secs, Some(0));
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_chain_info_current_share_target() {
+        let (metrics_handle, _metrics_dir) = test_metrics_handle().await;
+        let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
+        chain_store_handle
+            .init_or_setup_genesis(genesis_for_tests())
+            .await
+            .unwrap();
+
+        let tracker_handle = start_tracker_actor();
+        let state = Arc::new(AppState {
+            tracker_handle: tracker_handle.clone(),
+            ..test_app_state(chain_store_handle, metrics_handle)
+        });
+
+        // No job loaded yet
+        let (_, info) = chain_info_response(&state).await.unwrap();
+        assert_eq!(info.current_share_target, None);
+        assert_eq!(info.current_share_difficulty, None);
+
+        let template: BlockTemplate = serde_json::from_str(include_str!(
+            "../../../p2poolv2_tests/test_data/gbt/signet/gbt-no-transactions.json"
+        ))
+        .unwrap();
+        let bits = CompactTarget::from_consensus(0x1e0377ae);
+        let commitment = ShareCommitment {
+            prev_share_blockhash: genesis_for_tests().block_hash(),
+            uncles: vec![],
+            miner_pubkey: "020202020202020202020202020202020202020202020202020202020202020202"
+                .parse()
+                .unwrap(),
+            merkle_root: None,
+            bits,
+            time: 1700000000,
+        };
+        let job_id = tracker_handle.get_next_job_id();
+        tracker_handle.insert_job(
+            Arc::new(template),
+            String::new(),
+            String::new(),
+            Some(commitment),
+            job_id,
+        );
+
+        let (_, info) = chain_info_response(&state).await.unwrap();
+        assert_eq!(
+            info.current_share_target,
+            Some(hex::encode(Target::from_compact(bits).to_be_bytes()))
+        );
+        assert_eq!(
+            info.current_share_difficulty,
+            Some(compact_difficulty(bits))
+        );
+        assert!(info.current_share_difficulty.unwrap() > 0.0);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_chain_info_extended_uses_stats_cache() {
+        let (metrics_handle, _metrics_dir) = test_metrics_handle().await;
+        let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
+
+        let genesis = genesis_for_tests();
+        chain_store_handle
+            .init_or_setup_genesis(genesis.clone())
+            .await
+            .unwrap();
+        let share1 = TestShareBlockBuilder::new()
+            .prev_share_blockhash(genesis.block_hash().to_string())
+            .nonce(1)
+            .build();
+        chain_store_handle.add_share(&share1, true).await.unwrap();
+        let uncle = TestShareBlockBuilder::new()
+            .prev_share_blockhash(genesis.block_hash().to_string())
+            .nonce(10)
+            .build();
+        chain_store_handle.add_share(&uncle, true).await.unwrap();
+        let share2 = TestShareBlockBuilder::new()
+            .prev_share_blockhash(share1.block_hash().to_string())
+            .uncles(vec![uncle.block_hash()])
+            .nonce(2)
+            .build();
+        chain_store_handle.add_share(&share2, true).await.unwrap();
+
+        let state = Arc::new(test_app_state(chain_store_handle, metrics_handle));
+
+        // Cached counters are null until the cache is first filled
+        let response = chain_info_extended(State(state.clone())).await.unwrap();
+        assert_eq!(response.info.tip, share2.block_hash().to_string());
+        assert_eq!(response.info.height, Some(2));
+        assert_eq!(response.total_shares, None);
+        assert_eq!(response.uncle_rate, None);
+        assert_eq!(response.total_difficulty, None);
+        assert_eq!(response.reorg_count, 0);
+
+        let snapshot = compute_stats_snapshot(&state).unwrap();
+        *state.stats_snapshot.write().unwrap() = Some(snapshot);
+        let response = chain_info_extended(State(state.clone())).await.unwrap();
+        // Genesis, share1, the uncle and share2, with one uncle reference
+        assert_eq!(response.total_shares, Some(4));
+        assert_eq!(response.uncle_rate, Some(0.25));
+        assert_eq!(response.stats_height, Some(2));
+        assert_eq!(response.stats_truncated, Some(false));
+
+        // The chain_info fields are flattened into the same object
+        let json = serde_json::to_value(&response.0).unwrap();
+        assert_eq!(json["tip"], share2.block_hash().to_string());
+        assert_eq!(json["total_shares"], 4);
+        assert!(json.get("info").is_none());
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_chain_compare_fork() {
+        let (metrics_handle, _metrics_dir) = test_metrics_handle().await;
+        let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
+
+        let genesis = genesis_for_tests();
+        chain_store_handle
+            .init_or_setup_genesis(genesis.clone())
+            .await
+            .unwrap();
+
+        // genesis <- share1 <- {fork_a, fork_b}
+        let share1 = TestShareBlockBuilder::new()
+            .prev_share_blockhash(genesis.block_hash().to_string())
+            .nonce(0xe9695791)
+            .work(1)
+            .build();
+        chain_store_handle.add_share(&share1, true).await.unwrap();
+
+        let fork_a = TestShareBlockBuilder::new()
+            .prev_share_blockhash(share1.block_hash().to_string())
+            .nonce(0xe9695792)
+            .work(1)
+            .build();
+        chain_store_handle.add_share(&fork_a, true).await.unwrap();
+
+        let fork_b = TestShareBlockBuilder::new()
+            .prev_share_blockhash(share1.block_hash().to_string())
+            .nonce(0xe9695793)
+            .work(2)
+            .build();
+        chain_store_handle.add_share(&fork_b, true).await.unwrap();
+
+        let state = Arc::new(test_app_state(chain_store_handle, metrics_handle));
+
+        let Json(response) = chain_compare(
+            State(state.clone()),
+            Query(CompareQuery {
+                a: fork_a.block_hash().to_string(),
+                b: fork_b.block_hash().to_string(),
+            }),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            response.common_ancestor,
+            Some(share1.block_hash().to_string())
+        );
+        assert!(!response.prev_hash.differs);
+        assert!(!response.miner_pubkey.differs);
+        assert!(response.bitcoin_block_hash.differs);
+
+        // A share is its own common ancestor with its descendants
+        let Json(response) = chain_compare(
+            State(state.clone()),
+            Query(CompareQuery {
+                a: share1.block_hash().to_string(),
+                b: fork_b.block_hash().to_string(),
+            }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            response.common_ancestor,
+            Some(share1.block_hash().to_string())
+        );
+        assert!(response.prev_hash.differs);
+
+        let missing = chain_compare(
+            State(state.clone()),
+            Query(CompareQuery {
+                a: fork_a.block_hash().to_string(),
+                b: BlockHash::all_zeros().to_string(),
+            }),
+        )
+        .await;
+        assert!(matches!(missing, Err(ApiError::NotFound(_))));
+
+        let invalid = chain_compare(
+            State(state),
+            Query(CompareQuery {
+                a: "not-a-hash".to_string(),
+                b: fork_b.block_hash().to_string(),
+            }),
+        )
+        .await;
+        assert!(matches!(invalid, Err(ApiError::BadRequest(_))));
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_chain_tip_etag_returns_not_modified() {
+        let (metrics_handle, _metrics_dir) = test_metrics_handle().await;
+        let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
+
+        let genesis = genesis_for_tests();
+        chain_store_handle
+            .init_or_setup_genesis(genesis.clone())
+            .await
+            .unwrap();
+
+        let state = Arc::new(test_app_state(chain_store_handle.clone(), metrics_handle));
+
+        let response = chain_tip(State(state.clone()), HeaderMap::new())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let etag = response.headers().get(header::ETAG).unwrap().clone();
+        assert!(etag.to_str().unwrap().starts_with("W/\""));
+
+        let mut headers = HeaderMap::new();
+        headers.insert(header::IF_NONE_MATCH, etag.clone());
+        let response = chain_tip(State(state.clone()), headers.clone())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(response.headers().get(header::ETAG), Some(&etag));
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert!(body.is_empty());
+
+        // Info and tip_with_uncles carry their own ETags
+        let response = chain_info(State(state.clone()), HeaderMap::new())
+            .await
+            .unwrap();
+        let info_etag = response.headers().get(header::ETAG).unwrap().clone();
+        let mut info_headers = HeaderMap::new();
+        info_headers.insert(header::IF_NONE_MATCH, info_etag);
+        let response = chain_info(State(state.clone()), info_headers.clone())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        let response = chain_tip_with_uncles(State(state.clone()), info_headers.clone())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+
+        // A new tip changes the ETag
+        let share1 = TestShareBlockBuilder::new()
+            .prev_share_blockhash(genesis.block_hash().to_string())
+            .work(1)
+            .build();
+        chain_store_handle.add_share(&share1, true).await.unwrap();
+
+        let response = chain_tip(State(state.clone()), headers).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_ne!(response.headers().get(header::ETAG), Some(&etag));
+        let response = chain_info(State(state), info_headers).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_submit_share_json() {
+        let (metrics_handle, _metrics_dir) = test_metrics_handle().await;
+        let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
+
+        let genesis = genesis_for_tests();
+        chain_store_handle
+            .init_or_setup_genesis(genesis.clone())
+            .await
+            .unwrap();
+
+        let mut share = TestShareBlockBuilder::new()
+            .prev_share_blockhash(genesis.block_hash().to_string())
+            .work(1)
+            .build();
+        let stale_json = serde_json::to_value(&share).unwrap();
+        share.header.bitcoin_header.time = chrono::Utc::now().timestamp() as u32;
+        let share_json = serde_json::to_value(&share).unwrap();
+
+        let mut state = test_app_state(chain_store_handle.clone(), metrics_handle);
+
+        // Refused while the API is open
+        let result = submit_share(
+            State(Arc::new(state.clone())),
+            Json(serde_json::from_value(share_json.clone()).unwrap()),
+        )
+        .await;
+        assert!(matches!(result, Err(ApiError::Forbidden(_))));
+
+        state.api_keys = vec![ApiKeyConfig {
+            user: "user".to_string(),
+            token: "salt$hmac".to_string(),
+            scope: ApiScope::Admin,
+        }];
+        let state = Arc::new(state);
+
+        // Validation rejects a share with an old timestamp
+        let result = submit_share(
+            State(state.clone()),
+            Json(serde_json::from_value(stale_json).unwrap()),
+        )
+        .await;
+        assert!(matches!(result, Err(ApiError::BadRequest(_))));
+
+        let Json(response) = submit_share(
+            State(state),
+            Json(serde_json::from_value(share_json).unwrap()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.block_hash, share.block_hash().to_string());
+        assert_eq!(
+            chain_store_handle.get_share(&share.block_hash()),
+            Some(share)
+        );
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_validate_share_short_and_verbose() {
+        let (metrics_handle, _metrics_dir) = test_metrics_handle().await;
+        let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
+
+        let genesis = genesis_for_tests();
+        chain_store_handle
+            .init_or_setup_genesis(genesis.clone())
+            .await
+            .unwrap();
+
+        let mut share = TestShareBlockBuilder::new()
+            .prev_share_blockhash(genesis.block_hash().to_string())
+            .work(1)
+            .build();
+        share.header.bitcoin_header.time = chrono::Utc::now().timestamp() as u32;
+        // Old timestamp and unknown parent
+        let invalid = TestShareBlockBuilder::new()
+            .prev_share_blockhash(
+                "0000000086704a35f17580d06f76d4c02d2b1f68774800675fb45f0411205bb7".to_string(),
+            )
+            .work(1)
+            .build();
+
+        let state = Arc::new(test_app_state(chain_store_handle.clone(), metrics_handle));
+        let validate = |share: &ShareBlock, verbose| {
+            validate_share(
+                State(state.clone()),
+                Query(ValidateShareQuery { verbose }),
+                Json(share.clone()),
+            )
+        };
+
+        // The short form stops at the first failed rule
+        let Json(response) = validate(&invalid, None).await;
+        assert!(!response.valid);
+        assert!(
+            response
+                .reason
+                .unwrap()
+                .starts_with("Share timestamp validation failed")
+        );
+        assert!(response.rules.is_none());
+
+        let Json(response) = validate(&invalid, Some(true)).await;
+        assert!(!response.valid);
+        assert!(response.reason.is_none());
+        let rules: Vec<(String, bool)> = response
+            .rules
+            .unwrap()
+            .into_iter()
+            .map(|rule| (rule.rule, rule.passed))
+            .collect();
+        assert_eq!(
+            rules,
+            vec![
+                ("timestamp".to_string(), false),
+                ("prev_share_blockhash".to_string(), false),
+                ("uncles".to_string(), true),
+            ]
+        );
+
+        for verbose in [None, Some(true)] {
+            let Json(response) = validate(&share, verbose).await;
+            assert!(response.valid);
+            assert_eq!(response.block_hash, share.block_hash().to_string());
+        }
+        // Validating doesn't add the share
+        assert!(chain_store_handle.get_share(&share.block_hash()).is_none());
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_shares_at_height_confirmations() {
+        let (metrics_handle, _metrics_dir) = test_metrics_handle().await;
+        let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
+
+        let genesis = genesis_for_tests();
+        chain_store_handle
+            .init_or_setup_genesis(genesis.clone())
+            .await
+            .unwrap();
+        let share1 = TestShareBlockBuilder::new()
+            .prev_share_blockhash(genesis.block_hash().to_string())
+            .nonce(1)
+            .build();
+        chain_store_handle.add_share(&share1, true).await.unwrap();
+        let share2 = TestShareBlockBuilder::new()
+            .prev_share_blockhash(share1.block_hash().to_string())
+            .nonce(2)
+            .build();
+        chain_store_handle.add_share(&share2, true).await.unwrap();
+        let fork = TestShareBlockBuilder::new()
+            .prev_share_blockhash(share1.block_hash().to_string())
+            .nonce(3)
+            .build();
+        chain_store_handle.add_share(&fork, true).await.unwrap();
+        let share3 = TestShareBlockBuilder::new()
+            .prev_share_blockhash(share2.block_hash().to_string())
+            .nonce(4)
+            .build();
+        chain_store_handle.add_share(&share3, true).await.unwrap();
+        assert_eq!(chain_store_handle.get_chain_tip(), share3.block_hash());
+
+        let state = Arc::new(test_app_state(chain_store_handle, metrics_handle));
+
+        let confirmations = |response: &SharesAtHeightResponse, share: &ShareBlock| {
+            response
+                .shares
+                .iter()
+                .find(|info| info.blockhash == share.block_hash().to_string())
+                .unwrap()
+                .confirmations
+        };
+
+        let all_fields = || {
+            Query(SharesAtHeightQuery {
+                fields: None,
+                sort: None,
+            })
+        };
+        let at_tip = shares_at_height(State(state.clone()), HeightPath(3), all_fields())
+            .await
+            .unwrap();
+        assert_eq!(confirmations(&at_tip, &share3), 0);
+
+        let at_two = shares_at_height(State(state.clone()), HeightPath(2), all_fields())
+            .await
+            .unwrap();
+        assert_eq!(at_two.shares.len(), 2);
+        assert_eq!(confirmations(&at_two, &share2), 1);
+        assert_eq!(confirmations(&at_two, &fork), 0);
+
+        let at_one = shares_at_height(State(state.clone()), HeightPath(1), all_fields())
+            .await
+            .unwrap();
+        assert_eq!(confirmations(&at_one, &share1), 2);
+
+        // Genesis is answered from the confirmed height index, without
+        // walking down from the tip, so a small scan budget still finds it
+        let small_budget = Arc::new(AppState {
+            app_config: AppConfig {
+                max_scan_items: 1,
+                ..AppConfig::default()
+            },
+            ..(*state).clone()
+        });
+        let at_zero = shares_at_height(State(small_budget.clone()), HeightPath(0), all_fields())
+            .await
+            .unwrap();
+        assert_eq!(confirmations(&at_zero, &genesis), 3);
+        // Heights the index doesn't cover are walked no further than the
+        // budget
+        let at_one = shares_at_height(State(small_budget), HeightPath(1), all_fields())
+            .await
+            .unwrap();
+        assert_eq!(confirmations(&at_one, &share1), 0);
+
+        // Unknown fields are ignored, the rest are projected
+        let masked = shares_at_height(
+            State(state.clone()),
+            HeightPath(2),
+            Query(SharesAtHeightQuery {
+                fields: Some("blockhash, confirmations,bogus".to_string()),
+                sort: None,
+            }),
+        )
+        .await
+        .unwrap()
+        .into_response();
+        let body = axum::body::to_bytes(masked.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["height"], 2);
+        for share in json["shares"].as_array().unwrap() {
+            let mut keys: Vec<&String> = share.as_object().unwrap().keys().collect();
+            keys.sort();
+            assert_eq!(keys, vec!["blockhash", "confirmations"]);
+        }
+
+        // Heights a little past the tip are empty, further ones rejected
+        let ahead = shares_at_height(
+            State(state.clone()),
+            HeightPath(3 + MAX_HEIGHT_BEYOND_TIP),
+            all_fields(),
+        )
+        .await
+        .unwrap();
+        assert!(ahead.shares.is_empty());
+        let beyond_tip = shares_at_height(
+            State(state),
+            HeightPath(3 + MAX_HEIGHT_BEYOND_TIP + 1),
+            all_fields(),
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(beyond_tip, ApiError::BadRequest(msg) if msg == "height beyond tip"));
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_shares_at_height_sorted() {
+        let (metrics_handle, _metrics_dir) = test_metrics_handle().await;
+        let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
+
+        let genesis = genesis_for_tests();
+        chain_store_handle
+            .init_or_setup_genesis(genesis.clone())
+            .await
+            .unwrap();
+        // Competing shares at height 1 with different work and timestamps
+        let mut shares = Vec::new();
+        for (nonce, work, time) in [
+            (1, 1, 1_700_000_300),
+            (2, 3, 1_700_000_100),
+            (3, 2, 1_700_000_200),
+        ] {
+            let mut share = TestShareBlockBuilder::new()
+                .prev_share_blockhash(genesis.block_hash().to_string())
+                .nonce(nonce)
+                .work(work)
+                .build();
+            share.header.time = time;
+            chain_store_handle.add_share(&share, true).await.unwrap();
+            shares.push(share);
+        }
+
+        let state = Arc::new(test_app_state(chain_store_handle, metrics_handle));
+        let hashes_sorted_by = |sort: Option<&str>| {
+            let state = state.clone();
+            let sort = sort.map(String::from);
+            async move {
+                shares_at_height(
+                    State(state),
+                    HeightPath(1),
+                    Query(SharesAtHeightQuery { fields: None, sort }),
+                )
+                .await
+                .map(|response| {
+                    response
+                        .shares
+                        .iter()
+                        .map(|info| info.blockhash.clone())
+                        .collect::<Vec<String>>()
+                })
+            }
+        };
+        let hash = |index: usize| shares[index].block_hash().to_string();
+
+        // Repeated requests give the same order
+        let first = hashes_sorted_by(None).await.unwrap();
+        let second = hashes_sorted_by(None).await.unwrap();
+        assert_eq!(first, second);
+        let mut by_difficulty: Vec<&ShareBlock> = shares.iter().collect();
+        by_difficulty.sort_by(|a, b| {
+            b.header
+                .get_work()
+                .cmp(&a.header.get_work())
+                .then_with(|| a.block_hash().to_string().cmp(&b.block_hash().to_string()))
+        });
+        let by_difficulty: Vec<String> = by_difficulty
+            .iter()
+            .map(|share| share.block_hash().to_string())
+            .collect();
+        assert_eq!(first, by_difficulty);
+        assert_eq!(hashes_sorted_by(Some("difficulty")).await.unwrap(), first);
+
+        assert_eq!(
+            hashes_sorted_by(Some("time")).await.unwrap(),
+            vec![hash(1), hash(2), hash(0)]
+        );
+        let mut by_hash = vec![hash(0), hash(1), hash(2)];
+        by_hash.sort();
+        assert_eq!(hashes_sorted_by(Some("hash")).await.unwrap(), by_hash);
+
+        assert!(matches!(
+            hashes_sorted_by(Some("random")).await,
+            Err(ApiError::BadRequest(_))
+        ));
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_uncles_at_height() {
+        let (metrics_handle, _metrics_dir) = test_metrics_handle().await;
+        let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
+
+        let genesis = genesis_for_tests();
+        chain_store_handle
+            .init_or_setup_genesis(genesis.clone())
+            .await
+            .unwrap();
+        let share1 = TestShareBlockBuilder::new()
+            .prev_share_blockhash(genesis.block_hash().to_string())
+            .nonce(1)
+            .build();
+        chain_store_handle.add_share(&share1, true).await.unwrap();
+        let uncle = TestShareBlockBuilder::new()
+            .prev_share_blockhash(genesis.block_hash().to_string())
+            .nonce(2)
+            .build();
+        chain_store_handle.add_share(&uncle, true).await.unwrap();
+        let missing_uncle = BlockHash::from_byte_array([7; 32]);
+        let share2 = TestShareBlockBuilder::new()
+            .prev_share_blockhash(share1.block_hash().to_string())
+            .uncles(vec![uncle.block_hash(), missing_uncle])
+            .nonce(3)
+            .build();
+        chain_store_handle.add_share(&share2, true).await.unwrap();
+
+        let state = Arc::new(test_app_state(chain_store_handle, metrics_handle));
+
+        let response = uncles_at_height(State(state.clone()), HeightPath(2))
+            .await
+            .unwrap();
+        assert_eq!(response.height, 2);
+        assert_eq!(response.uncles.len(), 2);
+        let resolved = &response.uncles[0];
+        assert_eq!(resolved.blockhash, uncle.block_hash().to_string());
+        assert_eq!(resolved.included_by, share2.block_hash().to_string());
+        let uncle_share = resolved.share.as_ref().unwrap();
+        assert_eq!(
+            uncle_share.prev_share_blockhash,
+            genesis.block_hash().to_string()
+        );
+        assert_eq!(uncle_share.confirmations, 0);
+        assert_eq!(response.uncles[1].blockhash, missing_uncle.to_string());
+        assert!(response.uncles[1].share.is_none());
+
+        let response = uncles_at_height(State(state.clone()), HeightPath(1))
+            .await
+            .unwrap();
+        assert!(response.uncles.is_empty());
+        let response = uncles_at_height(State(state.clone()), HeightPath(100))
+            .await
+            .unwrap();
+        assert!(response.uncles.is_empty());
+
+        let beyond_tip = uncles_at_height(State(state), HeightPath(2 + MAX_HEIGHT_BEYOND_TIP + 1))
+            .await
+            .unwrap_err();
+        assert!(matches!(beyond_tip, ApiError::BadRequest(msg) if msg == "height beyond tip"));
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_chain_share_by_bitcoin_block() {
+        let (metrics_handle, _metrics_dir) = test_metrics_handle().await;
+        let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
+
+        let genesis = genesis_for_tests();
+        chain_store_handle
+            .init_or_setup_genesis(genesis.clone())
+            .await
+            .unwrap();
+        let share = TestShareBlockBuilder::new()
+            .prev_share_blockhash(genesis.block_hash().to_string())
+            .nonce(7)
+            .build();
+        chain_store_handle.add_share(&share, true).await.unwrap();
+
+        let state = Arc::new(test_app_state(chain_store_handle, metrics_handle));
+
+        let bitcoin_blockhash = share.header.bitcoin_header.block_hash().to_string();
+        let response = chain_share_by_bitcoin_block(State(state.clone()), Path(bitcoin_blockhash))
+            .await
+            .unwrap();
+        assert_eq!(response.blockhash, share.block_hash().to_string());
+        assert_eq!(
+            response.prev_share_blockhash,
+            genesis.block_hash().to_string()
+        );
+        assert_eq!(response.confirmations, 0);
+
+        let missing = chain_share_by_bitcoin_block(
+            State(state.clone()),
+            Path(BlockHash::all_zeros().to_string()),
+        )
+        .await;
+        assert!(matches!(missing, Err(ApiError::NotFound(_))));
+
+        let invalid =
+            chain_share_by_bitcoin_block(State(state), Path("not-a-hash".to_string())).await;
+        assert!(matches!(invalid, Err(ApiError::BadRequest(_))));
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_chain_locator_resolve() {
+        let (metrics_handle, _metrics_dir) = test_metrics_handle().await;
+        let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
+
+        let genesis = genesis_for_tests();
+        chain_store_handle
+            .init_or_setup_genesis(genesis.clone())
+            .await
+            .unwrap();
+        let share1 = TestShareBlockBuilder::new()
+            .prev_share_blockhash(genesis.block_hash().to_string())
+            .nonce(1)
+            .build();
+        chain_store_handle.add_share(&share1, true).await.unwrap();
+        // A share only the peer has, so it is not stored here
+        let unknown = TestShareBlockBuilder::new()
+            .prev_share_blockhash(share1.block_hash().to_string())
+            .nonce(2)
+            .build();
+
+        let state = Arc::new(test_app_state(chain_store_handle, metrics_handle));
+
+        let locator = vec![
+            unknown.block_hash().to_string(),
+            share1.block_hash().to_string(),
+            genesis.block_hash().to_string(),
+        ];
+        let response = chain_locator_resolve(State(state.clone()), Json(locator))
+            .await
+            .unwrap();
+        let resolved: Vec<(usize, bool, Option<u32>)> = response
+            .entries
+            .iter()
+            .map(|e| (e.index, e.known, e.height))
+            .collect();
+        assert_eq!(
+            resolved,
+            vec![(0, false, None), (1, true, Some(1)), (2, true, Some(0))]
+        );
+        let fork_point = response.fork_point.as_ref().unwrap();
+        assert_eq!(fork_point.index, 1);
+        assert_eq!(fork_point.hash, share1.block_hash().to_string());
+
+        let invalid =
+            chain_locator_resolve(State(state.clone()), Json(vec!["nothex".to_string()])).await;
+        assert!(matches!(invalid, Err(ApiError::BadRequest(_))));
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_chain_share_raw() {
+        let (metrics_handle, _metrics_dir) = test_metrics_handle().await;
+        let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
+
+        let genesis = genesis_for_tests();
+        chain_store_handle
+            .init_or_setup_genesis(genesis.clone())
+            .await
+            .unwrap();
+        let share = TestShareBlockBuilder::new()
+            .prev_share_blockhash(genesis.block_hash().to_string())
+            .nonce(7)
+            .build();
+        chain_store_handle.add_share(&share, true).await.unwrap();
+
+        let state = Arc::new(test_app_state(chain_store_handle.clone(), metrics_handle));
+        let raw = |format: Option<&str>, encoding: Option<&str>| {
+            chain_share_raw(
+                State(state.clone()),
+                Path(share.block_hash().to_string()),
+                Query(RawShareQuery {
+                    format: format.map(String::from),
+                    encoding: encoding.map(String::from),
+                }),
+            )
+        };
+        let body = |response: Response| async move {
+            axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap()
+        };
+
+        // Wire bytes decode back to the same share
+        let response = raw(None, None).await.unwrap();
+        assert_eq!(
+            response.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/octet-stream"
+        );
+        let wire = body(response).await;
+        let decoded: ShareBlock = bitcoin::consensus::deserialize(&wire).unwrap();
+        assert_eq!(decoded.block_hash(), share.block_hash());
+
+        let hex_body = body(raw(Some("hex"), None).await.unwrap()).await;
+        assert_eq!(hex_body, hex::encode(&wire).as_bytes());
+
+        // Storage bytes match the block column family entry
+        let storage = body(raw(None, Some("storage")).await.unwrap()).await;
+        let stored = chain_store_handle
+            .get_cf_entry(ColumnFamily::Block, share.block_hash().as_byte_array())
+            .unwrap()
+            .unwrap();
+        assert_eq!(storage.to_vec(), stored);
+
+        assert!(matches!(
+            raw(Some("base64"), None).await,
+            Err(ApiError::BadRequest(_))
+        ));
+        let missing = chain_share_raw(
+            State(state.clone()),
+            Path(BlockHash::all_zeros().to_string()),
+            Query(RawShareQuery {
+                format: None,
+                encoding: None,
+            }),
+        )
+        .await;
+        assert!(matches!(missing, Err(ApiError::NotFound(_))));
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_chain_share_verify_pow() {
+        let (metrics_handle, _metrics_dir) = test_metrics_handle().await;
+        let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
+
+        let genesis = genesis_for_tests();
+        chain_store_handle
+            .init_or_setup_genesis(genesis.clone())
+            .await
+            .unwrap();
+        // The default test share target is far below its bitcoin header hash
+        let unmet = TestShareBlockBuilder::new()
+            .prev_share_blockhash(genesis.block_hash().to_string())
+            .nonce(1)
+            .build();
+        chain_store_handle.add_share(&unmet, true).await.unwrap();
+        // With the regtest target about half of all nonces meet it
+        let easy_bits = CompactTarget::from_consensus(0x207fffff);
+        let met = (2..)
+            .map(|nonce| {
+                let mut share = TestShareBlockBuilder::new()
+                    .prev_share_blockhash(genesis.block_hash().to_string())
+                    .nonce(nonce)
+                    .build();
+                share.header.bits = easy_bits;
+                share
+            })
+            .find(|share| {
+                Target::from_compact(easy_bits).is_met_by(share.header.bitcoin_header.block_hash())
+            })
+            .unwrap();
+        chain_store_handle.add_share(&met, true).await.unwrap();
+
+        let state = Arc::new(test_app_state(chain_store_handle, metrics_handle));
+
+        let response =
+            chain_share_verify_pow(State(state.clone()), Path(unmet.block_hash().to_string()))
+                .await
+                .unwrap();
+        assert!(!response.meets_target);
+        assert_eq!(
+            response.hash,
+            unmet.header.bitcoin_header.block_hash().to_string()
+        );
+        assert_eq!(
+            response.target,
+            hex::encode(Target::from_compact(unmet.header.bits).to_be_bytes())
+        );
+
+        let response =
+            chain_share_verify_pow(State(state.clone()), Path(met.block_hash().to_string()))
+                .await
+                .unwrap();
+        assert!(response.meets_target);
+        assert_eq!(
+            response.target,
+            "7fffff0000000000000000000000000000000000000000000000000000000000"
+        );
+
+        let missing =
+            chain_share_verify_pow(State(state), Path(BlockHash::all_zeros().to_string())).await;
+        assert!(matches!(missing, Err(ApiError::NotFound(_))));
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_chain_tip_bitcoin() {
+        let (metrics_handle, _metrics_dir) = test_metrics_handle().await;
+        let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
+
+        let state = Arc::new(test_app_state(chain_store_handle.clone(), metrics_handle));
+
+        // No tip share in the store yet
+        let missing = chain_tip_bitcoin(State(state.clone())).await;
+        assert!(matches!(missing, Err(ApiError::NotFound(_))));
+
+        let genesis = genesis_for_tests();
+        chain_store_handle
+            .init_or_setup_genesis(genesis.clone())
+            .await
+            .unwrap();
+        let share = TestShareBlockBuilder::new()
+            .prev_share_blockhash(genesis.block_hash().to_string())
+            .nonce(3)
+            .build();
+        chain_store_handle.add_share(&share, true).await.unwrap();
+
+        let Json(response) = chain_tip_bitcoin(State(state)).await.unwrap();
+        let header = share.header.bitcoin_header;
+        assert_eq!(response.share_hash, share.block_hash().to_string());
+        assert_eq!(response.bitcoin_block_hash, header.block_hash().to_string());
+        assert_eq!(response.version, header.version.to_consensus());
+        assert_eq!(response.prev_blockhash, header.prev_blockhash.to_string());
+        assert_eq!(response.merkle_root, header.merkle_root.to_string());
+        assert_eq!(response.time, header.time);
+        assert_eq!(response.bits, format!("{:08x}", header.bits.to_consensus()));
+        assert_eq!(response.nonce, header.nonce);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_job_current() {
+        let (metrics_handle, _metrics_dir) = test_metrics_handle().await;
+        let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
+
+        let miner_address = parse_address(
+            "tb1q3udk7r26qs32ltf9nmqrjaaa7tr55qmkk30q5d",
+            Network::Signet,
+        )
+        .unwrap();
+
+        let tracker_handle = start_tracker_actor();
+        let state = Arc::new(AppState {
+            tracker_handle: tracker_handle.clone(),
+            ..test_app_state(chain_store_handle, metrics_handle)
+        });
+
+        let missing = job_current(State(state.clone())).await;
+        assert!(matches!(missing, Err(ApiError::NotFound(_))));
+
+        let coinbase = bitcoin::Transaction {
+            version: bitcoin::transaction::Version(2),
+            lock_time: bitcoin::absolute::LockTime::ZERO,
+            input: vec![bitcoin::TxIn {
+                previous_output: OutPoint::null(),
+                script_sig: bitcoin::script::Builder::new()
+                    .push_int(100)
+                    .push_slice([1u8; EXTRANONCE1_SIZE + EXTRANONCE2_SIZE])
+                    .push_slice(b"P2Poolv2")
+                    .into_script(),
+                sequence: bitcoin::Sequence::MAX,
+                witness: bitcoin::Witness::new(),
+            }],
+            output: vec![TxOut {
+                value: Amount::from_str("50 BTC").unwrap(),
+                script_pubkey: miner_address.script_pubkey(),
+            }],
+        };
+        let (coinbase1, coinbase2) = split_coinbase(&coinbase).unwrap();
+        let template: BlockTemplate = serde_json::from_str(include_str!(
+            "../../../p2poolv2_tests/test_data/gbt/signet/gbt-no-transactions.json"
+        ))
+        .unwrap();
+        let expected_template = template.clone();
+        let job_id = tracker_handle.get_next_job_id();
+        tracker_handle.insert_job(Arc::new(template), coinbase1, coinbase2, None, job_id);
+
+        let Json(response) = job_current(State(state)).await.unwrap();
+        assert_eq!(response.job_id, job_id.0);
+        assert_eq!(response.height, expected_template.height);
+        assert_eq!(
+            response.previousblockhash,
+            expected_template.previousblockhash
+        );
+        assert_eq!(response.bits, expected_template.bits);
+        assert_eq!(response.coinbasevalue, expected_template.coinbasevalue);
+        assert_eq!(response.outputs.len(), 1);
+        assert_eq!(response.outputs[0].value_sats, 50_0000_0000);
+        assert_eq!(response.outputs[0].address, Some(miner_address.to_string()));
+        assert_eq!(response.outputs[0].role, Some("miner"));
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_jobs_newest_first_within_prune_window() {
+        let (metrics_handle, _metrics_dir) = test_metrics_handle().await;
+        let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
+
+        let notify = |job_id: u64, height: i64| {
+            let coinbase = bitcoin::Transaction {
+                version: bitcoin::transaction::Version(2),
+                lock_time: bitcoin::absolute::LockTime::ZERO,
+                input: vec![bitcoin::TxIn {
+                    previous_output: OutPoint::null(),
+                    script_sig: bitcoin::script::Builder::new()
+                        .push_int(height)
+                        .push_slice([1u8; EXTRANONCE1_SIZE + EXTRANONCE2_SIZE])
+                        .push_slice(b"P2Poolv2")
+                        .into_script(),
+                    sequence: bitcoin::Sequence::MAX,
+                    witness: bitcoin::Witness::new(),
+                }],
+                output: vec![TxOut {
+                    value: Amount::from_str("50 BTC").unwrap(),
+                    script_pubkey: bitcoin::ScriptBuf::new(),
+                }],
+            };
+            let (coinbase1, coinbase2) = split_coinbase(&coinbase).unwrap();
+            serde_json::to_string(&Notify::new_notify(NotifyParams {
+                job_id: format!("{job_id:016x}"),
+                prevhash: String::new(),
+                coinbase1,
+                coinbase2,
+                merkle_branches: vec![],
+                version: String::new(),
+                nbits: String::new(),
+                ntime: String::new(),
+                clean_jobs: false,
+            }))
+            .unwrap()
+        };
+
+        let now_micros = chrono::Utc::now().timestamp_micros() as u64;
+        let hour_micros = 60 * 60 * 1_000_000;
+        let store_handle = chain_store_handle.store_handle();
+        // Older than the one day prune window, but not pruned yet
+        store_handle
+            .add_job(now_micros - 48 * hour_micros, notify(1, 100))
+            .await
+            .unwrap();
+        store_handle
+            .add_job(now_micros - 2 * hour_micros, notify(2, 101))
+            .await
+            .unwrap();
+        store_handle
+            .add_job(now_micros - hour_micros, notify(3, 5))
+            .await
+            .unwrap();
+        store_handle
+            .add_job(now_micros - 1000, "not a notify".to_string())
+            .await
+            .unwrap();
+
+        let state = Arc::new(test_app_state(chain_store_handle, metrics_handle));
+
+        let Json(listed) = jobs(State(state.clone()), Query(JobsQuery { limit: None }))
+            .await
+            .unwrap();
+        let listed: Vec<(Option<String>, Option<u32>, u64)> = listed
+            .into_iter()
+            .map(|job| (job.job_id, job.height, job.timestamp_micros))
+            .collect();
+        assert_eq!(
+            listed,
+            vec![
+                (None, None, now_micros - 1000),
+                (
+                    Some(format!("{:016x}", 3)),
+                    Some(5),
+                    now_micros - hour_micros
+                ),
+                (
+                    Some(format!("{:016x}", 2)),
+                    Some(101),
+                    now_micros - 2 * hour_micros
+                ),
+            ]
+        );
+
+        // limit is capped at the max page size
+        let Json(limited) = jobs(State(state.clone()), Query(JobsQuery { limit: Some(1) }))
+            .await
+            .unwrap();
+        assert_eq!(limited.len(), 1);
+        let Json(capped) = jobs(
+            State(state),
+            Query(JobsQuery {
+                limit: Some(u32::MAX),
+            }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(capped.len(), 3);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_chain_tip_coinbase() {
+        let (metrics_handle, _metrics_dir) = test_metrics_handle().await;
+        let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
+
+        let miner_address = parse_address(
+            "tb1q3udk7r26qs32ltf9nmqrjaaa7tr55qmkk30q5d",
+            Network::Signet,
+        )
+        .unwrap();
+        let donation_address = parse_address(
+            "tb1q0afww6y0kgl4tyjjyv6xlttvfwdfqxvrfzz35f",
+            Network::Signet,
+        )
+        .unwrap();
+
+        let tracker_handle = start_tracker_actor();
+        let state = Arc::new(AppState {
+            app_config: AppConfig {
+                coinbase_roles: CoinbaseRoles::new(None, Some(donation_address.clone())),
+                ..AppConfig::default()
+            },
+            tracker_handle: tracker_handle.clone(),
+            ..test_app_state(chain_store_handle, metrics_handle)
+        });
+
+        let missing = chain_tip_coinbase(State(state.clone())).await;
+        assert!(matches!(missing, Err(ApiError::NotFound(_))));
+
+        let witness_commitment = bitcoin::ScriptBuf::from_bytes(
+            hex::decode("6a24aa21a9ed010000000000000000000000000000000000000000000000000000000000")
+                .unwrap(),
+        );
+        let coinbase = bitcoin::Transaction {
+            version: bitcoin::transaction::Version(2),
+            lock_time: bitcoin::absolute::LockTime::ZERO,
+            input: vec![bitcoin::TxIn {
+                previous_output: OutPoint::null(),
+                script_sig: bitcoin::script::Builder::new()
+                    .push_int(100)
+                    .push_slice([1u8; EXTRANONCE1_SIZE + EXTRANONCE2_SIZE])
+                    .push_slice(b"P2Poolv2")
+                    .into_script(),
+                sequence: bitcoin::Sequence::MAX,
+                witness: bitcoin::Witness::new(),
+            }],
+            output: vec![
+                TxOut {
+                    value: Amount::from_str("49 BTC").unwrap(),
+                    script_pubkey: miner_address.script_pubkey(),
+                },
+                TxOut {
+                    value: Amount::from_str("1 BTC").unwrap(),
+                    script_pubkey: donation_address.script_pubkey(),
+                },
+                TxOut {
+                    value: Amount::ZERO,
+                    script_pubkey: witness_commitment.clone(),
+                },
+            ],
+        };
+        let (coinbase1, coinbase2) = split_coinbase(&coinbase).unwrap();
+        let template: BlockTemplate = serde_json::from_str(include_str!(
+            "../../../p2poolv2_tests/test_data/gbt/signet/gbt-no-transactions.json"
+        ))
+        .unwrap();
+        let template_height = template.height;
+        let job_id = tracker_handle.get_next_job_id();
+        tracker_handle.insert_job(Arc::new(template), coinbase1, coinbase2, None, job_id);
+
+        let Json(response) = chain_tip_coinbase(State(state)).await.unwrap();
+        assert_eq!(response.job_id, job_id.0);
+        assert_eq!(response.height, template_height);
+        assert_eq!(response.txid, coinbase.compute_txid().to_string());
+        assert_eq!(
+            response.hex,
+            bitcoin::consensus::encode::serialize_hex(&coinbase)
+        );
+        assert_eq!(
+            response.script_sig,
+            hex::encode(coinbase.input[0].script_sig.as_bytes())
+        );
+        assert_eq!(response.total_sats, 50_0000_0000);
+
+        let outputs: Vec<(u64, Option<&str>, Option<&str>)> = response
+            .outputs
+            .iter()
+            .map(|output| (output.value_sats, output.address.as_deref(), output.role))
+            .collect();
+        let miner_address = miner_address.to_string();
+        let donation_address = donation_address.to_string();
+        assert_eq!(
+            outputs,
+            vec![
+                (49_0000_0000, Some(miner_address.as_str()), Some("miner")),
+                (
+                    1_0000_0000,
+                    Some(donation_address.as_str()),
+                    Some("donation")
+                ),
+                (0, None, None),
+            ]
+        );
+        assert_eq!(
+            response.outputs[2].script_pubkey,
+            hex::encode(witness_commitment.as_bytes())
+        );
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_chain_shares_by_miner() {
+        let (metrics_handle, _metrics_dir) = test_metrics_handle().await;
+        let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
+
+        let genesis = genesis_for_tests();
+        chain_store_handle
+            .init_or_setup_genesis(genesis.clone())
+            .await
+            .unwrap();
+
+        // Miner a mines at 1000, 2000 and 3000, miner b in between
+        let miner_a = "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+        let miner_b = "02c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5";
+        let mut prev_hash = genesis.block_hash();
+        let mut shares = Vec::new();
+        for (miner, time) in [
+            (miner_a, 1000),
+            (miner_b, 1500),
+            (miner_a, 2000),
+            (miner_a, 3000),
+        ] {
+            let mut share = TestShareBlockBuilder::new()
+                .prev_share_blockhash(prev_hash.to_string())
+                .miner_pubkey(miner)
+                .build();
+            share.header.time = time;
+            chain_store_handle.add_share(&share, true).await.unwrap();
+            prev_hash = share.block_hash();
+            shares.push(share);
+        }
+
+        let state = Arc::new(test_app_state(chain_store_handle, metrics_handle));
+        let query = |page, page_size, from: Option<&str>, to: Option<&str>| {
+            Query(MinerSharesQuery {
+                page,
+                page_size,
+                from: from.map(str::to_string),
+                to: to.map(str::to_string),
+            })
+        };
+        let hashes = |response: &MinerSharesResponse| -> Vec<String> {
+            response
+                .shares
+                .iter()
+                .map(|share| share.hash.clone())
+                .collect()
+        };
+
+        // Pages of two, newest first, only miner a's shares
+        let Json(first) = chain_shares_by_miner(
+            State(state.clone()),
+            Path(miner_a.to_string()),
+            query(None, Some(2), None, None),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            hashes(&first),
+            vec![
+                shares[3].block_hash().to_string(),
+                shares[2].block_hash().to_string()
+            ]
+        );
+        assert_eq!(first.shares[0].height, Some(4));
+        assert_eq!(first.shares[0].timestamp, 3000);
+        assert!(first.has_more);
+
+        let Json(second) = chain_shares_by_miner(
+            State(state.clone()),
+            Path(miner_a.to_string()),
+            query(Some(2), Some(2), None, None),
+        )
+        .await
+        .unwrap();
+        assert_eq!(hashes(&second), vec![shares[0].block_hash().to_string()]);
+        assert!(!second.has_more);
+
+        let Json(miner_b_shares) = chain_shares_by_miner(
+            State(state.clone()),
+            Path(miner_b.to_string()),
+            query(None, None, None, None),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            hashes(&miner_b_shares),
+            vec![shares[1].block_hash().to_string()]
+        );
+
+        // Time range is inclusive at both ends
+        let Json(ranged) = chain_shares_by_miner(
+            State(state.clone()),
+            Path(miner_a.to_string()),
+            query(
+                None,
+                None,
+                Some("1970-01-01T00:16:40Z"),
+                Some("1970-01-01T00:33:20Z"),
+            ),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            hashes(&ranged),
+            vec![
+                shares[2].block_hash().to_string(),
+                shares[0].block_hash().to_string()
+            ]
+        );
+
+        let invalid = chain_shares_by_miner(
+            State(state.clone()),
+            Path("not-a-pubkey".to_string()),
+            query(None, None, None, None),
+        )
+        .await;
+        assert!(matches!(invalid, Err(ApiError::BadRequest(_))));
+
+        let reversed = chain_shares_by_miner(
+            State(state),
+            Path(miner_a.to_string()),
+            query(None, None, Some("1970-01-02"), Some("1970-01-01")),
+        )
+        .await;
+        assert!(matches!(reversed, Err(ApiError::BadRequest(_))));
     }
-    false
-}
 
-// ============================================================================
-// Server Setup
-// ============================================================================
+    #[test_log::test(tokio::test)]
+    async fn test_chain_known_miners() {
+        let (metrics_handle, _metrics_dir) = test_metrics_handle().await;
+        let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
 
-/// Start the API server and return a shutdown channel
-pub async fn start_api_server(
-    config: ApiConfig,
-    chain_store_handle: ChainStoreHandle,
-    metrics_handle: MetricsHandle,
-    tracker_handle: Arc<JobTracker>,
-    network: bitcoin::Network,
-    pool_signature: Option<String>,
-) -> Result<oneshot::Sender<()>, std::io::Error> {
-    let app_config = AppConfig {
-        pool_signature_length: pool_signature.unwrap_or_default().len(),
-        network,
-    };
+        let genesis = genesis_for_tests();
+        chain_store_handle
+            .init_or_setup_genesis(genesis.clone())
+            .await
+            .unwrap();
 
-    let app_state = Arc::new(AppState {
-        app_config: app_config.clone(),
-        chain_store_handle,
-        metrics_handle,
-        tracker_handle,
-        auth_user: config.auth_user.clone(),
-        auth_token: config.auth_token.clone(),
-    });
+        // Miner b is seen at height 2 only, miner a at 1, 3 and 4
+        let miner_a = "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+        let miner_b = "02c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5";
+        let mut prev_hash = genesis.block_hash();
+        for (miner, time) in [
+            (miner_a, 1000),
+            (miner_b, 1500),
+            (miner_a, 2000),
+            (miner_a, 3000),
+        ] {
+            let mut share = TestShareBlockBuilder::new()
+                .prev_share_blockhash(prev_hash.to_string())
+                .miner_pubkey(miner)
+                .build();
+            share.header.time = time;
+            chain_store_handle.add_share(&share, true).await.unwrap();
+            prev_hash = share.block_hash();
+        }
 
-    let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
-    let addr = SocketAddr::new(
-        std::net::IpAddr::V4(config.hostname.parse().unwrap()),
-        config.port,
-    );
+        let state = |max_scan_items| {
+            Arc::new(AppState {
+                app_config: AppConfig {
+                    max_scan_items,
+                    ..AppConfig::default()
+                },
+                ..test_app_state(chain_store_handle.clone(), metrics_handle.clone())
+            })
+        };
+        let seen = |response: &KnownMinersResponse| -> Vec<(String, Option<u32>, Option<u32>)> {
+            response
+                .miners
+                .iter()
+                .map(|miner| {
+                    (
+                        miner.miner_pubkey.clone(),
+                        miner.first_seen_height,
+                        miner.last_seen_height,
+                    )
+                })
+                .collect()
+        };
 
-    let app = Router::new()
-        // Health and metrics
-        .route(
-            "/",
-            get(|| async { axum::response::Html(include_str!("../../static/index.html")) }),
+        // Most recently seen first
+        let Json(known) = chain_known_miners(
+            State(state(DEFAULT_MAX_SCAN_ITEMS)),
+            Query(KnownMinersQuery { limit: None }),
         )
-        .route("/health", get(health_check))
-        .route("/metrics", get(metrics))
-        .route("/pplns_shares", get(pplns_shares))
-        // Chain endpoints
-        .route("/chain/tip", get(chain_tip))
-        .route("/chain/height", get(chain_height))
-        .route("/chain/tip_with_uncles", get(chain_tip_with_uncles))
-        .route("/chain/shares/{height}", get(shares_at_height))
-        .route("/chain/total_work", get(total_work))
-        .route("/chain/locator", get(chain_locator))
-        .route("/chain/info", get(chain_info))
-        .route("/chain/dag", get(chain_dag))
-        // Database viewer endpoints
-        .route("/db/cf", get(db_viewer::list_column_families))
-        .route("/db/cf/:cf/entries", get(db_viewer::list_cf_entries))
-        .route("/db/cf/:cf/entry/:key", get(db_viewer::get_cf_entry))
-        .route("/db/cf/:cf/stats", get(db_viewer::get_cf_stats))
-        // Middleware and state
-        .layer(middleware::from_fn_with_state(
-            app_state.clone(),
-            auth_middleware,
-        ))
-        .layer(Extension(app_config))
-        .with_state(app_state);
+        .await
+        .unwrap();
+        assert_eq!(
+            seen(&known),
+            vec![
+                (miner_a.to_string(), Some(1), Some(4)),
+                (miner_b.to_string(), Some(2), Some(2)),
+            ]
+        );
+        assert_eq!(known.miners[0].first_seen_time, 1000);
+        assert_eq!(known.miners[0].last_seen_time, 3000);
+        assert!(!known.truncated);
 
-    let listener = match tokio::net::TcpListener::bind(addr).await {
-        Ok(listener) => listener,
-        Err(e) => return Err(e),
-    };
+        let Json(limited) = chain_known_miners(
+            State(state(DEFAULT_MAX_SCAN_ITEMS)),
+            Query(KnownMinersQuery { limit: Some(1) }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            seen(&limited),
+            vec![(miner_a.to_string(), Some(1), Some(4))]
+        );
+
+        // A scan budget of one miner only reads miner a, by pubkey order
+        let Json(budgeted) =
+            chain_known_miners(State(state(1)), Query(KnownMinersQuery { limit: None }))
+                .await
+                .unwrap();
+        assert_eq!(
+            seen(&budgeted),
+            vec![(miner_a.to_string(), Some(1), Some(4))]
+        );
+        assert!(budgeted.truncated);
+    }
 
-    info!("API server listening on {}", addr);
+    #[test_log::test(tokio::test)]
+    async fn test_chain_spends_lookup() {
+        let (metrics_handle, _metrics_dir) = test_metrics_handle().await;
+        let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
 
-    tokio::spawn(async move {
-        axum::serve(listener, app)
-            .with_graceful_shutdown(async move {
-                let _ = shutdown_rx.await;
-                info!("API server shutdown signal received");
-            })
+        let genesis = genesis_for_tests();
+        chain_store_handle
+            .init_or_setup_genesis(genesis.clone())
             .await
-            .map_err(|e| ApiError::ServerError(e.to_string()))?;
+            .unwrap();
 
-        info!("API server stopped");
-        Ok::<(), ApiError>(())
-    });
-    Ok(shutdown_tx)
-}
+        let spent_txid = genesis.transactions[0].compute_txid();
+        let spending_tx = bitcoin::Transaction {
+            version: bitcoin::transaction::Version::TWO,
+            lock_time: bitcoin::absolute::LockTime::ZERO,
+            input: vec![bitcoin::TxIn {
+                previous_output: OutPoint {
+                    txid: spent_txid,
+                    vout: 0,
+                },
+                ..Default::default()
+            }],
+            output: vec![],
+        };
+        let share1 = TestShareBlockBuilder::new()
+            .prev_share_blockhash(genesis.block_hash().to_string())
+            .add_transaction(spending_tx.clone())
+            .work(1)
+            .build();
+        chain_store_handle.add_share(&share1, true).await.unwrap();
 
-async fn health_check() -> String {
-    "OK".into()
-}
+        let state = Arc::new(test_app_state(chain_store_handle, metrics_handle));
 
-/// Returns pool metrics in grafana exposition format
-async fn metrics(State(state): State<Arc<AppState>>) -> String {
-    let pool_metrics = state.metrics_handle.get_metrics().await;
-    let mut exposition = pool_metrics.get_exposition();
+        let Json(response) = chain_spends(
+            State(state.clone()),
+            Path((spent_txid.to_string(), "0".to_string())),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.txid, spent_txid.to_string());
+        assert_eq!(response.vout, 0);
+        assert_eq!(
+            response.spending_txid,
+            spending_tx.compute_txid().to_string()
+        );
+        assert_eq!(response.spending_input_index, 0);
 
-    if let Some(coinbase_distribution) = parse_coinbase::get_distribution(
-        &state.tracker_handle,
-        state.app_config.pool_signature_length,
-        state.app_config.network,
-    ) {
-        exposition.push_str("# HELP coinbase_rewards_distribution Current coinbase rewards distribution between users\n");
-        exposition.push_str(&coinbase_distribution);
+        let unspent = chain_spends(
+            State(state.clone()),
+            Path((spent_txid.to_string(), "1".to_string())),
+        )
+        .await;
+        assert!(matches!(unspent, Err(ApiError::NotFound(_))));
+
+        let bad_vout = chain_spends(
+            State(state.clone()),
+            Path((spent_txid.to_string(), "-1".to_string())),
+        )
+        .await;
+        assert!(matches!(bad_vout, Err(ApiError::BadRequest(_))));
+
+        let short_txid = chain_spends(
+            State(state.clone()),
+            Path(("abcd".to_string(), "0".to_string())),
+        )
+        .await;
+        assert!(matches!(short_txid, Err(ApiError::BadRequest(_))));
+
+        let not_hex = chain_spends(State(state), Path(("z".repeat(64), "0".to_string()))).await;
+        assert!(matches!(not_hex, Err(ApiError::BadRequest(_))));
     }
-    exposition
-}
 
-async fn pplns_shares(
-    State(state): State<Arc<AppState>>,
-    Query(query): Query<PplnsQuery>,
-) -> Result<Json<Vec<SimplePplnsShare>>, ApiError> {
-    let start_time = match query.start_time.as_ref() {
-        Some(s) => match DateTime::parse_from_rfc3339(s) {
-            Ok(dt) => dt.timestamp() as u64,
-            Err(_) => {
-                return Err(ApiError::ServerError("Invalid time format".into()));
-            }
-        },
-        None => 0,
-    };
+    #[test_log::test(tokio::test)]
+    async fn test_main_chain_beyond_a_thousand_shares() {
+        let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
 
-    let end_time = match query.end_time.as_ref() {
-        Some(s) => match DateTime::parse_from_rfc3339(s) {
-            Ok(dt) => dt.timestamp() as u64,
-            Err(_) => {
-                return Err(ApiError::ServerError("Invalid time format".into()));
-            }
-        },
-        None => {
-            let now = chrono::Utc::now();
-            now.timestamp() as u64
+        let genesis = genesis_for_tests();
+        chain_store_handle
+            .init_or_setup_genesis(genesis.clone())
+            .await
+            .unwrap();
+
+        // Linear chain of 1500 shares on top of genesis. Written straight to
+        // the store, as running the reorg logic per share is slow.
+        let store_handle = chain_store_handle.store_handle();
+        let mut hashes = vec![genesis.block_hash()];
+        let mut chain_work = genesis.header.get_work();
+        for height in 1..=1500u32 {
+            let share = TestShareBlockBuilder::new()
+                .prev_share_blockhash(hashes.last().unwrap().to_string())
+                .nonce(height)
+                .work(1)
+                .build();
+            chain_work = chain_work + share.header.get_work();
+            store_handle
+                .add_share(share.clone(), height, chain_work, true)
+                .await
+                .unwrap();
+            hashes.push(share.block_hash());
         }
-    };
+        store_handle.set_chain_tip(*hashes.last().unwrap());
 
-    if end_time < start_time {
-        return Err(ApiError::ServerError("Invalid date range".into()));
-    }
+        let tip = chain_store_handle.get_chain_tip();
+        assert_eq!(tip, *hashes.last().unwrap());
 
-    let shares = state.chain_store_handle.get_pplns_shares_filtered(
-        query.limit,
-        Some(start_time),
-        Some(end_time),
-    );
+        // Ancestor 1200 shares below the tip, at height 300
+        let ancestor = hashes[hashes.len() - 1 - 1200];
 
-    Ok(Json(shares))
-}
+        // The walk goes only as far down as the heights asked about
+        let not_stored = TestShareBlockBuilder::new()
+            .prev_share_blockhash(hashes[299].to_string())
+            .nonce(u32::MAX)
+            .build()
+            .block_hash();
+        let main_chain = MainChain::new(&chain_store_handle, DEFAULT_MAX_SCAN_ITEMS);
+        assert_eq!(main_chain.tip_height, 1500);
+        assert!(main_chain.contains(&tip, 1500));
+        assert!(main_chain.contains(&hashes[1000], 1000));
+        assert_eq!(main_chain.walk.borrow().hashes.len(), 501);
+        assert!(main_chain.contains(&ancestor, 300));
+        assert!(!main_chain.contains(&not_stored, 300));
+        assert!(main_chain.contains(&genesis.block_hash(), 0));
+        assert!(!main_chain.truncated());
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use axum::extract::State;
-    use bitcoin::{Amount, Network, TxOut};
-    use p2poolv2_lib::accounting::stats::metrics;
-    use p2poolv2_lib::shares::share_block::ShareBlock;
-    use p2poolv2_lib::stratum::work::block_template::BlockTemplate;
-    use p2poolv2_lib::stratum::work::coinbase::parse_address;
-    use p2poolv2_lib::stratum::work::tracker::start_tracker_actor;
-    use p2poolv2_lib::test_utils::setup_test_chain_store_handle;
-    use std::collections::HashMap;
-    use std::str::FromStr;
-    use std::sync::Arc;
+        // A walk cut short by the scan budget reports shares below it off
+        // the main chain, apart from those in the confirmed height index
+        let main_chain = MainChain::new(&chain_store_handle, 1000);
+        assert!(!main_chain.contains(&ancestor, 300));
+        assert!(main_chain.truncated());
+        assert_eq!(main_chain.walk.borrow().hashes.len(), 1000);
+        assert!(main_chain.contains(&genesis.block_hash(), 0));
+    }
 
     #[test_log::test(tokio::test)]
-    async fn test_metrics_endpoint_exposes_coinbase_split() {
-        let tracker_handle = start_tracker_actor();
+    async fn test_ancestry_depth_limit_per_network() {
+        let (metrics_handle, _metrics_dir) = test_metrics_handle().await;
+        let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
 
-        let temp_dir = tempfile::tempdir().unwrap();
-        let metrics_handle = metrics::start_metrics(temp_dir.path().to_str().unwrap().to_string())
+        let genesis = genesis_for_tests();
+        chain_store_handle
+            .init_or_setup_genesis(genesis.clone())
             .await
             .unwrap();
 
-        let address = parse_address(
-            "tb1q3udk7r26qs32ltf9nmqrjaaa7tr55qmkk30q5d",
-            Network::Signet,
-        )
-        .unwrap();
-
-        let donation_address = parse_address(
-            "tb1q0afww6y0kgl4tyjjyv6xlttvfwdfqxvrfzz35f",
-            Network::Signet,
-        )
-        .unwrap();
+        // Linear chain of 1200 shares on top of genesis, written straight
+        // to the store
+        let store_handle = chain_store_handle.store_handle();
+        let mut hashes = vec![genesis.block_hash()];
+        let mut chain_work = genesis.header.get_work();
+        for height in 1..=1200u32 {
+            let share = TestShareBlockBuilder::new()
+                .prev_share_blockhash(hashes.last().unwrap().to_string())
+                .nonce(height)
+                .work(1)
+                .build();
+            chain_work = chain_work + share.header.get_work();
+            store_handle
+                .add_share(share.clone(), height, chain_work, true)
+                .await
+                .unwrap();
+            hashes.push(share.block_hash());
+        }
+        store_handle.set_chain_tip(*hashes.last().unwrap());
 
-        let template = BlockTemplate {
-            default_witness_commitment: Some(
-                "6a24aa21a9ed010000000000000000000000000000000000000000000000000000000000"
-                    .to_string(),
-            ),
-            height: 100,
-            version: 0x20000000,
-            previousblockhash: "0000000000000000000000000000000000000000000000000000000000000000"
-                .to_string(),
-            bits: "1d00ffff".to_string(),
-            curtime: 1234567890,
-            transactions: vec![],
-            coinbasevalue: 50_0000_0000,
-            coinbaseaux: HashMap::new(),
-            rules: vec![],
-            vbavailable: HashMap::new(),
-            vbrequired: 0,
-            longpollid: "".to_string(),
-            target: "".to_string(),
-            mintime: 0,
-            mutable: vec![],
-            noncerange: "".to_string(),
-            sigoplimit: 0,
-            sizelimit: 0,
-            weightlimit: 0,
+        let state_for_network = |network: bitcoin::Network| {
+            Arc::new(AppState {
+                app_config: AppConfig {
+                    network,
+                    ..AppConfig::default()
+                },
+                ..test_app_state(chain_store_handle.clone(), metrics_handle.clone())
+            })
         };
 
-        let pool_signature = b"P2Poolv2";
-
-        let outputs = vec![
-            TxOut {
-                value: Amount::from_str("49 BTC").unwrap(),
-                script_pubkey: address.script_pubkey(),
-            },
-            TxOut {
-                value: Amount::from_str("1 BTC").unwrap(),
-                script_pubkey: donation_address.script_pubkey(),
-            },
-        ];
-
-        let mut coinbase2_bytes = Vec::new();
-        coinbase2_bytes.push(pool_signature.len() as u8);
-        coinbase2_bytes.extend_from_slice(pool_signature);
-        coinbase2_bytes.extend_from_slice(&[0xff, 0xff, 0xff, 0xff]);
-        coinbase2_bytes.extend_from_slice(&bitcoin::consensus::serialize(&outputs));
-        coinbase2_bytes.extend_from_slice(&[0x00, 0x00, 0x00, 0x00]);
+        // A fork off the first share only meets the tip's ancestry when
+        // the walk is long enough to get back to it
+        let fork = TestShareBlockBuilder::new()
+            .prev_share_blockhash(hashes[1].to_string())
+            .nonce(5000)
+            .build();
+        chain_store_handle.add_share(&fork, true).await.unwrap();
+        let tip = *hashes.last().unwrap();
 
-        let coinbase2_hex = hex::encode(coinbase2_bytes);
+        let signet = state_for_network(bitcoin::Network::Signet);
+        let (_, info) = chain_info_response(&signet).await.unwrap();
+        assert_eq!(info.ancestry_depth_limit, 1_000);
+        assert_eq!(
+            walk_ancestors(&chain_store_handle, tip, info.ancestry_depth_limit).len(),
+            1_000
+        );
+        assert_eq!(
+            find_common_ancestor(
+                &chain_store_handle,
+                tip,
+                fork.block_hash(),
+                info.ancestry_depth_limit
+            ),
+            None
+        );
 
-        let job_id = tracker_handle.get_next_job_id();
-        tracker_handle.insert_job(
-            Arc::new(template),
-            "".to_string(),
-            coinbase2_hex,
-            None,
-            job_id,
+        let mainnet = state_for_network(bitcoin::Network::Bitcoin);
+        let (_, info) = chain_info_response(&mainnet).await.unwrap();
+        assert_eq!(info.ancestry_depth_limit, 20_000);
+        // Reaches genesis
+        assert_eq!(
+            walk_ancestors(&chain_store_handle, tip, info.ancestry_depth_limit).len(),
+            1_201
+        );
+        assert_eq!(
+            find_common_ancestor(
+                &chain_store_handle,
+                tip,
+                fork.block_hash(),
+                info.ancestry_depth_limit
+            ),
+            Some(hashes[1])
         );
+    }
 
-        let _genesis = ShareBlock::build_genesis_for_network(Network::Signet);
+    #[test_log::test(tokio::test)]
+    async fn test_chain_genesis_for_network() {
+        let (metrics_handle, _metrics_dir) = test_metrics_handle().await;
         let (chain_store_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
 
-        let state = Arc::new(AppState {
-            app_config: AppConfig {
-                pool_signature_length: 8,
-                network: bitcoin::Network::Signet,
-            },
-            chain_store_handle,
-            metrics_handle,
-            tracker_handle,
-            auth_user: None,
-            auth_token: None,
-        });
-
-        let response_body = metrics(State(state)).await;
+        let mut state = test_app_state(chain_store_handle, metrics_handle);
 
-        println!("{}", response_body);
+        let Json(response) = chain_genesis(State(Arc::new(state.clone()))).await.unwrap();
+        let expected = ShareBlock::build_genesis_for_network(bitcoin::Network::Signet);
+        assert_eq!(response.network, "signet");
+        assert_eq!(response.hash, expected.block_hash().to_string());
+        assert_eq!(response.genesis, expected);
 
-        assert!(response_body.contains(
-            "coinbase_output{index=\"0\",address=\"tb1q3udk7r26qs32ltf9nmqrjaaa7tr55qmkk30q5d\"} 4900000000"
-        ));
-        assert!(response_body.contains(
-            "coinbase_output{index=\"1\",address=\"tb1q0afww6y0kgl4tyjjyv6xlttvfwdfqxvrfzz35f\"} 100000000"
-        ));
+        // Testnet3 has no genesis share
+        state.app_config.network = bitcoin::Network::Testnet;
+        let result = chain_genesis(State(Arc::new(state))).await;
+        assert!(matches!(result, Err(ApiError::NotFound(_))));
     }
 }
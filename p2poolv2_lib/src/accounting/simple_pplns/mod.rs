@@ -24,6 +24,7 @@ pub mod payout;
 /// btcaddress and workername are skipped during serialization to minimize storage
 /// They are restored from user_id when loading from database
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct SimplePplnsShare {
     /// Local node user id, used for tracking local pplns
     pub user_id: u64,
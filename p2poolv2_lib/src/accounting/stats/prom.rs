@@ -162,6 +162,36 @@ impl PoolMetrics {
     }
 }
 
+/// Prepend prefix to every metric name in a text exposition, including
+/// the names in HELP and TYPE comments, so several pools can be scraped
+/// into one Prometheus without collisions.
+pub fn prefix_metric_names(exposition: &str, prefix: &str) -> String {
+    let mut output = String::with_capacity(exposition.len());
+    for line in exposition.lines() {
+        if let Some(rest) = line.strip_prefix("# HELP ") {
+            output.push_str(&format!("# HELP {prefix}{rest}"));
+        } else if let Some(rest) = line.strip_prefix("# TYPE ") {
+            output.push_str(&format!("# TYPE {prefix}{rest}"));
+        } else if line.is_empty() || line.starts_with('#') {
+            output.push_str(line);
+        } else {
+            output.push_str(&format!("{prefix}{line}"));
+        }
+        output.push('\n');
+    }
+    output
+}
+
+/// Check prefix only uses characters allowed at the start of a metric name
+pub fn is_valid_metric_prefix(prefix: &str) -> bool {
+    let mut chars = prefix.chars();
+    match chars.next() {
+        Some(first) if first.is_ascii_alphabetic() || first == '_' || first == ':' => {}
+        _ => return false,
+    }
+    chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == ':')
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -343,4 +373,57 @@ mod tests {
             r#"worker_last_share_at{btcaddress="bc1qtest",workername="unnamed"} 1234567891"#
         ));
     }
+
+    #[test]
+    fn test_prefix_metric_names() {
+        let metrics = PoolMetrics {
+            accepted_total: 100,
+            ..Default::default()
+        };
+        let mut exposition = metrics.get_exposition();
+        exposition.push_str("# HELP coinbase_rewards_distribution Current coinbase rewards\n");
+        exposition
+            .push_str("coinbase_output{index=\"0\",address=\"bc1qtest\",role=\"miner\"} 50\n");
+        exposition.push_str("coinbase_total 50\n");
+
+        let prefixed = prefix_metric_names(&exposition, "poolA_");
+
+        assert!(prefixed.contains("# HELP poolA_shares_accepted_total "));
+        assert!(prefixed.contains("# TYPE poolA_shares_accepted_total counter"));
+        assert!(prefixed.contains("poolA_shares_accepted_total 100"));
+        assert!(prefixed.contains("# HELP poolA_coinbase_rewards_distribution "));
+        assert!(prefixed.contains("poolA_coinbase_total 50"));
+
+        // Every sample parses as a prefixed name, optional labels and a
+        // value, and every HELP/TYPE names a prefixed metric
+        for line in prefixed.lines().filter(|line| !line.is_empty()) {
+            let name = match line.strip_prefix("# HELP ") {
+                Some(rest) => rest.split(' ').next().unwrap(),
+                None => match line.strip_prefix("# TYPE ") {
+                    Some(rest) => {
+                        let mut parts = rest.split(' ');
+                        let name = parts.next().unwrap();
+                        assert!(matches!(parts.next(), Some("counter" | "gauge")));
+                        name
+                    }
+                    None => {
+                        let (series, value) = line.rsplit_once(' ').unwrap();
+                        assert!(value.parse::<f64>().is_ok(), "bad value in {line}");
+                        series.split('{').next().unwrap()
+                    }
+                },
+            };
+            assert!(name.starts_with("poolA_"), "unprefixed name in {line}");
+            assert!(is_valid_metric_prefix(name), "invalid name in {line}");
+        }
+    }
+
+    #[test]
+    fn test_is_valid_metric_prefix() {
+        assert!(is_valid_metric_prefix("poolA_"));
+        assert!(is_valid_metric_prefix("_pool:"));
+        assert!(!is_valid_metric_prefix(""));
+        assert!(!is_valid_metric_prefix("1pool_"));
+        assert!(!is_valid_metric_prefix("pool-a_"));
+    }
 }
@@ -37,7 +37,8 @@ pub fn execute(
     end_time: Option<u64>,
 ) -> Result<(), Box<dyn Error>> {
     // Get PPLNS shares with filtering
-    let shares = chain_store_handle.get_pplns_shares_filtered(Some(limit), start_time, end_time);
+    let shares =
+        chain_store_handle.get_pplns_shares_filtered(Some(limit), start_time, end_time, None);
 
     // Convert to display format
     let share_infos: Vec<PplnsShareInfo> = shares
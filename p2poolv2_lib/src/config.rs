@@ -301,6 +301,27 @@ fn default_stats_dir() -> String {
     "./logs/stats".to_string()
 }
 
+/// What an API key may do. Admin keys can also do everything read keys
+/// can.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum ApiScope {
+    /// Read endpoints only
+    Read,
+    /// Read endpoints, endpoints that modify the store and /chain/verify
+    Admin,
+}
+
+/// An API key, checked with Basic auth like auth_user and auth_token
+#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
+pub struct ApiKeyConfig {
+    /// Username sent with Basic auth
+    pub user: String,
+    /// Token of the format salt$hmac, as made by p2poolv2_cli gen-auth
+    pub token: String,
+    pub scope: ApiScope,
+}
+
 #[derive(Debug, Deserialize, Clone)]
 pub struct ApiConfig {
     /// The hostname for the API server
@@ -311,6 +332,66 @@ pub struct ApiConfig {
     pub auth_user: Option<String>,
     /// Optional authentication token
     pub auth_token: Option<String>,
+    /// API keys, each with its own scope. The auth_user and auth_token
+    /// pair, when set, is an admin key.
+    #[serde(default)]
+    pub api_keys: Vec<ApiKeyConfig>,
+    /// Report the chain as unhealthy when the tip share is older than
+    /// this many seconds. Staleness is not checked when unset.
+    #[serde(default)]
+    pub stale_after_secs: Option<u64>,
+    /// Page size for paginated endpoints when a request doesn't set one.
+    /// Defaults to 50.
+    #[serde(default)]
+    pub default_page_size: Option<u32>,
+    /// Largest page size a request may ask for. Defaults to 100 and is
+    /// capped at 1000.
+    #[serde(default)]
+    pub max_page_size: Option<u32>,
+    /// Most heights or column family entries a single request may scan
+    /// before it returns a partial response flagged as truncated.
+    /// Defaults to 10000.
+    #[serde(default)]
+    pub max_scan_items: Option<u32>,
+    /// Seconds between recomputing the cached /chain/dag/snapshot, which
+    /// is also recomputed on every tip change. Defaults to 30.
+    #[serde(default)]
+    pub dag_snapshot_interval_secs: Option<u64>,
+    /// Prepended to every metric name on /metrics, e.g. "poolA_", so
+    /// several pools can share one Prometheus. No prefix when unset.
+    #[serde(default)]
+    pub metrics_prefix: Option<String>,
+    /// Directory to periodically write the /metrics exposition to, one
+    /// file per snapshot. Disabled when unset.
+    #[serde(default)]
+    pub metrics_export_dir: Option<String>,
+    /// Seconds between metrics snapshots. Defaults to 60.
+    #[serde(default)]
+    pub metrics_export_interval_secs: Option<u64>,
+    /// Number of most recent metrics snapshots to keep. Defaults to 10.
+    #[serde(default)]
+    pub metrics_export_keep: Option<usize>,
+    /// Refuse DB viewer requests that modify the store, such as deleting
+    /// an entry, with 403 Forbidden. Defaults to true.
+    #[serde(default)]
+    pub db_read_only: Option<bool>,
+    /// Column families the DB viewer may browse, e.g. ["block", "share"]
+    /// to only expose shares publicly. All when unset.
+    #[serde(default)]
+    pub db_allowed_cfs: Option<Vec<String>>,
+    /// Column families the DB viewer refuses with 403 Forbidden, e.g.
+    /// ["user", "user_index"] to keep payout addresses private. Applied
+    /// on top of db_allowed_cfs.
+    #[serde(default)]
+    pub db_denied_cfs: Option<Vec<String>>,
+    /// Most expensive requests, like /chain/dag and /chain/stats, served
+    /// at once. Further ones get 503 until one finishes. Defaults to 4.
+    #[serde(default)]
+    pub max_concurrent_heavy_requests: Option<usize>,
+    /// Seconds between recounting the cached chain stats served by
+    /// /chain/info/extended. Defaults to 60.
+    #[serde(default)]
+    pub stats_cache_interval_secs: Option<u64>,
 }
 
 /// Config for p2poolv2 nodes
@@ -248,6 +248,7 @@ impl Node {
             Some(query.limit),
             query.start_time,
             query.end_time,
+            None,
         )
     }
 
@@ -545,6 +546,21 @@ mod tests {
                 port: 3000,
                 auth_user: None,
                 auth_token: None,
+                api_keys: Vec::new(),
+                stale_after_secs: None,
+                default_page_size: None,
+                max_page_size: None,
+                max_scan_items: None,
+                dag_snapshot_interval_secs: None,
+                metrics_prefix: None,
+                metrics_export_dir: None,
+                metrics_export_interval_secs: None,
+                metrics_export_keep: None,
+                db_read_only: None,
+                db_allowed_cfs: None,
+                db_denied_cfs: None,
+                max_concurrent_heavy_requests: None,
+                stats_cache_interval_secs: None,
             },
         };
         config.network = network_config;
@@ -24,12 +24,14 @@ use crate::accounting::simple_pplns::SimplePplnsShare;
 use crate::shares::share_block::{ShareBlock, ShareHeader};
 use crate::store::column_families::ColumnFamily;
 use crate::store::db_viewer_ops;
+use crate::store::share_store::KnownMiner;
 use crate::store::writer::{StoreError, StoreHandle};
 use bitcoin::hashes::Hash;
-use bitcoin::{BlockHash, Work};
-use std::collections::{HashMap, HashSet};
+use bitcoin::{BlockHash, CompressedPublicKey, Work};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use std::error::Error;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
 use tracing::{debug, info};
 
 /// The minimum number of shares that must be on the chain for a share to be considered confirmed
@@ -45,6 +47,36 @@ pub(crate) const COMMON_ANCESTOR_DEPTH: usize = 2160; // 6 shares per minute * 6
 /// PPLNS window in shares
 const PPLNS_WINDOW: usize = 2160; // 6 shares per minute * 60 * 6 hours.
 
+/// Number of recent reorgs kept in memory for get_reorg_history
+pub const REORG_HISTORY_CAPACITY: usize = 100;
+
+/// Number of recent chain tips kept in memory for get_tip_history
+pub const TIP_HISTORY_CAPACITY: usize = 100;
+
+/// A chain tip set by add_share, either extending the old tip or on a
+/// reorg
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TipHistoryEntry {
+    pub hash: BlockHash,
+    pub height: u32,
+    /// Chain work up to and including the tip share
+    pub total_work: Work,
+    /// Unix time in seconds the tip was set
+    pub timestamp: u64,
+}
+
+/// A switch of the chain tip to a share that does not extend the old tip
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReorgEvent {
+    pub old_tip: BlockHash,
+    pub new_tip: BlockHash,
+    /// Number of old tip shares above the common ancestor. The old tip
+    /// height when no common ancestor was found.
+    pub depth: u32,
+    /// Unix time in seconds
+    pub timestamp: u64,
+}
+
 /// Handle for chain-level store operations.
 ///
 /// Wraps `StoreHandle` to provide chain-level logic like height
@@ -56,6 +88,12 @@ const PPLNS_WINDOW: usize = 2160; // 6 shares per minute * 60 * 6 hours.
 pub struct ChainStoreHandle {
     store_handle: StoreHandle,
     network: bitcoin::Network,
+    /// Most recent reorgs, oldest first, shared by all clones
+    reorg_history: Arc<Mutex<VecDeque<ReorgEvent>>>,
+    /// Reorgs since startup, including those dropped from reorg_history
+    reorg_count: Arc<AtomicU64>,
+    /// Most recent chain tips, oldest first, shared by all clones
+    tip_history: Arc<Mutex<VecDeque<TipHistoryEntry>>>,
 }
 
 impl ChainStoreHandle {
@@ -64,6 +102,9 @@ impl ChainStoreHandle {
         Self {
             store_handle,
             network,
+            reorg_history: Arc::new(Mutex::new(VecDeque::with_capacity(REORG_HISTORY_CAPACITY))),
+            reorg_count: Arc::new(AtomicU64::new(0)),
+            tip_history: Arc::new(Mutex::new(VecDeque::with_capacity(TIP_HISTORY_CAPACITY))),
         }
     }
 
@@ -104,11 +145,61 @@ impl ChainStoreHandle {
     // DIRECT READS - These delegate to StoreHandle (may block briefly)
     // ========================================================================
 
+    /// Get up to limit of the most recent reorgs, newest first. At most
+    /// REORG_HISTORY_CAPACITY reorgs are kept.
+    pub fn get_reorg_history(&self, limit: usize) -> Vec<ReorgEvent> {
+        let history = self.reorg_history.lock().unwrap();
+        history.iter().rev().take(limit).cloned().collect()
+    }
+
+    /// Get up to limit of the most recent chain tips, newest first. At
+    /// most TIP_HISTORY_CAPACITY tips are kept, starting from when the
+    /// node started.
+    pub fn get_tip_history(&self, limit: usize) -> Vec<TipHistoryEntry> {
+        let history = self.tip_history.lock().unwrap();
+        history.iter().rev().take(limit).cloned().collect()
+    }
+
+    /// Get the number of reorgs since startup. Unlike get_reorg_history
+    /// this is not capped at REORG_HISTORY_CAPACITY.
+    pub fn get_reorg_count(&self) -> u64 {
+        self.reorg_count.load(Ordering::Relaxed)
+    }
+
     /// Get a share from the chain.
     pub fn get_share(&self, share_hash: &BlockHash) -> Option<ShareBlock> {
         self.store_handle.get_share(share_hash)
     }
 
+    /// Get the hash of the share whose bitcoin header has this block hash.
+    pub fn get_share_for_bitcoin_block(&self, bitcoin_blockhash: &BlockHash) -> Option<BlockHash> {
+        self.store_handle
+            .get_share_for_bitcoin_block(bitcoin_blockhash)
+    }
+
+    /// Get the time and hash of a miner's shares, newest first, within
+    /// an optional inclusive time range.
+    pub fn get_shares_by_miner(
+        &self,
+        miner_pubkey: &CompressedPublicKey,
+        from_time: Option<u32>,
+        to_time: Option<u32>,
+        skip: usize,
+        limit: usize,
+    ) -> Result<Vec<(u32, BlockHash)>, Box<dyn Error + Send + Sync>> {
+        self.store_handle
+            .get_shares_by_miner(miner_pubkey, from_time, to_time, skip, limit)
+    }
+
+    /// Get the distinct miners in the miner shares index, in pubkey
+    /// order, with their earliest and latest shares.
+    pub fn get_known_miners(
+        &self,
+        max_miners: usize,
+    ) -> Result<Vec<KnownMiner>, Box<dyn Error + Send + Sync>> {
+        self.store_handle.get_known_miners(max_miners)
+    }
+
     /// Get shares at a specific height.
     pub fn get_shares_at_height(
         &self,
@@ -159,6 +250,20 @@ impl ChainStoreHandle {
         Ok(metadata.expected_height)
     }
 
+    /// Get the height a share was stored at, from its block metadata.
+    pub fn get_share_height(&self, blockhash: &BlockHash) -> Option<u32> {
+        self.store_handle
+            .store()
+            .get_block_metadata(blockhash)
+            .ok()?
+            .expected_height
+    }
+
+    /// Get the confirmed share at a height from the BlockHeight index.
+    pub fn get_confirmed_at_height(&self, height: u32) -> Option<BlockHash> {
+        self.store_handle.store().get_confirmed_at_height(height)
+    }
+
     /// Get the chain tip blockhash
     pub fn get_chain_tip(&self) -> BlockHash {
         self.store_handle.get_chain_tip()
@@ -256,15 +361,16 @@ impl ChainStoreHandle {
         }
     }
 
-    /// Get PPLNS shares with filtering.
+    /// Get PPLNS shares with filtering, optionally for one btcaddress.
     pub fn get_pplns_shares_filtered(
         &self,
         limit: Option<usize>,
         start_time: Option<u64>,
         end_time: Option<u64>,
+        btcaddress: Option<&str>,
     ) -> Vec<SimplePplnsShare> {
         self.store_handle
-            .get_pplns_shares_filtered(limit, start_time, end_time)
+            .get_pplns_shares_filtered(limit, start_time, end_time, btcaddress)
     }
 
     /// Get the current target from the tip share block.
@@ -295,60 +401,106 @@ impl ChainStoreHandle {
     // DATABASE VIEWER METHODS
     // ========================================================================
 
+    /// The RocksDB handle owned by the store, for the db_viewer_ops calls
+    fn db(&self) -> &rocksdb::DB {
+        self.store_handle.store().get_db()
+    }
+
     /// Get estimated entry count for a column family
     pub fn get_cf_entry_count(&self, cf: ColumnFamily) -> Result<u64, String> {
-        // Store.get_db() returns &DB, but db_viewer_ops expects &Arc<DB>
-        // We need to use the existing DB reference without ownership transfer
-        // Since RocksDB is thread-safe, we can safely pass a raw pointer wrapped in Arc temporarily
-        let store = self.store_handle.store();
-        let db_ref: &rocksdb::DB = store.get_db();
-        // SAFETY: We're creating a temporary Arc from a raw pointer for the duration of this call
-        // The actual DB is owned by Store and will outlive this function call
-        let db_ptr = db_ref as *const rocksdb::DB;
-        let db_arc = unsafe { Arc::from_raw(db_ptr) };
-        let result = db_viewer_ops::get_cf_entry_count(&db_arc, cf);
-        // Prevent Arc from dropping and freeing the DB (it's owned by Store)
-        std::mem::forget(db_arc);
-        result
+        db_viewer_ops::get_cf_entry_count(self.db(), cf)
+    }
+
+    /// Names of the column families in the DB, including any this
+    /// version has no ColumnFamily variant for
+    pub fn list_column_family_names(&self) -> Result<Vec<String>, String> {
+        db_viewer_ops::list_column_family_names(self.db())
+    }
+
+    /// Get estimated entry count for a column family by name
+    pub fn get_named_cf_entry_count(&self, name: &str) -> Result<u64, String> {
+        db_viewer_ops::get_named_cf_entry_count(self.db(), name)
     }
 
     /// Get estimated size of a column family in bytes
     pub fn get_cf_size_estimate(&self, cf: ColumnFamily) -> Result<u64, String> {
-        let store = self.store_handle.store();
-        let db_ref: &rocksdb::DB = store.get_db();
-        let db_ptr = db_ref as *const rocksdb::DB;
-        let db_arc = unsafe { Arc::from_raw(db_ptr) };
-        let result = db_viewer_ops::get_cf_size_estimate(&db_arc, cf);
-        std::mem::forget(db_arc);
-        result
+        db_viewer_ops::get_cf_size_estimate(self.db(), cf)
+    }
+
+    /// Get the total SST files size of a column family in bytes
+    pub fn get_cf_sst_files_size(&self, cf: ColumnFamily) -> Result<u64, String> {
+        db_viewer_ops::get_cf_sst_files_size(self.db(), cf)
+    }
+
+    /// Sample a column family for its key range and largest value size
+    pub fn sample_cf_stats(
+        &self,
+        cf: ColumnFamily,
+        sample_limit: usize,
+    ) -> Result<db_viewer_ops::CfSampleStats, String> {
+        db_viewer_ops::sample_cf_stats(self.db(), cf, sample_limit)
+    }
+
+    /// Get multiple entries from a column family, in the order of keys
+    pub fn multi_get_cf_entries(
+        &self,
+        cf: ColumnFamily,
+        keys: &[Vec<u8>],
+    ) -> Result<Vec<Option<Vec<u8>>>, String> {
+        db_viewer_ops::multi_get_cf_entries(self.db(), cf, keys)
+    }
+
+    /// Get selected RocksDB properties for a column family
+    pub fn get_cf_properties(
+        &self,
+        cf: ColumnFamily,
+    ) -> Result<BTreeMap<String, Option<u64>>, String> {
+        db_viewer_ops::get_cf_properties(self.db(), cf)
     }
 
-    /// List entries from a column family with pagination
+    /// List entries from a column family with pagination, as selected by
+    /// options. An iterator error partway through is returned in the page.
     pub fn list_cf_entries(
         &self,
         cf: ColumnFamily,
-        skip: usize,
-        limit: usize,
-        search: Option<&str>,
-    ) -> Result<(Vec<(Vec<u8>, Vec<u8>)>, u64), String> {
-        let store = self.store_handle.store();
-        let db_ref: &rocksdb::DB = store.get_db();
-        let db_ptr = db_ref as *const rocksdb::DB;
-        let db_arc = unsafe { Arc::from_raw(db_ptr) };
-        let result = db_viewer_ops::list_cf_entries(&db_arc, cf, skip, limit, search);
-        std::mem::forget(db_arc);
-        result
-    }
-
-    /// Get a specific entry from a column family by key
-    pub fn get_cf_entry(&self, cf: ColumnFamily, key: &str) -> Result<Option<Vec<u8>>, String> {
-        let store = self.store_handle.store();
-        let db_ref: &rocksdb::DB = store.get_db();
-        let db_ptr = db_ref as *const rocksdb::DB;
-        let db_arc = unsafe { Arc::from_raw(db_ptr) };
-        let result = db_viewer_ops::get_cf_entry(&db_arc, cf, key);
-        std::mem::forget(db_arc);
-        result
+        options: &db_viewer_ops::ListOptions,
+    ) -> Result<db_viewer_ops::CfEntryPage, String> {
+        db_viewer_ops::list_cf_entries(self.db(), cf, options)
+    }
+
+    /// Get the first entry of a column family in key order, or the last
+    /// one when last is set
+    pub fn get_cf_boundary_entry(
+        &self,
+        cf: ColumnFamily,
+        last: bool,
+    ) -> Result<Option<(Vec<u8>, Vec<u8>)>, String> {
+        db_viewer_ops::get_cf_boundary_entry(self.db(), cf, last)
+    }
+
+    /// Call f with each entry of a column family in key order, stopping
+    /// early when f returns false
+    pub fn for_each_cf_entry<F>(&self, cf: ColumnFamily, f: F) -> Result<(), String>
+    where
+        F: FnMut(&[u8], &[u8]) -> bool,
+    {
+        db_viewer_ops::for_each_cf_entry(self.db(), cf, f)
+    }
+
+    /// Get a specific entry from a column family by its raw key
+    pub fn get_cf_entry(&self, cf: ColumnFamily, key: &[u8]) -> Result<Option<Vec<u8>>, String> {
+        db_viewer_ops::get_cf_entry(self.db(), cf, key)
+    }
+
+    /// Delete an entry from a column family
+    pub fn delete_cf_entry(&self, cf: ColumnFamily, key: &[u8]) -> Result<(), String> {
+        db_viewer_ops::delete_cf_entry(self.db(), cf, key)
+    }
+
+    /// Delete every entry of a column family whose key starts with prefix,
+    /// returning the number deleted
+    pub fn delete_cf_prefix(&self, cf: ColumnFamily, prefix: &[u8]) -> Result<u64, String> {
+        db_viewer_ops::delete_cf_prefix(self.db(), cf, prefix)
     }
 
     // ========================================================================
@@ -375,6 +527,7 @@ impl ChainStoreHandle {
         // Handle genesis case
         if tips.is_empty() {
             self.store_handle.setup_genesis(share.clone()).await?;
+            self.record_tip(blockhash, share_work);
             return Ok(());
         }
 
@@ -434,6 +587,10 @@ impl ChainStoreHandle {
                 debug!("Found common ancestor {common_ancestor}");
                 if new_chain_work > current_total_work {
                     self.store_handle.set_chain_tip(share_block_hash);
+                    self.record_tip(share_block_hash, new_chain_work);
+                    if share.header.prev_share_blockhash != tip {
+                        self.record_reorg(tip, share_block_hash, Some(&common_ancestor));
+                    }
                 }
                 self.store_handle.add_tip(share_block_hash);
             }
@@ -452,6 +609,10 @@ impl ChainStoreHandle {
                 );
                 if new_chain_work_calc > old_chain_work {
                     self.store_handle.set_chain_tip(share_block_hash);
+                    self.record_tip(share_block_hash, new_chain_work);
+                    if share.header.prev_share_blockhash != tip {
+                        self.record_reorg(tip, share_block_hash, None);
+                    }
                 }
                 self.store_handle.add_tip(share_block_hash);
             }
@@ -467,6 +628,69 @@ impl ChainStoreHandle {
         Ok(())
     }
 
+    /// Append a new chain tip to the history, dropping the oldest one when
+    /// full
+    fn record_tip(&self, hash: BlockHash, total_work: Work) {
+        let height = self
+            .store_handle
+            .store()
+            .get_block_metadata(&hash)
+            .ok()
+            .and_then(|metadata| metadata.expected_height)
+            .unwrap_or_default();
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default();
+
+        let mut history = self.tip_history.lock().unwrap();
+        if history.len() == TIP_HISTORY_CAPACITY {
+            history.pop_front();
+        }
+        history.push_back(TipHistoryEntry {
+            hash,
+            height,
+            total_work,
+            timestamp,
+        });
+    }
+
+    /// Append a reorg to the history, dropping the oldest one when full
+    fn record_reorg(
+        &self,
+        old_tip: BlockHash,
+        new_tip: BlockHash,
+        common_ancestor: Option<&BlockHash>,
+    ) {
+        let height = |hash: &BlockHash| {
+            self.store_handle
+                .store()
+                .get_block_metadata(hash)
+                .ok()
+                .and_then(|metadata| metadata.expected_height)
+                .unwrap_or_default()
+        };
+        let old_height = height(&old_tip);
+        let depth = old_height.saturating_sub(common_ancestor.map(height).unwrap_or_default());
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default();
+        info!("Reorg from {old_tip} to {new_tip}, depth {depth}");
+        self.reorg_count.fetch_add(1, Ordering::Relaxed);
+
+        let mut history = self.reorg_history.lock().unwrap();
+        if history.len() == REORG_HISTORY_CAPACITY {
+            history.pop_front();
+        }
+        history.push_back(ReorgEvent {
+            old_tip,
+            new_tip,
+            depth,
+            timestamp,
+        });
+    }
+
     /// Calculate work over PPLNS window.
     fn work_over_pplns_window(
         &self,
@@ -545,10 +769,18 @@ mockall::mock! {
         pub fn build_locator(&self) -> Result<Vec<BlockHash>, Box<dyn Error + Send + Sync>>;
         pub fn get_chain_tip(&self) -> BlockHash;
         pub fn get_chain_tip_and_uncles(&self) -> (BlockHash, HashSet<BlockHash>);
+        pub fn get_reorg_history(&self, limit: usize) -> Vec<ReorgEvent>;
+        pub fn get_tip_history(&self, limit: usize) -> Vec<TipHistoryEntry>;
+        pub fn get_reorg_count(&self) -> u64;
         pub fn get_genesis_blockhash(&self) -> Option<BlockHash>;
         pub fn get_missing_blockhashes(&self, blockhashes: &[BlockHash]) -> Vec<BlockHash>;
         pub fn get_depth(&self, blockhash: &BlockHash) -> Option<usize>;
-        pub fn get_pplns_shares_filtered(&self, limit: Option<usize>, start_time: Option<u64>, end_time: Option<u64>) -> Vec<SimplePplnsShare>;
+        pub fn get_share_for_bitcoin_block(&self, bitcoin_blockhash: &BlockHash) -> Option<BlockHash>;
+        pub fn get_shares_by_miner(&self, miner_pubkey: &CompressedPublicKey, from_time: Option<u32>, to_time: Option<u32>, skip: usize, limit: usize) -> Result<Vec<(u32, BlockHash)>, Box<dyn Error + Send + Sync>>;
+        pub fn get_known_miners(&self, max_miners: usize) -> Result<Vec<KnownMiner>, Box<dyn Error + Send + Sync>>;
+        pub fn get_share_height(&self, blockhash: &BlockHash) -> Option<u32>;
+        pub fn get_confirmed_at_height(&self, height: u32) -> Option<BlockHash>;
+        pub fn get_pplns_shares_filtered<'a>(&self, limit: Option<usize>, start_time: Option<u64>, end_time: Option<u64>, btcaddress: Option<&'a str>) -> Vec<SimplePplnsShare>;
         pub fn get_current_target(&self) -> Result<u32, Box<dyn Error + Send + Sync>>;
         pub fn setup_share_for_chain(&self, share_block: ShareBlock) -> ShareBlock;
         pub fn is_confirmed(&self, share: &ShareBlock) -> bool;
@@ -558,6 +790,17 @@ mockall::mock! {
         pub async fn add_job(&self, serialized_notify: String) -> Result<(), Box<dyn Error + Send + Sync>>;
         pub async fn add_user(&self, btcaddress: String) -> Result<u64, StoreError>;
         pub fn get_btcaddresses_for_user_ids(&self, user_ids: &[u64]) -> Result<Vec<(u64, String)>, Box<dyn Error + Send + Sync>>;
+        pub fn list_column_family_names(&self) -> Result<Vec<String>, String>;
+        pub fn get_named_cf_entry_count(&self, name: &str) -> Result<u64, String>;
+        pub fn get_cf_sst_files_size(&self, cf: ColumnFamily) -> Result<u64, String>;
+        pub fn sample_cf_stats(&self, cf: ColumnFamily, sample_limit: usize) -> Result<db_viewer_ops::CfSampleStats, String>;
+        pub fn multi_get_cf_entries(&self, cf: ColumnFamily, keys: &[Vec<u8>]) -> Result<Vec<Option<Vec<u8>>>, String>;
+        pub fn get_cf_properties(&self, cf: ColumnFamily) -> Result<BTreeMap<String, Option<u64>>, String>;
+        pub fn get_cf_boundary_entry(&self, cf: ColumnFamily, last: bool) -> Result<Option<(Vec<u8>, Vec<u8>)>, String>;
+        pub fn for_each_cf_entry<F: FnMut(&[u8], &[u8]) -> bool + 'static>(&self, cf: ColumnFamily, f: F) -> Result<(), String>;
+        pub fn get_cf_entry(&self, cf: ColumnFamily, key: &[u8]) -> Result<Option<Vec<u8>>, String>;
+        pub fn delete_cf_entry(&self, cf: ColumnFamily, key: &[u8]) -> Result<(), String>;
+        pub fn delete_cf_prefix(&self, cf: ColumnFamily, prefix: &[u8]) -> Result<u64, String>;
     }
 
     impl Clone for ChainStoreHandle {
@@ -655,4 +898,96 @@ mod tests {
         assert_eq!(chain_handle.get_depth(&share1.block_hash()), Some(1));
         assert_eq!(chain_handle.get_depth(&genesis.block_hash()), Some(2));
     }
+
+    #[tokio::test]
+    async fn test_chain_store_handle_records_reorg_history() {
+        let (chain_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
+        let genesis = genesis_for_tests();
+
+        chain_handle
+            .init_or_setup_genesis(genesis.clone())
+            .await
+            .unwrap();
+
+        // Extending the tip is not a reorg
+        let share1 = TestShareBlockBuilder::new()
+            .prev_share_blockhash(genesis.block_hash().to_string())
+            .nonce(1)
+            .build();
+        chain_handle.add_share(&share1, true).await.unwrap();
+        let share2 = TestShareBlockBuilder::new()
+            .prev_share_blockhash(share1.block_hash().to_string())
+            .nonce(2)
+            .build();
+        chain_handle.add_share(&share2, true).await.unwrap();
+        assert!(chain_handle.get_reorg_history(10).is_empty());
+        assert_eq!(chain_handle.get_reorg_count(), 0);
+
+        // A longer fork from genesis takes over the tip on its third share
+        let mut prev = genesis.block_hash();
+        let mut fork = Vec::new();
+        for nonce in 11..=14 {
+            let share = TestShareBlockBuilder::new()
+                .prev_share_blockhash(prev.to_string())
+                .nonce(nonce)
+                .build();
+            chain_handle.add_share(&share, true).await.unwrap();
+            prev = share.block_hash();
+            fork.push(prev);
+        }
+        assert_eq!(chain_handle.get_chain_tip(), fork[3]);
+
+        let history = chain_handle.get_reorg_history(10);
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].old_tip, share2.block_hash());
+        assert_eq!(history[0].new_tip, fork[2]);
+        assert_eq!(history[0].depth, 2);
+        assert!(history[0].timestamp > 0);
+        assert!(chain_handle.get_reorg_history(0).is_empty());
+        assert_eq!(chain_handle.get_reorg_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_chain_store_handle_records_tip_history() {
+        let (chain_handle, _temp_dir) = setup_test_chain_store_handle(true).await;
+        let genesis = genesis_for_tests();
+
+        chain_handle
+            .init_or_setup_genesis(genesis.clone())
+            .await
+            .unwrap();
+        let mut tips = vec![genesis.block_hash()];
+        for nonce in 1..=3 {
+            let share = TestShareBlockBuilder::new()
+                .prev_share_blockhash(tips.last().unwrap().to_string())
+                .nonce(nonce)
+                .build();
+            chain_handle.add_share(&share, true).await.unwrap();
+            tips.push(share.block_hash());
+        }
+        // A share on a shorter fork doesn't move the tip
+        let fork = TestShareBlockBuilder::new()
+            .prev_share_blockhash(genesis.block_hash().to_string())
+            .nonce(10)
+            .build();
+        chain_handle.add_share(&fork, true).await.unwrap();
+
+        let history = chain_handle.get_tip_history(10);
+        let hashes: Vec<bitcoin::BlockHash> = history.iter().map(|entry| entry.hash).collect();
+        let expected: Vec<bitcoin::BlockHash> = tips.iter().rev().copied().collect();
+        assert_eq!(hashes, expected);
+        let heights: Vec<u32> = history.iter().map(|entry| entry.height).collect();
+        assert_eq!(heights, vec![3, 2, 1, 0]);
+        assert!(
+            history
+                .windows(2)
+                .all(|pair| pair[0].total_work > pair[1].total_work)
+        );
+        assert!(history.iter().all(|entry| entry.timestamp > 0));
+
+        let history = chain_handle.get_tip_history(2);
+        let hashes: Vec<bitcoin::BlockHash> = history.iter().map(|entry| entry.hash).collect();
+        assert_eq!(hashes, vec![tips[3], tips[2]]);
+        assert!(chain_handle.get_tip_history(0).is_empty());
+    }
 }
@@ -61,7 +61,7 @@ pub struct ShareHeader {
 
 impl ShareHeader {
     /// Get the work defined by the bits field
-    pub(crate) fn get_work(&self) -> bitcoin::Work {
+    pub fn get_work(&self) -> bitcoin::Work {
         Target::from_compact(self.bits).to_work()
     }
 
@@ -159,7 +159,7 @@ impl Decodable for ShareHeader {
 ///
 /// This captures the share chain header and the list of transactions
 /// for the share chain, as well as bitcoin compact block.
-#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
 pub struct ShareBlock {
     /// Header for the block
     pub header: ShareHeader,
@@ -56,6 +56,50 @@ pub async fn validate(
     Ok(())
 }
 
+/// Outcome of one validation rule, as reported by validate_all
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleResult {
+    /// Name of the rule, e.g. "timestamp"
+    pub rule: &'static str,
+    pub passed: bool,
+    /// Why the rule failed, None when it passed
+    pub detail: Option<String>,
+}
+
+impl RuleResult {
+    fn new(rule: &'static str, result: Result<(), String>) -> Self {
+        Self {
+            rule,
+            passed: result.is_ok(),
+            detail: result.err(),
+        }
+    }
+}
+
+/// Run every rule checked by validate, without stopping at the first
+/// failure, for reporting all the rules a share breaks. Rules are
+/// returned in the order validate checks them.
+pub async fn validate_all(
+    share: &ShareBlock,
+    chain_store_handle: &ChainStoreHandle,
+    time_provider: &impl TimeProvider,
+) -> Vec<RuleResult> {
+    let timestamp = validate_timestamp(share, time_provider)
+        .await
+        .map_err(|e| e.to_string());
+    let prev_share_blockhash = validate_prev_share_blockhash(share, chain_store_handle)
+        .await
+        .map_err(|e| e.to_string());
+    let uncles = validate_uncles(share, chain_store_handle)
+        .await
+        .map_err(|e| e.to_string());
+    vec![
+        RuleResult::new("timestamp", timestamp),
+        RuleResult::new("prev_share_blockhash", prev_share_blockhash),
+        RuleResult::new("uncles", uncles),
+    ]
+}
+
 /// Validate prev_share_blockhash is in store or block is genesis
 pub async fn validate_prev_share_blockhash(
     share: &ShareBlock,
@@ -175,6 +219,48 @@ mod tests {
         assert!(validate_timestamp(&share, &time_provider).await.is_ok());
     }
 
+    #[tokio::test]
+    async fn test_validate_all_reports_every_failed_rule() {
+        let share = TestShareBlockBuilder::new()
+            .prev_share_blockhash(
+                "0000000086704a35f17580d06f76d4c02d2b1f68774800675fb45f0411205bb7".to_string(),
+            )
+            .miner_pubkey("020202020202020202020202020202020202020202020202020202020202020202")
+            .build();
+        let mut time_provider = TestTimeProvider::new(SystemTime::now());
+        time_provider.set_time(
+            bitcoin::absolute::Time::from_consensus(share.header.bitcoin_header.time + 120)
+                .unwrap(),
+        );
+
+        let mut chain_store_handle = ChainStoreHandle::default();
+        chain_store_handle
+            .expect_get_share()
+            .returning(move |_| None);
+
+        let results = validate_all(&share, &chain_store_handle, &time_provider).await;
+        let summary: Vec<(&str, bool)> = results
+            .iter()
+            .map(|result| (result.rule, result.passed))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("timestamp", false),
+                ("prev_share_blockhash", false),
+                ("uncles", true),
+            ]
+        );
+        assert!(
+            results[1]
+                .detail
+                .as_deref()
+                .unwrap()
+                .contains("not found in store")
+        );
+        assert_eq!(results[2].detail, None);
+    }
+
     #[tokio::test]
     async fn test_validate_prev_blockhash_exists() {
         // Create and add initial share to chain
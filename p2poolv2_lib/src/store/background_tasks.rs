@@ -180,7 +180,7 @@ mod tests {
         }
 
         // Verify all shares are stored
-        let all_shares = store.get_pplns_shares_filtered(None, None, None);
+        let all_shares = store.get_pplns_shares_filtered(None, None, None, None);
         assert_eq!(all_shares.len(), 3);
 
         // Run cleanup with TTL of 1500 seconds (25 minutes)
@@ -190,7 +190,7 @@ mod tests {
         store.prune_shares(ttl).unwrap();
 
         // Verify only the newest share remains
-        let remaining_shares = store.get_pplns_shares_filtered(None, None, None);
+        let remaining_shares = store.get_pplns_shares_filtered(None, None, None, None);
         assert_eq!(remaining_shares.len(), 1);
         assert_eq!(remaining_shares[0].n_time, now - 300);
     }
@@ -227,7 +227,7 @@ mod tests {
         assert!(result.is_ok());
 
         // Verify share still exists
-        let remaining_shares = store.get_pplns_shares_filtered(None, None, None);
+        let remaining_shares = store.get_pplns_shares_filtered(None, None, None, None);
         assert_eq!(remaining_shares.len(), 1);
     }
 
@@ -242,7 +242,7 @@ mod tests {
         assert!(result.is_ok());
 
         // Verify still empty
-        let shares = store.get_pplns_shares_filtered(None, None, None);
+        let shares = store.get_pplns_shares_filtered(None, None, None, None);
         assert_eq!(shares.len(), 0);
     }
 
@@ -287,7 +287,7 @@ mod tests {
         tokio::time::sleep(Duration::from_millis(200)).await;
 
         // Verify share was deleted (it's 1 hour old, TTL is 30 minutes)
-        let remaining_shares = store.get_pplns_shares_filtered(None, None, None);
+        let remaining_shares = store.get_pplns_shares_filtered(None, None, None, None);
         assert_eq!(remaining_shares.len(), 0);
 
         // Verify job was deleted (use a future end_time to capture all jobs)
@@ -33,9 +33,33 @@ pub enum ColumnFamily {
     UserIndex,
     Metadata,
     SpendsIndex,
+    BitcoinBlockToShare,
+    MinerShares,
 }
 
 impl ColumnFamily {
+    /// All column families, in the order they are listed by the DB viewer
+    pub const ALL: [ColumnFamily; 18] = [
+        ColumnFamily::Block,
+        ColumnFamily::BlockTxids,
+        ColumnFamily::TxidsBlocks,
+        ColumnFamily::Uncles,
+        ColumnFamily::BitcoinTxids,
+        ColumnFamily::Inputs,
+        ColumnFamily::Outputs,
+        ColumnFamily::Tx,
+        ColumnFamily::BlockIndex,
+        ColumnFamily::BlockHeight,
+        ColumnFamily::Share,
+        ColumnFamily::Job,
+        ColumnFamily::User,
+        ColumnFamily::UserIndex,
+        ColumnFamily::Metadata,
+        ColumnFamily::SpendsIndex,
+        ColumnFamily::BitcoinBlockToShare,
+        ColumnFamily::MinerShares,
+    ];
+
     pub fn as_str(&self) -> &'static str {
         match self {
             ColumnFamily::Block => "block",
@@ -54,8 +78,25 @@ impl ColumnFamily {
             ColumnFamily::UserIndex => "user_index",
             ColumnFamily::Metadata => "metadata",
             ColumnFamily::SpendsIndex => "spends_index",
+            ColumnFamily::BitcoinBlockToShare => "bitcoin_block_to_share",
+            ColumnFamily::MinerShares => "miner_shares",
         }
     }
+
+    /// Look up a column family by name, ignoring case and surrounding
+    /// whitespace. Also accepts common aliases like "blocks" and "txs".
+    pub fn from_name(name: &str) -> Option<ColumnFamily> {
+        let name = name.trim().to_ascii_lowercase();
+        let name = match name.as_str() {
+            "blocks" => "block",
+            "txs" | "transactions" => "tx",
+            "shares" => "share",
+            "jobs" => "job",
+            "users" => "user",
+            other => other,
+        };
+        ColumnFamily::ALL.into_iter().find(|cf| cf.as_str() == name)
+    }
 }
 
 impl std::ops::Deref for ColumnFamily {
@@ -83,3 +124,31 @@ impl From<ColumnFamily> for String {
         val.as_str().to_string()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_name_round_trips_all() {
+        for cf in ColumnFamily::ALL {
+            assert_eq!(ColumnFamily::from_name(cf.as_str()), Some(cf));
+        }
+    }
+
+    #[test]
+    fn test_from_name_normalizes_case_and_aliases() {
+        for name in ["Block", "BLOCK", " block ", "blocks", "Blocks"] {
+            assert_eq!(ColumnFamily::from_name(name), Some(ColumnFamily::Block));
+        }
+        for name in ["txs", "TX", "transactions"] {
+            assert_eq!(ColumnFamily::from_name(name), Some(ColumnFamily::Tx));
+        }
+        assert_eq!(
+            ColumnFamily::from_name("Miner_Shares"),
+            Some(ColumnFamily::MinerShares)
+        );
+        assert_eq!(ColumnFamily::from_name("nope"), None);
+        assert_eq!(ColumnFamily::from_name(""), None);
+    }
+}
@@ -15,17 +15,107 @@
 // P2Poolv2. If not, see <https://www.gnu.org/licenses/>.
 
 use crate::store::column_families::ColumnFamily;
-use rocksdb::{DB, IteratorMode};
-use std::sync::Arc;
+use crate::store::organise::{CANDIDATE_SUFFIX, CONFIRMED_SUFFIX};
+use crate::store::share_store::HEIGHT_KEY_PREFIX;
+use rocksdb::{DB, Direction, IteratorMode, WriteBatch};
+use std::collections::BTreeMap;
+
+/// How a key supplied as a string is converted to the raw key bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyEncoding {
+    /// Decode the key from hex, failing if it is not valid hex
+    Hex,
+    /// Use the UTF-8 bytes of the key as is
+    Utf8,
+    /// Decode as hex if possible, otherwise fall back to UTF-8 bytes
+    Auto,
+    /// Encode a decimal height, e.g. "150" or "150:c", as a block_height
+    /// key. Anything that is not a height falls back to Auto.
+    Height,
+}
+
+impl KeyEncoding {
+    /// Default encoding for a column family.
+    /// Column families keyed by strings use UTF-8, so a key that happens to
+    /// look like hex, e.g. "deadbeef", is not decoded into other bytes.
+    pub fn default_for(cf: ColumnFamily) -> Self {
+        match cf {
+            ColumnFamily::UserIndex | ColumnFamily::Metadata => KeyEncoding::Utf8,
+            ColumnFamily::BlockHeight => KeyEncoding::Height,
+            _ => KeyEncoding::Auto,
+        }
+    }
+}
+
+/// Convert a key string to bytes using the given encoding
+pub fn decode_key(key: &str, encoding: KeyEncoding) -> Result<Vec<u8>, String> {
+    match encoding {
+        KeyEncoding::Hex => hex::decode(key).map_err(|e| format!("Invalid hex key: {}", e)),
+        KeyEncoding::Utf8 => Ok(key.as_bytes().to_vec()),
+        KeyEncoding::Auto => Ok(hex::decode(key).unwrap_or_else(|_| key.as_bytes().to_vec())),
+        KeyEncoding::Height => match encode_height_key(key) {
+            Some(key_bytes) => Ok(key_bytes),
+            None => decode_key(key, KeyEncoding::Auto),
+        },
+    }
+}
+
+/// Encode a decimal height as a block_height key.
+/// "150" is the height index key, "150:c" and "150:f" the candidate and
+/// confirmed keys. Heights are stored as big-endian u32.
+fn encode_height_key(key: &str) -> Option<Vec<u8>> {
+    for suffix in [CANDIDATE_SUFFIX, CONFIRMED_SUFFIX] {
+        if let Some(height) = key.strip_suffix(suffix) {
+            let height: u32 = height.parse().ok()?;
+            return Some([&height.to_be_bytes(), suffix.as_bytes()].concat());
+        }
+    }
+    let height: u32 = key.parse().ok()?;
+    Some([HEIGHT_KEY_PREFIX, &height.to_be_bytes()].concat())
+}
+
+/// Render a block_height key with its height in decimal, the inverse of
+/// encode_height_key. Returns None for keys that do not hold a height.
+pub fn format_height_key(key: &[u8]) -> Option<String> {
+    if let Some(height) = key.strip_prefix(HEIGHT_KEY_PREFIX) {
+        let height: [u8; 4] = height.try_into().ok()?;
+        return Some(u32::from_be_bytes(height).to_string());
+    }
+    let (height, suffix) = key.split_at_checked(4)?;
+    let suffix = std::str::from_utf8(suffix).ok()?;
+    if suffix != CANDIDATE_SUFFIX && suffix != CONFIRMED_SUFFIX {
+        return None;
+    }
+    let height: [u8; 4] = height.try_into().ok()?;
+    Some(format!("{}{}", u32::from_be_bytes(height), suffix))
+}
+
+/// Names of the column families in the DB, read from the DB itself so
+/// column families this version doesn't know about are included. The
+/// default column family is never written to and is left out.
+pub fn list_column_family_names(db: &DB) -> Result<Vec<String>, String> {
+    let names = DB::list_cf(&rocksdb::Options::default(), db.path())
+        .map_err(|e| format!("Failed to list column families: {}", e))?;
+    Ok(names
+        .into_iter()
+        .filter(|name| name != rocksdb::DEFAULT_COLUMN_FAMILY_NAME)
+        .collect())
+}
 
 /// Get estimated entry count for a column family
 pub fn get_cf_entry_count(
-    db: &Arc<DB>,
+    db: &DB,
     cf: ColumnFamily,
 ) -> Result<u64, String> {
+    get_named_cf_entry_count(db, cf.as_str())
+}
+
+/// Get estimated entry count for a column family by name, including
+/// column families without a ColumnFamily variant
+pub fn get_named_cf_entry_count(db: &DB, name: &str) -> Result<u64, String> {
     let cf_handle = db
-        .cf_handle(cf.as_str())
-        .ok_or_else(|| format!("Column family {} not found", cf.as_str()))?;
+        .cf_handle(name)
+        .ok_or_else(|| format!("Column family {} not found", name))?;
 
     // Approximate count using RocksDB property
     match db.property_int_value_cf(&cf_handle, "rocksdb.estimate-num-keys") {
@@ -41,7 +131,7 @@ pub fn get_cf_entry_count(
 
 /// Get estimated size of a column family in bytes
 pub fn get_cf_size_estimate(
-    db: &Arc<DB>,
+    db: &DB,
     cf: ColumnFamily,
 ) -> Result<u64, String> {
     let cf_handle = db
@@ -56,33 +146,223 @@ pub fn get_cf_size_estimate(
     }
 }
 
-/// List entries from a column family with pagination
-pub fn list_cf_entries(
-    db: &Arc<DB>,
+/// Get the total size of the SST files of a column family in bytes
+pub fn get_cf_sst_files_size(db: &DB, cf: ColumnFamily) -> Result<u64, String> {
+    let cf_handle = db
+        .cf_handle(cf.as_str())
+        .ok_or_else(|| format!("Column family {} not found", cf.as_str()))?;
+
+    match db.property_int_value_cf(&cf_handle, "rocksdb.total-sst-files-size") {
+        Ok(Some(size)) => Ok(size),
+        Ok(None) => Ok(0),
+        Err(e) => Err(format!("Failed to get SST files size: {}", e)),
+    }
+}
+
+/// RocksDB properties reported for each column family by get_cf_properties
+pub const CF_PROPERTIES: [&str; 4] = [
+    "rocksdb.num-running-compactions",
+    "rocksdb.mem-table-flush-pending",
+    "rocksdb.estimate-num-keys",
+    "rocksdb.total-sst-files-size",
+];
+
+/// Read the CF_PROPERTIES integer properties for a column family.
+/// Properties RocksDB doesn't report are None.
+pub fn get_cf_properties(
+    db: &DB,
     cf: ColumnFamily,
-    skip: usize,
-    limit: usize,
-    search: Option<&str>,
-) -> Result<(Vec<(Vec<u8>, Vec<u8>)>, u64), String> {
+) -> Result<BTreeMap<String, Option<u64>>, String> {
     let cf_handle = db
         .cf_handle(cf.as_str())
         .ok_or_else(|| format!("Column family {} not found", cf.as_str()))?;
 
-    let mut entries = Vec::new();
-    let mut skipped = 0usize;
-    let mut total_count = 0u64;
+    CF_PROPERTIES
+        .iter()
+        .map(|property| {
+            db.property_int_value_cf(&cf_handle, *property)
+                .map(|value| (property.to_string(), value))
+                .map_err(|e| format!("Failed to get property {}: {}", property, e))
+        })
+        .collect()
+}
 
+/// Key range and value sizes seen while sampling a column family
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CfSampleStats {
+    /// Number of entries read during the sample scan
+    pub sampled_entries: u64,
+    /// Size in bytes of the largest value in the sample
+    pub largest_value_bytes: u64,
+    /// First key in the column family
+    pub first_key: Option<Vec<u8>>,
+    /// Last key in the column family
+    pub last_key: Option<Vec<u8>>,
+}
+
+/// Scan up to sample_limit entries from the start of a column family,
+/// tracking the largest value, and read the first and last keys.
+pub fn sample_cf_stats(
+    db: &DB,
+    cf: ColumnFamily,
+    sample_limit: usize,
+) -> Result<CfSampleStats, String> {
+    let cf_handle = db
+        .cf_handle(cf.as_str())
+        .ok_or_else(|| format!("Column family {} not found", cf.as_str()))?;
+
+    let mut stats = CfSampleStats::default();
     let iter = db.iterator_cf(&cf_handle, IteratorMode::Start);
+    for item in iter.take(sample_limit) {
+        let (key, value) = item.map_err(|e| format!("Failed to iterate column family: {}", e))?;
+        if stats.first_key.is_none() {
+            stats.first_key = Some(key.to_vec());
+        }
+        stats.sampled_entries += 1;
+        stats.largest_value_bytes = stats.largest_value_bytes.max(value.len() as u64);
+    }
+
+    if let Some(item) = db.iterator_cf(&cf_handle, IteratorMode::End).next() {
+        let (key, _) = item.map_err(|e| format!("Failed to iterate column family: {}", e))?;
+        stats.last_key = Some(key.to_vec());
+    }
+
+    Ok(stats)
+}
+
+/// Get the first entry of a column family in key order, or the last one
+/// when last is set. Returns None for an empty column family.
+pub fn get_cf_boundary_entry(
+    db: &DB,
+    cf: ColumnFamily,
+    last: bool,
+) -> Result<Option<(Vec<u8>, Vec<u8>)>, String> {
+    let cf_handle = db
+        .cf_handle(cf.as_str())
+        .ok_or_else(|| format!("Column family {} not found", cf.as_str()))?;
+
+    let mode = if last {
+        IteratorMode::End
+    } else {
+        IteratorMode::Start
+    };
+    match db.iterator_cf(&cf_handle, mode).next() {
+        Some(Ok((key, value))) => Ok(Some((key.to_vec(), value.to_vec()))),
+        Some(Err(e)) => Err(format!("Failed to iterate column family: {}", e)),
+        None => Ok(None),
+    }
+}
+
+/// A page of entries read by list_cf_entries
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CfEntryPage {
+    /// Entries in the page, as key and value bytes
+    pub entries: Vec<(Vec<u8>, Vec<u8>)>,
+    /// Number of entries counted during the scan
+    pub total_count: u64,
+    /// Set when the scan budget ran out before the page was filled
+    pub truncated: bool,
+    /// Iterator error that ended the scan early. The entries read before
+    /// the error are kept.
+    pub error: Option<String>,
+}
+
+/// Which entries list_cf_entries reads. The default lists every entry
+/// in key order.
+#[derive(Debug, Clone, Copy)]
+pub struct ListOptions<'a> {
+    /// Seek past this key instead of starting from the first or last key
+    pub start_after: Option<&'a [u8]>,
+    /// Number of matching entries to skip before the page starts
+    pub skip: usize,
+    /// Maximum number of entries in the page
+    pub limit: usize,
+    /// Only keep entries whose key, read as UTF-8, contains this text
+    pub search: Option<&'a str>,
+    /// List in descending key order
+    pub reverse: bool,
+    /// Maximum number of entries read before the scan stops
+    pub max_scan: usize,
+}
+
+impl Default for ListOptions<'_> {
+    fn default() -> Self {
+        Self {
+            start_after: None,
+            skip: 0,
+            limit: usize::MAX,
+            search: None,
+            reverse: false,
+            max_scan: usize::MAX,
+        }
+    }
+}
+
+/// List entries from a column family with pagination.
+/// Entries are in key order, or descending key order when reverse is set.
+/// When start_after is set the listing seeks past that key instead of
+/// starting from the first or last key, and the count starts there.
+/// At most max_scan entries are read, the page is marked truncated when
+/// the scan stopped there before filling it. An iterator error partway
+/// through, e.g. from a corrupt record, ends the scan and is returned in
+/// the page with the entries read so far.
+pub fn list_cf_entries(
+    db: &DB,
+    cf: ColumnFamily,
+    options: &ListOptions,
+) -> Result<CfEntryPage, String> {
+    let cf_handle = db
+        .cf_handle(cf.as_str())
+        .ok_or_else(|| format!("Column family {} not found", cf.as_str()))?;
+
+    let mode = match (options.start_after, options.reverse) {
+        (Some(start), false) => IteratorMode::From(start, Direction::Forward),
+        (Some(start), true) => IteratorMode::From(start, Direction::Reverse),
+        (None, false) => IteratorMode::Start,
+        (None, true) => IteratorMode::End,
+    };
+    let iter = db.iterator_cf(&cf_handle, mode);
+
+    Ok(collect_cf_entries(iter, options))
+}
+
+/// Page through the items of a column family iterator for list_cf_entries
+fn collect_cf_entries<I, K, V, E>(iter: I, options: &ListOptions) -> CfEntryPage
+where
+    I: Iterator<Item = Result<(K, V), E>>,
+    K: AsRef<[u8]>,
+    V: AsRef<[u8]>,
+    E: std::fmt::Display,
+{
+    let ListOptions {
+        start_after,
+        skip,
+        limit,
+        search,
+        max_scan,
+        ..
+    } = *options;
+    let mut page = CfEntryPage::default();
+    let mut skipped = 0usize;
 
     for item in iter {
         match item {
             Ok((key, value)) => {
+                let key = key.as_ref();
+                // The seek lands on the start key itself while it exists
+                if start_after.is_some_and(|start| key == start) {
+                    continue;
+                }
+                if page.total_count >= max_scan as u64 && page.entries.len() < limit {
+                    page.truncated = true;
+                    break;
+                }
                 // Count all items for total
-                total_count += 1;
+                page.total_count += 1;
 
                 // Apply search filter if provided
                 if let Some(search_term) = search {
-                    let key_str = String::from_utf8_lossy(&key);
+                    let key_str = String::from_utf8_lossy(key);
                     if !key_str.contains(search_term) {
                         continue;
                     }
@@ -95,8 +375,8 @@ pub fn list_cf_entries(
                 }
 
                 // Add to results until we reach the limit
-                if entries.len() < limit {
-                    entries.push((key.to_vec(), value.to_vec()));
+                if page.entries.len() < limit {
+                    page.entries.push((key.to_vec(), value.as_ref().to_vec()));
                 } else {
                     // We have enough entries, but continue counting for total
                     // Break early to save iteration time
@@ -106,64 +386,139 @@ pub fn list_cf_entries(
                 }
             }
             Err(e) => {
-                return Err(format!("Failed to iterate column family: {}", e));
+                page.error = Some(format!("Failed to iterate column family: {}", e));
+                break;
             }
         }
     }
 
-    Ok((entries, total_count))
+    page
+}
+
+/// Call f with each entry of a column family in key order, stopping
+/// early when f returns false
+pub fn for_each_cf_entry<F>(db: &DB, cf: ColumnFamily, mut f: F) -> Result<(), String>
+where
+    F: FnMut(&[u8], &[u8]) -> bool,
+{
+    let cf_handle = db
+        .cf_handle(cf.as_str())
+        .ok_or_else(|| format!("Column family {} not found", cf.as_str()))?;
+
+    for item in db.iterator_cf(&cf_handle, IteratorMode::Start) {
+        let (key, value) = item.map_err(|e| format!("Failed to iterate column family: {}", e))?;
+        if !f(&key, &value) {
+            break;
+        }
+    }
+    Ok(())
 }
 
 /// Get a specific entry from a column family by key
 pub fn get_cf_entry(
-    db: &Arc<DB>,
+    db: &DB,
     cf: ColumnFamily,
-    key: &str,
+    key: &[u8],
 ) -> Result<Option<Vec<u8>>, String> {
     let cf_handle = db
         .cf_handle(cf.as_str())
         .ok_or_else(|| format!("Column family {} not found", cf.as_str()))?;
 
-    // Try to parse the key as hex first, then as UTF-8
-    let key_bytes = if let Ok(hex_bytes) = hex::decode(key) {
-        hex_bytes
-    } else {
-        key.as_bytes().to_vec()
-    };
-
-    match db.get_cf(&cf_handle, &key_bytes) {
+    match db.get_cf(&cf_handle, key) {
         Ok(value) => Ok(value.map(|v| v.to_vec())),
         Err(e) => Err(format!("Failed to get entry: {}", e)),
     }
 }
 
+/// Get multiple entries from a column family with a single multi_get.
+/// Values are returned in the same order as keys, None for missing keys.
+pub fn multi_get_cf_entries(
+    db: &DB,
+    cf: ColumnFamily,
+    keys: &[Vec<u8>],
+) -> Result<Vec<Option<Vec<u8>>>, String> {
+    let cf_handle = db
+        .cf_handle(cf.as_str())
+        .ok_or_else(|| format!("Column family {} not found", cf.as_str()))?;
+
+    db.multi_get_cf(keys.iter().map(|key| (&cf_handle, key)))
+        .into_iter()
+        .map(|result| result.map_err(|e| format!("Failed to get entry: {}", e)))
+        .collect()
+}
+
 /// Delete an entry from a column family (optional, for admin)
 pub fn delete_cf_entry(
-    db: &Arc<DB>,
+    db: &DB,
     cf: ColumnFamily,
-    key: &str,
+    key: &[u8],
 ) -> Result<(), String> {
     let cf_handle = db
         .cf_handle(cf.as_str())
         .ok_or_else(|| format!("Column family {} not found", cf.as_str()))?;
 
-    // Try to parse the key as hex first, then as UTF-8
-    let key_bytes = if let Ok(hex_bytes) = hex::decode(key) {
-        hex_bytes
-    } else {
-        key.as_bytes().to_vec()
-    };
-
-    match db.delete_cf(&cf_handle, &key_bytes) {
+    match db.delete_cf(&cf_handle, key) {
         Ok(_) => Ok(()),
         Err(e) => Err(format!("Failed to delete entry: {}", e)),
     }
 }
 
+/// Smallest key that sorts after every key starting with prefix, or None
+/// when there is none, i.e. the prefix is empty or all 0xff bytes
+fn prefix_successor(prefix: &[u8]) -> Option<Vec<u8>> {
+    let mut successor = prefix.to_vec();
+    while let Some(last) = successor.pop() {
+        if last < u8::MAX {
+            successor.push(last + 1);
+            return Some(successor);
+        }
+    }
+    None
+}
+
+/// Delete every entry in a column family whose key starts with prefix.
+/// An empty prefix deletes the whole column family.
+///
+/// Keys are counted before they are deleted, so the returned count may be
+/// off if the column family is written to concurrently.
+pub fn delete_cf_prefix(db: &DB, cf: ColumnFamily, prefix: &[u8]) -> Result<u64, String> {
+    let cf_handle = db
+        .cf_handle(cf.as_str())
+        .ok_or_else(|| format!("Column family {} not found", cf.as_str()))?;
+
+    // delete_range_cf needs an exclusive upper bound, which a prefix of
+    // 0xff bytes doesn't have, so those keys are batched one by one
+    let end = prefix_successor(prefix);
+    let mut batch = WriteBatch::default();
+    let mut count = 0u64;
+    for item in db.iterator_cf(&cf_handle, IteratorMode::From(prefix, Direction::Forward)) {
+        let (key, _) = item.map_err(|e| format!("Failed to iterate column family: {}", e))?;
+        if !key.starts_with(prefix) {
+            break;
+        }
+        if end.is_none() {
+            batch.delete_cf(&cf_handle, &key);
+        }
+        count += 1;
+    }
+    if count == 0 {
+        return Ok(0);
+    }
+
+    let result = match end {
+        Some(end) => db.delete_range_cf(&cf_handle, prefix, end),
+        None => db.write(batch),
+    };
+    result.map_err(|e| format!("Failed to delete entries: {}", e))?;
+    Ok(count)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::store::Store;
     use crate::store::column_families::ColumnFamily;
+    use crate::store::stored_user::StoredUser;
     use rocksdb::{Options, DB};
     use std::sync::Arc;
     use tempfile::tempdir;
@@ -188,12 +543,21 @@ mod tests {
         db_arc.put_cf(&cf_handle, b"test_key", b"test_value").unwrap();
 
         // Test list entries
-        let (entries, total) = list_cf_entries(&db_arc, ColumnFamily::Metadata, 0, 10, None).unwrap();
-        assert_eq!(entries.len(), 1);
-        assert_eq!(total, 1);
+        let page = list_cf_entries(
+            &db_arc,
+            ColumnFamily::Metadata,
+            &ListOptions {
+                limit: 10,
+                ..ListOptions::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(page.entries.len(), 1);
+        assert_eq!(page.total_count, 1);
+        assert_eq!(page.error, None);
 
         // Test get entry
-        let value = get_cf_entry(&db_arc, ColumnFamily::Metadata, "test_key").unwrap();
+        let value = get_decoded(&db_arc, ColumnFamily::Metadata, "test_key", KeyEncoding::Auto);
         assert!(value.is_some());
         assert_eq!(value.unwrap(), b"test_value");
 
@@ -202,13 +566,412 @@ mod tests {
             db_arc.put_cf(&cf_handle, format!("key{}", i).as_bytes(), format!("value{}", i).as_bytes()).unwrap();
         }
 
-        let (entries, _) = list_cf_entries(&db_arc, ColumnFamily::Metadata, 0, 10, None).unwrap();
-        assert_eq!(entries.len(), 10);
+        let page = list_cf_entries(
+            &db_arc,
+            ColumnFamily::Metadata,
+            &ListOptions {
+                limit: 10,
+                ..ListOptions::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(page.entries.len(), 10);
+
+        let page = list_cf_entries(
+            &db_arc,
+            ColumnFamily::Metadata,
+            &ListOptions {
+                skip: 10,
+                limit: 10,
+                ..ListOptions::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(page.entries.len(), 10);
+
+        let page = list_cf_entries(
+            &db_arc,
+            ColumnFamily::Metadata,
+            &ListOptions {
+                skip: 20,
+                limit: 10,
+                ..ListOptions::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(page.entries.len(), 6); // 26 total entries, skip 20, get 6 remaining
+
+        // Reverse iteration starts from the largest key
+        let page = list_cf_entries(
+            &db_arc,
+            ColumnFamily::Metadata,
+            &ListOptions {
+                limit: 3,
+                reverse: true,
+                ..ListOptions::default()
+            },
+        )
+        .unwrap();
+        let keys: Vec<&[u8]> = page.entries.iter().map(|(key, _)| key.as_slice()).collect();
+        assert_eq!(keys, vec![b"test_key".as_slice(), b"key9", b"key8"]);
+
+        // Seeking past a key starts just after it, in either direction
+        let page = list_cf_entries(
+            &db_arc,
+            ColumnFamily::Metadata,
+            &ListOptions {
+                start_after: Some(b"key8".as_slice()),
+                limit: 10,
+                ..ListOptions::default()
+            },
+        )
+        .unwrap();
+        let keys: Vec<&[u8]> = page.entries.iter().map(|(key, _)| key.as_slice()).collect();
+        assert_eq!(keys, vec![b"key9".as_slice(), b"test_key"]);
+        assert_eq!(page.total_count, 2);
+        let page = list_cf_entries(
+            &db_arc,
+            ColumnFamily::Metadata,
+            &ListOptions {
+                start_after: Some(b"key8".as_slice()),
+                limit: 2,
+                reverse: true,
+                ..ListOptions::default()
+            },
+        )
+        .unwrap();
+        let keys: Vec<&[u8]> = page.entries.iter().map(|(key, _)| key.as_slice()).collect();
+        assert_eq!(keys, vec![b"key7".as_slice(), b"key6"]);
+
+        // A scan budget below skip + limit returns a truncated, partial page
+        let page = list_cf_entries(
+            &db_arc,
+            ColumnFamily::Metadata,
+            &ListOptions {
+                skip: 20,
+                limit: 10,
+                max_scan: 24,
+                ..ListOptions::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(page.entries.len(), 4);
+        assert_eq!(page.total_count, 24);
+        assert!(page.truncated);
+
+        // Filling the page at the budget is not truncated
+        let page = list_cf_entries(
+            &db_arc,
+            ColumnFamily::Metadata,
+            &ListOptions {
+                limit: 10,
+                max_scan: 10,
+                ..ListOptions::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(page.entries.len(), 10);
+        assert!(!page.truncated);
+    }
+
+    #[test]
+    fn test_collect_cf_entries_keeps_entries_before_iterator_error() {
+        let items: Vec<Result<(Vec<u8>, Vec<u8>), String>> = vec![
+            Ok((b"key1".to_vec(), b"value1".to_vec())),
+            Err("Corruption: bad block".to_string()),
+            Ok((b"key2".to_vec(), b"value2".to_vec())),
+        ];
+
+        let page = collect_cf_entries(
+            items.into_iter(),
+            &ListOptions {
+                limit: 10,
+                ..ListOptions::default()
+            },
+        );
+        assert_eq!(page.entries, vec![(b"key1".to_vec(), b"value1".to_vec())]);
+        assert_eq!(page.total_count, 1);
+        assert!(!page.truncated);
+        assert_eq!(
+            page.error.as_deref(),
+            Some("Failed to iterate column family: Corruption: bad block")
+        );
+    }
+
+    /// Look up a key given as a string, decoded with encoding
+    fn get_decoded(
+        db: &DB,
+        cf: ColumnFamily,
+        key: &str,
+        encoding: KeyEncoding,
+    ) -> Option<Vec<u8>> {
+        get_cf_entry(db, cf, &decode_key(key, encoding).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_get_cf_entry_hex_lookalike_username() {
+        let temp_dir = tempdir().unwrap();
+        let store = Store::new(temp_dir.path().to_str().unwrap().to_string(), false).unwrap();
+        let db = store.get_db();
+
+        // "deadbeef" is a valid hex string, but usernames are string keys
+        let user_id = store.add_user("deadbeef".to_string()).unwrap();
+
+        let encoding = KeyEncoding::default_for(ColumnFamily::UserIndex);
+        assert_eq!(encoding, KeyEncoding::Utf8);
+        let value = get_decoded(db, ColumnFamily::UserIndex, "deadbeef", encoding);
+        assert_eq!(value.unwrap(), user_id.to_be_bytes());
+
+        // Auto decodes the username as hex and misses it
+        let value = get_decoded(db, ColumnFamily::UserIndex, "deadbeef", KeyEncoding::Auto);
+        assert!(value.is_none());
+
+        // The user record is found in the User CF by its binary user ID
+        let key = hex::encode(user_id.to_be_bytes());
+        let encoding = KeyEncoding::default_for(ColumnFamily::User);
+        let value = get_decoded(db, ColumnFamily::User, &key, encoding).unwrap();
+        let user: StoredUser = bitcoin::consensus::encode::deserialize(&value).unwrap();
+        assert_eq!(user.btcaddress, "deadbeef");
+
+        assert!(decode_key("not hex", KeyEncoding::Hex).is_err());
+    }
+
+    #[test]
+    fn test_get_cf_entry_by_decimal_height() {
+        let temp_dir = tempdir().unwrap();
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        opts.create_missing_column_families(true);
+
+        let db = DB::open_cf(&opts, temp_dir.path(), ["block_height"]).unwrap();
+        let db_arc = Arc::new(db);
+
+        let cf_handle = db_arc.cf_handle("block_height").unwrap();
+        let height_key = [HEIGHT_KEY_PREFIX, &150u32.to_be_bytes()].concat();
+        db_arc
+            .put_cf(&cf_handle, &height_key, b"shares at 150")
+            .unwrap();
+        let candidate_key = [150u32.to_be_bytes().as_slice(), b":c"].concat();
+        db_arc.put_cf(&cf_handle, &candidate_key, b"candidate").unwrap();
+        db_arc
+            .put_cf(&cf_handle, b"meta:top_confirmed_height", b"meta")
+            .unwrap();
+
+        let encoding = KeyEncoding::default_for(ColumnFamily::BlockHeight);
+        assert_eq!(encoding, KeyEncoding::Height);
+        let value = get_decoded(&db_arc, ColumnFamily::BlockHeight, "150", encoding);
+        assert_eq!(value.unwrap(), b"shares at 150");
+        let value = get_decoded(&db_arc, ColumnFamily::BlockHeight, "150:c", encoding);
+        assert_eq!(value.unwrap(), b"candidate");
+        let value = get_decoded(
+            &db_arc,
+            ColumnFamily::BlockHeight,
+            "meta:top_confirmed_height",
+            encoding,
+        );
+        assert_eq!(value.unwrap(), b"meta");
+        let value = get_decoded(&db_arc, ColumnFamily::BlockHeight, "151", encoding);
+        assert!(value.is_none());
 
-        let (entries, _) = list_cf_entries(&db_arc, ColumnFamily::Metadata, 10, 10, None).unwrap();
-        assert_eq!(entries.len(), 10);
+        assert_eq!(format_height_key(&height_key).as_deref(), Some("150"));
+        assert_eq!(format_height_key(&candidate_key).as_deref(), Some("150:c"));
+        assert_eq!(format_height_key(b"meta:top_confirmed_height"), None);
+    }
+
+    #[test]
+    fn test_sample_cf_stats() {
+        let temp_dir = tempdir().unwrap();
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        opts.create_missing_column_families(true);
+
+        let db = DB::open_cf(&opts, temp_dir.path(), ["metadata"]).unwrap();
+        let db_arc = Arc::new(db);
+
+        let stats = sample_cf_stats(&db_arc, ColumnFamily::Metadata, 10).unwrap();
+        assert_eq!(stats, CfSampleStats::default());
+
+        let cf_handle = db_arc.cf_handle("metadata").unwrap();
+        db_arc.put_cf(&cf_handle, b"a", [0u8; 4]).unwrap();
+        db_arc.put_cf(&cf_handle, b"b", [0u8; 32]).unwrap();
+        db_arc.put_cf(&cf_handle, b"c", [0u8; 64]).unwrap();
+
+        let stats = sample_cf_stats(&db_arc, ColumnFamily::Metadata, 10).unwrap();
+        assert_eq!(stats.sampled_entries, 3);
+        assert_eq!(stats.largest_value_bytes, 64);
+        assert_eq!(stats.first_key, Some(b"a".to_vec()));
+        assert_eq!(stats.last_key, Some(b"c".to_vec()));
+
+        // The sample is bounded but the last key still comes from the end
+        let stats = sample_cf_stats(&db_arc, ColumnFamily::Metadata, 2).unwrap();
+        assert_eq!(stats.sampled_entries, 2);
+        assert_eq!(stats.largest_value_bytes, 32);
+        assert_eq!(stats.last_key, Some(b"c".to_vec()));
+    }
+
+    #[test]
+    fn test_get_cf_boundary_entry() {
+        let temp_dir = tempdir().unwrap();
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        opts.create_missing_column_families(true);
+
+        let db = DB::open_cf(&opts, temp_dir.path(), ["metadata"]).unwrap();
+        let db_arc = Arc::new(db);
+
+        assert_eq!(
+            get_cf_boundary_entry(&db_arc, ColumnFamily::Metadata, false).unwrap(),
+            None
+        );
+
+        let cf_handle = db_arc.cf_handle("metadata").unwrap();
+        db_arc.put_cf(&cf_handle, b"b", b"value b").unwrap();
+        db_arc.put_cf(&cf_handle, b"a", b"value a").unwrap();
+        db_arc.put_cf(&cf_handle, b"c", b"value c").unwrap();
+
+        assert_eq!(
+            get_cf_boundary_entry(&db_arc, ColumnFamily::Metadata, false).unwrap(),
+            Some((b"a".to_vec(), b"value a".to_vec()))
+        );
+        assert_eq!(
+            get_cf_boundary_entry(&db_arc, ColumnFamily::Metadata, true).unwrap(),
+            Some((b"c".to_vec(), b"value c".to_vec()))
+        );
+    }
+
+    #[test]
+    fn test_multi_get_cf_entries_preserves_order() {
+        let temp_dir = tempdir().unwrap();
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        opts.create_missing_column_families(true);
+
+        let db = DB::open_cf(&opts, temp_dir.path(), ["metadata"]).unwrap();
+        let db_arc = Arc::new(db);
+
+        let cf_handle = db_arc.cf_handle("metadata").unwrap();
+        db_arc.put_cf(&cf_handle, b"a", b"value a").unwrap();
+        db_arc.put_cf(&cf_handle, b"c", b"value c").unwrap();
+
+        let keys = vec![b"c".to_vec(), b"b".to_vec(), b"a".to_vec()];
+        let values = multi_get_cf_entries(&db_arc, ColumnFamily::Metadata, &keys).unwrap();
+        assert_eq!(
+            values,
+            vec![Some(b"value c".to_vec()), None, Some(b"value a".to_vec())]
+        );
+    }
+
+    #[test]
+    fn test_delete_cf_prefix_keeps_surrounding_keys() {
+        let temp_dir = tempdir().unwrap();
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        opts.create_missing_column_families(true);
+
+        let db = DB::open_cf(&opts, temp_dir.path(), ["metadata"]).unwrap();
+        let db_arc = Arc::new(db);
+
+        let cf_handle = db_arc.cf_handle("metadata").unwrap();
+        for key in [
+            b"ab".as_slice(),
+            b"abc",
+            b"abc\x00",
+            b"abc\xff",
+            b"abd",
+            b"b",
+            b"\xff\xff",
+            b"\xff\xff\x01",
+        ] {
+            db_arc.put_cf(&cf_handle, key, b"value").unwrap();
+        }
+        let remaining = || {
+            let mut keys = Vec::new();
+            for_each_cf_entry(&db_arc, ColumnFamily::Metadata, |key, _| {
+                keys.push(key.to_vec());
+                true
+            })
+            .unwrap();
+            keys
+        };
+
+        assert_eq!(
+            delete_cf_prefix(&db_arc, ColumnFamily::Metadata, b"abc").unwrap(),
+            3
+        );
+        assert_eq!(
+            remaining(),
+            vec![
+                b"ab".to_vec(),
+                b"abd".to_vec(),
+                b"b".to_vec(),
+                b"\xff\xff".to_vec(),
+                b"\xff\xff\x01".to_vec(),
+            ]
+        );
+
+        // No upper bound for an all 0xff prefix
+        assert_eq!(
+            delete_cf_prefix(&db_arc, ColumnFamily::Metadata, b"\xff\xff").unwrap(),
+            2
+        );
+        assert_eq!(
+            delete_cf_prefix(&db_arc, ColumnFamily::Metadata, b"zzz").unwrap(),
+            0
+        );
+
+        // An empty prefix clears the column family
+        assert_eq!(
+            delete_cf_prefix(&db_arc, ColumnFamily::Metadata, b"").unwrap(),
+            3
+        );
+        assert!(remaining().is_empty());
+    }
+
+    #[test]
+    fn test_prefix_successor() {
+        assert_eq!(prefix_successor(b"abc"), Some(b"abd".to_vec()));
+        assert_eq!(prefix_successor(b"a\xff"), Some(b"b".to_vec()));
+        assert_eq!(prefix_successor(b"\xff\xff"), None);
+        assert_eq!(prefix_successor(b""), None);
+    }
+
+    #[test]
+    fn test_list_column_family_names_includes_unknown() {
+        let temp_dir = tempdir().unwrap();
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        opts.create_missing_column_families(true);
+
+        let db = DB::open_cf(&opts, temp_dir.path(), ["metadata", "legacy_index"]).unwrap();
+        let db_arc = Arc::new(db);
+        let cf_handle = db_arc.cf_handle("legacy_index").unwrap();
+        db_arc.put_cf(&cf_handle, b"key", b"value").unwrap();
+
+        let names = list_column_family_names(&db_arc).unwrap();
+        assert_eq!(names, vec!["metadata", "legacy_index"]);
+        assert_eq!(get_named_cf_entry_count(&db_arc, "legacy_index").unwrap(), 1);
+        assert!(get_named_cf_entry_count(&db_arc, "missing").is_err());
+    }
+
+    #[test]
+    fn test_get_cf_properties() {
+        let temp_dir = tempdir().unwrap();
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        opts.create_missing_column_families(true);
+
+        let db = DB::open_cf(&opts, temp_dir.path(), ["metadata"]).unwrap();
+        let db_arc = Arc::new(db);
+
+        let cf_handle = db_arc.cf_handle("metadata").unwrap();
+        db_arc.put_cf(&cf_handle, b"a", b"value a").unwrap();
+        db_arc.put_cf(&cf_handle, b"b", b"value b").unwrap();
 
-        let (entries, _) = list_cf_entries(&db_arc, ColumnFamily::Metadata, 20, 10, None).unwrap();
-        assert_eq!(entries.len(), 6); // 26 total entries, skip 20, get 6 remaining
+        let properties = get_cf_properties(&db_arc, ColumnFamily::Metadata).unwrap();
+        assert_eq!(properties.len(), CF_PROPERTIES.len());
+        assert_eq!(properties["rocksdb.estimate-num-keys"], Some(2));
+        assert_eq!(properties["rocksdb.num-running-compactions"], Some(0));
+        // Nothing has been flushed to SST files yet
+        assert_eq!(properties["rocksdb.total-sst-files-size"], Some(0));
     }
 }
@@ -146,7 +146,15 @@ impl Store {
         let spends_index_cf =
             ColumnFamilyDescriptor::new(ColumnFamily::SpendsIndex, RocksDbOptions::default());
 
-        let cfs = vec![
+        let bitcoin_block_to_share_cf = ColumnFamilyDescriptor::new(
+            ColumnFamily::BitcoinBlockToShare,
+            RocksDbOptions::default(),
+        );
+
+        let miner_shares_cf =
+            ColumnFamilyDescriptor::new(ColumnFamily::MinerShares, RocksDbOptions::default());
+
+        let mut cfs = vec![
             block_cf,
             block_txids_cf,
             txids_blocks_cf,
@@ -163,8 +171,28 @@ impl Store {
             user_index_cf,
             metadata_cf,
             spends_index_cf,
+            bitcoin_block_to_share_cf,
+            miner_shares_cf,
         ];
 
+        // A store from before the miner shares or bitcoin block to share
+        // index gets it filled from the shares already stored
+        let mut backfill_miner_shares = false;
+        let mut backfill_bitcoin_block_to_share = false;
+        // RocksDB refuses to open a db without all its column families, so
+        // also open any on disk that this version doesn't know about
+        if let Ok(existing) = DB::list_cf(&RocksDbOptions::default(), &path) {
+            let missing = |cf: ColumnFamily| !existing.iter().any(|name| name == cf.as_str());
+            backfill_miner_shares = missing(ColumnFamily::MinerShares);
+            backfill_bitcoin_block_to_share = missing(ColumnFamily::BitcoinBlockToShare);
+            let known: HashSet<&str> = ColumnFamily::ALL.iter().map(|cf| cf.as_str()).collect();
+            for name in existing {
+                if name != rocksdb::DEFAULT_COLUMN_FAMILY_NAME && !known.contains(name.as_str()) {
+                    cfs.push(ColumnFamilyDescriptor::new(name, RocksDbOptions::default()));
+                }
+            }
+        }
+
         // for the db too, we use default options for now
         let mut db_options = RocksDbOptions::default();
         db_options.create_missing_column_families(true);
@@ -182,6 +210,12 @@ impl Store {
             chain_tip: Arc::new(RwLock::new(BlockHash::all_zeros())),
             tips: Arc::new(RwLock::new(HashSet::new())),
         };
+        if backfill_miner_shares && !read_only {
+            store.backfill_miner_shares()?;
+        }
+        if backfill_bitcoin_block_to_share && !read_only {
+            store.backfill_bitcoin_block_to_share()?;
+        }
         Ok(store)
     }
 
@@ -424,6 +458,22 @@ mod tests {
         ); // 1 + 2 + 3 = 6
     }
 
+    #[test]
+    fn test_store_opens_unknown_column_families() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().to_str().unwrap().to_string();
+        {
+            let mut opts = RocksDbOptions::default();
+            opts.create_if_missing(true);
+            opts.create_missing_column_families(true);
+            DB::open_cf(&opts, &path, ["block", "legacy_index"]).unwrap();
+        }
+
+        let store = Store::new(path, false).unwrap();
+        assert!(store.get_db().cf_handle("legacy_index").is_some());
+        assert!(store.get_db().cf_handle("metadata").is_some());
+    }
+
     #[test]
     fn test_merge_operator_for_block_height() {
         let temp_dir = tempdir().unwrap();
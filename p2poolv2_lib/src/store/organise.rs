@@ -21,8 +21,8 @@ use bitcoin::{
 };
 use std::error::Error;
 
-const CANDIDATE_SUFFIX: &str = ":c";
-const CONFIRMED_SUFFIX: &str = ":f";
+pub(crate) const CANDIDATE_SUFFIX: &str = ":c";
+pub(crate) const CONFIRMED_SUFFIX: &str = ":f";
 const TOP_CANDIDATE_KEY: &str = "meta:top_candidate_height";
 const TOP_CONFIRMED_KEY: &str = "meta:top_confirmed_height";
 
@@ -45,20 +45,33 @@ impl Store {
 
     // Get PPLNS shares, no filter yet
     pub fn get_pplns_shares(&self) -> Vec<SimplePplnsShare> {
-        self.get_pplns_shares_filtered(None, None, None)
+        self.get_pplns_shares_filtered(None, None, None, None)
     }
 
     /// Get PPLNS shares with filtering support using timestamp-based keys for efficient range queries
     /// Deserializes SimplePplnsShare from DB (btcaddress/workername are skipped during serialization)
-    /// and enriches with btcaddress from user store
+    /// and enriches with btcaddress from user store.
+    /// With btcaddress set, only that user's shares are returned, and none
+    /// if the address is not a known user.
     pub fn get_pplns_shares_filtered(
         &self,
         limit: Option<usize>,
         start_time: Option<u64>,
         end_time: Option<u64>,
+        btcaddress: Option<&str>,
     ) -> Vec<SimplePplnsShare> {
         let pplns_share_cf = self.db.cf_handle(&ColumnFamily::Share).unwrap();
 
+        // The user_id is part of the key, so other users' shares are
+        // skipped without deserializing them
+        let user_id = match btcaddress {
+            Some(btcaddress) => match self.get_user_by_btcaddress(btcaddress) {
+                Ok(Some(user)) => Some(user.user_id),
+                _ => return Vec::new(),
+            },
+            None => None,
+        };
+
         // Convert end_time to microseconds, default to current time if not specified
         let effective_end_time = end_time.map(|t| t * 1_000_000).unwrap_or_else(|| {
             SystemTime::now()
@@ -89,7 +102,10 @@ impl Store {
         let use_limit = limit.unwrap_or(INITIAL_SHARE_VEC_CAPACITY);
         let mut shares: Vec<SimplePplnsShare> = Vec::with_capacity(use_limit);
 
-        for (_key, mut value) in iter.take(use_limit).flatten() {
+        let matching = iter.flatten().filter(|(key, _)| {
+            user_id.is_none_or(|user_id| SimplePplnsShare::parse_key(key).1 == user_id)
+        });
+        for (_key, mut value) in matching.take(use_limit) {
             if let Ok(share) = encode::deserialize::<SimplePplnsShare>(&mut value) {
                 shares.push(share);
             }
@@ -215,7 +231,7 @@ mod tests {
         }
 
         // Test limit functionality
-        let result = store.get_pplns_shares_filtered(Some(2), None, None);
+        let result = store.get_pplns_shares_filtered(Some(2), None, None, None);
         assert_eq!(result.len(), 2);
     }
 
@@ -267,8 +283,50 @@ mod tests {
         }
 
         // Test time filtering
-        let result = store.get_pplns_shares_filtered(Some(10), Some(1500), Some(2500));
+        let result = store.get_pplns_shares_filtered(Some(10), Some(1500), Some(2500), None);
         assert_eq!(result.len(), 1);
         assert_eq!(result[0].n_time, 2000);
     }
+
+    #[test]
+    fn test_get_pplns_shares_filtered_by_btcaddress() {
+        let temp_dir = tempdir().unwrap();
+        let store = Store::new(temp_dir.path().to_str().unwrap().to_string(), false).unwrap();
+
+        let user_id1 = store.add_user("addr1".to_string()).unwrap();
+        let user_id2 = store.add_user("addr2".to_string()).unwrap();
+
+        // Interleave the two users' shares in time
+        for (user_id, btcaddress, n_time) in [
+            (user_id1, "addr1", 1000),
+            (user_id2, "addr2", 2000),
+            (user_id1, "addr1", 3000),
+            (user_id2, "addr2", 4000),
+        ] {
+            let share = SimplePplnsShare::new(
+                user_id,
+                100.0,
+                btcaddress.to_string(),
+                "worker".to_string(),
+                n_time,
+                "job".to_string(),
+                "extra".to_string(),
+                "nonce".to_string(),
+            );
+            store.add_pplns_share(share).unwrap();
+        }
+
+        let result = store.get_pplns_shares_filtered(None, None, None, Some("addr1"));
+        assert_eq!(result.len(), 2);
+        assert!(result.iter().all(|share| share.user_id == user_id1));
+
+        // The limit applies to the matching shares, newest first
+        let result = store.get_pplns_shares_filtered(Some(1), None, None, Some("addr1"));
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].n_time, 3000);
+
+        // Unknown addresses have no shares
+        let result = store.get_pplns_shares_filtered(None, None, None, Some("addr3"));
+        assert!(result.is_empty());
+    }
 }
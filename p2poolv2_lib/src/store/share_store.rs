@@ -20,10 +20,42 @@ use crate::shares::share_block::{
     ShareBlock, ShareHeader, ShareTransaction, StorageShareBlock, Txids,
 };
 use bitcoin::consensus::{self, Encodable, encode};
-use bitcoin::{BlockHash, Work};
+use bitcoin::hashes::Hash;
+use bitcoin::{BlockHash, CompressedPublicKey, Work};
 use std::collections::HashMap;
 use std::error::Error;
-use tracing::debug;
+use tracing::{debug, info};
+
+/// Prefix of the height to blockhashes keys in the block_height column family
+pub(crate) const HEIGHT_KEY_PREFIX: &[u8] = b"h:";
+
+/// Miner pubkey (33) + time (4) + share hash (32)
+const MINER_SHARE_KEY_LEN: usize = 69;
+
+/// Cap on the up front allocation for a page of miner shares
+const INITIAL_MINER_SHARES_CAPACITY: usize = 1000;
+
+/// Shares indexed per write batch when backfilling a share index
+const SHARE_INDEX_BACKFILL_BATCH: usize = 10_000;
+
+/// A miner in the miner shares index, with the time and hash of its
+/// earliest and latest indexed shares
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KnownMiner {
+    pub miner_pubkey: CompressedPublicKey,
+    pub first_share: (u32, BlockHash),
+    pub last_share: (u32, BlockHash),
+}
+
+/// Split a miner shares index key into the share time and hash
+fn parse_miner_share_key(key: &[u8]) -> Result<(u32, BlockHash), Box<dyn Error + Send + Sync>> {
+    if key.len() != MINER_SHARE_KEY_LEN {
+        return Err(format!("Invalid miner share key length {}", key.len()).into());
+    }
+    let time = u32::from_be_bytes(key[33..37].try_into()?);
+    let blockhash = BlockHash::from_slice(&key[37..])?;
+    Ok((time, blockhash))
+}
 
 impl Store {
     /// Add a share to the store
@@ -97,6 +129,12 @@ impl Store {
             chain_work,
         };
         self.set_block_metadata(&blockhash, &block_metadata, batch)?;
+        self.set_bitcoin_block_to_share(
+            &share.header.bitcoin_header.block_hash(),
+            &blockhash,
+            batch,
+        );
+        self.set_miner_share(&share.header, &blockhash, batch);
 
         // Add the share block itself
         let storage_share_block: StorageShareBlock = share.into();
@@ -245,7 +283,7 @@ impl Store {
         batch: &mut rocksdb::WriteBatch,
     ) -> Result<(), Box<dyn Error + Send + Sync>> {
         let column_family = self.db.cf_handle(&ColumnFamily::BlockHeight).unwrap();
-        let mut key = b"h:".to_vec();
+        let mut key = HEIGHT_KEY_PREFIX.to_vec();
         let height_bytes = height.to_be_bytes();
         key.extend_from_slice(&height_bytes);
 
@@ -261,7 +299,7 @@ impl Store {
     /// Get the blockhashes for a specific height
     pub fn get_blockhashes_for_height(&self, height: u32) -> Vec<BlockHash> {
         let column_family = self.db.cf_handle(&ColumnFamily::BlockHeight).unwrap();
-        let mut key = b"h:".to_vec();
+        let mut key = HEIGHT_KEY_PREFIX.to_vec();
         let height_bytes = height.to_be_bytes();
         key.extend_from_slice(&height_bytes);
 
@@ -335,6 +373,184 @@ impl Store {
         Ok(())
     }
 
+    /// Index the share by the hash of the bitcoin block header it commits to
+    fn set_bitcoin_block_to_share(
+        &self,
+        bitcoin_blockhash: &BlockHash,
+        blockhash: &BlockHash,
+        batch: &mut rocksdb::WriteBatch,
+    ) {
+        let cf = self
+            .db
+            .cf_handle(&ColumnFamily::BitcoinBlockToShare)
+            .unwrap();
+        batch.put_cf(&cf, bitcoin_blockhash, consensus::serialize(blockhash));
+    }
+
+    /// Get the share whose bitcoin header has the given block hash
+    pub fn get_share_for_bitcoin_block(&self, bitcoin_blockhash: &BlockHash) -> Option<BlockHash> {
+        let cf = self
+            .db
+            .cf_handle(&ColumnFamily::BitcoinBlockToShare)
+            .unwrap();
+        match self.db.get_cf::<&[u8]>(&cf, bitcoin_blockhash.as_ref()) {
+            Ok(Some(blockhash)) => encode::deserialize(&blockhash).ok(),
+            Ok(None) | Err(_) => None,
+        }
+    }
+
+    /// Index the share by its miner's public key and timestamp
+    ///
+    /// Key is miner pubkey (33) + time (4, big endian) + share hash
+    /// (32), so a miner's shares are contiguous and in time order. The
+    /// value is empty.
+    fn set_miner_share(
+        &self,
+        header: &ShareHeader,
+        blockhash: &BlockHash,
+        batch: &mut rocksdb::WriteBatch,
+    ) {
+        let cf = self.db.cf_handle(&ColumnFamily::MinerShares).unwrap();
+        let mut key = Vec::with_capacity(MINER_SHARE_KEY_LEN);
+        key.extend_from_slice(&header.miner_pubkey.to_bytes());
+        key.extend_from_slice(&header.time.to_be_bytes());
+        key.extend_from_slice(blockhash.as_ref());
+        batch.put_cf(&cf, key, b"");
+    }
+
+    /// Call index with every share in the block column family, committing
+    /// its writes in batches. Returns the number of shares indexed.
+    fn backfill_share_index(
+        &self,
+        index: impl Fn(&Self, &BlockHash, &StorageShareBlock, &mut rocksdb::WriteBatch),
+    ) -> Result<usize, Box<dyn Error + Send + Sync>> {
+        let block_cf = self.db.cf_handle(&ColumnFamily::Block).unwrap();
+        let mut batch = Self::get_write_batch();
+        let mut indexed = 0;
+        for item in self.db.iterator_cf(&block_cf, rocksdb::IteratorMode::Start) {
+            let (key, value) = item?;
+            // Skip the hash + "_md" block metadata keys in the same family
+            let Ok(blockhash) = BlockHash::from_slice(&key) else {
+                continue;
+            };
+            let share: StorageShareBlock = encode::deserialize(&value)?;
+            index(self, &blockhash, &share, &mut batch);
+            indexed += 1;
+            if indexed % SHARE_INDEX_BACKFILL_BATCH == 0 {
+                self.commit_batch(std::mem::take(&mut batch))?;
+            }
+        }
+        self.commit_batch(batch)?;
+        Ok(indexed)
+    }
+
+    /// Index every share in the block column family by miner. Run once,
+    /// when a store from before the miner shares index is opened, so the
+    /// index covers shares stored before it existed. Returns the number
+    /// of shares indexed.
+    pub(crate) fn backfill_miner_shares(&self) -> Result<usize, Box<dyn Error + Send + Sync>> {
+        let indexed = self.backfill_share_index(|store, blockhash, share, batch| {
+            store.set_miner_share(&share.header, blockhash, batch)
+        })?;
+        info!("Backfilled the miner shares index with {indexed} shares");
+        Ok(indexed)
+    }
+
+    /// Index every share in the block column family by the hash of its
+    /// bitcoin header. Run once, when a store from before the bitcoin
+    /// block to share index is opened. Returns the number of shares
+    /// indexed.
+    pub(crate) fn backfill_bitcoin_block_to_share(
+        &self,
+    ) -> Result<usize, Box<dyn Error + Send + Sync>> {
+        let indexed = self.backfill_share_index(|store, blockhash, share, batch| {
+            store.set_bitcoin_block_to_share(
+                &share.header.bitcoin_header.block_hash(),
+                blockhash,
+                batch,
+            )
+        })?;
+        info!("Backfilled the bitcoin block to share index with {indexed} shares");
+        Ok(indexed)
+    }
+
+    /// Get the time and hash of a miner's shares, newest first, with
+    /// times within the inclusive from_time..=to_time range. Skips the
+    /// first skip matches and returns at most limit.
+    pub fn get_shares_by_miner(
+        &self,
+        miner_pubkey: &CompressedPublicKey,
+        from_time: Option<u32>,
+        to_time: Option<u32>,
+        skip: usize,
+        limit: usize,
+    ) -> Result<Vec<(u32, BlockHash)>, Box<dyn Error + Send + Sync>> {
+        let cf = self.db.cf_handle(&ColumnFamily::MinerShares).unwrap();
+        let pubkey = miner_pubkey.to_bytes();
+
+        let mut lower_bound = pubkey.to_vec();
+        lower_bound.extend_from_slice(&from_time.unwrap_or(0).to_be_bytes());
+        // Longer than any key at to_time, so those keys sort below it
+        let mut upper_bound = pubkey.to_vec();
+        upper_bound.extend_from_slice(&to_time.unwrap_or(u32::MAX).to_be_bytes());
+        upper_bound.extend_from_slice(&[0xff; MINER_SHARE_KEY_LEN]);
+
+        let mut read_opts = rocksdb::ReadOptions::default();
+        read_opts.set_iterate_lower_bound(lower_bound);
+        read_opts.set_iterate_upper_bound(upper_bound);
+        let iter = self
+            .db
+            .iterator_cf_opt(&cf, read_opts, rocksdb::IteratorMode::End);
+
+        let mut shares = Vec::with_capacity(limit.min(INITIAL_MINER_SHARES_CAPACITY));
+        for item in iter.skip(skip).take(limit) {
+            let (key, _) = item?;
+            shares.push(parse_miner_share_key(&key)?);
+        }
+        Ok(shares)
+    }
+
+    /// Get the distinct miners in the miner shares index, in pubkey
+    /// order, with their earliest and latest shares. Returns at most
+    /// max_miners. Seeks over each miner's shares, so the cost grows
+    /// with the number of miners, not shares.
+    pub fn get_known_miners(
+        &self,
+        max_miners: usize,
+    ) -> Result<Vec<KnownMiner>, Box<dyn Error + Send + Sync>> {
+        let cf = self.db.cf_handle(&ColumnFamily::MinerShares).unwrap();
+        let mut iter = self.db.raw_iterator_cf(&cf);
+        let mut miners = Vec::new();
+
+        iter.seek_to_first();
+        while miners.len() < max_miners {
+            let Some(key) = iter.key() else {
+                break;
+            };
+            let first_share = parse_miner_share_key(key)?;
+            let miner_pubkey = CompressedPublicKey::from_slice(&key[..33])?;
+
+            // Longer than any key for this miner, so the last one sorts below it
+            let mut miner_end = key[..33].to_vec();
+            miner_end.extend_from_slice(&[0xff; MINER_SHARE_KEY_LEN]);
+            iter.seek_for_prev(&miner_end);
+            let last_share = match iter.key() {
+                Some(key) => parse_miner_share_key(key)?,
+                None => first_share,
+            };
+
+            miners.push(KnownMiner {
+                miner_pubkey,
+                first_share,
+                last_share,
+            });
+            // Step onto the next miner's first share
+            iter.next();
+        }
+        iter.status()?;
+        Ok(miners)
+    }
+
     /// Mark a block as valid in the store
     pub fn set_block_valid(
         &self,
@@ -367,7 +583,6 @@ impl Store {
 mod tests {
     use super::*;
     use crate::test_utils::TestShareBlockBuilder;
-    use bitcoin::hashes::Hash;
     use tempfile::tempdir;
 
     #[test]
@@ -513,6 +728,200 @@ mod tests {
         assert_eq!(header, Some(block.header.clone()));
     }
 
+    #[test]
+    fn test_get_share_for_bitcoin_block() {
+        let temp_dir = tempdir().unwrap();
+        let store = Store::new(temp_dir.path().to_str().unwrap().to_string(), false).unwrap();
+
+        let block = TestShareBlockBuilder::new().build();
+        let bitcoin_blockhash = block.header.bitcoin_header.block_hash();
+        assert_eq!(store.get_share_for_bitcoin_block(&bitcoin_blockhash), None);
+
+        let mut batch = Store::get_write_batch();
+        store
+            .add_share(&block, 0, block.header.get_work(), true, &mut batch)
+            .unwrap();
+        store.commit_batch(batch).unwrap();
+
+        assert_eq!(
+            store.get_share_for_bitcoin_block(&bitcoin_blockhash),
+            Some(block.block_hash())
+        );
+        assert_eq!(store.get_share_for_bitcoin_block(&block.block_hash()), None);
+    }
+
+    #[test]
+    fn test_get_shares_by_miner() {
+        let temp_dir = tempdir().unwrap();
+        let store = Store::new(temp_dir.path().to_str().unwrap().to_string(), false).unwrap();
+
+        let miner = "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+        let miner_pubkey: CompressedPublicKey = miner.parse().unwrap();
+        let mut miner_shares = Vec::new();
+        let mut batch = Store::get_write_batch();
+        for (nonce, time) in [(1, 1000), (2, 2000), (3, 3000)] {
+            let mut share = TestShareBlockBuilder::new()
+                .miner_pubkey(miner)
+                .nonce(nonce)
+                .build();
+            share.header.time = time;
+            store
+                .add_share(&share, 0, share.header.get_work(), true, &mut batch)
+                .unwrap();
+            miner_shares.push((time, share.block_hash()));
+        }
+        let other = TestShareBlockBuilder::new().nonce(4).build();
+        store
+            .add_share(&other, 0, other.header.get_work(), true, &mut batch)
+            .unwrap();
+        store.commit_batch(batch).unwrap();
+
+        // Newest first, and only this miner's shares
+        let shares = store
+            .get_shares_by_miner(&miner_pubkey, None, None, 0, 10)
+            .unwrap();
+        let newest_first: Vec<_> = miner_shares.iter().rev().cloned().collect();
+        assert_eq!(shares, newest_first);
+
+        // Pages
+        let shares = store
+            .get_shares_by_miner(&miner_pubkey, None, None, 1, 1)
+            .unwrap();
+        assert_eq!(shares, vec![miner_shares[1]]);
+
+        // Inclusive time range
+        let shares = store
+            .get_shares_by_miner(&miner_pubkey, Some(1000), Some(2000), 0, 10)
+            .unwrap();
+        assert_eq!(shares, vec![miner_shares[1], miner_shares[0]]);
+
+        let other_shares = store
+            .get_shares_by_miner(&other.header.miner_pubkey, None, None, 0, 10)
+            .unwrap();
+        assert_eq!(other_shares, vec![(other.header.time, other.block_hash())]);
+    }
+
+    #[test]
+    fn test_get_known_miners() {
+        let temp_dir = tempdir().unwrap();
+        let store = Store::new(temp_dir.path().to_str().unwrap().to_string(), false).unwrap();
+        assert!(store.get_known_miners(10).unwrap().is_empty());
+
+        let miner_a = "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+        let miner_b = "02c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5";
+        let mut shares = Vec::new();
+        let mut batch = Store::get_write_batch();
+        for (miner, nonce, time) in [
+            (miner_b, 1, 1500),
+            (miner_a, 2, 1000),
+            (miner_a, 3, 3000),
+            (miner_a, 4, 2000),
+        ] {
+            let mut share = TestShareBlockBuilder::new()
+                .miner_pubkey(miner)
+                .nonce(nonce)
+                .build();
+            share.header.time = time;
+            store
+                .add_share(&share, 0, share.header.get_work(), true, &mut batch)
+                .unwrap();
+            shares.push((time, share.block_hash()));
+        }
+        store.commit_batch(batch).unwrap();
+
+        // Pubkey order, with each miner's earliest and latest shares by time
+        let miners = store.get_known_miners(10).unwrap();
+        assert_eq!(
+            miners,
+            vec![
+                KnownMiner {
+                    miner_pubkey: miner_a.parse().unwrap(),
+                    first_share: shares[1],
+                    last_share: shares[2],
+                },
+                KnownMiner {
+                    miner_pubkey: miner_b.parse().unwrap(),
+                    first_share: shares[0],
+                    last_share: shares[0],
+                },
+            ]
+        );
+
+        assert_eq!(store.get_known_miners(1).unwrap(), miners[..1].to_vec());
+    }
+
+    #[test]
+    fn test_miner_shares_backfilled_for_store_without_index() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().to_str().unwrap().to_string();
+
+        let miner = "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+        let mut shares = Vec::new();
+        {
+            let store = Store::new(path.clone(), false).unwrap();
+            let mut batch = Store::get_write_batch();
+            for (nonce, time) in [(1, 1000), (2, 2000)] {
+                let mut share = TestShareBlockBuilder::new()
+                    .miner_pubkey(miner)
+                    .nonce(nonce)
+                    .build();
+                share.header.time = time;
+                store
+                    .add_share(&share, 0, share.header.get_work(), true, &mut batch)
+                    .unwrap();
+                shares.push((time, share.block_hash()));
+            }
+            store.commit_batch(batch).unwrap();
+        }
+
+        // Drop the index, as in a store written before it was added
+        {
+            let opts = rocksdb::Options::default();
+            let names = rocksdb::DB::list_cf(&opts, &path).unwrap();
+            let mut db = rocksdb::DB::open_cf(&opts, &path, names).unwrap();
+            db.drop_cf(ColumnFamily::MinerShares.as_str()).unwrap();
+        }
+
+        let store = Store::new(path, false).unwrap();
+        let indexed = store
+            .get_shares_by_miner(&miner.parse().unwrap(), None, None, 0, 10)
+            .unwrap();
+        assert_eq!(indexed, vec![shares[1], shares[0]]);
+        assert_eq!(store.get_known_miners(10).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_bitcoin_block_to_share_backfilled_for_store_without_index() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().to_str().unwrap().to_string();
+
+        let share = TestShareBlockBuilder::new().nonce(1).build();
+        let bitcoin_blockhash = share.header.bitcoin_header.block_hash();
+        {
+            let store = Store::new(path.clone(), false).unwrap();
+            let mut batch = Store::get_write_batch();
+            store
+                .add_share(&share, 0, share.header.get_work(), true, &mut batch)
+                .unwrap();
+            store.commit_batch(batch).unwrap();
+        }
+
+        // Drop the index, as in a store written before it was added
+        {
+            let opts = rocksdb::Options::default();
+            let names = rocksdb::DB::list_cf(&opts, &path).unwrap();
+            let mut db = rocksdb::DB::open_cf(&opts, &path, names).unwrap();
+            db.drop_cf(ColumnFamily::BitcoinBlockToShare.as_str())
+                .unwrap();
+        }
+
+        let store = Store::new(path, false).unwrap();
+        assert_eq!(
+            store.get_share_for_bitcoin_block(&bitcoin_blockhash),
+            Some(share.block_hash())
+        );
+    }
+
     #[test]
     fn test_get_share_header_nonexistent() {
         let temp_dir = tempdir().unwrap();
@@ -24,8 +24,9 @@ use super::{StoreError, WriteCommand, WriteSender};
 use crate::accounting::simple_pplns::SimplePplnsShare;
 use crate::shares::share_block::{ShareBlock, ShareHeader};
 use crate::store::Store;
+use crate::store::share_store::KnownMiner;
 use crate::store::stored_user::StoredUser;
-use bitcoin::{BlockHash, Work};
+use bitcoin::{BlockHash, CompressedPublicKey, Work};
 use std::collections::{HashMap, HashSet};
 use std::error::Error;
 use std::sync::Arc;
@@ -133,15 +134,16 @@ impl StoreHandle {
         self.store.get_pplns_shares()
     }
 
-    /// Get PPLNS shares with filtering.
+    /// Get PPLNS shares with filtering, optionally for one btcaddress.
     pub fn get_pplns_shares_filtered(
         &self,
         limit: Option<usize>,
         start_time: Option<u64>,
         end_time: Option<u64>,
+        btcaddress: Option<&str>,
     ) -> Vec<SimplePplnsShare> {
         self.store
-            .get_pplns_shares_filtered(limit, start_time, end_time)
+            .get_pplns_shares_filtered(limit, start_time, end_time, btcaddress)
     }
 
     /// Get jobs within a time range.
@@ -186,6 +188,33 @@ impl StoreHandle {
         self.store.get_children_blockhashes(blockhash)
     }
 
+    /// Get the share that committed to a bitcoin block hash.
+    pub fn get_share_for_bitcoin_block(&self, bitcoin_blockhash: &BlockHash) -> Option<BlockHash> {
+        self.store.get_share_for_bitcoin_block(bitcoin_blockhash)
+    }
+
+    /// Get the time and hash of a miner's shares, newest first.
+    pub fn get_shares_by_miner(
+        &self,
+        miner_pubkey: &CompressedPublicKey,
+        from_time: Option<u32>,
+        to_time: Option<u32>,
+        skip: usize,
+        limit: usize,
+    ) -> Result<Vec<(u32, BlockHash)>, Box<dyn Error + Send + Sync>> {
+        self.store
+            .get_shares_by_miner(miner_pubkey, from_time, to_time, skip, limit)
+    }
+
+    /// Get the distinct miners in the miner shares index with their
+    /// earliest and latest shares.
+    pub fn get_known_miners(
+        &self,
+        max_miners: usize,
+    ) -> Result<Vec<KnownMiner>, Box<dyn Error + Send + Sync>> {
+        self.store.get_known_miners(max_miners)
+    }
+
     // ========================================================================
     // SERIALIZED WRITES - These go through the channel to StoreWriter
     //
@@ -336,12 +365,15 @@ mockall::mock! {
         pub fn get_tips(&self) -> HashSet<BlockHash>;
         pub fn get_total_work(&self) -> Result<Work, Box<dyn Error + Send + Sync>>;
         pub fn get_pplns_shares(&self) -> Vec<SimplePplnsShare>;
-        pub fn get_pplns_shares_filtered(&self, limit: Option<usize>, start_time: Option<u64>, end_time: Option<u64>) -> Vec<SimplePplnsShare>;
+        pub fn get_pplns_shares_filtered<'a>(&self, limit: Option<usize>, start_time: Option<u64>, end_time: Option<u64>, btcaddress: Option<&'a str>) -> Vec<SimplePplnsShare>;
         pub fn get_jobs(&self, start_time: Option<u64>, end_time: Option<u64>, limit: usize) -> Result<Vec<(u64, String)>, Box<dyn Error + Send + Sync>>;
         pub fn get_user_by_id(&self, user_id: u64) -> Result<Option<StoredUser>, Box<dyn Error + Send + Sync>>;
         pub fn get_user_by_btcaddress(&self, btcaddress: &str) -> Result<Option<StoredUser>, Box<dyn Error + Send + Sync>>;
         pub fn get_btcaddresses_for_user_ids(&self, user_ids: &[u64]) -> Result<Vec<(u64, String)>, Box<dyn Error + Send + Sync>>;
         pub fn get_children_blockhashes(&self, blockhash: &BlockHash) -> Result<Option<Vec<BlockHash>>, Box<dyn Error + Send + Sync>>;
+        pub fn get_share_for_bitcoin_block(&self, bitcoin_blockhash: &BlockHash) -> Option<BlockHash>;
+        pub fn get_shares_by_miner(&self, miner_pubkey: &CompressedPublicKey, from_time: Option<u32>, to_time: Option<u32>, skip: usize, limit: usize) -> Result<Vec<(u32, BlockHash)>, Box<dyn Error + Send + Sync>>;
+        pub fn get_known_miners(&self, max_miners: usize) -> Result<Vec<KnownMiner>, Box<dyn Error + Send + Sync>>;
 
         // Serialized writes (async)
         pub async fn add_share(&self, share: ShareBlock, height: u32, chain_work: Work, confirm_txs: bool) -> Result<(), StoreError>;
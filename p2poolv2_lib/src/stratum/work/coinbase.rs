@@ -191,6 +191,24 @@ pub fn split_coinbase(coinbase: &Transaction) -> Result<(String, String), WorkEr
     Ok((coinbase1, coinbase2))
 }
 
+/// Joins coinbase1 and coinbase2 back into the coinbase transaction, the
+/// reverse of `split_coinbase`. Each miner fills the extranonces with its
+/// own values, so the separator stands in for them.
+pub fn join_coinbase(coinbase1_hex: &str, coinbase2_hex: &str) -> Result<Transaction, WorkError> {
+    let parse_error = || WorkError {
+        message: "Error parsing coinbase hex".into(),
+    };
+    let coinbase1 = Vec::from_hex(coinbase1_hex).map_err(|_| parse_error())?;
+    let coinbase2 = Vec::from_hex(coinbase2_hex).map_err(|_| parse_error())?;
+
+    let mut coinbase = coinbase1;
+    coinbase.extend_from_slice(&EXTRANONCE_SEPARATOR);
+    coinbase.extend_from_slice(&coinbase2);
+    deserialize::<Transaction>(&coinbase).map_err(|_| WorkError {
+        message: "Invalid coinbase transaction".into(),
+    })
+}
+
 /// Parses the `coinbase2` hex string to extract the transaction outputs.
 ///
 /// Based on `build_coinbase_transaction` and `split_coinbase`, `coinbase2`
@@ -371,6 +389,34 @@ mod tests {
         assert_eq!(reconstructed_coinbase, coinbase);
     }
 
+    #[test]
+    fn test_join_coinbase_reverses_split() {
+        let addr = parse_address(
+            "tb1q3udk7r26qs32ltf9nmqrjaaa7tr55qmkk30q5d",
+            Network::Signet,
+        )
+        .unwrap();
+        let coinbase = build_coinbase_transaction(
+            Version(2),
+            &[OutputPair {
+                address: addr,
+                amount: Amount::from_str("50 BTC").unwrap(),
+            }],
+            100,
+            PushBytesBuf::from(&[0u8]),
+            None,
+            b"P2Poolv2",
+            None,
+        )
+        .unwrap();
+
+        let (coinbase1, coinbase2) = split_coinbase(&coinbase).unwrap();
+        assert_eq!(join_coinbase(&coinbase1, &coinbase2).unwrap(), coinbase);
+
+        assert!(join_coinbase("zz", &coinbase2).is_err());
+        assert!(join_coinbase(&coinbase1, "").is_err());
+    }
+
     #[test]
     fn test_building_coinbase_with_regtest_ckpool_data() {
         // Load GBT and expected notify JSON
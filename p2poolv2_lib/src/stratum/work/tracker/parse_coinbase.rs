@@ -15,14 +15,74 @@
 // P2Poolv2. If not, see <https://www.gnu.org/licenses/>.
 
 use crate::stratum::work::{coinbase::extract_outputs_from_coinbase2, tracker::JobTracker};
-use bitcoin::Amount;
+use bitcoin::address::NetworkChecked;
+use bitcoin::{Address, Amount, TxOut};
 use std::sync::Arc;
 
+/// Known pool fee and donation addresses, used to label coinbase outputs
+/// with the role they play. Any other output is a miner payout.
+#[derive(Clone, Debug, Default)]
+pub struct CoinbaseRoles {
+    fee_address: Option<Address<NetworkChecked>>,
+    donation_address: Option<Address<NetworkChecked>>,
+}
+
+impl CoinbaseRoles {
+    pub fn new(
+        fee_address: Option<Address<NetworkChecked>>,
+        donation_address: Option<Address<NetworkChecked>>,
+    ) -> Self {
+        Self {
+            fee_address,
+            donation_address,
+        }
+    }
+
+    /// Role label for an output address. The fee address wins if it is
+    /// also the donation address.
+    pub fn role(&self, address: &Address<NetworkChecked>) -> &'static str {
+        if self.fee_address.as_ref() == Some(address) {
+            "pool"
+        } else if self.donation_address.as_ref() == Some(address) {
+            "donation"
+        } else {
+            "miner"
+        }
+    }
+}
+
+/// Coinbase outputs with a non-zero value that pay an address, with
+/// their index in the coinbase. OP_RETURN outputs, like the witness
+/// commitment, are skipped wherever they are, so templates with no
+/// witness commitment parse the same way. Other outputs without an
+/// address are skipped with an error.
+pub fn address_outputs(
+    outputs: &[TxOut],
+    network: bitcoin::network::Network,
+) -> Vec<(usize, Address<NetworkChecked>, Amount)> {
+    outputs
+        .iter()
+        .enumerate()
+        .filter(|(_, tx_out)| !tx_out.script_pubkey.is_op_return())
+        .filter(|(_, tx_out)| tx_out.value != Amount::ZERO)
+        .filter_map(
+            |(index, tx_out)| match Address::from_script(&tx_out.script_pubkey, network) {
+                Ok(address) => Some((index, address, tx_out.value)),
+                Err(_) => {
+                    tracing::error!("Error parsing address from coinbase");
+                    None
+                }
+            },
+        )
+        .collect()
+}
+
 /// Parse the coinbase in the latest job and return
 pub fn get_distribution(
     tracker: &Arc<JobTracker>,
     pool_signature_length: usize,
     network: bitcoin::network::Network,
+    roles: &CoinbaseRoles,
 ) -> Option<String> {
     let job_id = tracker.get_latest_job_id();
     let job_details = match tracker.get_job(job_id) {
@@ -38,18 +98,12 @@ pub fn get_distribution(
             // Use index in case the fees and donation addresses are
             // the same. This way we can leave on grafana how to show
             // them
-            for (index, tx_out) in outputs.iter().enumerate() {
-                if tx_out.value != Amount::ZERO {
-                    match bitcoin::Address::from_script(&tx_out.script_pubkey, network) {
-                        Ok(address) => {
-                            exposition.push_str(&format!(
-                                "coinbase_output{{index=\"{index}\",address=\"{address}\"}} {}\n",
-                                tx_out.value.to_sat()
-                            ));
-                        }
-                        Err(_) => tracing::error!("Error parsing address from coinbase"),
-                    }
-                }
+            for (index, address, value) in address_outputs(&outputs, network) {
+                let role = roles.role(&address);
+                exposition.push_str(&format!(
+                    "coinbase_output{{index=\"{index}\",address=\"{address}\",role=\"{role}\"}} {}\n",
+                    value.to_sat()
+                ));
             }
 
             exposition.push_str(&format!("coinbase_total {total_value}\n"));
@@ -134,7 +188,7 @@ mod tests {
             job_id,
         );
 
-        let result = get_distribution(&tracker, 8, Network::Signet);
+        let result = get_distribution(&tracker, 8, Network::Signet, &CoinbaseRoles::default());
 
         assert!(result.is_some());
         let exposition = result.unwrap();
@@ -143,7 +197,7 @@ mod tests {
         assert_eq!(exposition.matches("coinbase_output").count(), 1);
 
         assert!(exposition.contains(
-            "coinbase_output{index=\"0\",address=\"tb1q3udk7r26qs32ltf9nmqrjaaa7tr55qmkk30q5d\"} 4900000000"
+            "coinbase_output{index=\"0\",address=\"tb1q3udk7r26qs32ltf9nmqrjaaa7tr55qmkk30q5d\",role=\"miner\"} 4900000000"
         ));
         assert!(exposition.contains("coinbase_total 5000000000"));
     }
@@ -153,7 +207,7 @@ mod tests {
         let tracker = start_tracker_actor();
 
         // Don't insert any jobs
-        let result = get_distribution(&tracker, 8, Network::Signet);
+        let result = get_distribution(&tracker, 8, Network::Signet, &CoinbaseRoles::default());
 
         assert!(result.is_none());
     }
@@ -172,7 +226,7 @@ mod tests {
             job_id,
         );
 
-        let result = get_distribution(&tracker, 8, Network::Signet);
+        let result = get_distribution(&tracker, 8, Network::Signet, &CoinbaseRoles::default());
 
         assert!(result.is_none());
     }
@@ -214,15 +268,15 @@ mod tests {
             job_id,
         );
 
-        let result = get_distribution(&tracker, 8, Network::Signet);
+        let result = get_distribution(&tracker, 8, Network::Signet, &CoinbaseRoles::default());
 
         assert!(result.is_some());
         let exposition = result.unwrap();
         assert!(exposition.contains(
-            "coinbase_output{index=\"0\",address=\"tb1q3udk7r26qs32ltf9nmqrjaaa7tr55qmkk30q5d\"} 4800000000"
+            "coinbase_output{index=\"0\",address=\"tb1q3udk7r26qs32ltf9nmqrjaaa7tr55qmkk30q5d\",role=\"miner\"} 4800000000"
         ));
         assert!(exposition.contains(
-            "coinbase_output{index=\"1\",address=\"tb1q0afww6y0kgl4tyjjyv6xlttvfwdfqxvrfzz35f\"} 200000000"
+            "coinbase_output{index=\"1\",address=\"tb1q0afww6y0kgl4tyjjyv6xlttvfwdfqxvrfzz35f\",role=\"miner\"} 200000000"
         ));
         assert!(exposition.contains("coinbase_total 5000000000"));
     }
@@ -264,16 +318,128 @@ mod tests {
             job_id,
         );
 
-        let result = get_distribution(&tracker, 8, Network::Signet);
+        let result = get_distribution(&tracker, 8, Network::Signet, &CoinbaseRoles::default());
 
         // Should still return Some, just without the unparseable output
         assert!(result.is_some());
         let exposition = result.unwrap();
         assert!(exposition.contains(
-            "coinbase_output{index=\"0\",address=\"tb1q3udk7r26qs32ltf9nmqrjaaa7tr55qmkk30q5d\"} 4900000000"
+            "coinbase_output{index=\"0\",address=\"tb1q3udk7r26qs32ltf9nmqrjaaa7tr55qmkk30q5d\",role=\"miner\"} 4900000000"
         ));
         // OP_RETURN output should be skipped (no coinbase_output line for it)
         assert!(!exposition.contains("OP_RETURN"));
         assert!(exposition.contains("coinbase_total 5000000000"));
     }
+
+    #[tokio::test]
+    async fn test_get_distribution_without_witness_commitment() {
+        let tracker = start_tracker_actor();
+
+        let miner = parse_address(
+            "tb1q3udk7r26qs32ltf9nmqrjaaa7tr55qmkk30q5d",
+            Network::Signet,
+        )
+        .unwrap();
+        let donation = parse_address(
+            "tb1q0afww6y0kgl4tyjjyv6xlttvfwdfqxvrfzz35f",
+            Network::Signet,
+        )
+        .unwrap();
+
+        // No default_witness_commitment, so no commitment output. A valued
+        // OP_RETURN between the payouts is still not a payout.
+        let template = create_test_template();
+        assert!(template.default_witness_commitment.is_none());
+        let outputs = vec![
+            TxOut {
+                value: Amount::from_str("49 BTC").unwrap(),
+                script_pubkey: miner.script_pubkey(),
+            },
+            TxOut {
+                value: Amount::from_sat(1000),
+                script_pubkey: bitcoin::ScriptBuf::from_bytes(vec![0x6a, 0x02, 0xbe, 0xef]),
+            },
+            TxOut {
+                value: Amount::from_str("1 BTC").unwrap(),
+                script_pubkey: donation.script_pubkey(),
+            },
+        ];
+
+        let coinbase2 = create_valid_coinbase2(b"P2Poolv2", &outputs);
+
+        let job_id = tracker.get_next_job_id();
+        tracker.insert_job(Arc::new(template), "".to_string(), coinbase2, None, job_id);
+
+        let roles = CoinbaseRoles::new(None, Some(donation.clone()));
+        let exposition = get_distribution(&tracker, 8, Network::Signet, &roles).unwrap();
+
+        assert_eq!(exposition.matches("coinbase_output").count(), 2);
+        assert!(exposition.contains(&format!(
+            "coinbase_output{{index=\"0\",address=\"{miner}\",role=\"miner\"}} 4900000000"
+        )));
+        assert!(exposition.contains(&format!(
+            "coinbase_output{{index=\"2\",address=\"{donation}\",role=\"donation\"}} 100000000"
+        )));
+        assert!(exposition.contains("coinbase_total 5000000000"));
+    }
+
+    #[tokio::test]
+    async fn test_get_distribution_labels_roles() {
+        let tracker = start_tracker_actor();
+
+        let miner = parse_address(
+            "tb1q3udk7r26qs32ltf9nmqrjaaa7tr55qmkk30q5d",
+            Network::Signet,
+        )
+        .unwrap();
+        let fee = parse_address(
+            "tb1q0afww6y0kgl4tyjjyv6xlttvfwdfqxvrfzz35f",
+            Network::Signet,
+        )
+        .unwrap();
+        let donation = parse_address(
+            "tb1qyazxde6558qj6z3d9np5e6msmrspwpf6k0qggk",
+            Network::Signet,
+        )
+        .unwrap();
+
+        let outputs = vec![
+            TxOut {
+                value: Amount::from_str("47 BTC").unwrap(),
+                script_pubkey: miner.script_pubkey(),
+            },
+            TxOut {
+                value: Amount::from_str("2 BTC").unwrap(),
+                script_pubkey: fee.script_pubkey(),
+            },
+            TxOut {
+                value: Amount::from_str("1 BTC").unwrap(),
+                script_pubkey: donation.script_pubkey(),
+            },
+        ];
+
+        let coinbase2 = create_valid_coinbase2(b"P2Poolv2", &outputs);
+
+        let job_id = tracker.get_next_job_id();
+        tracker.insert_job(
+            Arc::new(create_test_template()),
+            "".to_string(),
+            coinbase2,
+            None,
+            job_id,
+        );
+
+        let roles = CoinbaseRoles::new(Some(fee.clone()), Some(donation.clone()));
+        let exposition = get_distribution(&tracker, 8, Network::Signet, &roles).unwrap();
+
+        assert!(exposition.contains(&format!(
+            "coinbase_output{{index=\"0\",address=\"{miner}\",role=\"miner\"}} 4700000000"
+        )));
+        assert!(exposition.contains(&format!(
+            "coinbase_output{{index=\"1\",address=\"{fee}\",role=\"pool\"}} 200000000"
+        )));
+        assert!(exposition.contains(&format!(
+            "coinbase_output{{index=\"2\",address=\"{donation}\",role=\"donation\"}} 100000000"
+        )));
+    }
 }
@@ -27,11 +27,11 @@ use std::sync::Arc;
 use tempfile::{TempDir, tempdir};
 
 // Imports only needed for internal tests
-#[cfg(test)]
+#[cfg(any(test, feature = "test-utils"))]
 use crate::shares::share_block::{ShareBlock, ShareHeader, ShareTransaction};
 #[cfg(test)]
 use crate::shares::share_commitment::ShareCommitment;
-#[cfg(test)]
+#[cfg(any(test, feature = "test-utils"))]
 use crate::shares::transactions::coinbase::create_coinbase_transaction;
 #[cfg(test)]
 use crate::stratum::messages::Notify;
@@ -41,13 +41,15 @@ use crate::stratum::messages::Response;
 use crate::stratum::messages::SimpleRequest;
 #[cfg(test)]
 use crate::stratum::work::block_template::BlockTemplate;
-#[cfg(test)]
+#[cfg(any(test, feature = "test-utils"))]
 use bitcoin::CompressedPublicKey;
 #[cfg(test)]
+use bitcoin::TxMerkleNode;
+#[cfg(any(test, feature = "test-utils"))]
 use bitcoin::hashes::Hash;
-#[cfg(test)]
-use bitcoin::{Block, BlockHash, CompactTarget, Transaction, TxMerkleNode, block::Header};
-#[cfg(test)]
+#[cfg(any(test, feature = "test-utils"))]
+use bitcoin::{Block, BlockHash, CompactTarget, Transaction, block::Header};
+#[cfg(any(test, feature = "test-utils"))]
 use std::str::FromStr;
 
 /// Setup returns both chain handle and tempdir (tempdir must stay alive)
@@ -72,7 +74,7 @@ pub async fn setup_test_chain_store_handle(start_writer: bool) -> (ChainStoreHan
     (chain_handle, temp_dir)
 }
 
-#[cfg(test)]
+#[cfg(any(test, feature = "test-utils"))]
 pub fn genesis_for_tests() -> ShareBlock {
     TestShareBlockBuilder::new().build()
 }
@@ -110,7 +112,7 @@ pub fn random_hex_string(length: usize, leading_zeroes: usize) -> String {
         .collect()
 }
 
-#[cfg(test)]
+#[cfg(any(test, feature = "test-utils"))]
 pub fn test_coinbase_transaction() -> bitcoin::Transaction {
     let pubkey = "020202020202020202020202020202020202020202020202020202020202020202"
         .parse::<bitcoin::CompressedPublicKey>()
@@ -219,7 +221,7 @@ pub fn build_block_from_work_components(path: &str) -> ShareBlock {
     }
 }
 
-#[cfg(test)]
+#[cfg(any(test, feature = "test-utils"))]
 #[derive(Debug, Clone, Default)]
 pub struct TestShareBlockBuilder {
     bitcoin_block: Option<Block>,
@@ -231,7 +233,7 @@ pub struct TestShareBlockBuilder {
     nonce: Option<u32>,
 }
 
-#[cfg(test)]
+#[cfg(any(test, feature = "test-utils"))]
 impl TestShareBlockBuilder {
     pub fn new() -> Self {
         Self::default()
@@ -308,7 +310,7 @@ pub fn multiplied_compact_target_as_work(bits: u32, multiplier: u32) -> bitcoin:
     bitcoin::Target::from_compact(CompactTarget::from_consensus(bits * multiplier)).to_work()
 }
 
-#[cfg(test)]
+#[cfg(any(test, feature = "test-utils"))]
 fn test_share_block(
     bitcoin_block: Option<Block>,
     prev_share_blockhash: &str,
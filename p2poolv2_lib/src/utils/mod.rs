@@ -17,3 +17,4 @@
 pub mod serde_support;
 pub mod snowflake_simplified;
 pub mod time_provider;
+pub mod work;
@@ -0,0 +1,86 @@
+// Copyright (C) 2024, 2025 P2Poolv2 Developers (see AUTHORS)
+//
+// This file is part of P2Poolv2
+//
+// P2Poolv2 is free software: you can redistribute it and/or modify it under
+// the terms of the GNU General Public License as published by the Free
+// Software Foundation, either version 3 of the License, or (at your option)
+// any later version.
+//
+// P2Poolv2 is distributed in the hope that it will be useful, but WITHOUT ANY
+// WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License along with
+// P2Poolv2. If not, see <https://www.gnu.org/licenses/>.
+
+use bitcoin::Work;
+
+/// Add two work values, returning None if the sum doesn't fit in 256 bits.
+/// Work's + wraps in release builds and panics in debug builds instead.
+pub fn checked_add_work(a: Work, b: Work) -> Option<Work> {
+    let a = a.to_be_bytes();
+    let b = b.to_be_bytes();
+    let mut sum = [0u8; 32];
+    let mut carry = 0u16;
+    for i in (0..32).rev() {
+        let total = u16::from(a[i]) + u16::from(b[i]) + carry;
+        sum[i] = total as u8;
+        carry = total >> 8;
+    }
+    (carry == 0).then(|| Work::from_be_bytes(sum))
+}
+
+/// Sum work values, returning None on overflow rather than wrapping. An
+/// empty iterator sums to zero work.
+pub fn sum_work(works: impl IntoIterator<Item = Work>) -> Option<Work> {
+    works
+        .into_iter()
+        .try_fold(Work::from_be_bytes([0; 32]), checked_add_work)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::{CompactTarget, Target};
+
+    #[test]
+    fn test_sum_work_many_large_shares_is_exact() {
+        // Each share is u128::MAX work, so a u128 total would wrap on the
+        // second share
+        let mut bytes = [0u8; 32];
+        bytes[16..].copy_from_slice(&[0xff; 16]);
+        let share_work = Work::from_be_bytes(bytes);
+
+        let total = sum_work(std::iter::repeat_n(share_work, 1000)).unwrap();
+
+        // 1000 * (2^128 - 1) = 999 * 2^128 + (2^128 - 1000)
+        let mut expected = [0u8; 32];
+        expected[..16].copy_from_slice(&999u128.to_be_bytes());
+        expected[16..].copy_from_slice(&(u128::MAX - 999).to_be_bytes());
+        assert_eq!(total, Work::from_be_bytes(expected));
+    }
+
+    #[test]
+    fn test_sum_work_max_difficulty_shares_overflow_is_none() {
+        // The hardest target, 1, commits to the largest work there is
+        let max_difficulty_work =
+            Target::from_compact(CompactTarget::from_consensus(0x0300_0001)).to_work();
+        assert_eq!(
+            sum_work([max_difficulty_work]),
+            Some(Work::from_be_bytes([0xff; 32]))
+        );
+        assert_eq!(
+            sum_work(std::iter::repeat_n(max_difficulty_work, 1000)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_sum_work_empty_is_zero() {
+        assert_eq!(
+            sum_work(std::iter::empty()),
+            Some(Work::from_be_bytes([0; 32]))
+        );
+    }
+}
@@ -29,6 +29,7 @@ use p2poolv2_lib::stratum::emission::Emission;
 use p2poolv2_lib::stratum::server::StratumServerBuilder;
 use p2poolv2_lib::stratum::work::gbt::start_gbt;
 use p2poolv2_lib::stratum::work::notify::start_notify;
+use p2poolv2_lib::stratum::work::tracker::parse_coinbase::CoinbaseRoles;
 use p2poolv2_lib::stratum::work::tracker::start_tracker_actor;
 use p2poolv2_lib::stratum::zmq_listener::{ZmqListener, ZmqListenerTrait};
 use std::process::ExitCode;
@@ -126,11 +127,13 @@ async fn main() -> ExitCode {
     let height = chain_store_handle.get_tip_height();
     info!("Latest tip {:?} at height {:?}", tip, height);
 
+    // Jobs are pruned on the same window as PPLNS shares
+    let pplns_ttl = Duration::from_secs(config.store.pplns_ttl_days * 3600 * 24);
     let background_tasks_store = store.clone();
     p2poolv2_lib::store::background_tasks::start_background_tasks(
         background_tasks_store,
         Duration::from_secs(config.store.background_task_frequency_hours * 3600),
-        Duration::from_secs(config.store.pplns_ttl_days * 3600 * 24),
+        pplns_ttl,
     );
 
     let stratum_config = config.stratum.clone().parse().unwrap();
@@ -208,6 +211,10 @@ async fn main() -> ExitCode {
     let chain_store_handle_for_stratum = chain_store_handle.clone();
     let tracker_handle_cloned = tracker_handle.clone();
     let exit_sender_stratum = exit_sender.clone();
+    let coinbase_roles = CoinbaseRoles::new(
+        stratum_config.fee_address().cloned(),
+        stratum_config.donation_address().cloned(),
+    );
 
     tokio::spawn(async move {
         let mut stratum_server = StratumServerBuilder::default()
@@ -253,6 +260,8 @@ async fn main() -> ExitCode {
         tracker_handle,
         stratum_config.network,
         stratum_config.pool_signature,
+        coinbase_roles,
+        pplns_ttl,
     )
     .await
     {
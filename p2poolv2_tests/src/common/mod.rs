@@ -70,6 +70,21 @@ pub fn default_test_config() -> Config {
             port: 3000,
             auth_user: None,
             auth_token: None,
+            api_keys: Vec::new(),
+            stale_after_secs: None,
+            default_page_size: None,
+            max_page_size: None,
+            max_scan_items: None,
+            dag_snapshot_interval_secs: None,
+            metrics_prefix: None,
+            metrics_export_dir: None,
+            metrics_export_interval_secs: None,
+            metrics_export_keep: None,
+            db_read_only: None,
+            db_allowed_cfs: None,
+            db_denied_cfs: None,
+            max_concurrent_heavy_requests: None,
+            stats_cache_interval_secs: None,
         },
     }
 }
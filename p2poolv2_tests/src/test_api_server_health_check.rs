@@ -19,13 +19,16 @@ use chrono::{TimeZone, Utc};
 use p2poolv2_api::api::error::ApiError;
 use p2poolv2_api::start_api_server;
 use p2poolv2_lib::accounting::{simple_pplns::SimplePplnsShare, stats::metrics::start_metrics};
-use p2poolv2_lib::config::ApiConfig;
+use p2poolv2_lib::config::{ApiConfig, ApiKeyConfig, ApiScope};
 use p2poolv2_lib::shares::share_block::ShareBlock;
-use p2poolv2_lib::stratum::work::tracker::start_tracker_actor;
+use p2poolv2_lib::stratum::work::tracker::{parse_coinbase::CoinbaseRoles, start_tracker_actor};
 use p2poolv2_lib::test_utils::setup_test_chain_store_handle;
 use reqwest::{Client, header};
 use tokio::time::{Duration, sleep};
 
+/// Job prune window passed to the API server
+const JOB_TTL: Duration = Duration::from_secs(3 * 24 * 60 * 60);
+
 #[tokio::test]
 async fn test_api_server_without_authentication() -> Result<(), ApiError> {
     let (chain_store_handle, temp_dir) = setup_test_chain_store_handle(true).await;
@@ -41,6 +44,21 @@ async fn test_api_server_without_authentication() -> Result<(), ApiError> {
         port: 4000,
         auth_user: None,
         auth_token: None,
+        api_keys: Vec::new(),
+        stale_after_secs: None,
+        default_page_size: None,
+        max_page_size: None,
+        max_scan_items: None,
+        dag_snapshot_interval_secs: None,
+        metrics_prefix: None,
+        metrics_export_dir: None,
+        metrics_export_interval_secs: None,
+        metrics_export_keep: None,
+        db_read_only: None,
+        db_allowed_cfs: None,
+        db_denied_cfs: None,
+        max_concurrent_heavy_requests: None,
+        stats_cache_interval_secs: None,
     };
 
     // Start API server with the new signature
@@ -51,6 +69,8 @@ async fn test_api_server_without_authentication() -> Result<(), ApiError> {
         tracker_handle,
         bitcoin::Network::Signet,
         Some("p2poolv2".to_string()),
+        CoinbaseRoles::default(),
+        JOB_TTL,
     )
     .await
     .map_err(|e| ApiError::ServerError(e.to_string()))?;
@@ -120,6 +140,21 @@ async fn test_api_server_with_authentication() -> Result<(), ApiError> {
         port: 4001,
         auth_user: Some("testuser".to_string()),
         auth_token: Some(test_token),
+        api_keys: Vec::new(),
+        stale_after_secs: None,
+        default_page_size: None,
+        max_page_size: None,
+        max_scan_items: None,
+        dag_snapshot_interval_secs: None,
+        metrics_prefix: None,
+        metrics_export_dir: None,
+        metrics_export_interval_secs: None,
+        metrics_export_keep: None,
+        db_read_only: None,
+        db_allowed_cfs: None,
+        db_denied_cfs: None,
+        max_concurrent_heavy_requests: None,
+        stats_cache_interval_secs: None,
     };
 
     // Start API server with authentication
@@ -130,6 +165,8 @@ async fn test_api_server_with_authentication() -> Result<(), ApiError> {
         tracker_handle,
         bitcoin::Network::Signet,
         Some("p2poolv2".to_string()),
+        CoinbaseRoles::default(),
+        JOB_TTL,
     )
     .await
     .map_err(|e| ApiError::ServerError(e.to_string()))?;
@@ -214,6 +251,108 @@ async fn test_api_server_with_authentication() -> Result<(), ApiError> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_api_server_api_key_scopes() -> Result<(), ApiError> {
+    let (chain_store_handle, temp_dir) = setup_test_chain_store_handle(true).await;
+    let metrics_handle = start_metrics(temp_dir.path().to_str().unwrap().to_string())
+        .await
+        .map_err(|e| ApiError::ServerError(e.to_string()))?;
+
+    // HMAC-SHA256 of "testpassword" keyed with this salt, as made by gen_auth
+    let test_token = "0123456789abcdef0123456789abcdef$ae9b643bfa9f224a9c11accafec1ab89c3851c54ac036af2ac7f5b7a7d064fcb";
+    let api_key = |user: &str, scope| ApiKeyConfig {
+        user: user.to_string(),
+        token: test_token.to_string(),
+        scope,
+    };
+
+    let api_config = ApiConfig {
+        hostname: "127.0.0.1".into(),
+        port: 40010,
+        auth_user: None,
+        auth_token: None,
+        api_keys: vec![
+            api_key("dashboard", ApiScope::Read),
+            api_key("operator", ApiScope::Admin),
+        ],
+        stale_after_secs: None,
+        default_page_size: None,
+        max_page_size: None,
+        max_scan_items: None,
+        dag_snapshot_interval_secs: None,
+        metrics_prefix: None,
+        metrics_export_dir: None,
+        metrics_export_interval_secs: None,
+        metrics_export_keep: None,
+        db_read_only: Some(false),
+        db_allowed_cfs: None,
+        db_denied_cfs: None,
+        max_concurrent_heavy_requests: None,
+        stats_cache_interval_secs: None,
+    };
+
+    let shutdown_tx = start_api_server(
+        api_config.clone(),
+        chain_store_handle,
+        metrics_handle,
+        start_tracker_actor(),
+        bitcoin::Network::Signet,
+        None,
+        CoinbaseRoles::default(),
+        JOB_TTL,
+    )
+    .await
+    .map_err(|e| ApiError::ServerError(e.to_string()))?;
+
+    sleep(Duration::from_millis(500)).await;
+
+    let client = Client::new();
+    let basic_auth = |user: &str| {
+        format!(
+            "Basic {}",
+            base64::engine::general_purpose::STANDARD.encode(format!("{user}:testpassword"))
+        )
+    };
+    let entry_url = format!(
+        "http://127.0.0.1:{}/db/cf/metadata/entry/missing_key",
+        api_config.port
+    );
+
+    // A read key can read entries but not delete them
+    let response = client
+        .get(&entry_url)
+        .header(header::AUTHORIZATION, basic_auth("dashboard"))
+        .send()
+        .await
+        .map_err(|e| ApiError::ServerError(e.to_string()))?;
+    assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+
+    let response = client
+        .delete(&entry_url)
+        .header(header::AUTHORIZATION, basic_auth("dashboard"))
+        .send()
+        .await
+        .map_err(|e| ApiError::ServerError(e.to_string()))?;
+    assert_eq!(
+        response.status(),
+        reqwest::StatusCode::FORBIDDEN,
+        "Delete with a read key should return 403"
+    );
+
+    let response = client
+        .delete(&entry_url)
+        .header(header::AUTHORIZATION, basic_auth("operator"))
+        .send()
+        .await
+        .map_err(|e| ApiError::ServerError(e.to_string()))?;
+    assert_eq!(response.status(), reqwest::StatusCode::NO_CONTENT);
+
+    let _ = shutdown_tx.send(());
+    sleep(Duration::from_millis(200)).await;
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_pplns_shares_endpoint_get_all() -> Result<(), ApiError> {
     let (chain_store_handle, temp_dir) = setup_test_chain_store_handle(true).await;
@@ -233,6 +372,21 @@ async fn test_pplns_shares_endpoint_get_all() -> Result<(), ApiError> {
         port: 40002,
         auth_user: None,
         auth_token: None,
+        api_keys: Vec::new(),
+        stale_after_secs: None,
+        default_page_size: None,
+        max_page_size: None,
+        max_scan_items: None,
+        dag_snapshot_interval_secs: None,
+        metrics_prefix: None,
+        metrics_export_dir: None,
+        metrics_export_interval_secs: None,
+        metrics_export_keep: None,
+        db_read_only: None,
+        db_allowed_cfs: None,
+        db_denied_cfs: None,
+        max_concurrent_heavy_requests: None,
+        stats_cache_interval_secs: None,
     };
 
     // Start API server
@@ -243,6 +397,8 @@ async fn test_pplns_shares_endpoint_get_all() -> Result<(), ApiError> {
         tracker_handle,
         bitcoin::Network::Signet,
         Some("p2poolv2".to_string()),
+        CoinbaseRoles::default(),
+        JOB_TTL,
     )
     .await
     .map_err(|e| ApiError::ServerError(e.to_string()))?;
@@ -269,7 +425,7 @@ async fn test_pplns_shares_endpoint_get_all() -> Result<(), ApiError> {
     let shares = vec![
         SimplePplnsShare::new(
             user_id,
-            100,
+            100.0,
             "tb1qtestaddress".to_string(),
             "worker1".to_string(),
             share1_timestamp,
@@ -279,7 +435,7 @@ async fn test_pplns_shares_endpoint_get_all() -> Result<(), ApiError> {
         ),
         SimplePplnsShare::new(
             user_id,
-            101,
+            101.0,
             "tb1qtestaddress".to_string(),
             "worker2".to_string(),
             share2_timestamp,
@@ -343,6 +499,21 @@ async fn test_pplns_shares_endpoint_limit() -> Result<(), ApiError> {
         port: 40003,
         auth_user: None,
         auth_token: None,
+        api_keys: Vec::new(),
+        stale_after_secs: None,
+        default_page_size: None,
+        max_page_size: None,
+        max_scan_items: None,
+        dag_snapshot_interval_secs: None,
+        metrics_prefix: None,
+        metrics_export_dir: None,
+        metrics_export_interval_secs: None,
+        metrics_export_keep: None,
+        db_read_only: None,
+        db_allowed_cfs: None,
+        db_denied_cfs: None,
+        max_concurrent_heavy_requests: None,
+        stats_cache_interval_secs: None,
     };
 
     // Start API server
@@ -353,6 +524,8 @@ async fn test_pplns_shares_endpoint_limit() -> Result<(), ApiError> {
         tracker_handle,
         bitcoin::Network::Signet,
         Some("p2poolv2".to_string()),
+        CoinbaseRoles::default(),
+        JOB_TTL,
     )
     .await
     .map_err(|e| ApiError::ServerError(e.to_string()))?;
@@ -379,7 +552,7 @@ async fn test_pplns_shares_endpoint_limit() -> Result<(), ApiError> {
     let shares = vec![
         SimplePplnsShare::new(
             user_id,
-            100,
+            100.0,
             "tb1qtestaddress".to_string(),
             "worker1".to_string(),
             t1,
@@ -389,7 +562,7 @@ async fn test_pplns_shares_endpoint_limit() -> Result<(), ApiError> {
         ),
         SimplePplnsShare::new(
             user_id,
-            101,
+            101.0,
             "tb1qtestaddress".to_string(),
             "worker2".to_string(),
             t2,
@@ -448,6 +621,21 @@ async fn test_pplns_shares_endpoint_time_filter() -> Result<(), ApiError> {
         port: 40004,
         auth_user: None,
         auth_token: None,
+        api_keys: Vec::new(),
+        stale_after_secs: None,
+        default_page_size: None,
+        max_page_size: None,
+        max_scan_items: None,
+        dag_snapshot_interval_secs: None,
+        metrics_prefix: None,
+        metrics_export_dir: None,
+        metrics_export_interval_secs: None,
+        metrics_export_keep: None,
+        db_read_only: None,
+        db_allowed_cfs: None,
+        db_denied_cfs: None,
+        max_concurrent_heavy_requests: None,
+        stats_cache_interval_secs: None,
     };
 
     // Start API server
@@ -458,6 +646,8 @@ async fn test_pplns_shares_endpoint_time_filter() -> Result<(), ApiError> {
         tracker_handle,
         bitcoin::Network::Signet,
         Some("p2poolv2".to_string()),
+        CoinbaseRoles::default(),
+        JOB_TTL,
     )
     .await
     .map_err(|e| ApiError::ServerError(e.to_string()))?;
@@ -484,7 +674,7 @@ async fn test_pplns_shares_endpoint_time_filter() -> Result<(), ApiError> {
     let shares = vec![
         SimplePplnsShare::new(
             user_id,
-            100,
+            100.0,
             "tb1qtestaddress".to_string(),
             "worker1".to_string(),
             share1_timestamp,
@@ -494,7 +684,7 @@ async fn test_pplns_shares_endpoint_time_filter() -> Result<(), ApiError> {
         ),
         SimplePplnsShare::new(
             user_id,
-            101,
+            101.0,
             "tb1qtestaddress".to_string(),
             "worker2".to_string(),
             share2_timestamp,
@@ -532,9 +722,433 @@ async fn test_pplns_shares_endpoint_time_filter() -> Result<(), ApiError> {
         "Returned share should have correct timestamp"
     );
 
+    // Test: Bare dates are accepted as UTC midnight
+    let response = client
+        .get(format!(
+            "http://127.0.0.1:{}/pplns_shares?start_time=2025-10-17&end_time=2025-10-18",
+            api_config.port
+        ))
+        .send()
+        .await
+        .map_err(|e| ApiError::ServerError(e.to_string()))?;
+    let filtered: Vec<SimplePplnsShare> = response
+        .json()
+        .await
+        .map_err(|e| ApiError::ServerError(e.to_string()))?;
+    assert_eq!(filtered.len(), 2, "Date filter should return both shares");
+
+    // Test: Pre-epoch dates are rejected with 400
+    let response = client
+        .get(format!(
+            "http://127.0.0.1:{}/pplns_shares?start_time=1969-12-31",
+            api_config.port
+        ))
+        .send()
+        .await
+        .map_err(|e| ApiError::ServerError(e.to_string()))?;
+    assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+
     // Shutdown server
     let _ = shutdown_tx.send(());
     sleep(Duration::from_millis(200)).await;
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_api_server_binds_ipv6_hostname() -> Result<(), ApiError> {
+    let (chain_store_handle, temp_dir) = setup_test_chain_store_handle(true).await;
+    let metrics_handle = start_metrics(temp_dir.path().to_str().unwrap().to_string())
+        .await
+        .map_err(|e| ApiError::ServerError(e.to_string()))?;
+
+    let mut api_config = ApiConfig {
+        hostname: "not-an-ip".into(),
+        port: 40005,
+        auth_user: None,
+        auth_token: None,
+        api_keys: Vec::new(),
+        stale_after_secs: None,
+        default_page_size: None,
+        max_page_size: None,
+        max_scan_items: None,
+        dag_snapshot_interval_secs: None,
+        metrics_prefix: None,
+        metrics_export_dir: None,
+        metrics_export_interval_secs: None,
+        metrics_export_keep: None,
+        db_read_only: None,
+        db_allowed_cfs: None,
+        db_denied_cfs: None,
+        max_concurrent_heavy_requests: None,
+        stats_cache_interval_secs: None,
+    };
+
+    // A bad hostname is reported as an error instead of panicking
+    let result = start_api_server(
+        api_config.clone(),
+        chain_store_handle.clone(),
+        metrics_handle.clone(),
+        start_tracker_actor(),
+        bitcoin::Network::Signet,
+        None,
+        CoinbaseRoles::default(),
+        JOB_TTL,
+    )
+    .await;
+    assert!(result.is_err(), "Invalid hostname should fail to start");
+
+    api_config.hostname = "::1".into();
+    let shutdown_tx = start_api_server(
+        api_config.clone(),
+        chain_store_handle,
+        metrics_handle,
+        start_tracker_actor(),
+        bitcoin::Network::Signet,
+        None,
+        CoinbaseRoles::default(),
+        JOB_TTL,
+    )
+    .await
+    .map_err(|e| ApiError::ServerError(e.to_string()))?;
+
+    sleep(Duration::from_millis(500)).await;
+
+    let response = Client::new()
+        .get(format!("http://[::1]:{}/health", api_config.port))
+        .send()
+        .await
+        .map_err(|e| ApiError::ServerError(e.to_string()))?;
+    assert!(
+        response.status().is_success(),
+        "Health endpoint did not return 200 OK over IPv6"
+    );
+
+    let _ = shutdown_tx.send(());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_api_server_unknown_route_returns_json_404() -> Result<(), ApiError> {
+    let (chain_store_handle, temp_dir) = setup_test_chain_store_handle(true).await;
+    let metrics_handle = start_metrics(temp_dir.path().to_str().unwrap().to_string())
+        .await
+        .map_err(|e| ApiError::ServerError(e.to_string()))?;
+
+    let api_config = ApiConfig {
+        hostname: "127.0.0.1".into(),
+        port: 40006,
+        auth_user: None,
+        auth_token: None,
+        api_keys: Vec::new(),
+        stale_after_secs: None,
+        default_page_size: None,
+        max_page_size: None,
+        max_scan_items: None,
+        dag_snapshot_interval_secs: None,
+        metrics_prefix: None,
+        metrics_export_dir: None,
+        metrics_export_interval_secs: None,
+        metrics_export_keep: None,
+        db_read_only: None,
+        db_allowed_cfs: None,
+        db_denied_cfs: None,
+        max_concurrent_heavy_requests: None,
+        stats_cache_interval_secs: None,
+    };
+
+    let shutdown_tx = start_api_server(
+        api_config.clone(),
+        chain_store_handle,
+        metrics_handle,
+        start_tracker_actor(),
+        bitcoin::Network::Signet,
+        None,
+        CoinbaseRoles::default(),
+        JOB_TTL,
+    )
+    .await
+    .map_err(|e| ApiError::ServerError(e.to_string()))?;
+
+    sleep(Duration::from_millis(500)).await;
+
+    let response = Client::new()
+        .get(format!(
+            "http://127.0.0.1:{}/no/such/route",
+            api_config.port
+        ))
+        .send()
+        .await
+        .map_err(|e| ApiError::ServerError(e.to_string()))?;
+    assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+    let body: serde_json::Value = response
+        .json()
+        .await
+        .map_err(|e| ApiError::ServerError(e.to_string()))?;
+    assert_eq!(body["error"], "route not found");
+    assert_eq!(body["path"], "/no/such/route");
+
+    let _ = shutdown_tx.send(());
+    sleep(Duration::from_millis(200)).await;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_api_server_pretty_prints_json_on_request() -> Result<(), ApiError> {
+    let (chain_store_handle, temp_dir) = setup_test_chain_store_handle(true).await;
+    let metrics_handle = start_metrics(temp_dir.path().to_str().unwrap().to_string())
+        .await
+        .map_err(|e| ApiError::ServerError(e.to_string()))?;
+
+    let api_config = ApiConfig {
+        hostname: "127.0.0.1".into(),
+        port: 40007,
+        auth_user: None,
+        auth_token: None,
+        api_keys: Vec::new(),
+        stale_after_secs: None,
+        default_page_size: None,
+        max_page_size: None,
+        max_scan_items: None,
+        dag_snapshot_interval_secs: None,
+        metrics_prefix: None,
+        metrics_export_dir: None,
+        metrics_export_interval_secs: None,
+        metrics_export_keep: None,
+        db_read_only: None,
+        db_allowed_cfs: None,
+        db_denied_cfs: None,
+        max_concurrent_heavy_requests: None,
+        stats_cache_interval_secs: None,
+    };
+
+    let shutdown_tx = start_api_server(
+        api_config.clone(),
+        chain_store_handle,
+        metrics_handle,
+        start_tracker_actor(),
+        bitcoin::Network::Signet,
+        None,
+        CoinbaseRoles::default(),
+        JOB_TTL,
+    )
+    .await
+    .map_err(|e| ApiError::ServerError(e.to_string()))?;
+
+    sleep(Duration::from_millis(500)).await;
+
+    let client = Client::new();
+    let get = |query: &'static str| {
+        client
+            .get(format!("http://127.0.0.1:{}/db/cf{query}", api_config.port))
+            .send()
+    };
+
+    let compact = get("")
+        .await
+        .map_err(|e| ApiError::ServerError(e.to_string()))?
+        .text()
+        .await
+        .map_err(|e| ApiError::ServerError(e.to_string()))?;
+    assert!(!compact.contains('\n'), "JSON should be compact by default");
+
+    let response = get("?pretty=true")
+        .await
+        .map_err(|e| ApiError::ServerError(e.to_string()))?;
+    assert_eq!(
+        response.headers()[header::CONTENT_TYPE],
+        "application/json",
+        "Pretty JSON should keep the JSON content type"
+    );
+    let pretty = response
+        .text()
+        .await
+        .map_err(|e| ApiError::ServerError(e.to_string()))?;
+    assert!(pretty.contains("\n  \""), "JSON should be indented");
+    assert_eq!(
+        serde_json::from_str::<serde_json::Value>(&pretty).unwrap(),
+        serde_json::from_str::<serde_json::Value>(&compact).unwrap()
+    );
+
+    let _ = shutdown_tx.send(());
+    sleep(Duration::from_millis(200)).await;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_api_server_accepts_gzip_request_bodies() -> Result<(), ApiError> {
+    use flate2::{Compression, write::GzEncoder};
+    use std::io::Write;
+
+    let gzip = |body: &[u8]| {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(body).unwrap();
+        encoder.finish().unwrap()
+    };
+
+    let (chain_store_handle, temp_dir) = setup_test_chain_store_handle(true).await;
+    let metrics_handle = start_metrics(temp_dir.path().to_str().unwrap().to_string())
+        .await
+        .map_err(|e| ApiError::ServerError(e.to_string()))?;
+
+    let api_config = ApiConfig {
+        hostname: "127.0.0.1".into(),
+        port: 40008,
+        auth_user: None,
+        auth_token: None,
+        api_keys: Vec::new(),
+        stale_after_secs: None,
+        default_page_size: None,
+        max_page_size: None,
+        max_scan_items: None,
+        dag_snapshot_interval_secs: None,
+        metrics_prefix: None,
+        metrics_export_dir: None,
+        metrics_export_interval_secs: None,
+        metrics_export_keep: None,
+        db_read_only: None,
+        db_allowed_cfs: None,
+        db_denied_cfs: None,
+        max_concurrent_heavy_requests: None,
+        stats_cache_interval_secs: None,
+    };
+
+    let shutdown_tx = start_api_server(
+        api_config.clone(),
+        chain_store_handle,
+        metrics_handle,
+        start_tracker_actor(),
+        bitcoin::Network::Signet,
+        None,
+        CoinbaseRoles::default(),
+        JOB_TTL,
+    )
+    .await
+    .map_err(|e| ApiError::ServerError(e.to_string()))?;
+
+    sleep(Duration::from_millis(500)).await;
+
+    let client = Client::new();
+    let url = format!(
+        "http://127.0.0.1:{}/db/cf/metadata/batch_get",
+        api_config.port
+    );
+
+    let body = serde_json::json!({ "keys": ["missing_key"], "key_encoding": "utf8" });
+    let response = client
+        .post(&url)
+        .header(header::CONTENT_TYPE, "application/json")
+        .header(header::CONTENT_ENCODING, "gzip")
+        .body(gzip(body.to_string().as_bytes()))
+        .send()
+        .await
+        .map_err(|e| ApiError::ServerError(e.to_string()))?;
+    assert!(
+        response.status().is_success(),
+        "Gzip batch_get returned {}",
+        response.status()
+    );
+    let entries: serde_json::Value = response
+        .json()
+        .await
+        .map_err(|e| ApiError::ServerError(e.to_string()))?;
+    assert_eq!(entries[0]["key"], "missing_key");
+    assert_eq!(entries[0]["found"], false);
+
+    // A few KB of gzip that expands past the body limit is rejected
+    let mut bomb = br#"{"keys":[""#.to_vec();
+    bomb.extend(std::iter::repeat_n(b'a', 8 * 1024 * 1024));
+    bomb.extend(br#""]}"#);
+    let compressed = gzip(&bomb);
+    assert!(compressed.len() < 64 * 1024);
+    let response = client
+        .post(&url)
+        .header(header::CONTENT_TYPE, "application/json")
+        .header(header::CONTENT_ENCODING, "gzip")
+        .body(compressed)
+        .send()
+        .await
+        .map_err(|e| ApiError::ServerError(e.to_string()))?;
+    assert_eq!(response.status(), reqwest::StatusCode::PAYLOAD_TOO_LARGE);
+
+    let _ = shutdown_tx.send(());
+    sleep(Duration::from_millis(200)).await;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_api_server_rejects_unparsable_heights_with_json_400() -> Result<(), ApiError> {
+    let (chain_store_handle, temp_dir) = setup_test_chain_store_handle(true).await;
+    let metrics_handle = start_metrics(temp_dir.path().to_str().unwrap().to_string())
+        .await
+        .map_err(|e| ApiError::ServerError(e.to_string()))?;
+
+    let api_config = ApiConfig {
+        hostname: "127.0.0.1".into(),
+        port: 40009,
+        auth_user: None,
+        auth_token: None,
+        api_keys: Vec::new(),
+        stale_after_secs: None,
+        default_page_size: None,
+        max_page_size: None,
+        max_scan_items: None,
+        dag_snapshot_interval_secs: None,
+        metrics_prefix: None,
+        metrics_export_dir: None,
+        metrics_export_interval_secs: None,
+        metrics_export_keep: None,
+        db_read_only: None,
+        db_allowed_cfs: None,
+        db_denied_cfs: None,
+        max_concurrent_heavy_requests: None,
+        stats_cache_interval_secs: None,
+    };
+
+    let shutdown_tx = start_api_server(
+        api_config.clone(),
+        chain_store_handle,
+        metrics_handle,
+        start_tracker_actor(),
+        bitcoin::Network::Signet,
+        None,
+        CoinbaseRoles::default(),
+        JOB_TTL,
+    )
+    .await
+    .map_err(|e| ApiError::ServerError(e.to_string()))?;
+
+    sleep(Duration::from_millis(500)).await;
+
+    let client = Client::new();
+    for path in [
+        "/chain/shares/4294967296",
+        "/chain/shares/-1",
+        "/chain/uncles/abc",
+    ] {
+        let response = client
+            .get(format!("http://127.0.0.1:{}{path}", api_config.port))
+            .send()
+            .await
+            .map_err(|e| ApiError::ServerError(e.to_string()))?;
+        assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| ApiError::ServerError(e.to_string()))?;
+        assert!(
+            body["error"]
+                .as_str()
+                .unwrap()
+                .starts_with("Invalid height")
+        );
+    }
+
+    let _ = shutdown_tx.send(());
+    sleep(Duration::from_millis(200)).await;
+
+    Ok(())
+}